@@ -0,0 +1,78 @@
+//! Compares iterating over records and fields with wrappers and with raw
+//! cursors.
+
+use std::hint::black_box;
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use tblgen::{RecordKeeper, TableGenParser};
+
+const DEFS: usize = 10_000;
+const FIELDS: usize = 32;
+
+fn keeper() -> RecordKeeper<'static> {
+    let fields = (0..FIELDS)
+        .map(|index| format!("int f{} = {};", index, index))
+        .collect::<String>();
+    let source = format!(
+        "class C {{ {} }}\n{}",
+        fields,
+        (0..DEFS)
+            .map(|index| format!("def D{} : C;\n", index))
+            .collect::<String>()
+    );
+    TableGenParser::new()
+        .add_source(&source)
+        .unwrap()
+        .parse()
+        .expect("valid tablegen")
+}
+
+fn defs(criterion: &mut Criterion) {
+    let keeper = keeper();
+    let mut group = criterion.benchmark_group("defs");
+    group.throughput(Throughput::Elements(DEFS as u64));
+    group.bench_function("defs", |bencher| {
+        bencher.iter(|| {
+            for (name, def) in keeper.defs() {
+                black_box((name.ok(), def));
+            }
+        })
+    });
+    group.bench_function("raw_cursor", |bencher| {
+        bencher.iter(|| {
+            for def in keeper.raw_cursor() {
+                black_box(def);
+            }
+        })
+    });
+    group.finish();
+}
+
+fn fields(criterion: &mut Criterion) {
+    let keeper = keeper();
+    let mut group = criterion.benchmark_group("fields");
+    group.throughput(Throughput::Elements((DEFS * FIELDS) as u64));
+    group.bench_function("values", |bencher| {
+        bencher.iter(|| {
+            for (_, def) in keeper.defs() {
+                for value in def.values() {
+                    black_box(value);
+                }
+            }
+        })
+    });
+    group.bench_function("raw_cursor", |bencher| {
+        bencher.iter(|| {
+            for def in keeper.raw_cursor() {
+                // The record keeper outlives the raw record.
+                for value in unsafe { tblgen::Record::from_raw(def) }.raw_cursor() {
+                    black_box(value);
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, defs, fields);
+criterion_main!(benches);
@@ -1,9 +1,11 @@
 use std::{
+    collections::HashMap,
     env,
     error::Error,
     ffi::OsStr,
-    fs::read_dir,
-    path::Path,
+    fs::{copy, create_dir_all, read_dir, read_to_string, write},
+    io,
+    path::{Path, PathBuf},
     process::{Command, exit},
     str,
 };
@@ -24,6 +26,10 @@ const LLVM_MAJOR_VERSION: usize = if cfg!(feature = "llvm16-0") {
     22
 };
 
+/// LLVM components used by the C wrapper. `llvm-config` adds the components
+/// they depend on, so that not all LLVM libraries are linked.
+const LLVM_COMPONENTS: &str = "tablegen support";
+
 fn main() {
     if let Err(error) = run() {
         eprintln!("{error}");
@@ -32,6 +38,49 @@ fn main() {
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
+    println!("cargo:rerun-if-changed=wrapper.h");
+    println!("cargo:rerun-if-changed=cc");
+
+    let include_directories = if cfg!(feature = "vendored") {
+        build_vendored()?
+    } else {
+        link_llvm()?
+    };
+
+    if let Some(name) = get_system_libcpp() {
+        println!("cargo:rustc-link-lib={name}");
+    }
+
+    let bindings_path = Path::new(&env::var("OUT_DIR")?).join("bindings.rs");
+    if let Some(path) = prebuilt_path("BINDINGS") {
+        println!("cargo:rerun-if-changed={}", path.display());
+        copy(path, bindings_path)?;
+    } else {
+        // bindgen passes the target to clang itself.
+        let mut builder = bindgen::builder()
+            .header("wrapper.h")
+            .clang_arg("-Icc/include")
+            .clang_args(
+                include_directories
+                    .iter()
+                    .map(|directory| format!("-I{}", directory.display())),
+            );
+        if let Some(sysroot) = target_sysroot() {
+            builder = builder.clang_arg(format!("--sysroot={}", sysroot.display()));
+        }
+        builder
+            .default_enum_style(bindgen::EnumVariation::ModuleConsts)
+            .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+            .generate()?
+            .write_to_file(bindings_path)?;
+    }
+
+    Ok(())
+}
+
+/// Links the libraries of the LLVM installation found with `llvm-config`, and
+/// returns its include directories.
+fn link_llvm() -> Result<Vec<PathBuf>, Box<dyn Error>> {
     let version = llvm_config(false, "--version")?;
 
     if !version.starts_with(&format!("{LLVM_MAJOR_VERSION}.")) {
@@ -41,21 +90,27 @@ fn run() -> Result<(), Box<dyn Error>> {
         .into());
     }
 
-    println!("cargo:rerun-if-changed=wrapper.h");
-    println!("cargo:rerun-if-changed=cc");
     println!(
         "cargo:rustc-link-search={}",
         llvm_config(false, "--libdir")?
     );
 
-    build_c_library()?;
+    // A prebuilt wrapper must have been built from the same sources and for
+    // the same LLVM version, e.g. by an earlier build cached in CI.
+    if let Some(directory) = prebuilt_path("CTABLEGEN_DIR") {
+        println!("cargo:rustc-link-search=native={}", directory.display());
+        println!("cargo:rustc-link-lib=static=CTableGen");
+    } else {
+        build_c_library()?;
+    }
 
     let link_static = resolve_link_mode()?;
 
     // When using the shared libLLVM, it may not export all C++ symbols that
     // libCTableGen.a references (e.g. VarInit::getName()). Link the available
-    // static component libs to cover the gap.
-    if !link_static {
+    // static component libs to cover the gap, unless only the shared library
+    // is requested.
+    if !link_static && !cfg!(feature = "dynamic-linking") {
         let libdir = llvm_config(false, "--libdir")?;
         for lib in &["LLVMTableGen", "LLVMSupport", "LLVMDemangle"] {
             if Path::new(&format!("{}/lib{}.a", libdir, lib)).exists() {
@@ -64,7 +119,7 @@ fn run() -> Result<(), Box<dyn Error>> {
         }
     }
 
-    for name in llvm_config(link_static, "--libnames")?
+    for name in llvm_config(link_static, &format!("--libnames {LLVM_COMPONENTS}"))?
         .trim()
         .split(' ')
         .filter(|s| !s.is_empty())
@@ -83,7 +138,7 @@ fn run() -> Result<(), Box<dyn Error>> {
         );
     }
 
-    for flag in llvm_config(link_static, "--system-libs")?
+    for flag in llvm_config(link_static, &format!("--system-libs {LLVM_COMPONENTS}"))?
         .trim()
         .split(' ')
         .filter(|s| !s.is_empty())
@@ -108,20 +163,15 @@ fn run() -> Result<(), Box<dyn Error>> {
         }
     }
 
-    if let Some(name) = get_system_libcpp() {
-        println!("cargo:rustc-link-lib={name}");
-    }
-
-    bindgen::builder()
-        .header("wrapper.h")
-        .clang_arg("-Icc/include")
-        .clang_arg(format!("-I{}", llvm_config(false, "--includedir")?))
-        .default_enum_style(bindgen::EnumVariation::ModuleConsts)
-        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
-        .generate()?
-        .write_to_file(Path::new(&env::var("OUT_DIR")?).join("bindings.rs"))?;
+    Ok(vec![llvm_config(false, "--includedir")?.into()])
+}
 
-    Ok(())
+/// Returns the path in the `TABLEGEN_<version>_<name>` environment variable,
+/// which refers to a prebuilt part of the crate.
+fn prebuilt_path(name: &str) -> Option<PathBuf> {
+    let variable = format!("TABLEGEN_{LLVM_MAJOR_VERSION}0_{name}");
+    println!("cargo:rerun-if-env-changed={variable}");
+    env::var_os(variable).map(PathBuf::from)
 }
 
 fn filter_fortify_source(flags: &str) -> String {
@@ -157,45 +207,444 @@ fn build_c_library() -> Result<(), Box<dyn Error>> {
     } else {
         (filter_std_flag(&raw_cxxflags), raw_cflags)
     };
+    let (cxxflags, static_crt) = if is_msvc() {
+        translate_msvc_flags(&cxxflags)
+    } else {
+        (cxxflags, None)
+    };
     unsafe { env::set_var("CXXFLAGS", cxxflags) };
     unsafe { env::set_var("CFLAGS", cflags) };
 
-    cc::Build::new()
+    let mut build = cc::Build::new();
+    build.include(llvm_config(false, "--includedir")?);
+    add_wrapper_sources(&mut build)?;
+
+    // The wrapper must use the same CRT as the LLVM libraries, and so must the
+    // Rust code linking both.
+    if let Some(static_crt) = static_crt {
+        build.static_crt(static_crt);
+        let rust_static_crt = env::var("CARGO_CFG_TARGET_FEATURE")
+            .is_ok_and(|features| features.split(',').any(|feature| feature == "crt-static"));
+        if static_crt != rust_static_crt {
+            println!(
+                "cargo:warning=LLVM uses the {} CRT, build with `-C target-feature={}crt-static`",
+                if static_crt { "static" } else { "dynamic" },
+                if static_crt { "+" } else { "-" },
+            );
+        }
+    }
+
+    build.compile("CTableGen");
+
+    Ok(())
+}
+
+/// Adds the sources and flags of the C++ wrapper to a build that includes the
+/// LLVM headers.
+fn add_wrapper_sources(build: &mut cc::Build) -> Result<(), Box<dyn Error>> {
+    build
         .cpp(true)
+        .files(source_files(Path::new("cc/lib"), "cpp")?)
+        .include("cc/include")
+        .std("c++20");
+
+    if is_msvc() {
+        // LLVM 22.1.7 headers trigger C4245/C4244 on MSVC; don't treat as errors
+        build.flag("/W4").flag("/WX-");
+    } else {
+        build
+            .flag("-Werror")
+            .flag("-Wall")
+            .flag("-Wno-unused-parameter");
+    }
+
+    Ok(())
+}
+
+/// Returns the files with the given extension in a directory, without the
+/// ones in its subdirectories.
+fn source_files(directory: &Path, extension: &str) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = read_dir(directory)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>, io::Error>>()?;
+    files.retain(|path| path.is_file() && path.extension() == Some(OsStr::new(extension)));
+    files.sort();
+    Ok(files)
+}
+
+/// Builds the wrapper together with the TableGen parser and the LLVM libraries
+/// it depends on from the LLVM sources in `TABLEGEN_<version>_SOURCE_DIR`, so
+/// that no LLVM installation is needed. Returns the include directories of
+/// LLVM.
+fn build_vendored() -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if cfg!(feature = "force-static") || cfg!(feature = "dynamic-linking") {
+        return Err(
+            "the `vendored` feature cannot be enabled together with `force-static` or `dynamic-linking`"
+                .into(),
+        );
+    }
+
+    let variable = format!("TABLEGEN_{LLVM_MAJOR_VERSION}0_SOURCE_DIR");
+    println!("cargo:rerun-if-env-changed={variable}");
+    let source = PathBuf::from(env::var_os(&variable).ok_or_else(|| {
+        format!("{variable} must refer to the LLVM sources for the `vendored` feature")
+    })?);
+    // Both the monorepo and its `llvm` directory are accepted.
+    let source = if source.join("llvm/lib/TableGen").is_dir() {
+        source.join("llvm")
+    } else {
+        source
+    };
+
+    let version = llvm_source_version(&source)?;
+    if !version.starts_with(&format!("{LLVM_MAJOR_VERSION}.")) {
+        return Err(format!(
+            "failed to find correct version ({LLVM_MAJOR_VERSION}.x.x) of the LLVM sources (found {version})",
+        )
+        .into());
+    }
+
+    let config = Path::new(&env::var("OUT_DIR")?).join("include");
+    configure_llvm(&source, &config, &version)?;
+    let include_directories = vec![source.join("include"), config];
+
+    // Static libraries are linked in the order they are built, so each one is
+    // built before the libraries it depends on.
+    let mut build = llvm_build(&include_directories, true);
+    add_wrapper_sources(&mut build)?;
+    build.compile("CTableGen");
+
+    let mut build = llvm_build(&include_directories, true);
+    for library in ["TableGen", "Support", "Demangle"] {
+        build.files(source_files(&source.join("lib").join(library), "cpp")?);
+    }
+    build.warnings(false).compile("LLVMTableGen");
+
+    // The SIMD implementations of BLAKE3 are partly written in assembly, and
+    // the portable one is sufficient for LLVM's hashing.
+    let support = source.join("lib/Support");
+    let mut build = llvm_build(&include_directories, false);
+    build
+        .files(source_files(&support, "c")?)
         .files(
-            read_dir("cc/lib")?
-                .collect::<Result<Vec<_>, _>>()?
-                .into_iter()
-                .map(|entry| entry.path())
-                .filter(|path| path.is_file() && path.extension() == Some(OsStr::new("cpp"))),
+            ["blake3.c", "blake3_dispatch.c", "blake3_portable.c"]
+                .map(|name| support.join("BLAKE3").join(name)),
         )
-        .include("cc/include")
-        .include(llvm_config(false, "--includedir")?)
-        .flag(if cfg!(target_env = "msvc") {
-            "/WX-" // LLVM 22.1.7 headers trigger C4245/C4244 on MSVC; don't treat as errors
-        } else {
-            "-Werror"
-        })
-        .flag(if cfg!(target_env = "msvc") {
-            "/W4"
+        .define("BLAKE3_NO_SSE2", None)
+        .define("BLAKE3_NO_SSE41", None)
+        .define("BLAKE3_NO_AVX2", None)
+        .define("BLAKE3_NO_AVX512", None)
+        .define("BLAKE3_USE_NEON", "0")
+        .warnings(false)
+        .compile("LLVMSupportC");
+
+    // The system libraries that CMake links to LLVMSupport.
+    let libraries: &[&str] = match env::var("CARGO_CFG_TARGET_OS")?.as_str() {
+        "windows" => &[
+            "psapi", "shell32", "ole32", "uuid", "advapi32", "ws2_32", "ntdll",
+        ],
+        "macos" | "ios" => &[],
+        _ => &["pthread", "dl", "m"],
+    };
+    for library in libraries {
+        println!("cargo:rustc-link-lib={library}");
+    }
+
+    Ok(include_directories)
+}
+
+/// Returns a build with the definitions and flags that the CMake build of
+/// LLVM uses for its libraries. The wrapper is built with them as well, e.g.
+/// so that it does not require RTTI of LLVM classes.
+fn llvm_build(include_directories: &[PathBuf], cpp: bool) -> cc::Build {
+    let mut build = cc::Build::new();
+    build
+        .cpp(cpp)
+        .includes(include_directories)
+        .define("NDEBUG", None)
+        .define("__STDC_CONSTANT_MACROS", None)
+        .define("__STDC_FORMAT_MACROS", None)
+        .define("__STDC_LIMIT_MACROS", None);
+
+    if is_msvc() {
+        build
+            .define("_CRT_SECURE_NO_DEPRECATE", None)
+            .define("_CRT_NONSTDC_NO_DEPRECATE", None)
+            .define("_SCL_SECURE_NO_WARNINGS", None)
+            .define("UNICODE", None)
+            .define("_UNICODE", None);
+        if cpp {
+            build.std("c++20").flag("/GR-").flag("/Zc:__cplusplus");
+        }
+    } else {
+        build.define("_FILE_OFFSET_BITS", "64");
+        if env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("linux") {
+            build.define("_GNU_SOURCE", None);
+        }
+        if cpp {
+            build.std("c++20").flag("-fno-rtti");
+        }
+    }
+
+    build
+}
+
+/// Returns the version of the LLVM sources, which is set in
+/// `cmake/Modules/LLVMVersion.cmake` since LLVM 18 and in `CMakeLists.txt`
+/// before.
+fn llvm_source_version(source: &Path) -> Result<String, Box<dyn Error>> {
+    for file in ["cmake/Modules/LLVMVersion.cmake", "CMakeLists.txt"] {
+        let Ok(content) = read_to_string(source.join(file)) else {
+            continue;
+        };
+        let component = |name: &str| {
+            content.lines().find_map(|line| {
+                line.trim()
+                    .strip_prefix("set(")?
+                    .strip_prefix(name)?
+                    .strip_suffix(')')
+                    .map(str::trim)
+            })
+        };
+        if let (Some(major), Some(minor), Some(patch)) = (
+            component("LLVM_VERSION_MAJOR"),
+            component("LLVM_VERSION_MINOR"),
+            component("LLVM_VERSION_PATCH"),
+        ) {
+            return Ok(format!("{major}.{minor}.{patch}"));
+        }
+    }
+
+    Err(format!(
+        "failed to find the version of the LLVM sources in {}",
+        source.display()
+    )
+    .into())
+}
+
+/// Generates the headers that CMake configures in `include/llvm/Config` into
+/// the given include directory, with the settings of a minimal LLVM build for
+/// the target.
+fn configure_llvm(source: &Path, output: &Path, version: &str) -> Result<(), Box<dyn Error>> {
+    let target = env::var("TARGET")?;
+    let target_os = env::var("CARGO_CFG_TARGET_OS")?;
+    let shared_library_extension = match target_os.as_str() {
+        "windows" => ".dll",
+        "macos" | "ios" => ".dylib",
+        _ => ".so",
+    };
+    let mut components = version.split('.').map(String::from);
+
+    let mut variables = HashMap::from([
+        ("LLVM_VERSION_MAJOR", components.next().unwrap_or_default()),
+        ("LLVM_VERSION_MINOR", components.next().unwrap_or_default()),
+        ("LLVM_VERSION_PATCH", components.next().unwrap_or_default()),
+        ("PACKAGE_NAME", "LLVM".into()),
+        ("PACKAGE_VERSION", version.into()),
+        ("PACKAGE_STRING", format!("LLVM {version}")),
+        (
+            "BUG_REPORT_URL",
+            "https://github.com/llvm/llvm-project/issues/".into(),
+        ),
+        ("LLVM_DEFAULT_TARGET_TRIPLE", target.clone()),
+        ("LLVM_HOST_TRIPLE", target),
+        ("LTDL_SHLIB_EXT", shared_library_extension.into()),
+        ("LLVM_PLUGIN_EXT", shared_library_extension.into()),
+    ]);
+    let mut enabled = vec![
+        "LLVM_ENABLE_THREADS",
+        "LLVM_HAS_ATOMICS",
+        "LLVM_UNREACHABLE_OPTIMIZE",
+    ];
+    if env::var("CARGO_CFG_TARGET_FAMILY").as_deref() == Ok("unix") {
+        enabled.extend([
+            "LLVM_ON_UNIX",
+            "HAVE_SYSEXITS_H",
+            "HAVE_DLFCN_H",
+            "HAVE_DLOPEN",
+            "HAVE_DLADDR",
+            "HAVE_ERRNO_H",
+            "HAVE_FCNTL_H",
+            "HAVE_FUTIMENS",
+            "HAVE_FUTIMES",
+            "HAVE_GETPAGESIZE",
+            "HAVE_GETRUSAGE",
+            "HAVE_ISATTY",
+            "HAVE_LIBPTHREAD",
+            "HAVE_PTHREAD_H",
+            "HAVE_PTHREAD_MUTEX_LOCK",
+            "HAVE_PTHREAD_RWLOCK_INIT",
+            "HAVE_SETENV",
+            "HAVE_SIGALTSTACK",
+            "HAVE_SIGNAL_H",
+            "HAVE_STRERROR_R",
+            "HAVE_SYSCONF",
+            "HAVE_SYS_IOCTL_H",
+            "HAVE_SYS_MMAN_H",
+            "HAVE_SYS_RESOURCE_H",
+            "HAVE_SYS_STAT_H",
+            "HAVE_SYS_TIME_H",
+            "HAVE_TERMIOS_H",
+            "HAVE_UNISTD_H",
+        ]);
+    }
+    match target_os.as_str() {
+        "linux" => enabled.extend(["HAVE_LINK_H", "HAVE_STRUCT_STAT_ST_MTIM_TV_NSEC"]),
+        "macos" | "ios" => enabled.extend([
+            "HAVE_MACH_MACH_H",
+            "HAVE_MALLOC_MALLOC_H",
+            "HAVE_MALLOC_ZONE_STATISTICS",
+            "HAVE_PROC_PID_RUSAGE",
+            "HAVE_PTHREAD_GETNAME_NP",
+            "HAVE_PTHREAD_SETNAME_NP",
+            "HAVE_STRUCT_STAT_ST_MTIMESPEC_TV_NSEC",
+        ]),
+        _ => {}
+    }
+    variables.extend(enabled.into_iter().map(|name| (name, "1".into())));
+
+    let templates = source.join("include/llvm/Config");
+    let config = output.join("llvm/Config");
+    create_dir_all(&config)?;
+    // e.g. `config.h.cmake` or `Targets.def.in`
+    for path in source_files(&templates, "cmake")?
+        .into_iter()
+        .chain(source_files(&templates, "in")?)
+    {
+        if let Some(name) = path.file_stem() {
+            write(
+                config.join(name),
+                configure_file(&read_to_string(&path)?, &variables),
+            )?;
+        }
+    }
+
+    // The revision is only printed in version messages.
+    let support = output.join("llvm/Support");
+    create_dir_all(&support)?;
+    write(
+        support.join("VCSRevision.h"),
+        "#undef LLVM_REVISION\n#undef LLVM_REPOSITORY\n",
+    )?;
+
+    Ok(())
+}
+
+/// Configures a header template of LLVM like CMake's `configure_file`, where
+/// only the given variables are set.
+fn configure_file(template: &str, variables: &HashMap<&str, String>) -> String {
+    let enabled = |name: &str| {
+        variables
+            .get(name)
+            .is_some_and(|value| !value.is_empty() && value != "0")
+    };
+
+    let mut output = String::new();
+    for line in template.lines() {
+        if let Some(name) = line.strip_prefix("#cmakedefine01 ") {
+            let name = name.trim();
+            output.push_str(&format!("#define {name} {}", u8::from(enabled(name))));
+        } else if let Some(definition) = line.strip_prefix("#cmakedefine ") {
+            let name = definition.split_whitespace().next().unwrap_or_default();
+            if enabled(name) {
+                output.push_str(&format!("#define {}", substitute(definition, variables)));
+            } else {
+                output.push_str(&format!("/* #undef {name} */"));
+            }
         } else {
-            "-Wall"
-        })
-        .flag(if cfg!(target_env = "msvc") {
-            "" // /W4 already covers extras on MSVC
+            output.push_str(&substitute(line, variables));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Replaces the `${NAME}` and `@NAME@` references to CMake variables in a
+/// line, with an empty string for unset variables.
+fn substitute(line: &str, variables: &HashMap<&str, String>) -> String {
+    let is_name = |name: &str| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+
+    let mut output = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find(['$', '@']) {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = if let Some(reference) = rest.strip_prefix('@') {
+            reference.split_once('@')
         } else {
-            "-Wno-unused-parameter"
-        })
-        .std("c++20")
-        .compile("CTableGen");
+            rest.strip_prefix("${")
+                .and_then(|reference| reference.split_once('}'))
+        };
+        match reference {
+            Some((name, tail)) if is_name(name) => {
+                output.push_str(variables.get(name).map_or("", String::as_str));
+                rest = tail;
+            }
+            _ => {
+                output.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
 
-    Ok(())
+/// Returns true if the target uses the MSVC toolchain. Unlike
+/// `cfg!(target_env = "msvc")`, this refers to the target and not to the
+/// host building the build script.
+fn is_msvc() -> bool {
+    env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("msvc")
+}
+
+/// Translates the flags of `llvm-config --cxxflags` to `cl.exe` flags, and
+/// returns whether LLVM was built with the static CRT (`/MT`) if the flags
+/// select a CRT.
+///
+/// The CRT flag is removed, since the cc crate adds its own. Depending on how
+/// LLVM was built, the flags may be GCC-style, in which case the ones without
+/// a `cl.exe` equivalent are removed.
+fn translate_msvc_flags(flags: &str) -> (String, Option<bool>) {
+    let mut static_crt = None;
+    let flags = flags
+        .split_whitespace()
+        .filter_map(|flag| match flag.trim_start_matches(['/', '-']) {
+            "MT" | "MTd" => {
+                static_crt = Some(true);
+                None
+            }
+            "MD" | "MDd" => {
+                static_crt = Some(false);
+                None
+            }
+            "fno-exceptions" => Some("/EHs-c-"),
+            "fno-rtti" => Some("/GR-"),
+            // GCC-only options (e.g. `-fPIC`, `-Wall` or `-Wl,...`), unlike
+            // `cl.exe` warning levels like `-W4`.
+            option
+                if flag.starts_with('-')
+                    && (option.starts_with('f')
+                        || option.starts_with("pedantic")
+                        || option.starts_with("Wl,")
+                        || option.strip_prefix('W').is_some_and(|rest| {
+                            rest.starts_with(|c: char| c.is_ascii_lowercase())
+                        })) =>
+            {
+                None
+            }
+            _ => Some(flag),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    (flags, static_crt)
 }
 
 fn get_system_libcpp() -> Option<&'static str> {
-    if cfg!(target_env = "msvc") {
+    if is_msvc() {
         None
-    } else if cfg!(target_os = "macos") {
+    } else if env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("macos") {
         Some("c++")
     } else {
         Some("stdc++")
@@ -203,7 +652,17 @@ fn get_system_libcpp() -> Option<&'static str> {
 }
 
 fn resolve_link_mode() -> Result<bool, Box<dyn Error>> {
-    let available = llvm_config(true, "--libnames").is_ok();
+    if cfg!(feature = "dynamic-linking") {
+        if cfg!(feature = "force-static") {
+            return Err(
+                "the `force-static` and `dynamic-linking` features cannot be enabled together"
+                    .into(),
+            );
+        }
+        return Ok(false);
+    }
+
+    let available = llvm_config(true, &format!("--libnames {LLVM_COMPONENTS}")).is_ok();
     if cfg!(feature = "force-static") {
         if !available {
             return Err(
@@ -216,16 +675,52 @@ fn resolve_link_mode() -> Result<bool, Box<dyn Error>> {
     }
 }
 
+/// Runs `llvm-config` for the LLVM installation of the target.
+///
+/// When cross-compiling, the `llvm-config` of the target may not run on the
+/// host. If `TABLEGEN_HOST_PREFIX` is set, the `llvm-config` of that host
+/// installation of the same LLVM version is run instead, and the paths it
+/// reports are replaced with the ones of `TABLEGEN_<version>_PREFIX`.
 fn llvm_config(link_static: bool, argument: &str) -> Result<String, Box<dyn Error>> {
-    let prefix = env::var(format!("TABLEGEN_{}0_PREFIX", LLVM_MAJOR_VERSION))
-        .map(|path| Path::new(&path).join("bin"))
-        .unwrap_or_default();
-    let static_flag = if link_static { "--link-static " } else { "" };
+    let prefix = env::var_os(format!("TABLEGEN_{}0_PREFIX", LLVM_MAJOR_VERSION)).map(PathBuf::from);
+    let host_prefix = if is_cross_compiling() {
+        println!("cargo:rerun-if-env-changed=TABLEGEN_HOST_PREFIX");
+        env::var_os("TABLEGEN_HOST_PREFIX").map(PathBuf::from)
+    } else {
+        None
+    };
+    let link_flag = if link_static {
+        "--link-static "
+    } else if cfg!(feature = "dynamic-linking") {
+        "--link-shared "
+    } else {
+        ""
+    };
+    let output = run_llvm_config(
+        host_prefix.as_ref().or(prefix.as_ref()),
+        &format!("{link_flag}{argument}"),
+    )?;
+
+    match (host_prefix, prefix) {
+        (Some(host_prefix), Some(prefix)) => {
+            let host_prefix = run_llvm_config(Some(&host_prefix), "--prefix")?;
+            Ok(output.replace(&host_prefix, &prefix.to_string_lossy()))
+        }
+        _ => Ok(output),
+    }
+}
+
+fn run_llvm_config(prefix: Option<&PathBuf>, arguments: &str) -> Result<String, Box<dyn Error>> {
     let call = format!(
-        "{} {static_flag}{argument}",
-        prefix.join("llvm-config").display()
+        "{} {arguments}",
+        prefix
+            .map(|prefix| prefix.join("bin"))
+            .unwrap_or_default()
+            .join("llvm-config")
+            .display()
     );
 
+    // The command runs on the host, so `cfg!` is correct here.
     let output = if cfg!(target_os = "windows") {
         Command::new("cmd").args(["/C", &call]).output()?
     } else {
@@ -233,12 +728,40 @@ fn llvm_config(link_static: bool, argument: &str) -> Result<String, Box<dyn Erro
     };
 
     if !output.status.success() {
-        return Err(format!("llvm-config {static_flag}{argument} failed").into());
+        return Err(format!("llvm-config {arguments} failed").into());
     }
 
     Ok(str::from_utf8(&output.stdout)?.trim().to_string())
 }
 
+/// Returns true if the crate is compiled for another platform than the one
+/// running the build script.
+fn is_cross_compiling() -> bool {
+    env::var("TARGET").ok() != env::var("HOST").ok()
+}
+
+/// Returns the sysroot of the target for bindgen, which is the
+/// `TABLEGEN_SYSROOT` environment variable or, when cross-compiling, the
+/// sysroot of the C compiler for the target (e.g. `aarch64-linux-gnu-gcc`).
+fn target_sysroot() -> Option<PathBuf> {
+    println!("cargo:rerun-if-env-changed=TABLEGEN_SYSROOT");
+    if let Some(path) = env::var_os("TABLEGEN_SYSROOT") {
+        return Some(path.into());
+    }
+    if !is_cross_compiling() {
+        return None;
+    }
+
+    let output = cc::Build::new()
+        .get_compiler()
+        .to_command()
+        .arg("-print-sysroot")
+        .output()
+        .ok()?;
+    let sysroot = str::from_utf8(&output.stdout).ok()?.trim();
+    (output.status.success() && !sysroot.is_empty()).then(|| sysroot.into())
+}
+
 fn parse_library_name(name: &str) -> Result<&str, String> {
     if let Some(stem) = name.strip_prefix("lib") {
         return stem
@@ -51,17 +51,79 @@ typedef struct TableGenStringRef {
 
 typedef void (*TableGenStringCallback)(TableGenStringRef, void *);
 
+typedef enum {
+  TABLEGEN_PARSE_LOADING,
+  TABLEGEN_PARSE_PARSING,
+  TABLEGEN_PARSE_FINISHED,
+} TableGenParsePhase;
+
+typedef void (*TableGenProgressCallback)(TableGenParsePhase, size_t, size_t,
+                                         void *);
+
+/// Called with the name of a source buffer, its contents and the offset of a
+/// location in it.
+typedef void (*TableGenSourceBufferCallback)(TableGenStringRef,
+                                             TableGenStringRef, size_t,
+                                             void *);
+
 TableGenParserRef tableGenGet();
+TableGenParserRef tableGenClone(TableGenParserRef tg_ref);
+void tableGenAssign(TableGenParserRef tg_ref, TableGenParserRef other_ref);
 void tableGenFree(TableGenParserRef tg_ref);
 TableGenBool tableGenAddSource(TableGenParserRef tg_ref, const char *source);
+void tableGenAddSourceCopy(TableGenParserRef tg_ref, TableGenStringRef source,
+                           TableGenStringRef name);
 void tableGenAddSourceFile(TableGenParserRef tg_ref, TableGenStringRef source);
 void tableGenAddIncludeDirectory(TableGenParserRef tg_ref,
                                  TableGenStringRef include);
+void tableGenSetIncludeCache(TableGenParserRef tg_ref,
+                             TableGenIncludeCacheRef cache_ref);
+void tableGenAddPrelude(TableGenParserRef tg_ref, TableGenStringRef source);
+void tableGenDefineMacro(TableGenParserRef tg_ref, TableGenStringRef name);
+void tableGenSetWarnOnUnusedTemplateArgs(TableGenParserRef tg_ref,
+                                         TableGenBool warn);
+void tableGenSetMemoryMapFiles(TableGenParserRef tg_ref, TableGenBool map);
+void tableGenSetProgressCallback(TableGenParserRef tg_ref,
+                                 TableGenProgressCallback callback,
+                                 void *userData);
+void tableGenClearSources(TableGenParserRef tg_ref);
 
 /// NOTE: TableGen currently relies on global state within a given parser
 ///       invocation, so this function is not thread-safe.
 TableGenRecordKeeperRef tableGenParse(TableGenParserRef tg_ref);
 
+// Diagnostics reported while parsing
+size_t tableGenGetNumDiagnostics(TableGenParserRef tg_ref);
+TableGenDiagnosticRef tableGenGetDiagnostic(TableGenParserRef tg_ref,
+                                            size_t index);
+TableGenDiagKind tableGenDiagnosticGetKind(TableGenDiagnosticRef diag_ref);
+TableGenStringRef
+tableGenDiagnosticGetMessage(TableGenDiagnosticRef diag_ref);
+TableGenStringRef
+tableGenDiagnosticGetFilename(TableGenDiagnosticRef diag_ref);
+int tableGenDiagnosticGetLine(TableGenDiagnosticRef diag_ref);
+int tableGenDiagnosticGetColumn(TableGenDiagnosticRef diag_ref);
+TableGenStringRef
+tableGenDiagnosticGetLineContents(TableGenDiagnosticRef diag_ref);
+void tableGenDiagnosticPrint(TableGenDiagnosticRef diag_ref,
+                             TableGenStringCallback callback, void *userData);
+
+// Source buffers loaded while parsing
+size_t tableGenGetNumSourceBuffers(TableGenParserRef tg_ref);
+TableGenStringRef tableGenSourceBufferGetName(TableGenParserRef tg_ref,
+                                              size_t index);
+TableGenBool tableGenSourceBufferGetIncludeLoc(TableGenParserRef tg_ref,
+                                               size_t index, size_t *parent,
+                                               unsigned *line,
+                                               unsigned *column);
+
+// Include cache
+TableGenIncludeCacheRef tableGenIncludeCacheCreate();
+void tableGenIncludeCacheFree(TableGenIncludeCacheRef cache_ref);
+size_t tableGenIncludeCacheSize(TableGenIncludeCacheRef cache_ref);
+void tableGenIncludeCacheClear(TableGenIncludeCacheRef cache_ref);
+TableGenBool tableGenIncludeCacheCachesIncludes();
+
 // LLVM RecordKeeper
 void tableGenRecordKeeperFree(TableGenRecordKeeperRef rk_ref);
 TableGenRecordMapRef
@@ -77,6 +139,9 @@ tableGenRecordKeeperGetAllDerivedDefinitions(TableGenRecordKeeperRef rk_ref,
                                              TableGenStringRef className);
 TableGenRecordVectorRef tableGenRecordKeeperGetAllDerivedDefinitionsIfDefined(
     TableGenRecordKeeperRef rk_ref, TableGenStringRef className);
+TableGenRecordVectorRef
+tableGenRecordKeeperGetAllDerivedDefinitionsOf(TableGenRecordKeeperRef rk_ref,
+                                               TableGenRecordRef class_ref);
 
 TableGenRecordRef tableGenRecordVectorGet(TableGenRecordVectorRef vec_ref,
                                           size_t index);
@@ -91,6 +156,9 @@ tableGenRecordKeeperGetFirstDef(TableGenRecordKeeperRef rk_ref);
 
 void tableGenRecordKeeperGetNextClass(TableGenRecordKeeperIteratorRef *item);
 void tableGenRecordKeeperGetNextDef(TableGenRecordKeeperIteratorRef *item);
+size_t tableGenRecordKeeperIteratorNextChunk(
+    TableGenRecordKeeperIteratorRef item, TableGenStringRef *names,
+    TableGenRecordRef *records, size_t capacity);
 
 TableGenStringRef
 tableGenRecordKeeperItemGetName(TableGenRecordKeeperIteratorRef item);
@@ -105,6 +173,13 @@ TableGenRecordKeeperRef tableGenRecordGetRecords(TableGenRecordRef record_ref);
 TableGenStringRef tableGenRecordGetName(TableGenRecordRef record_ref);
 TableGenRecordValRef tableGenRecordGetValue(TableGenRecordRef record_ref,
                                             TableGenStringRef name);
+TableGenRecordValRef tableGenRecordGetValueByInit(TableGenRecordRef record_ref,
+                                                  TableGenTypedInitRef name);
+TableGenBool tableGenRecordGetValues(TableGenRecordRef record_ref,
+                                     const TableGenStringRef *names,
+                                     size_t count, TableGenRecordValRef *values,
+                                     TableGenTypedInitRef *name_inits,
+                                     TableGenTypedInitRef *inits);
 TableGenRecTyKind tableGenRecordGetFieldType(TableGenRecordRef record_ref,
                                              TableGenStringRef name);
 TableGenBool tableGenRecordIsAnonymous(TableGenRecordRef record_ref);
@@ -130,6 +205,14 @@ void tableGenRecordValTest(TableGenRecordValRef rv_ref);
 TableGenRecordValRef tableGenRecordGetFirstValue(TableGenRecordRef record_ref);
 TableGenRecordValRef tableGenRecordValNext(TableGenRecordRef record,
                                            TableGenRecordValRef current);
+size_t tableGenRecordGetValuesChunk(TableGenRecordRef record_ref,
+                                    size_t start, TableGenRecordValRef *values,
+                                    TableGenTypedInitRef *name_inits,
+                                    TableGenTypedInitRef *inits,
+                                    size_t capacity);
+TableGenRecordValRef tableGenRecordGetValuesArray(TableGenRecordRef record_ref,
+                                                  size_t *count,
+                                                  size_t *stride);
 void tableGenRecordValPrint(TableGenRecordValRef rv_ref,
                             TableGenStringCallback callback, void *userData);
 void tableGenRecordValDump(TableGenRecordValRef rv_ref);
@@ -152,6 +235,7 @@ TableGenRecTyKind tableGenListInitGetElementType(TableGenTypedInitRef ti);
 
 // LLVM DagType
 TableGenRecordRef tableGenDagRecordOperator(TableGenTypedInitRef rv_ref);
+TableGenTypedInitRef tableGenDagRecordOperatorInit(TableGenTypedInitRef rv_ref);
 TableGenTypedInitRef tableGenDagRecordGet(TableGenTypedInitRef rv_ref,
                                           size_t index);
 TableGenStringRef tableGenDagRecordArgName(TableGenTypedInitRef rv_ref,
@@ -177,9 +261,26 @@ TableGenBool tableGenPrintError(TableGenParserRef ref,
                                 TableGenDiagKind dk, TableGenStringRef message,
                                 TableGenStringCallback callback,
                                 void *userData);
+TableGenBool tableGenFindSourceBuffer(TableGenParserRef ref,
+                                      TableGenSourceLocationRef loc_ref,
+                                      TableGenStringRef *buffer,
+                                      size_t *offset);
+TableGenBool tableGenFindSourceBufferName(TableGenParserRef ref,
+                                          TableGenSourceLocationRef loc_ref,
+                                          TableGenStringRef *name);
 TableGenSourceLocationRef tableGenSourceLocationNull();
 TableGenSourceLocationRef
 tableGenSourceLocationClone(TableGenSourceLocationRef loc_ref);
+size_t tableGenSourceLocationSize(TableGenSourceLocationRef loc_ref);
+TableGenSourceLocationRef
+tableGenSourceLocationGet(TableGenSourceLocationRef loc_ref, size_t index);
+TableGenBool
+tableGenSourceLocationReadBuffer(TableGenSourceLocationRef loc_ref,
+                                 size_t index,
+                                 TableGenSourceBufferCallback callback,
+                                 void *userData);
+const char *tableGenSourceLocationGetPointer(TableGenSourceLocationRef loc_ref,
+                                             size_t index);
 
 // VarBitInit support (variable bit references in BitsInit fields)
 TableGenBool tableGenBitInitIsVarBit(TableGenTypedInitRef ti);
@@ -251,6 +352,7 @@ TableGenBool tableGenRecordRecTyIsSubClassOf(TableGenRecordRef record_ref,
 // RecordVal accessors
 TableGenBool tableGenRecordValIsTemplateArg(TableGenRecordValRef rv_ref);
 TableGenBool tableGenRecordValIsNonconcreteOK(TableGenRecordValRef rv_ref);
+TableGenBool tableGenRecordValIsUnset(TableGenRecordValRef rv_ref);
 
 // DagInit accessors
 size_t tableGenDagRecordGetArgNo(TableGenTypedInitRef dag_ref,
@@ -262,6 +364,68 @@ tableGenRecordKeeperGetInputFilename(TableGenRecordKeeperRef rk_ref);
 TableGenTypedInitRef
 tableGenRecordKeeperGetGlobal(TableGenRecordKeeperRef rk_ref,
                               TableGenStringRef name);
+void tableGenRecordKeeperPrint(TableGenRecordKeeperRef rk_ref,
+                               TableGenStringCallback callback,
+                               void *userData);
+void tableGenRecordKeeperDumpJson(TableGenRecordKeeperRef rk_ref,
+                                  TableGenStringCallback callback,
+                                  void *userData);
+
+// Class instantiation
+TableGenTypedInitRef tableGenBitInitGet(TableGenRecordKeeperRef rk_ref,
+                                       TableGenBool value);
+TableGenTypedInitRef tableGenIntInitGet(TableGenRecordKeeperRef rk_ref,
+                                       int64_t value);
+TableGenTypedInitRef tableGenStringInitGet(TableGenRecordKeeperRef rk_ref,
+                                          TableGenStringRef value);
+TableGenRecordRef tableGenRecordKeeperInstantiateClass(
+    TableGenRecordKeeperRef rk_ref, TableGenRecordRef class_ref,
+    TableGenStringRef name, const TableGenTypedInitRef *args, size_t num_args,
+    TableGenStringCallback callback, void *userData);
+
+// Record construction
+TableGenRecordKeeperRef tableGenRecordKeeperCreate();
+TableGenRecTyRef tableGenRecordKeeperParseType(TableGenRecordKeeperRef rk_ref,
+                                               TableGenStringRef type);
+TableGenTypedInitRef tableGenBitsInitGet(TableGenRecordKeeperRef rk_ref,
+                                        const TableGenBool *bits,
+                                        size_t num_bits);
+TableGenTypedInitRef tableGenCodeInitGet(TableGenRecordKeeperRef rk_ref,
+                                        TableGenStringRef value);
+TableGenTypedInitRef tableGenListInitGet(TableGenRecordKeeperRef rk_ref,
+                                        const TableGenTypedInitRef *elements,
+                                        size_t num_elements,
+                                        TableGenRecTyRef element_type);
+TableGenTypedInitRef tableGenDagInitGet(TableGenRecordKeeperRef rk_ref,
+                                       TableGenTypedInitRef operator_ref,
+                                       const TableGenTypedInitRef *args,
+                                       const TableGenStringRef *names,
+                                       size_t num_args);
+TableGenRecordRef tableGenRecordKeeperAddRecord(
+    TableGenRecordKeeperRef rk_ref, TableGenStringRef name,
+    TableGenBool is_class, const TableGenRecordRef *super_classes,
+    size_t num_super_classes, const TableGenStringRef *field_names,
+    const TableGenRecTyRef *field_types,
+    const TableGenTypedInitRef *field_values, size_t num_fields,
+    TableGenStringCallback callback, void *userData);
+
+// LLVM RecTy
+TableGenRecTyKind tableGenRecTyGetKind(TableGenRecTyRef ty_ref);
+TableGenRecTyRef tableGenRecordValGetRecTy(TableGenRecordValRef rv_ref);
+TableGenRecTyRef tableGenInitGetRecTy(TableGenTypedInitRef ti);
+TableGenRecTyRef tableGenListRecTyGetElementType(TableGenRecTyRef ty_ref);
+size_t tableGenBitsRecTyGetNumBits(TableGenRecTyRef ty_ref);
+size_t tableGenRecTyGetNumClasses(TableGenRecTyRef ty_ref);
+TableGenRecordRef tableGenRecTyGetClass(TableGenRecTyRef ty_ref, size_t index);
+TableGenBool tableGenRecTyIsConvertibleTo(TableGenRecTyRef ty_ref,
+                                          TableGenRecTyRef other_ref);
+TableGenBool tableGenRecTyIsA(TableGenRecTyRef ty_ref,
+                              TableGenRecTyRef other_ref);
+TableGenRecordKeeperRef tableGenRecTyGetRecordKeeper(TableGenRecTyRef ty_ref);
+void tableGenRecTyPrint(TableGenRecTyRef ty_ref,
+                        TableGenStringCallback callback, void *userData);
+TableGenRecTyRef tableGenRecTyResolveTypes(TableGenRecTyRef ty_ref,
+                                           TableGenRecTyRef other_ref);
 
 #ifdef __cplusplus
 }
@@ -31,10 +31,16 @@ typedef struct TableGenRecordValArray *TableGenRecordValArrayRef;
 
 typedef struct TableGenTypedInit *TableGenTypedInitRef;
 
+typedef struct TableGenRecTy *TableGenRecTyRef;
+
 typedef struct TableGenDagPair *TableGenDagPairRef;
 
 typedef struct TableGenSourceLocation *TableGenSourceLocationRef;
 
+typedef struct TableGenDiagnostic *TableGenDiagnosticRef;
+
+typedef struct TableGenIncludeCache *TableGenIncludeCacheRef;
+
 #ifdef __cplusplus
 }
 #endif
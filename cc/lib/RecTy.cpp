@@ -0,0 +1,78 @@
+#include "TableGen.hpp"
+#include "Types.h"
+
+using namespace llvm;
+using ctablegen::tableGenFromRecType;
+
+TableGenRecTyKind tableGenRecTyGetKind(TableGenRecTyRef ty_ref) {
+  return tableGenFromRecType(unwrap(ty_ref));
+}
+
+TableGenRecTyRef tableGenRecordValGetRecTy(TableGenRecordValRef rv_ref) {
+  return wrap(unwrap(rv_ref)->getType());
+}
+
+TableGenRecTyRef tableGenInitGetRecTy(TableGenTypedInitRef ti) {
+  if (!ti)
+    return nullptr;
+  auto typed_init = dyn_cast<TypedInit>(unwrap(ti));
+  if (!typed_init)
+    return nullptr;
+  return wrap(typed_init->getType());
+}
+
+TableGenRecTyRef tableGenListRecTyGetElementType(TableGenRecTyRef ty_ref) {
+  auto *list_ty = dyn_cast<ListRecTy>(unwrap(ty_ref));
+  if (!list_ty)
+    return nullptr;
+  return wrap(list_ty->getElementType());
+}
+
+size_t tableGenBitsRecTyGetNumBits(TableGenRecTyRef ty_ref) {
+  auto *bits_ty = dyn_cast<BitsRecTy>(unwrap(ty_ref));
+  if (!bits_ty)
+    return 0;
+  return bits_ty->getNumBits();
+}
+
+size_t tableGenRecTyGetNumClasses(TableGenRecTyRef ty_ref) {
+  auto *record_ty = dyn_cast<RecordRecTy>(unwrap(ty_ref));
+  if (!record_ty)
+    return 0;
+  return record_ty->getClasses().size();
+}
+
+TableGenRecordRef tableGenRecTyGetClass(TableGenRecTyRef ty_ref, size_t index) {
+  auto *record_ty = dyn_cast<RecordRecTy>(unwrap(ty_ref));
+  if (!record_ty)
+    return nullptr;
+  auto classes = record_ty->getClasses();
+  if (index >= classes.size())
+    return nullptr;
+  return wrap(const_cast<Record *>(classes[index]));
+}
+
+TableGenBool tableGenRecTyIsConvertibleTo(TableGenRecTyRef ty_ref,
+                                          TableGenRecTyRef other_ref) {
+  return unwrap(ty_ref)->typeIsConvertibleTo(unwrap(other_ref));
+}
+
+TableGenBool tableGenRecTyIsA(TableGenRecTyRef ty_ref,
+                              TableGenRecTyRef other_ref) {
+  return unwrap(ty_ref)->typeIsA(unwrap(other_ref));
+}
+
+TableGenRecordKeeperRef tableGenRecTyGetRecordKeeper(TableGenRecTyRef ty_ref) {
+  return wrap(&unwrap(ty_ref)->getRecordKeeper());
+}
+
+void tableGenRecTyPrint(TableGenRecTyRef ty_ref,
+                        TableGenStringCallback callback, void *userData) {
+  ctablegen::CallbackOstream stream(callback, userData);
+  stream << unwrap(ty_ref)->getAsString();
+}
+
+TableGenRecTyRef tableGenRecTyResolveTypes(TableGenRecTyRef ty_ref,
+                                           TableGenRecTyRef other_ref) {
+  return wrap(resolveTypes(unwrap(ty_ref), unwrap(other_ref)));
+}
@@ -29,6 +29,33 @@ TableGenRecordValRef tableGenRecordGetValue(TableGenRecordRef record_ref,
   return wrap(unwrap(record_ref)->getValue(StringRef(name.data, name.len)));
 }
 
+TableGenRecordValRef tableGenRecordGetValueByInit(TableGenRecordRef record_ref,
+                                                  TableGenTypedInitRef name) {
+  return wrap(unwrap(record_ref)->getValue(unwrap(name)));
+}
+
+TableGenBool tableGenRecordGetValues(TableGenRecordRef record_ref,
+                                     const TableGenStringRef *names,
+                                     size_t count, TableGenRecordValRef *values,
+                                     TableGenTypedInitRef *name_inits,
+                                     TableGenTypedInitRef *inits) {
+  auto *record = unwrap(record_ref);
+  bool found = true;
+  for (size_t i = 0; i < count; ++i) {
+    auto *value = record->getValue(StringRef(names[i].data, names[i].len));
+    values[i] = wrap(value);
+    if (!value) {
+      name_inits[i] = nullptr;
+      inits[i] = nullptr;
+      found = false;
+      continue;
+    }
+    name_inits[i] = wrap(dyn_cast<TypedInit>(value->getNameInit()));
+    inits[i] = wrap(dyn_cast<TypedInit>(value->getValue()));
+  }
+  return found;
+}
+
 TableGenRecTyKind tableGenRecordGetFieldType(TableGenRecordRef record_ref,
                                              TableGenStringRef name) {
   auto value = unwrap(record_ref)->getValue(StringRef(name.data, name.len));
@@ -54,6 +81,31 @@ TableGenRecordValRef tableGenRecordValNext(TableGenRecordRef record,
   return wrap(next);
 }
 
+size_t tableGenRecordGetValuesChunk(TableGenRecordRef record_ref,
+                                    size_t start, TableGenRecordValRef *values,
+                                    TableGenTypedInitRef *name_inits,
+                                    TableGenTypedInitRef *inits,
+                                    size_t capacity) {
+  auto all = unwrap(record_ref)->getValues();
+  size_t count = 0;
+  for (; count < capacity && start + count < all.size(); ++count) {
+    auto &value = all[start + count];
+    values[count] = wrap(&value);
+    name_inits[count] = wrap(dyn_cast<TypedInit>(value.getNameInit()));
+    inits[count] = wrap(dyn_cast<TypedInit>(value.getValue()));
+  }
+  return count;
+}
+
+TableGenRecordValRef tableGenRecordGetValuesArray(TableGenRecordRef record_ref,
+                                                  size_t *count,
+                                                  size_t *stride) {
+  auto values = unwrap(record_ref)->getValues();
+  *count = values.size();
+  *stride = sizeof(RecordVal);
+  return wrap(values.data());
+}
+
 TableGenBool tableGenRecordIsAnonymous(TableGenRecordRef record_ref) {
   return unwrap(record_ref)->isAnonymous();
 }
@@ -64,8 +116,7 @@ TableGenBool tableGenRecordIsSubclassOf(TableGenRecordRef record_ref,
 }
 
 TableGenSourceLocationRef tableGenRecordGetLoc(TableGenRecordRef record_ref) {
-  auto locs = unwrap(record_ref)->getLoc();
-  return wrap(new std::vector<SMLoc>(locs.begin(), locs.end()));
+  return wrap(ctablegen::createSourceLocation(unwrap(record_ref)->getLoc()));
 }
 
 void tableGenRecordPrint(TableGenRecordRef record_ref,
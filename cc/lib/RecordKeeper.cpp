@@ -10,10 +10,36 @@
 
 #include "TableGen.hpp"
 #include "Types.h"
+#include <llvm/Config/llvm-config.h>
 
 using namespace llvm;
 using ctablegen::RecordMap;
 
+/// Returns the defs deriving from the given class in name order, or no defs
+/// if the class is not defined.
+///
+/// Unlike RecordKeeper::getAllDerivedDefinitions, the result is not cached,
+/// so that it includes defs added by tableGenRecordKeeperInstantiateClass.
+ctablegen::RecordVector
+ctablegen::getAllDerivedDefinitions(const RecordKeeper &records,
+                                    StringRef className) {
+  auto *cls = records.getClass(className);
+  if (!cls)
+    return {};
+  return getAllDerivedDefinitions(records, cls);
+}
+
+/// Returns the defs deriving from the given class in name order.
+ctablegen::RecordVector
+ctablegen::getAllDerivedDefinitions(const RecordKeeper &records,
+                                    const Record *cls) {
+  RecordVector defs;
+  for (const auto &[_, def] : records.getDefs())
+    if (def->isSubClassOf(cls))
+      defs.push_back(def.get());
+  return defs;
+}
+
 void tableGenRecordKeeperFree(TableGenRecordKeeperRef rk_ref) {
   delete unwrap(rk_ref);
 }
@@ -50,6 +76,19 @@ void tableGenRecordKeeperGetNextDef(TableGenRecordKeeperIteratorRef *item) {
   }
 }
 
+size_t tableGenRecordKeeperIteratorNextChunk(
+    TableGenRecordKeeperIteratorRef item, TableGenStringRef *names,
+    TableGenRecordRef *records, size_t capacity) {
+  auto *iter = unwrap(item);
+  size_t count = 0;
+  for (; count < capacity && iter->it != iter->end; ++count, ++iter->it) {
+    auto &name = iter->it->first;
+    names[count] = TableGenStringRef{.data = name.data(), .len = name.size()};
+    records[count] = wrap(iter->it->second.get());
+  }
+  return count;
+}
+
 void tableGenRecordKeeperIteratorFree(TableGenRecordKeeperIteratorRef item) {
   if (item)
     delete unwrap(item);
@@ -95,9 +134,13 @@ TableGenRecordRef tableGenRecordKeeperGetDef(TableGenRecordKeeperRef rk_ref,
 TableGenRecordVectorRef
 tableGenRecordKeeperGetAllDerivedDefinitions(TableGenRecordKeeperRef rk_ref,
                                              TableGenStringRef className) {
-  return wrap(
-      new ctablegen::RecordVector(unwrap(rk_ref)->getAllDerivedDefinitions(
-          StringRef(className.data, className.len))));
+  // LLVM reports undefined classes with PrintFatalError, which exits the
+  // process.
+  auto name = StringRef(className.data, className.len);
+  if (!unwrap(rk_ref)->getClass(name))
+    return nullptr;
+  return wrap(new ctablegen::RecordVector(
+      ctablegen::getAllDerivedDefinitions(*unwrap(rk_ref), name)));
 }
 
 TableGenRecordRef tableGenRecordVectorGet(TableGenRecordVectorRef vec_ref,
@@ -118,9 +161,15 @@ void tableGenRecordVectorFree(TableGenRecordVectorRef vec_ref) {
 
 TableGenRecordVectorRef tableGenRecordKeeperGetAllDerivedDefinitionsIfDefined(
     TableGenRecordKeeperRef rk_ref, TableGenStringRef className) {
+  return wrap(new ctablegen::RecordVector(ctablegen::getAllDerivedDefinitions(
+      *unwrap(rk_ref), StringRef(className.data, className.len))));
+}
+
+TableGenRecordVectorRef
+tableGenRecordKeeperGetAllDerivedDefinitionsOf(TableGenRecordKeeperRef rk_ref,
+                                               TableGenRecordRef class_ref) {
   return wrap(new ctablegen::RecordVector(
-      unwrap(rk_ref)->getAllDerivedDefinitionsIfDefined(
-          StringRef(className.data, className.len))));
+      ctablegen::getAllDerivedDefinitions(*unwrap(rk_ref), unwrap(class_ref))));
 }
 
 TableGenStringRef
@@ -137,3 +186,448 @@ tableGenRecordKeeperGetGlobal(TableGenRecordKeeperRef rk_ref,
     return nullptr;
   return wrap(dyn_cast<TypedInit>(init));
 }
+
+void tableGenRecordKeeperPrint(TableGenRecordKeeperRef rk_ref,
+                               TableGenStringCallback callback,
+                               void *userData) {
+  ctablegen::CallbackOstream stream(callback, userData);
+  stream << *unwrap(rk_ref);
+}
+
+void tableGenRecordKeeperDumpJson(TableGenRecordKeeperRef rk_ref,
+                                  TableGenStringCallback callback,
+                                  void *userData) {
+  ctablegen::CallbackOstream stream(callback, userData);
+  EmitJSON(*unwrap(rk_ref), stream);
+}
+
+TableGenTypedInitRef tableGenBitInitGet(TableGenRecordKeeperRef rk_ref,
+                                       TableGenBool value) {
+  return wrap(BitInit::get(*unwrap(rk_ref), value));
+}
+
+TableGenTypedInitRef tableGenIntInitGet(TableGenRecordKeeperRef rk_ref,
+                                       int64_t value) {
+  return wrap(IntInit::get(*unwrap(rk_ref), value));
+}
+
+TableGenTypedInitRef tableGenStringInitGet(TableGenRecordKeeperRef rk_ref,
+                                          TableGenStringRef value) {
+  return wrap(
+      StringInit::get(*unwrap(rk_ref), StringRef(value.data, value.len)));
+}
+
+/// Checks the `!cast`s from strings to records and the divisions in `init`,
+/// which TableGen folds with PrintFatalError if the record is undefined or has
+/// the wrong type, or if the divisor is zero. This exits the process, so they
+/// are reported as errors before `def` is resolved with `resolver`.
+///
+/// Follows UnOpInit::Fold and BinOpInit::Fold. Fields that `init` refers to
+/// are checked as well when resolving the final def, as RecordResolver
+/// resolves them recursively.
+static std::string checkFolds(const Init *init, Resolver &resolver,
+                              Record &def,
+                              SmallPtrSetImpl<const Init *> &visited) {
+  auto check = [&](const Init *child) {
+    return child ? checkFolds(child, resolver, def, visited) : std::string();
+  };
+  std::string error;
+  if (auto *op = dyn_cast<OpInit>(init)) {
+    for (unsigned i = 0; error.empty() && i < op->getNumOperands(); ++i)
+      error = check(op->getOperand(i));
+  } else if (auto *list = dyn_cast<ListInit>(init)) {
+    for (unsigned i = 0; error.empty() && i < list->size(); ++i)
+      error = check(list->getElement(i));
+  } else if (auto *dag = dyn_cast<DagInit>(init)) {
+    error = check(dag->getOperator());
+    for (unsigned i = 0; error.empty() && i < dag->getNumArgs(); ++i)
+      error = check(dag->getArg(i));
+  } else if (auto *cond = dyn_cast<CondOpInit>(init)) {
+    for (unsigned i = 0; error.empty() && i < cond->getNumConds(); ++i) {
+      error = check(cond->getCond(i));
+      if (error.empty())
+        error = check(cond->getVal(i));
+    }
+  } else if (auto *bits = dyn_cast<BitsInit>(init)) {
+    for (unsigned i = 0; error.empty() && i < bits->getNumBits(); ++i)
+      error = check(bits->getBit(i));
+  } else if (auto *bit = dyn_cast<VarBitInit>(init)) {
+    error = check(bit->getBitVar());
+  } else if (auto *field = dyn_cast<FieldInit>(init)) {
+    error = check(field->getRecord());
+  } else if (auto *var = dyn_cast<VarInit>(init)) {
+    auto *value = def.getValue(var->getNameInit());
+    if (resolver.isFinal() && value && visited.insert(var).second)
+      error = check(value->getValue());
+  }
+  if (!error.empty())
+    return error;
+
+  if (auto *binOp = dyn_cast<BinOpInit>(init)) {
+    if (binOp->getOpcode() != BinOpInit::DIV)
+      return {};
+    auto *divisor = dyn_cast_or_null<IntInit>(
+        binOp->getRHS()->resolveReferences(resolver)->convertInitializerTo(
+            IntRecTy::get(def.getRecords())));
+    if (divisor && !divisor->getValue())
+      return "Illegal operation: division by zero";
+    return {};
+  }
+
+  auto *cast = dyn_cast<UnOpInit>(init);
+  if (!cast || cast->getOpcode() != UnOpInit::CAST ||
+      !isa<RecordRecTy>(cast->getType()))
+    return {};
+  auto *name =
+      dyn_cast<StringInit>(cast->getOperand()->resolveReferences(resolver));
+  if (!name)
+    return {};
+  auto *record = def.getRecords().getDef(name->getValue());
+  if (!record && name->getValue() == def.getName()) {
+    // Self-references are only resolved in the final def.
+    if (!resolver.isFinal())
+      return {};
+    record = &def;
+  }
+  if (!record) {
+    if (!resolver.isFinal())
+      return {};
+    return (Twine("undefined reference to record: '") + name->getValue() +
+            "'")
+        .str();
+  }
+  if (!record->getType()->typeIsA(cast->getType()))
+    return (Twine("expected type '") + cast->getType()->getAsString() +
+            "', got '" + record->getType()->getAsString() +
+            "' in: " + cast->getAsString())
+        .str();
+  return {};
+}
+
+/// Checks the folds in the values and assertions of `def`, see checkFolds.
+static std::string checkRecordFolds(Record &def, Resolver &resolver) {
+  SmallPtrSet<const Init *, 8> visited;
+  for (const RecordVal &value : def.getValues()) {
+    auto error = checkFolds(value.getValue(), resolver, def, visited);
+    if (!error.empty())
+      return error;
+  }
+  for (const auto &assertion : def.getAssertions())
+    for (auto *init : {assertion.Condition, assertion.Message}) {
+      auto error = checkFolds(init, resolver, def, visited);
+      if (!error.empty())
+        return error;
+    }
+  return {};
+}
+
+TableGenRecordRef tableGenRecordKeeperInstantiateClass(
+    TableGenRecordKeeperRef rk_ref, TableGenRecordRef class_ref,
+    TableGenStringRef name, const TableGenTypedInitRef *args, size_t num_args,
+    TableGenStringCallback callback, void *userData) {
+  auto &records = *unwrap(rk_ref);
+  auto *cls = unwrap(class_ref);
+  auto defName = StringRef(name.data, name.len);
+  auto error = [&](const Twine &message) -> TableGenRecordRef {
+    ctablegen::CallbackOstream stream(callback, userData);
+    stream << message;
+    return nullptr;
+  };
+
+  if (!cls->isClass())
+    return error("'" + cls->getName() + "' is not a class");
+  if (defName.empty())
+    return error("def name must not be empty");
+  if (records.getDef(defName))
+    return error("def '" + defName + "' already defined");
+  auto templateArgs = cls->getTemplateArgs();
+  if (num_args > templateArgs.size())
+    return error("too many template arguments for class '" + cls->getName() +
+                 "'");
+
+  // Follows TGParser::AddSubClass and TGParser::addDefOne.
+  auto def = std::make_unique<Record>(defName, cls->getLoc(), records);
+  for (const RecordVal &value : cls->getValues())
+    if (!value.isTemplateArg())
+      def->addValue(value);
+
+  MapResolver resolver(def.get());
+  for (size_t i = 0; i < templateArgs.size(); ++i) {
+    const RecordVal *arg = cls->getValue(templateArgs[i]);
+    auto *value = arg->getValue();
+    if (i < num_args) {
+      // Values of other record keepers would refer to freed records once
+      // their keeper is dropped.
+      if (!args[i] || unwrap(args[i])->getRecordKeeper() != &records)
+        return error("value of template argument '" + arg->getName() +
+                     "' does not belong to this record keeper");
+      value = unwrap(args[i])->convertInitializerTo(arg->getType());
+      if (!value)
+        return error("value of template argument '" + arg->getName() +
+                     "' is not of type '" + arg->getType()->getAsString() +
+                     "'");
+    } else if (!value->isComplete()) {
+      return error("value not specified for template argument '" +
+                   arg->getName() + "'");
+    }
+    resolver.set(templateArgs[i], value);
+  }
+
+#if LLVM_VERSION_MAJOR >= 21
+  def->addDirectSuperClass(cls, SMRange(cls->getLoc().front(),
+                                        cls->getLoc().front()));
+#else
+  for (const auto &[super, range] : cls->getSuperClasses())
+    def->addSuperClass(super, range);
+  def->addSuperClass(cls,
+                     SMRange(cls->getLoc().front(), cls->getLoc().front()));
+#endif
+  def->appendAssertions(cls);
+
+  auto foldError = checkRecordFolds(*def, resolver);
+  if (!foldError.empty())
+    return error(foldError);
+  def->resolveReferences(resolver);
+  RecordResolver finalResolver(*def);
+  finalResolver.setFinal(true);
+  foldError = checkRecordFolds(*def, finalResolver);
+  if (!foldError.empty())
+    return error(foldError);
+  def->resolveReferences();
+
+  for (const RecordVal &value : def->getValues())
+    if (!value.isNonconcreteOK() && !value.getValue()->isConcrete())
+      return error("initializer of '" + value.getName() + "' in '" + defName +
+                   "' could not be fully resolved: " +
+                   value.getValue()->getAsString());
+
+  // Follows Record::checkRecordAssertions, which prints failed assertions to
+  // stderr instead of reporting them.
+  for (const auto &assertion : def->getAssertions()) {
+    auto *condition = dyn_cast_or_null<IntInit>(
+        assertion.Condition->resolveReferences(finalResolver)
+            ->convertInitializerTo(IntRecTy::get(records)));
+    if (!condition)
+      return error("assert condition must of type bit, bits, or int.");
+    if (!condition->getValue()) {
+      auto *message = dyn_cast<StringInit>(
+          assertion.Message->resolveReferences(finalResolver));
+      return error("assertion failed: " +
+                   (message ? message->getValue()
+                            : "(assert message is not a string)"));
+    }
+  }
+
+  records.addDef(std::move(def));
+  return wrap(records.getDef(defName));
+}
+
+TableGenRecordKeeperRef tableGenRecordKeeperCreate() {
+  return wrap(new RecordKeeper());
+}
+
+/// Parses a type in TableGen syntax from the front of `text`, e.g.
+/// `list<bits<8>>`, or returns null if it is not a valid type.
+///
+/// Follows TGParser::ParseType.
+static const RecTy *parseType(RecordKeeper &records, StringRef &text) {
+  text = text.ltrim();
+  auto name = text.take_while([](char c) { return isAlnum(c) || c == '_'; });
+  text = text.drop_front(name.size()).ltrim();
+  auto element = [&]() -> const RecTy * {
+    if (!text.consume_front("<"))
+      return nullptr;
+    auto *type = parseType(records, text);
+    text = text.ltrim();
+    return type && text.consume_front(">") ? type : nullptr;
+  };
+
+  if (name == "bit")
+    return BitRecTy::get(records);
+  if (name == "int")
+    return IntRecTy::get(records);
+  if (name == "string" || name == "code")
+    return StringRecTy::get(records);
+  if (name == "dag")
+    return DagRecTy::get(records);
+  if (name == "bits") {
+    unsigned width;
+    if (!text.consume_front("<") || text.consumeInteger(10, width))
+      return nullptr;
+    text = text.ltrim();
+    return text.consume_front(">") ? BitsRecTy::get(records, width) : nullptr;
+  }
+  if (name == "list") {
+    auto *type = element();
+    return type ? ListRecTy::get(const_cast<RecTy *>(type)) : nullptr;
+  }
+  auto *cls = records.getClass(name);
+  return cls ? RecordRecTy::get(cls) : nullptr;
+}
+
+TableGenRecTyRef tableGenRecordKeeperParseType(TableGenRecordKeeperRef rk_ref,
+                                               TableGenStringRef type) {
+  auto text = StringRef(type.data, type.len);
+  auto *result = parseType(*unwrap(rk_ref), text);
+  return result && text.trim().empty() ? wrap(result) : nullptr;
+}
+
+TableGenTypedInitRef tableGenBitsInitGet(TableGenRecordKeeperRef rk_ref,
+                                        const TableGenBool *bits,
+                                        size_t num_bits) {
+  auto &records = *unwrap(rk_ref);
+  std::vector<Init *> inits;
+  for (size_t i = 0; i < num_bits; ++i)
+    inits.push_back(const_cast<BitInit *>(BitInit::get(records, bits[i])));
+  return wrap(BitsInit::get(records, inits));
+}
+
+TableGenTypedInitRef tableGenCodeInitGet(TableGenRecordKeeperRef rk_ref,
+                                        TableGenStringRef value) {
+  return wrap(StringInit::get(*unwrap(rk_ref),
+                              StringRef(value.data, value.len),
+                              StringInit::SF_Code));
+}
+
+/// Returns the init of a value, where null stands for an unset value (`?`).
+static Init *valueInit(RecordKeeper &records, TableGenTypedInitRef value) {
+  if (!value)
+    return const_cast<UnsetInit *>(UnsetInit::get(records));
+  return unwrap(value);
+}
+
+TableGenTypedInitRef tableGenListInitGet(TableGenRecordKeeperRef rk_ref,
+                                        const TableGenTypedInitRef *elements,
+                                        size_t num_elements,
+                                        TableGenRecTyRef element_type) {
+  auto &records = *unwrap(rk_ref);
+  auto *type = unwrap(element_type);
+  std::vector<Init *> inits;
+  for (size_t i = 0; i < num_elements; ++i) {
+    auto *element = valueInit(records, elements[i])->convertInitializerTo(type);
+    if (!element)
+      return nullptr;
+    inits.push_back(const_cast<Init *>(element));
+  }
+  return wrap(ListInit::get(inits, type));
+}
+
+TableGenTypedInitRef tableGenDagInitGet(TableGenRecordKeeperRef rk_ref,
+                                       TableGenTypedInitRef operator_ref,
+                                       const TableGenTypedInitRef *args,
+                                       const TableGenStringRef *names,
+                                       size_t num_args) {
+  auto &records = *unwrap(rk_ref);
+  std::vector<Init *> inits;
+  std::vector<StringInit *> argNames;
+  for (size_t i = 0; i < num_args; ++i) {
+    inits.push_back(valueInit(records, args[i]));
+    argNames.push_back(
+        names[i].data ? const_cast<StringInit *>(StringInit::get(
+                            records, StringRef(names[i].data, names[i].len)))
+                      : nullptr);
+  }
+  return wrap(DagInit::get(unwrap(operator_ref), nullptr, inits, argNames));
+}
+
+/// Creates a class or def record. Since LLVM 17, records are created with a
+/// kind instead of a class flag.
+template <typename R = Record>
+static std::unique_ptr<R> makeRecord(StringRef name, RecordKeeper &records,
+                                     bool isClass) {
+  // The records are not declared in a source file.
+  SMLoc loc;
+  if constexpr (std::is_constructible_v<R, StringRef, ArrayRef<SMLoc>,
+                                        RecordKeeper &, bool>)
+    return std::make_unique<R>(name, loc, records, isClass);
+  else
+    return std::make_unique<R>(name, loc, records,
+                               isClass ? R::RK_Class : R::RK_Def);
+}
+
+TableGenRecordRef tableGenRecordKeeperAddRecord(
+    TableGenRecordKeeperRef rk_ref, TableGenStringRef name,
+    TableGenBool is_class, const TableGenRecordRef *super_classes,
+    size_t num_super_classes, const TableGenStringRef *field_names,
+    const TableGenRecTyRef *field_types,
+    const TableGenTypedInitRef *field_values, size_t num_fields,
+    TableGenStringCallback callback, void *userData) {
+  auto &records = *unwrap(rk_ref);
+  auto recordName = StringRef(name.data, name.len);
+  auto error = [&](const Twine &message) -> TableGenRecordRef {
+    ctablegen::CallbackOstream stream(callback, userData);
+    stream << message;
+    return nullptr;
+  };
+
+  if (recordName.empty())
+    return error("record name must not be empty");
+  if (records.getClass(recordName) || records.getDef(recordName))
+    return error("record '" + recordName + "' already defined");
+
+  // Follows TGParser::AddSubClass and TGParser::addDefOne.
+  auto record = makeRecord(recordName, records, is_class);
+  for (size_t i = 0; i < num_super_classes; ++i) {
+    auto *cls = unwrap(super_classes[i]);
+    if (!cls->isClass() || &cls->getRecords() != &records)
+      return error("'" + cls->getName() + "' is not a class");
+    if (!cls->getTemplateArgs().empty())
+      return error("class '" + cls->getName() + "' has template arguments");
+    if (record->isSubClassOf(cls))
+      return error("already subclass of '" + cls->getName() + "'");
+    for (const RecordVal &value : cls->getValues())
+      if (!record->getValue(value.getNameInit()))
+        record->addValue(value);
+#if LLVM_VERSION_MAJOR >= 21
+    record->addDirectSuperClass(cls, SMRange(SMLoc(), SMLoc()));
+#else
+    for (const auto &[super, range] : cls->getSuperClasses())
+      if (!record->isSubClassOf(super))
+        record->addSuperClass(super, range);
+    record->addSuperClass(cls, SMRange(SMLoc(), SMLoc()));
+#endif
+    record->appendAssertions(cls);
+  }
+
+  for (size_t i = 0; i < num_fields; ++i) {
+    auto fieldName = StringRef(field_names[i].data, field_names[i].len);
+    auto *value = valueInit(records, field_values[i]);
+    if (!record->getValue(fieldName)) {
+      const RecTy *type = field_types[i] ? unwrap(field_types[i]) : nullptr;
+      if (!type) {
+        auto *typed = dyn_cast<TypedInit>(value);
+        if (!typed)
+          return error("type of field '" + fieldName + "' is not known");
+        type = typed->getType();
+      }
+      record->addValue(RecordVal(
+          const_cast<StringInit *>(StringInit::get(records, fieldName)),
+          const_cast<RecTy *>(type), RecordVal::FK_Normal));
+    }
+    auto *field = record->getValue(fieldName);
+    if (field->setValue(value))
+      return error("field '" + fieldName + "' of type '" +
+                   field->getType()->getAsString() +
+                   "' is incompatible with value '" + value->getAsString() +
+                   "'");
+  }
+
+  if (is_class) {
+    records.addClass(std::move(record));
+    return wrap(records.getClass(recordName));
+  }
+
+  RecordResolver resolver(*record);
+  resolver.setFinal(true);
+  auto foldError = checkRecordFolds(*record, resolver);
+  if (!foldError.empty())
+    return error(foldError);
+  record->resolveReferences();
+  for (const RecordVal &value : record->getValues())
+    if (!value.isNonconcreteOK() && !value.getValue()->isConcrete())
+      return error("initializer of '" + value.getName() + "' in '" +
+                   recordName + "' could not be fully resolved: " +
+                   value.getValue()->getAsString());
+  records.addDef(std::move(record));
+  return wrap(records.getDef(recordName));
+}
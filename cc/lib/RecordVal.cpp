@@ -65,8 +65,7 @@ void tableGenRecordValDump(TableGenRecordValRef rv_ref) {
 }
 
 TableGenSourceLocationRef tableGenRecordValGetLoc(TableGenRecordValRef rv_ref) {
-  auto loc = unwrap(rv_ref)->getLoc();
-  return wrap(new std::vector<SMLoc>(1, loc));
+  return wrap(ctablegen::createSourceLocation(unwrap(rv_ref)->getLoc()));
 }
 
 size_t tableGenRecordValGetBitsWidth(TableGenRecordValRef rv_ref) {
@@ -91,3 +90,16 @@ TableGenBool tableGenRecordValIsTemplateArg(TableGenRecordValRef rv_ref) {
 TableGenBool tableGenRecordValIsNonconcreteOK(TableGenRecordValRef rv_ref) {
   return unwrap(rv_ref)->isNonconcreteOK();
 }
+
+/// Returns true if the value is `?`. Bits fields store `?` as bits that are
+/// all unset.
+TableGenBool tableGenRecordValIsUnset(TableGenRecordValRef rv_ref) {
+  auto *value = unwrap(rv_ref)->getValue();
+  if (auto *bits = dyn_cast<BitsInit>(value)) {
+    for (unsigned i = 0, e = bits->getNumBits(); i != e; ++i)
+      if (!isa<UnsetInit>(bits->getBit(i)))
+        return false;
+    return bits->getNumBits() > 0;
+  }
+  return isa<UnsetInit>(value);
+}
@@ -11,24 +11,354 @@
 #include "TableGen.hpp"
 #include "TableGen.h"
 #include "Types.h"
+#include <algorithm>
 #include <cstring>
+#include <llvm/Support/Path.h>
+#include <type_traits>
 
 using namespace llvm;
 using ctablegen::RecordMap;
 using ctablegen::tableGenFromRecType;
 
+// `TableGenParseFile` parses into the global source manager `llvm::SrcMgr`,
+// so concurrent parsers take turns calling it. Everything else (e.g. loading
+// source files) uses the state of each parser and runs concurrently.
+static std::mutex parseFileMutex;
+
+// Locations are resolved while holding the lock of the registry, so that the
+// parser (and the sources it borrows) cannot be dropped in the meantime.
+static std::mutex sourceMgrRegistryMutex;
+static std::vector<std::shared_ptr<const SourceMgr>> sourceMgrRegistry;
+
+void ctablegen::registerSourceMgr(std::shared_ptr<const SourceMgr> sourceMgr) {
+  std::lock_guard<std::mutex> lock(sourceMgrRegistryMutex);
+  sourceMgrRegistry.push_back(std::move(sourceMgr));
+}
+
+void ctablegen::unregisterSourceMgr(const SourceMgr *sourceMgr) {
+  std::lock_guard<std::mutex> lock(sourceMgrRegistryMutex);
+  sourceMgrRegistry.erase(
+      std::remove_if(sourceMgrRegistry.begin(), sourceMgrRegistry.end(),
+                     [&](const auto &registered) {
+                       return registered.get() == sourceMgr;
+                     }),
+      sourceMgrRegistry.end());
+}
+
+ctablegen::SourceLocation *
+ctablegen::createSourceLocation(ArrayRef<SMLoc> locs) {
+  auto *loc = new SourceLocation();
+  loc->locs.assign(locs.begin(), locs.end());
+  if (locs.empty())
+    return loc;
+  std::lock_guard<std::mutex> lock(sourceMgrRegistryMutex);
+  for (const auto &sourceMgr : sourceMgrRegistry) {
+    if (sourceMgr->FindBufferContainingLoc(locs.front()))
+      loc->sourceMgrs.push_back(sourceMgr);
+  }
+  return loc;
+}
+
+/// Returns a source manager of the location whose parser is alive. Must be
+/// called while holding the lock of the registry.
+static std::shared_ptr<const SourceMgr>
+findSourceMgr(const ctablegen::SourceLocation &loc) {
+  // A source manager outlives its parser if a location is resolved while the
+  // parser is destroyed, but the buffers may not, so it must be registered.
+  for (const auto &weakSourceMgr : loc.sourceMgrs) {
+    auto sourceMgr = weakSourceMgr.lock();
+    if (sourceMgr && std::find(sourceMgrRegistry.begin(),
+                               sourceMgrRegistry.end(),
+                               sourceMgr) != sourceMgrRegistry.end())
+      return sourceMgr;
+  }
+  return nullptr;
+}
+
+bool ctablegen::isLiveSourceLocation(const SourceLocation &loc) {
+  std::lock_guard<std::mutex> lock(sourceMgrRegistryMutex);
+  return findSourceMgr(loc) != nullptr;
+}
+
+bool ctablegen::readSourceBuffer(const SourceLocation &loc, size_t index,
+                                 TableGenSourceBufferCallback callback,
+                                 void *userData) {
+  if (index >= loc.locs.size())
+    return false;
+  std::lock_guard<std::mutex> lock(sourceMgrRegistryMutex);
+  auto sourceMgr = findSourceMgr(loc);
+  if (!sourceMgr)
+    return false;
+  unsigned id = sourceMgr->FindBufferContainingLoc(loc.locs[index]);
+  if (!id)
+    return false;
+  auto *memoryBuffer = sourceMgr->getMemoryBuffer(id);
+  auto name = memoryBuffer->getBufferIdentifier();
+  auto buffer = memoryBuffer->getBuffer();
+  callback(TableGenStringRef{.data = name.data(), .len = name.size()},
+           TableGenStringRef{.data = buffer.data(), .len = buffer.size()},
+           loc.locs[index].getPointer() - buffer.data(), userData);
+  return true;
+}
+
+namespace {
+/// Buffer that shares the contents of a cached file, so that they stay alive
+/// as long as a source manager uses them, even if the cache is cleared.
+class SharedMemoryBuffer : public MemoryBuffer {
+public:
+  SharedMemoryBuffer(std::shared_ptr<const MemoryBuffer> buffer,
+                     StringRef name)
+      : buffer(std::move(buffer)), name(name) {
+    init(this->buffer->getBufferStart(), this->buffer->getBufferEnd(),
+         /*RequiresNullTerminator=*/true);
+  }
+
+  StringRef getBufferIdentifier() const override { return name; }
+  BufferKind getBufferKind() const override {
+    return buffer->getBufferKind();
+  }
+
+private:
+  std::shared_ptr<const MemoryBuffer> buffer;
+  std::string name;
+};
+
+/// File opened through an include cache.
+class CachedFile : public vfs::File {
+public:
+  CachedFile(vfs::Status fileStatus, std::shared_ptr<const MemoryBuffer> buffer)
+      : fileStatus(std::move(fileStatus)), buffer(std::move(buffer)) {}
+
+  ErrorOr<vfs::Status> status() override { return fileStatus; }
+  ErrorOr<std::unique_ptr<MemoryBuffer>> getBuffer(const Twine &name, int64_t,
+                                                   bool, bool) override {
+    return std::make_unique<SharedMemoryBuffer>(buffer, name.str());
+  }
+  std::error_code close() override { return {}; }
+
+private:
+  vfs::Status fileStatus;
+  std::shared_ptr<const MemoryBuffer> buffer;
+};
+
+/// File system that reads files through an include cache, which source
+/// managers use to open files pulled in by `include` directives.
+class CachingFileSystem : public vfs::ProxyFileSystem {
+public:
+  CachingFileSystem(ctablegen::IncludeCache &cache)
+      : ProxyFileSystem(vfs::getRealFileSystem()), cache(cache) {}
+
+  ErrorOr<std::unique_ptr<vfs::File>>
+  openFileForRead(const Twine &path) override {
+    auto status = getUnderlyingFS().status(path);
+    if (!status)
+      return status.getError();
+    auto buffer = cache.getFile(path, getUnderlyingFS(), false);
+    if (!buffer)
+      return buffer.getError();
+    return std::make_unique<CachedFile>(std::move(*status),
+                                        std::move(*buffer));
+  }
+
+private:
+  ctablegen::IncludeCache &cache;
+};
+
+/// Source managers open included files through a virtual file system since
+/// LLVM 22. The setter is detected, so that older versions still cache the
+/// files passed to the parser.
+template <typename Mgr, typename = void>
+struct HasFileSystem : std::false_type {};
+template <typename Mgr>
+struct HasFileSystem<
+    Mgr, std::void_t<decltype(std::declval<Mgr &>().setVirtualFileSystem(
+             std::declval<IntrusiveRefCntPtr<vfs::FileSystem>>()))>>
+    : std::true_type {};
+
+template <typename Mgr>
+void setFileSystem(Mgr &sourceMgr, IntrusiveRefCntPtr<vfs::FileSystem> fs) {
+  if constexpr (HasFileSystem<Mgr>::value)
+    sourceMgr.setVirtualFileSystem(std::move(fs));
+}
+} // namespace
+
+ErrorOr<std::shared_ptr<const MemoryBuffer>>
+ctablegen::IncludeCache::getFile(const Twine &path, vfs::FileSystem &fs,
+                                 bool isVolatile) {
+  auto key = path.str();
+  auto status = fs.status(key);
+  if (!status)
+    return status.getError();
+  {
+    std::lock_guard<std::mutex> lock(mutex);
+    auto it = entries.find(key);
+    if (it != entries.end() &&
+        it->second.modificationTime == status->getLastModificationTime() &&
+        it->second.size == status->getSize())
+      return it->second.buffer;
+  }
+
+  // Files are read without holding the lock, so that parsers do not wait for
+  // each other while reading different files.
+  auto bufferOrErr = fs.getBufferForFile(key, /*FileSize=*/-1,
+                                         /*RequiresNullTerminator=*/true,
+                                         isVolatile);
+  if (!bufferOrErr)
+    return bufferOrErr.getError();
+  std::shared_ptr<const MemoryBuffer> buffer = std::move(*bufferOrErr);
+  std::lock_guard<std::mutex> lock(mutex);
+  entries[key] =
+      Entry{status->getLastModificationTime(), status->getSize(), buffer};
+  return buffer;
+}
+
+size_t ctablegen::IncludeCache::size() {
+  std::lock_guard<std::mutex> lock(mutex);
+  return entries.size();
+}
+
+void ctablegen::IncludeCache::clear() {
+  std::lock_guard<std::mutex> lock(mutex);
+  entries.clear();
+}
+
+bool ctablegen::IncludeCache::cachesIncludes() {
+  return HasFileSystem<SourceMgr>::value;
+}
+
+std::string
+ctablegen::TableGenParser::resolveSourceFile(const std::string &file) {
+  if (sys::fs::exists(file))
+    return file;
+  for (const auto &dir : includeDirs) {
+    SmallString<128> path(dir);
+    sys::path::append(path, file);
+    if (sys::fs::exists(path))
+      return std::string(path);
+  }
+  return file;
+}
+
+std::unique_ptr<MemoryBuffer>
+ctablegen::TableGenParser::loadSourceFile(const std::string &file) {
+  // Like llvm-tblgen, `-` refers to the standard input.
+  if (file == "-") {
+    auto bufferOrErr = MemoryBuffer::getSTDIN();
+    if (!bufferOrErr)
+      return nullptr;
+    return std::move(*bufferOrErr);
+  }
+
+  auto path = resolveSourceFile(file);
+  if (includeCache) {
+    auto bufferOrErr =
+        includeCache->getFile(path, *vfs::getRealFileSystem(), !memoryMapFiles);
+    if (!bufferOrErr)
+      return nullptr;
+    return std::make_unique<SharedMemoryBuffer>(std::move(*bufferOrErr), path);
+  }
+
+  // Volatile files are always read, while LLVM maps other files if they are
+  // large enough and the null terminator can be placed after their end.
+  auto fileOrErr =
+      MemoryBuffer::getFile(path, /*IsText=*/false,
+                            /*RequiresNullTerminator=*/true, !memoryMapFiles);
+  if (!fileOrErr)
+    return nullptr;
+  return std::move(*fileOrErr);
+}
+
+void ctablegen::TableGenParser::reportProgress(TableGenParsePhase phase) {
+  if (!progressCallback)
+    return;
+  size_t bytes = 0;
+  for (unsigned id = 1; id <= sourceMgr->getNumBuffers(); ++id) {
+    bytes += sourceMgr->getMemoryBuffer(id)->getBufferSize();
+  }
+  progressCallback(phase, sourceMgr->getNumBuffers(), bytes, progressUserData);
+}
+
+void ctablegen::TableGenParser::handleDiagnostic(const SMDiagnostic &diag,
+                                                 void *context) {
+  auto *parser = static_cast<TableGenParser *>(context);
+  if (!parser->warnOnUnusedTemplateArgs &&
+      diag.getKind() == SourceMgr::DK_Warning &&
+      diag.getMessage().find("unused template argument") == 0)
+    return;
+  parser->diagnostics.push_back(diag);
+}
+
 RecordKeeper *ctablegen::TableGenParser::parse() {
   auto recordKeeper = std::unique_ptr<RecordKeeper>(new RecordKeeper);
-  sourceMgr.setIncludeDirs(includeDirs);
+  unregisterSourceMgr(sourceMgr.get());
+  sourceMgr = std::make_shared<SourceMgr>();
+  sourceMgr->setIncludeDirs(includeDirs);
+  if (includeCache)
+    setFileSystem(*sourceMgr, makeIntrusiveRefCnt<CachingFileSystem>(
+                                  *includeCache));
+  diagnostics.clear();
+
+  // TableGen reports errors through the diagnostic handler of the global
+  // source manager, which is copied from ours while parsing.
+  sourceMgr->setDiagHandler(handleDiagnostic, this);
+
+  std::vector<std::unique_ptr<MemoryBuffer>> buffers;
+  for (const auto &source : sources) {
+    buffers.push_back(MemoryBuffer::getMemBuffer(source.text, source.name));
+  }
 
   for (const auto &file : files) {
-    std::string full_path;
-    if (!sourceMgr.AddIncludeFile(file, SMLoc(), full_path)) {
+    auto buffer = loadSourceFile(file);
+    if (!buffer) {
+      diagnostics.emplace_back(file, SourceMgr::DK_Error,
+                               "could not open input file");
       return nullptr;
     }
+    buffers.push_back(std::move(buffer));
+  }
+
+  // Macros are defined by the prelude, since `TableGenParseFile` does not
+  // accept any macros.
+  std::string preludeSource;
+  for (const auto &macro : macros) {
+    preludeSource += "#define " + macro + "\n";
   }
+  preludeSource += prelude;
+
+  // The prelude becomes the main buffer and is treated as if it was included
+  // at the start of the first source. When the lexer reaches the end of the
+  // prelude, it continues with that source, whose line numbers are therefore
+  // not affected.
+  if (!preludeSource.empty()) {
+    SMLoc includeLoc;
+    if (!buffers.empty())
+      includeLoc = SMLoc::getFromPointer(buffers.front()->getBufferStart());
+    sourceMgr->AddNewSourceBuffer(
+        MemoryBuffer::getMemBufferCopy(preludeSource, "<prelude>"), includeLoc);
+  }
+
+  for (auto &buffer : buffers) {
+    sourceMgr->AddNewSourceBuffer(std::move(buffer), SMLoc());
+    reportProgress(TABLEGEN_PARSE_LOADING);
+  }
+
+  reportProgress(TABLEGEN_PARSE_PARSING);
+  bool result;
+  {
+    std::lock_guard<std::mutex> lock(parseFileMutex);
+    result = TableGenParseFile(*sourceMgr, *recordKeeper);
+  }
+  if (!preludeSource.empty() && sourceMgr->getNumBuffers() > 1) {
+    recordKeeper->saveInputFilename(
+        sourceMgr->getMemoryBuffer(2)->getBufferIdentifier().str());
+  }
+
+  // Messages printed after parsing (e.g. by `tableGenPrintError`) must not
+  // end up in the diagnostics.
+  sourceMgr->setDiagHandler(nullptr);
+  registerSourceMgr(sourceMgr);
+  reportProgress(TABLEGEN_PARSE_FINISHED);
 
-  bool result = TableGenParseFile(sourceMgr, *recordKeeper);
   if (!result) {
     return recordKeeper.release();
   }
@@ -40,25 +370,52 @@ void ctablegen::TableGenParser::addIncludeDirectory(const StringRef include) {
 }
 
 bool ctablegen::TableGenParser::addSource(const char *source) {
-  ErrorOr<std::unique_ptr<MemoryBuffer>> FileOrErr =
-      MemoryBuffer::getMemBuffer(source);
-
-  if (std::error_code EC = FileOrErr.getError()) {
+  if (!source) {
     return false;
   }
 
-  sourceMgr.AddNewSourceBuffer(std::move(*FileOrErr), SMLoc());
+  // Buffers are created on every parse, so that the parser can be reused.
+  sources.push_back(Source{source, std::string(), nullptr});
   return true;
 }
 
+void ctablegen::TableGenParser::addSourceCopy(const StringRef source,
+                                              const StringRef name) {
+  // `std::string` keeps its contents null-terminated.
+  auto storage = std::make_shared<const std::string>(source);
+  sources.push_back(Source{*storage, std::string(name), storage});
+}
+
 void ctablegen::TableGenParser::addSourceFile(const StringRef file) {
   files.push_back(std::string(file));
 }
 
+void ctablegen::TableGenParser::addPrelude(const StringRef source) {
+  prelude.append(source.data(), source.size());
+  prelude.push_back('\n');
+}
+
+void ctablegen::TableGenParser::addMacro(const StringRef name) {
+  macros.push_back(std::string(name));
+}
+
+void ctablegen::TableGenParser::clearSources() {
+  sources.clear();
+  files.clear();
+}
+
 TableGenParserRef tableGenGet() {
   return wrap(new ctablegen::TableGenParser());
 }
 
+TableGenParserRef tableGenClone(TableGenParserRef tg_ref) {
+  return wrap(new ctablegen::TableGenParser(*unwrap(tg_ref)));
+}
+
+void tableGenAssign(TableGenParserRef tg_ref, TableGenParserRef other_ref) {
+  *unwrap(tg_ref) = *unwrap(other_ref);
+}
+
 void tableGenFree(TableGenParserRef tg_ref) { delete unwrap(tg_ref); }
 
 void tableGenAddSourceFile(TableGenParserRef tg_ref, TableGenStringRef source) {
@@ -69,16 +426,152 @@ TableGenBool tableGenAddSource(TableGenParserRef tg_ref, const char *source) {
   return unwrap(tg_ref)->addSource(source);
 }
 
+void tableGenAddSourceCopy(TableGenParserRef tg_ref, TableGenStringRef source,
+                           TableGenStringRef name) {
+  unwrap(tg_ref)->addSourceCopy(StringRef(source.data, source.len),
+                                StringRef(name.data, name.len));
+}
+
 void tableGenAddIncludeDirectory(TableGenParserRef tg_ref,
                                  TableGenStringRef include) {
   return unwrap(tg_ref)->addIncludeDirectory(
       StringRef(include.data, include.len));
 }
 
+void tableGenSetIncludeCache(TableGenParserRef tg_ref,
+                             TableGenIncludeCacheRef cache_ref) {
+  unwrap(tg_ref)->setIncludeCache(unwrap(cache_ref));
+}
+
+void tableGenAddPrelude(TableGenParserRef tg_ref, TableGenStringRef source) {
+  unwrap(tg_ref)->addPrelude(StringRef(source.data, source.len));
+}
+
+void tableGenDefineMacro(TableGenParserRef tg_ref, TableGenStringRef name) {
+  unwrap(tg_ref)->addMacro(StringRef(name.data, name.len));
+}
+
+void tableGenSetWarnOnUnusedTemplateArgs(TableGenParserRef tg_ref,
+                                         TableGenBool warn) {
+  unwrap(tg_ref)->setWarnOnUnusedTemplateArgs(warn);
+}
+
+void tableGenSetMemoryMapFiles(TableGenParserRef tg_ref, TableGenBool map) {
+  unwrap(tg_ref)->setMemoryMapFiles(map);
+}
+
+void tableGenSetProgressCallback(TableGenParserRef tg_ref,
+                                 TableGenProgressCallback callback,
+                                 void *userData) {
+  unwrap(tg_ref)->setProgressCallback(callback, userData);
+}
+
+void tableGenClearSources(TableGenParserRef tg_ref) {
+  unwrap(tg_ref)->clearSources();
+}
+
 TableGenRecordKeeperRef tableGenParse(TableGenParserRef tg_ref) {
   return wrap(unwrap(tg_ref)->parse());
 }
 
+size_t tableGenGetNumDiagnostics(TableGenParserRef tg_ref) {
+  return unwrap(tg_ref)->diagnostics.size();
+}
+
+TableGenDiagnosticRef tableGenGetDiagnostic(TableGenParserRef tg_ref,
+                                            size_t index) {
+  auto &diagnostics = unwrap(tg_ref)->diagnostics;
+  if (index >= diagnostics.size())
+    return nullptr;
+  return wrap(&diagnostics[index]);
+}
+
+TableGenDiagKind tableGenDiagnosticGetKind(TableGenDiagnosticRef diag_ref) {
+  return static_cast<TableGenDiagKind>(unwrap(diag_ref)->getKind());
+}
+
+TableGenStringRef
+tableGenDiagnosticGetMessage(TableGenDiagnosticRef diag_ref) {
+  auto s = unwrap(diag_ref)->getMessage();
+  return TableGenStringRef{.data = s.data(), .len = s.size()};
+}
+
+TableGenStringRef
+tableGenDiagnosticGetFilename(TableGenDiagnosticRef diag_ref) {
+  auto s = unwrap(diag_ref)->getFilename();
+  return TableGenStringRef{.data = s.data(), .len = s.size()};
+}
+
+int tableGenDiagnosticGetLine(TableGenDiagnosticRef diag_ref) {
+  return unwrap(diag_ref)->getLineNo();
+}
+
+int tableGenDiagnosticGetColumn(TableGenDiagnosticRef diag_ref) {
+  return unwrap(diag_ref)->getColumnNo();
+}
+
+TableGenStringRef
+tableGenDiagnosticGetLineContents(TableGenDiagnosticRef diag_ref) {
+  auto s = unwrap(diag_ref)->getLineContents();
+  return TableGenStringRef{.data = s.data(), .len = s.size()};
+}
+
+void tableGenDiagnosticPrint(TableGenDiagnosticRef diag_ref,
+                             TableGenStringCallback callback, void *userData) {
+  ctablegen::CallbackOstream stream(callback, userData);
+  unwrap(diag_ref)->print(nullptr, stream, /*ShowColors=*/false);
+}
+
+size_t tableGenGetNumSourceBuffers(TableGenParserRef tg_ref) {
+  return unwrap(tg_ref)->sourceMgr->getNumBuffers();
+}
+
+TableGenStringRef tableGenSourceBufferGetName(TableGenParserRef tg_ref,
+                                              size_t index) {
+  auto &sourceMgr = *unwrap(tg_ref)->sourceMgr;
+  if (index >= sourceMgr.getNumBuffers())
+    return TableGenStringRef{.data = nullptr, .len = 0};
+  auto s = sourceMgr.getMemoryBuffer(index + 1)->getBufferIdentifier();
+  return TableGenStringRef{.data = s.data(), .len = s.size()};
+}
+
+TableGenBool tableGenSourceBufferGetIncludeLoc(TableGenParserRef tg_ref,
+                                               size_t index, size_t *parent,
+                                               unsigned *line,
+                                               unsigned *column) {
+  auto &sourceMgr = *unwrap(tg_ref)->sourceMgr;
+  if (index >= sourceMgr.getNumBuffers())
+    return false;
+  auto loc = sourceMgr.getParentIncludeLoc(index + 1);
+  unsigned parentID = sourceMgr.FindBufferContainingLoc(loc);
+  if (!loc.isValid() || !parentID)
+    return false;
+  *parent = parentID - 1;
+  std::tie(*line, *column) = sourceMgr.getLineAndColumn(loc, parentID);
+  return true;
+}
+
+// Include cache
+TableGenIncludeCacheRef tableGenIncludeCacheCreate() {
+  return wrap(new ctablegen::IncludeCache());
+}
+
+void tableGenIncludeCacheFree(TableGenIncludeCacheRef cache_ref) {
+  delete unwrap(cache_ref);
+}
+
+size_t tableGenIncludeCacheSize(TableGenIncludeCacheRef cache_ref) {
+  return unwrap(cache_ref)->size();
+}
+
+void tableGenIncludeCacheClear(TableGenIncludeCacheRef cache_ref) {
+  unwrap(cache_ref)->clear();
+}
+
+TableGenBool tableGenIncludeCacheCachesIncludes() {
+  return ctablegen::IncludeCache::cachesIncludes();
+}
+
 // LLVM ListType
 TableGenRecTyKind tableGenListRecordGetType(TableGenRecordValRef rv_ref) {
   if (!rv_ref)
@@ -150,7 +643,20 @@ TableGenRecordRef tableGenDagRecordOperator(TableGenTypedInitRef rv_ref) {
   auto dag = dyn_cast<DagInit>(unwrap(rv_ref));
   if (!dag)
     return 0;
-  return wrap(dag->getOperatorAsDef(SMLoc()));
+  // `getOperatorAsDef` exits the process if the operator is not a def, e.g.
+  // a template argument of a class.
+  auto def = dyn_cast<DefInit>(dag->getOperator());
+  if (!def)
+    return 0;
+  return wrap(def->getDef());
+}
+
+TableGenTypedInitRef
+tableGenDagRecordOperatorInit(TableGenTypedInitRef rv_ref) {
+  auto dag = dyn_cast<DagInit>(unwrap(rv_ref));
+  if (!dag)
+    return nullptr;
+  return wrap(dyn_cast<TypedInit>(dag->getOperator()));
 }
 
 TableGenStringRef tableGenDagRecordArgName(TableGenTypedInitRef rv_ref,
@@ -12,12 +12,15 @@
 #define _CTABLEGEN_TABLEGEN_HPP_
 
 #include <memory>
+#include <mutex>
 #include <utility>
 
+#include <llvm/ADT/StringMap.h>
 #include <llvm/Support/CommandLine.h>
 #include <llvm/Support/FileSystem.h>
 #include <llvm/Support/MemoryBuffer.h>
 #include <llvm/Support/ToolOutputFile.h>
+#include <llvm/Support/VirtualFileSystem.h>
 #include <llvm/TableGen/Error.h>
 #include <llvm/TableGen/Parser.h>
 #include <llvm/TableGen/Record.h>
@@ -33,23 +36,136 @@ typedef std::map<std::string, std::unique_ptr<llvm::Record>, std::less<>>
 typedef std::vector<const llvm::Record *> RecordVector;
 typedef std::pair<std::string, llvm::TypedInit *> DagPair;
 
+/// Contents of source files shared between parsers, keyed by their path. A
+/// file is read again if its size or modification time changed.
+class IncludeCache {
+public:
+  /// Returns the contents of the file at the given path, which is read with
+  /// the given file system if it is not cached yet.
+  llvm::ErrorOr<std::shared_ptr<const llvm::MemoryBuffer>>
+  getFile(const llvm::Twine &path, llvm::vfs::FileSystem &fs, bool isVolatile);
+  size_t size();
+  void clear();
+
+  /// Returns whether files pulled in by `include` directives are read through
+  /// the cache, which requires source managers with a virtual file system.
+  static bool cachesIncludes();
+
+private:
+  struct Entry {
+    llvm::sys::TimePoint<> modificationTime;
+    uint64_t size;
+    std::shared_ptr<const llvm::MemoryBuffer> buffer;
+  };
+
+  std::mutex mutex;
+  llvm::StringMap<Entry> entries;
+};
+
+/// Locations in the source buffers of a parser, together with the source
+/// managers that contain the buffers. Parsers that borrow the same source share
+/// its memory, so a location may be contained in several source managers.
+struct SourceLocation {
+  std::vector<llvm::SMLoc> locs;
+  std::vector<std::weak_ptr<const llvm::SourceMgr>> sourceMgrs;
+};
+
+/// Registry of the source managers of all parsers that are alive, which is
+/// used to find the source manager of the locations of a record.
+void registerSourceMgr(std::shared_ptr<const llvm::SourceMgr> sourceMgr);
+void unregisterSourceMgr(const llvm::SourceMgr *sourceMgr);
+
+/// Creates a location from the locations of a record or field, which must be
+/// alive, so that its source manager is registered.
+SourceLocation *createSourceLocation(llvm::ArrayRef<llvm::SMLoc> locs);
+
+/// Returns whether the parser of the location is alive, so that the location
+/// does not point into memory that was reused by another parser.
+bool isLiveSourceLocation(const SourceLocation &loc);
+
+/// Calls the callback with the source buffer containing the location at the
+/// given index. The callback is called while the parser cannot be destroyed,
+/// since the buffers of sources that were not copied are owned by its caller.
+bool readSourceBuffer(const SourceLocation &loc, size_t index,
+                      TableGenSourceBufferCallback callback, void *userData);
+
 class TableGenParser {
 public:
   TableGenParser() {}
+  TableGenParser(const TableGenParser &other) { *this = other; }
+  ~TableGenParser() { unregisterSourceMgr(sourceMgr.get()); }
+
+  /// Copies the configuration (sources, include directories, ...) of the
+  /// given parser, reusing the memory that is already allocated.
+  TableGenParser &operator=(const TableGenParser &other) {
+    includeDirs = other.includeDirs;
+    files = other.files;
+    sources = other.sources;
+    prelude = other.prelude;
+    macros = other.macros;
+    warnOnUnusedTemplateArgs = other.warnOnUnusedTemplateArgs;
+    memoryMapFiles = other.memoryMapFiles;
+    includeCache = other.includeCache;
+    return *this;
+  }
+
   bool addSource(const char *source);
+  void addSourceCopy(const llvm::StringRef source, const llvm::StringRef name);
   void addSourceFile(const llvm::StringRef source);
   void addIncludeDirectory(const llvm::StringRef include);
+  void addPrelude(const llvm::StringRef source);
+  void addMacro(const llvm::StringRef name);
+  void setWarnOnUnusedTemplateArgs(bool warn) {
+    warnOnUnusedTemplateArgs = warn;
+  }
+  void setMemoryMapFiles(bool map) { memoryMapFiles = map; }
+  void setIncludeCache(IncludeCache *cache) { includeCache = cache; }
+  void setProgressCallback(TableGenProgressCallback callback, void *userData) {
+    progressCallback = callback;
+    progressUserData = userData;
+  }
+  void clearSources();
   llvm::RecordKeeper *parse();
 
-  llvm::SourceMgr sourceMgr;
+  /// Replaced on every parse, so that locations of previous parses are not
+  /// found in the new buffers.
+  std::shared_ptr<llvm::SourceMgr> sourceMgr =
+      std::make_shared<llvm::SourceMgr>();
+  std::vector<llvm::SMDiagnostic> diagnostics;
 
 private:
+  static void handleDiagnostic(const llvm::SMDiagnostic &diag, void *context);
+  std::unique_ptr<llvm::MemoryBuffer> loadSourceFile(const std::string &file);
+  std::string resolveSourceFile(const std::string &file);
+  void reportProgress(TableGenParsePhase phase);
+
   std::vector<std::string> includeDirs;
   std::vector<std::string> files;
+  /// A source string, which is null-terminated as required by the lexer.
+  struct Source {
+    llvm::StringRef text;
+    std::string name;
+    /// Owns the text if it was copied, and is shared by copied parsers.
+    std::shared_ptr<const std::string> storage;
+  };
+
+  std::vector<Source> sources;
+  std::string prelude;
+  std::vector<std::string> macros;
+  bool warnOnUnusedTemplateArgs = true;
+  bool memoryMapFiles = false;
+  /// Owned by the caller, which keeps it alive as long as the parser.
+  IncludeCache *includeCache = nullptr;
+  TableGenProgressCallback progressCallback = nullptr;
+  void *progressUserData = nullptr;
 };
 
 // Utility
 TableGenRecTyKind tableGenFromRecType(const llvm::RecTy *rt);
+RecordVector getAllDerivedDefinitions(const llvm::RecordKeeper &records,
+                                      llvm::StringRef className);
+RecordVector getAllDerivedDefinitions(const llvm::RecordKeeper &records,
+                                      const llvm::Record *cls);
 
 /// A simple raw ostream subclass that forwards write_impl calls to the
 /// user-supplied callback together with opaque user-supplied data.
@@ -83,6 +199,8 @@ struct RecordMapIterator {
 
 DEFINE_SIMPLE_CONVERSION_FUNCTIONS(ctablegen::TableGenParser,
                                    TableGenParserRef);
+DEFINE_SIMPLE_CONVERSION_FUNCTIONS(ctablegen::IncludeCache,
+                                   TableGenIncludeCacheRef);
 DEFINE_SIMPLE_CONVERSION_FUNCTIONS(llvm::RecordKeeper, TableGenRecordKeeperRef);
 
 DEFINE_SIMPLE_CONVERSION_FUNCTIONS(ctablegen::RecordMap, TableGenRecordMapRef);
@@ -97,12 +215,14 @@ DEFINE_SIMPLE_CONVERSION_FUNCTIONS(llvm::Record, TableGenRecordRef);
 DEFINE_SIMPLE_CONVERSION_FUNCTIONS(llvm::RecordVal, TableGenRecordValRef);
 
 DEFINE_SIMPLE_CONVERSION_FUNCTIONS(llvm::TypedInit, TableGenTypedInitRef);
+DEFINE_SIMPLE_CONVERSION_FUNCTIONS(llvm::RecTy, TableGenRecTyRef);
 DEFINE_SIMPLE_CONVERSION_FUNCTIONS(ctablegen::DagPair, TableGenDagPairRef);
 
 DEFINE_SIMPLE_CONVERSION_FUNCTIONS(ctablegen::RecordMapIterator,
                                    TableGenRecordKeeperIteratorRef);
 
-DEFINE_SIMPLE_CONVERSION_FUNCTIONS(std::vector<llvm::SMLoc>,
+DEFINE_SIMPLE_CONVERSION_FUNCTIONS(ctablegen::SourceLocation,
                                    TableGenSourceLocationRef);
+DEFINE_SIMPLE_CONVERSION_FUNCTIONS(llvm::SMDiagnostic, TableGenDiagnosticRef);
 
 #endif
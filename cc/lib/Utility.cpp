@@ -77,6 +77,9 @@ TableGenTypedInitRef tableGenBitsInitGetBitInit(TableGenTypedInitRef ti,
   auto bits_init = dyn_cast<BitsInit>(unwrap(ti));
   if (!bits_init)
     return nullptr;
+  // BitsInit::getBit only asserts on the index.
+  if (index >= bits_init->getNumBits())
+    return nullptr;
 
   // Return the raw Init* -- may be BitInit or VarBitInit.
   // Caller must use tableGenBitInitIsVarBit() to distinguish.
@@ -176,19 +179,24 @@ size_t tableGenVarBitInitGetBitNum(TableGenTypedInitRef ti) {
   return var_bit->getBitNum();
 }
 
+/// Returns whether the location can be looked up in the sources of a parser,
+/// instead of pointing into memory that a parser may have reused. The location
+/// may belong to another parser that borrows the same source.
+static bool isLiveLocation(const ctablegen::SourceLocation &loc) {
+  return !loc.locs.empty() && ctablegen::isLiveSourceLocation(loc);
+}
+
 TableGenBool tableGenPrintError(TableGenParserRef ref,
                                 TableGenSourceLocationRef loc_ref,
                                 TableGenDiagKind dk, TableGenStringRef message,
                                 TableGenStringCallback callback,
                                 void *userData) {
   ctablegen::CallbackOstream stream(callback, userData);
-  auto &LocVec = *unwrap(loc_ref);
-  ArrayRef<SMLoc> Loc(LocVec);
-
-  SMLoc NullLoc;
-  if (Loc.empty())
-    Loc = NullLoc;
-  auto &SrcMgr = unwrap(ref)->sourceMgr;
+  auto &loc = *unwrap(loc_ref);
+  if (!isLiveLocation(loc))
+    return false;
+  ArrayRef<SMLoc> Loc(loc.locs);
+  auto &SrcMgr = *unwrap(ref)->sourceMgr;
 
   if (!SrcMgr.FindBufferContainingLoc(Loc.front()))
     return false;
@@ -206,13 +214,75 @@ TableGenBool tableGenPrintError(TableGenParserRef ref,
   return true;
 }
 
+TableGenBool tableGenFindSourceBuffer(TableGenParserRef ref,
+                                      TableGenSourceLocationRef loc_ref,
+                                      TableGenStringRef *buffer,
+                                      size_t *offset) {
+  auto &loc = *unwrap(loc_ref);
+  if (!isLiveLocation(loc))
+    return false;
+  auto &srcMgr = *unwrap(ref)->sourceMgr;
+  unsigned id = srcMgr.FindBufferContainingLoc(loc.locs.front());
+  if (!id)
+    return false;
+  auto s = srcMgr.getMemoryBuffer(id)->getBuffer();
+  *buffer = TableGenStringRef{.data = s.data(), .len = s.size()};
+  *offset = loc.locs.front().getPointer() - s.data();
+  return true;
+}
+
+TableGenBool tableGenFindSourceBufferName(TableGenParserRef ref,
+                                          TableGenSourceLocationRef loc_ref,
+                                          TableGenStringRef *name) {
+  auto &loc = *unwrap(loc_ref);
+  if (!isLiveLocation(loc))
+    return false;
+  auto &srcMgr = *unwrap(ref)->sourceMgr;
+  unsigned id = srcMgr.FindBufferContainingLoc(loc.locs.front());
+  if (!id)
+    return false;
+  auto s = srcMgr.getMemoryBuffer(id)->getBufferIdentifier();
+  *name = TableGenStringRef{.data = s.data(), .len = s.size()};
+  return true;
+}
+
 TableGenSourceLocationRef tableGenSourceLocationNull() {
-  return wrap(new std::vector<SMLoc>());
+  return wrap(new ctablegen::SourceLocation());
 }
 
 TableGenSourceLocationRef
 tableGenSourceLocationClone(TableGenSourceLocationRef loc_ref) {
-  return wrap(new std::vector<SMLoc>(*unwrap(loc_ref)));
+  return wrap(new ctablegen::SourceLocation(*unwrap(loc_ref)));
+}
+
+size_t tableGenSourceLocationSize(TableGenSourceLocationRef loc_ref) {
+  return unwrap(loc_ref)->locs.size();
+}
+
+TableGenSourceLocationRef
+tableGenSourceLocationGet(TableGenSourceLocationRef loc_ref, size_t index) {
+  auto &loc = *unwrap(loc_ref);
+  if (index >= loc.locs.size())
+    return wrap(new ctablegen::SourceLocation());
+  return wrap(new ctablegen::SourceLocation{
+      std::vector<SMLoc>(1, loc.locs[index]), loc.sourceMgrs});
+}
+
+TableGenBool
+tableGenSourceLocationReadBuffer(TableGenSourceLocationRef loc_ref,
+                                 size_t index,
+                                 TableGenSourceBufferCallback callback,
+                                 void *userData) {
+  return ctablegen::readSourceBuffer(*unwrap(loc_ref), index, callback,
+                                     userData);
+}
+
+const char *tableGenSourceLocationGetPointer(TableGenSourceLocationRef loc_ref,
+                                             size_t index) {
+  auto &locs = unwrap(loc_ref)->locs;
+  if (index >= locs.size())
+    return nullptr;
+  return locs[index].getPointer();
 }
 
 void tableGenSourceLocationFree(TableGenSourceLocationRef loc_ref) {
@@ -25,7 +25,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let name = name?;
         println!("  {} (is_class={})", name, class.is_class());
         for field in class.values() {
-            println!("    field: {}", field.name.to_str()?);
+            println!("    field: {}", field.name().to_str()?);
         }
     }
 
@@ -46,7 +46,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     println!("\n=== Pets (derived from Pet) ===");
-    for def in keeper.all_derived_definitions("Pet") {
+    for def in keeper.all_derived_definitions("Pet")? {
         let name = def.str_value("Name")?;
         let legs = def.int_value("Legs")?;
         println!("  {} has {} legs", name, legs);
@@ -79,7 +79,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // dag with arg_no lookup
     let dag = rec.dag_value("instr")?;
-    println!("instr op   = {}", dag.operator().name()?);
+    println!("instr op   = {}", dag.operator()?.name()?);
     if let Some(idx) = dag.arg_no("dst") {
         println!("  $dst at index {}", idx);
     }
@@ -124,7 +124,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // RecordValue metadata
     println!("\n--- field metadata ---");
     for field in rec.values() {
-        let type_name = match field.init {
+        let type_name = match field.init() {
             TypedInit::Bit(_) => "bit",
             TypedInit::Bits(_) => "bits",
             TypedInit::Int(_) => "int",
@@ -144,7 +144,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         };
         println!(
             "  {:10} : {}{:6}  template_arg={}  nonconcrete_ok={}",
-            field.name.to_str()?,
+            field.name().to_str()?,
             type_name,
             extra,
             field.is_template_arg(),
@@ -39,7 +39,7 @@ const SOURCE: &str = r#"
 
 fn print_registers(keeper: &RecordKeeper) -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Registers ===");
-    for reg in keeper.all_derived_definitions("Register") {
+    for reg in keeper.all_derived_definitions("Register")? {
         let name = reg.str_value("Name")?;
         let index = reg.int_value("Index")?;
         println!("  {:4}  index={}", name, index);
@@ -49,7 +49,7 @@ fn print_registers(keeper: &RecordKeeper) -> Result<(), Box<dyn std::error::Erro
 
 fn print_instructions(keeper: &RecordKeeper) -> Result<(), Box<dyn std::error::Error>> {
     println!("\n=== Instructions ===");
-    for instr in keeper.all_derived_definitions("Instruction") {
+    for instr in keeper.all_derived_definitions("Instruction")? {
         let mnemonic = instr.str_value("Mnemonic")?;
         let operands = instr.int_value("Operands")?;
         let opcode: Vec<bool> = instr.bits_value("Opcode")?;
@@ -79,7 +79,7 @@ fn print_instructions(keeper: &RecordKeeper) -> Result<(), Box<dyn std::error::E
 
 fn print_class_hierarchy(keeper: &RecordKeeper) -> Result<(), Box<dyn std::error::Error>> {
     println!("\n=== Class hierarchy ===");
-    for instr in keeper.all_derived_definitions("Instruction") {
+    for instr in keeper.all_derived_definitions("Instruction")? {
         let name = instr.name()?;
         print!("  {} -> direct supers:", name);
         for sc in instr.direct_super_classes() {
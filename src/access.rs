@@ -0,0 +1,101 @@
+//! Implementations of the traits of the `tblgen-access` crate for records
+//! parsed by TableGen.
+//!
+//! Records loaded from the output of `llvm-tblgen --dump-json`
+//! (`tblgen_access::json_dump::JsonRecordKeeper`) implement the same traits,
+//! so that code generators can be written once and run without linking LLVM.
+//!
+//! ```rust
+//! use tblgen::{
+//!     TableGenParser,
+//!     access::{RecordAccess, RecordKeeperAccess},
+//! };
+//!
+//! fn opcodes<K: RecordKeeperAccess>(keeper: &K) -> Result<Vec<i64>, K::Error> {
+//!     keeper
+//!         .all_derived_definitions("Inst")?
+//!         .into_iter()
+//!         .map(|def| def.int_value("opcode"))
+//!         .collect()
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source("class Inst<int o> { int opcode = o; } def A : Inst<1>;")?
+//!     .parse()?;
+//! assert_eq!(opcodes(&keeper)?, vec![1]);
+//! # Ok(())
+//! # }
+//! ```
+
+pub use tblgen_access::{RecordAccess, RecordKeeperAccess};
+
+use crate::{Error, Record, RecordKeeper};
+
+impl<'a> RecordAccess<'a> for Record<'a> {
+    type Error = Error;
+
+    fn name(self) -> Result<&'a str, Error> {
+        Record::name(self)
+    }
+
+    fn anonymous(self) -> bool {
+        Record::anonymous(self)
+    }
+
+    fn subclass_of(self, class: &str) -> bool {
+        Record::subclass_of(self, class)
+    }
+
+    fn has_field(self, name: &str) -> bool {
+        Record::has_field(self, name)
+    }
+
+    fn bit_value(self, name: &str) -> Result<bool, Error> {
+        Record::bit_value(self, name)
+    }
+
+    fn int_value(self, name: &str) -> Result<i64, Error> {
+        Record::int_value(self, name)
+    }
+
+    fn str_value(self, name: &str) -> Result<&'a str, Error> {
+        Record::str_value(self, name)
+    }
+
+    fn def_value(self, name: &str) -> Result<Self, Error> {
+        Record::def_value(self, name)
+    }
+
+    fn list_of_defs_value(self, name: &str) -> Result<Vec<Self>, Error> {
+        Record::list_of_defs_value(self, name)
+    }
+
+    fn list_of_ints_value(self, name: &str) -> Result<Vec<i64>, Error> {
+        Record::list_of_ints_value(self, name)
+    }
+
+    fn list_of_strings_value(self, name: &str) -> Result<Vec<&'a str>, Error> {
+        Record::list_of_strings_value(self, name)
+    }
+}
+
+impl RecordKeeperAccess for RecordKeeper<'_> {
+    type Error = Error;
+    type Record<'a>
+        = Record<'a>
+    where
+        Self: 'a;
+
+    fn def(&self, name: &str) -> Result<Record<'_>, Error> {
+        RecordKeeper::def(self, name)
+    }
+
+    fn def_records(&self) -> impl Iterator<Item = Record<'_>> {
+        self.defs().map(|(_, def)| def)
+    }
+
+    fn all_derived_definitions(&self, class: &str) -> Result<Vec<Record<'_>>, Error> {
+        Ok(RecordKeeper::all_derived_definitions(self, class)?.collect())
+    }
+}
@@ -0,0 +1,367 @@
+//! Backends written in Rust, organized and invoked like the backends of
+//! `llvm-tblgen`.
+//!
+//! A backend implements [`TableGenBackend`] and is registered in [`Backends`]
+//! under the name of its command line option (e.g. `gen-enums` for
+//! `-gen-enums`). The registry can then run a backend by name, or act as the
+//! driver of a `llvm-tblgen` compatible tool with
+//! [`run_options`](Backends::run_options).
+//!
+//! ```rust
+//! use std::io::Write;
+//! use tblgen::{
+//!     RecordKeeper, TableGenParser,
+//!     backend::{BackendError, Backends, TableGenBackend, emit_source_file_header},
+//! };
+//!
+//! struct Enums;
+//!
+//! impl TableGenBackend for Enums {
+//!     fn name(&self) -> &str {
+//!         "gen-enums"
+//!     }
+//!
+//!     fn run(&self, keeper: &RecordKeeper, output: &mut dyn Write) -> Result<(), BackendError> {
+//!         emit_source_file_header("Enum definitions", keeper, output)?;
+//!         writeln!(output, "enum Kind {{")?;
+//!         for record in keeper.all_derived_definitions("Kind")? {
+//!             writeln!(output, "  {},", record.name()?)?;
+//!         }
+//!         writeln!(output, "}};")?;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source("class Kind; def A : Kind; def B : Kind;")?
+//!     .parse()?;
+//! let mut backends = Backends::new();
+//! backends.register(Enums);
+//! let mut output = Vec::new();
+//! backends.run("gen-enums", &keeper, &mut output)?;
+//! assert!(String::from_utf8(output)?.ends_with("enum Kind {\n  A,\n  B,\n};\n"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+    io::{self, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{Error, RecordKeeper, TableGenOptions};
+
+/// A backend that generates output from the records of a record keeper.
+pub trait TableGenBackend {
+    /// Returns the name of the backend, i.e. the name of its command line
+    /// option without leading dashes (e.g. `gen-enums`).
+    fn name(&self) -> &str;
+
+    /// Returns a short description of the backend.
+    fn description(&self) -> &str {
+        ""
+    }
+
+    /// Generates the output of the backend.
+    fn run(&self, keeper: &RecordKeeper, output: &mut dyn Write) -> Result<(), BackendError>;
+}
+
+/// Error returned by a backend.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum BackendError {
+    /// Error while accessing or parsing records.
+    TableGen(Error),
+    /// Error while writing the output.
+    Io(io::Error),
+    /// No backend is registered with the given name.
+    UnknownBackend(String),
+    /// Error reported by a backend, e.g. because of invalid records.
+    Custom(String),
+}
+
+impl Display for BackendError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::TableGen(error) => write!(f, "{}", error),
+            Self::Io(error) => write!(f, "failed to write output: {}", error),
+            Self::UnknownBackend(name) => write!(f, "unknown backend: {}", name),
+            Self::Custom(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for BackendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::TableGen(error) => Some(error),
+            Self::Io(error) => Some(error),
+            Self::UnknownBackend(_) | Self::Custom(_) => None,
+        }
+    }
+}
+
+impl From<Error> for BackendError {
+    fn from(error: Error) -> Self {
+        Self::TableGen(error)
+    }
+}
+
+impl From<io::Error> for BackendError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Registry of backends.
+#[derive(Default)]
+pub struct Backends {
+    backends: Vec<Box<dyn TableGenBackend>>,
+}
+
+impl fmt::Debug for Backends {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.backends.iter().map(|backend| backend.name()))
+            .finish()
+    }
+}
+
+impl Backends {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a backend, replacing any backend with the same name.
+    pub fn register(&mut self, backend: impl TableGenBackend + 'static) -> &mut Self {
+        self.backends.retain(|other| other.name() != backend.name());
+        self.backends.push(Box::new(backend));
+        self
+    }
+
+    /// Returns the backend with the given name.
+    pub fn get(&self, name: &str) -> Option<&dyn TableGenBackend> {
+        self.backends
+            .iter()
+            .find(|backend| backend.name() == name)
+            .map(Box::as_ref)
+    }
+
+    /// Returns an iterator over all backends in the order they were
+    /// registered.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn TableGenBackend> {
+        self.backends.iter().map(Box::as_ref)
+    }
+
+    /// Runs the backend with the given name and returns the time it took.
+    pub fn run(
+        &self,
+        name: &str,
+        keeper: &RecordKeeper,
+        output: &mut dyn Write,
+    ) -> Result<Duration, BackendError> {
+        let backend = self
+            .get(name)
+            .ok_or_else(|| BackendError::UnknownBackend(name.into()))?;
+        let start = Instant::now();
+        backend.run(keeper, output)?;
+        Ok(start.elapsed())
+    }
+
+    /// Parses the input file of the given options and runs the backend
+    /// selected by them, like `llvm-tblgen` does.
+    ///
+    /// The backend is selected by the first argument of
+    /// [`other_args`](TableGenOptions::other_args) that names a registered
+    /// backend (e.g. `-gen-enums` or `--gen-enums`). The output is written to
+    /// the output file, or to the standard output if there is none or it is
+    /// `-`. An existing output file is only overwritten if its contents
+    /// changed, so that build systems do not rebuild its dependents.
+    pub fn run_options(&self, options: &TableGenOptions) -> Result<Duration, BackendError> {
+        let name = options
+            .other_args
+            .iter()
+            .map(|arg| arg.trim_start_matches('-'))
+            .find(|name| self.get(name).is_some())
+            .ok_or_else(|| BackendError::UnknownBackend(options.other_args.join(" ")))?;
+        let keeper = options.parser().parse()?;
+
+        let mut output = Vec::new();
+        let duration = self.run(name, &keeper, &mut output)?;
+        match options.output_filename.as_deref() {
+            None | Some("-") => io::stdout().write_all(&output)?,
+            Some(path) => write_if_changed(Path::new(path), &output)?,
+        }
+        Ok(duration)
+    }
+}
+
+/// Writes a file, unless it already has the given contents, so that build
+/// systems do not rebuild its dependents.
+pub(crate) fn write_if_changed(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if fs::read(path).ok().as_deref() != Some(contents) {
+        fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+/// Length of the lines of the header, including the comment delimiters.
+const HEADER_WIDTH: usize = 80;
+
+/// Writes the header of generated files that `llvm-tblgen` backends emit,
+/// with the given description and the name of the input file of the record
+/// keeper.
+///
+/// ```text
+/// /*===- TableGen'erated file -------------------------------------*- C++ -*-===*\
+/// |*                                                                            *|
+/// |* Enum definitions                                                           *|
+/// |*                                                                            *|
+/// |* Automatically generated file, do not edit!                                 *|
+/// |* From: Kinds.td                                                             *|
+/// |*                                                                            *|
+/// \*===----------------------------------------------------------------------===*/
+/// ```
+pub fn emit_source_file_header(
+    description: &str,
+    keeper: &RecordKeeper,
+    output: &mut dyn Write,
+) -> io::Result<()> {
+    const PREFIX: &str = "|* ";
+    const SUFFIX: &str = " *|";
+
+    let line = |output: &mut dyn Write, start: &str, fill: char, end: &str| {
+        let count = (HEADER_WIDTH - end.len()).saturating_sub(start.chars().count());
+        writeln!(output, "{}{}{}", start, fill.to_string().repeat(count), end)
+    };
+    let text = |output: &mut dyn Write, text: &str| {
+        line(output, &format!("{}{}", PREFIX, text), ' ', SUFFIX)
+    };
+
+    line(
+        output,
+        "/*===- TableGen'erated file ",
+        '-',
+        "*- C++ -*-===*\\",
+    )?;
+    text(output, "")?;
+    let chars = description.chars().collect::<Vec<_>>();
+    for chunk in chars.chunks(HEADER_WIDTH - PREFIX.len() - SUFFIX.len()) {
+        text(output, &chunk.iter().collect::<String>())?;
+    }
+    if chars.is_empty() {
+        text(output, "")?;
+    }
+    text(output, "")?;
+    text(output, "Automatically generated file, do not edit!")?;
+    if let Some(name) = keeper
+        .input_filename()
+        .ok()
+        .and_then(|name| Path::new(name).file_name())
+    {
+        text(output, &format!("From: {}", name.to_string_lossy()))?;
+    }
+    text(output, "")?;
+    line(output, "\\*===", '-', "===*/")?;
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TableGenParser, error::TableGenError};
+
+    struct Defs;
+
+    impl TableGenBackend for Defs {
+        fn name(&self) -> &str {
+            "print-defs"
+        }
+
+        fn run(&self, keeper: &RecordKeeper, output: &mut dyn Write) -> Result<(), BackendError> {
+            for (name, _) in keeper.defs() {
+                writeln!(
+                    output,
+                    "{}",
+                    name.map_err(|error| Error::from(TableGenError::from(error)))?
+                )?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn registry() {
+        let keeper = TableGenParser::new()
+            .add_source("def A; def B;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let mut backends = Backends::new();
+        backends.register(Defs).register(Defs);
+        assert_eq!(format!("{:?}", backends), "[\"print-defs\"]");
+        assert_eq!(backends.get("print-defs").unwrap().description(), "");
+
+        let mut output = Vec::new();
+        backends.run("print-defs", &keeper, &mut output).unwrap();
+        assert_eq!(output, b"A\nB\n");
+        assert!(matches!(
+            backends.run("gen-defs", &keeper, &mut output),
+            Err(BackendError::UnknownBackend(name)) if name == "gen-defs"
+        ));
+    }
+
+    #[test]
+    fn run_options() {
+        let directory = std::env::temp_dir().join("tblgen-backend-run-options");
+        fs::create_dir_all(&directory).unwrap();
+        let input = directory.join("Defs.td");
+        let output = directory.join("Defs.inc");
+        fs::write(&input, "def A;").unwrap();
+
+        let mut backends = Backends::new();
+        backends.register(Defs);
+        let options = TableGenOptions::from_args([
+            "--print-defs",
+            input.to_str().unwrap(),
+            "-o",
+            output.to_str().unwrap(),
+        ])
+        .unwrap();
+        backends.run_options(&options).unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap(), "A\n");
+        assert!(matches!(
+            Backends::new().run_options(&options),
+            Err(BackendError::UnknownBackend(_))
+        ));
+    }
+
+    #[test]
+    fn source_file_header() {
+        let keeper = TableGenParser::new()
+            .add_source("def A;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let mut output = Vec::new();
+        emit_source_file_header("Enum definitions", &keeper, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert!(lines[..lines.len() - 1].iter().all(|line| line.len() == 80));
+        assert_eq!(
+            lines[2],
+            "|* Enum definitions                                                           *|"
+        );
+        assert_eq!(
+            lines[4],
+            "|* Automatically generated file, do not edit!                                 *|"
+        );
+        assert!(lines[lines.len() - 2].starts_with("\\*===---"));
+        assert_eq!(lines[lines.len() - 1], "");
+    }
+}
@@ -0,0 +1,179 @@
+//! Encoding of values in the binary MessagePack and CBOR formats.
+
+use std::io::{self, Write};
+
+use crate::json::Json;
+
+/// Writes a value in the MessagePack format, using the smallest encoding of
+/// each integer, string, array and map.
+pub(crate) fn write_msgpack(value: &Json, writer: &mut impl Write) -> io::Result<()> {
+    match value {
+        Json::Null => writer.write_all(&[0xc0]),
+        Json::Bool(value) => writer.write_all(&[if *value { 0xc3 } else { 0xc2 }]),
+        Json::Int(value) => match *value {
+            // Positive and negative fixints.
+            value @ -32..0x80 => writer.write_all(&[value as u8]),
+            value @ 0x80..0x100 => writer.write_all(&[0xcc, value as u8]),
+            value @ 0x100..0x1_0000 => msgpack_head(writer, 0xcd, &(value as u16).to_be_bytes()),
+            value @ 0x1_0000..0x1_0000_0000 => {
+                msgpack_head(writer, 0xce, &(value as u32).to_be_bytes())
+            }
+            value @ 0x1_0000_0000.. => msgpack_head(writer, 0xcf, &value.to_be_bytes()),
+            value @ -0x80..-32 => writer.write_all(&[0xd0, value as u8]),
+            value @ -0x8000..-0x80 => msgpack_head(writer, 0xd1, &(value as i16).to_be_bytes()),
+            value @ -0x8000_0000..-0x8000 => {
+                msgpack_head(writer, 0xd2, &(value as i32).to_be_bytes())
+            }
+            value => msgpack_head(writer, 0xd3, &value.to_be_bytes()),
+        },
+        Json::String(value) => {
+            msgpack_length(writer, value.len(), 0xa0, 32, [0xd9, 0xda, 0xdb])?;
+            writer.write_all(value.as_bytes())
+        }
+        Json::Array(values) => {
+            msgpack_length(writer, values.len(), 0x90, 16, [0, 0xdc, 0xdd])?;
+            values
+                .iter()
+                .try_for_each(|value| write_msgpack(value, writer))
+        }
+        Json::Object(entries) => {
+            msgpack_length(writer, entries.len(), 0x80, 16, [0, 0xde, 0xdf])?;
+            entries.iter().try_for_each(|(key, value)| {
+                write_msgpack(&Json::String(key.clone()), writer)?;
+                write_msgpack(value, writer)
+            })
+        }
+    }
+}
+
+fn msgpack_head(writer: &mut impl Write, marker: u8, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(&[marker])?;
+    writer.write_all(bytes)
+}
+
+/// Writes the length of a string, array or map, with a fixed size marker if
+/// the length is below `fixed_limit` and with 8, 16 or 32 bit markers
+/// otherwise. Arrays and maps have no 8 bit marker.
+fn msgpack_length(
+    writer: &mut impl Write,
+    length: usize,
+    fixed: u8,
+    fixed_limit: usize,
+    markers: [u8; 3],
+) -> io::Result<()> {
+    if length < fixed_limit {
+        writer.write_all(&[fixed | length as u8])
+    } else if markers[0] != 0 && length <= 0xff {
+        writer.write_all(&[markers[0], length as u8])
+    } else if let Ok(length) = u16::try_from(length) {
+        msgpack_head(writer, markers[1], &length.to_be_bytes())
+    } else {
+        let length = u32::try_from(length)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "value too large"))?;
+        msgpack_head(writer, markers[2], &length.to_be_bytes())
+    }
+}
+
+/// Writes a value in the CBOR format, using definite lengths and the smallest
+/// encoding of each integer and length.
+pub(crate) fn write_cbor(value: &Json, writer: &mut impl Write) -> io::Result<()> {
+    match value {
+        Json::Null => writer.write_all(&[0xf6]),
+        Json::Bool(value) => writer.write_all(&[if *value { 0xf5 } else { 0xf4 }]),
+        Json::Int(value) if *value >= 0 => cbor_head(writer, 0, *value as u64),
+        Json::Int(value) => cbor_head(writer, 1, !*value as u64),
+        Json::String(value) => {
+            cbor_head(writer, 3, value.len() as u64)?;
+            writer.write_all(value.as_bytes())
+        }
+        Json::Array(values) => {
+            cbor_head(writer, 4, values.len() as u64)?;
+            values
+                .iter()
+                .try_for_each(|value| write_cbor(value, writer))
+        }
+        Json::Object(entries) => {
+            cbor_head(writer, 5, entries.len() as u64)?;
+            entries.iter().try_for_each(|(key, value)| {
+                write_cbor(&Json::String(key.clone()), writer)?;
+                write_cbor(value, writer)
+            })
+        }
+    }
+}
+
+/// Writes the initial byte of a data item of the given major type, followed
+/// by its argument.
+fn cbor_head(writer: &mut impl Write, major: u8, argument: u64) -> io::Result<()> {
+    let major = major << 5;
+    match argument {
+        0..24 => writer.write_all(&[major | argument as u8]),
+        24..0x100 => writer.write_all(&[major | 24, argument as u8]),
+        0x100..0x1_0000 => {
+            writer.write_all(&[major | 25])?;
+            writer.write_all(&(argument as u16).to_be_bytes())
+        }
+        0x1_0000..0x1_0000_0000 => {
+            writer.write_all(&[major | 26])?;
+            writer.write_all(&(argument as u32).to_be_bytes())
+        }
+        _ => {
+            writer.write_all(&[major | 27])?;
+            writer.write_all(&argument.to_be_bytes())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msgpack(value: Json) -> Vec<u8> {
+        let mut output = Vec::new();
+        write_msgpack(&value, &mut output).unwrap();
+        output
+    }
+
+    fn cbor(value: Json) -> Vec<u8> {
+        let mut output = Vec::new();
+        write_cbor(&value, &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn msgpack_values() {
+        assert_eq!(msgpack(Json::Null), [0xc0]);
+        assert_eq!(msgpack(true.into()), [0xc3]);
+        assert_eq!(msgpack(Json::Int(5)), [0x05]);
+        assert_eq!(msgpack(Json::Int(-1)), [0xff]);
+        assert_eq!(msgpack(Json::Int(200)), [0xcc, 200]);
+        assert_eq!(msgpack(Json::Int(-200)), [0xd1, 0xff, 0x38]);
+        assert_eq!(msgpack(Json::Int(1 << 40)), [0xcf, 0, 0, 1, 0, 0, 0, 0, 0]);
+        assert_eq!(msgpack("ab".into()), [0xa2, b'a', b'b']);
+        assert_eq!(msgpack("a".repeat(40).into())[..2], [0xd9, 40]);
+        assert_eq!(
+            msgpack(Json::Array(vec![Json::Null; 20]))[..3],
+            [0xdc, 0, 20]
+        );
+        assert_eq!(
+            msgpack(Json::object([("a", Json::Int(1))])),
+            [0x81, 0xa1, b'a', 0x01]
+        );
+    }
+
+    #[test]
+    fn cbor_values() {
+        assert_eq!(cbor(Json::Null), [0xf6]);
+        assert_eq!(cbor(false.into()), [0xf4]);
+        assert_eq!(cbor(Json::Int(23)), [0x17]);
+        assert_eq!(cbor(Json::Int(24)), [0x18, 24]);
+        assert_eq!(cbor(Json::Int(-1)), [0x20]);
+        assert_eq!(cbor(Json::Int(-500)), [0x39, 0x01, 0xf3]);
+        assert_eq!(cbor(Json::Int(1 << 32)), [0x1b, 0, 0, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(cbor("ab".into()), [0x62, b'a', b'b']);
+        assert_eq!(
+            cbor(Json::object([("a", Json::Array(vec![Json::Int(1)]))])),
+            [0xa1, 0x61, b'a', 0x81, 0x01]
+        );
+    }
+}
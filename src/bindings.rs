@@ -0,0 +1,319 @@
+//! Generation of Rust structs mirroring TableGen classes, e.g. in build
+//! scripts.
+//!
+//! For each selected class, a struct with the name of the class is generated
+//! with a field for each field of the class (template arguments excluded),
+//! and the `record` field with the def itself. The structs implement
+//! [`FromRecord`](crate::FromRecord) and have an `all` function that returns
+//! all defs deriving from the class. Field types are mapped as follows:
+//!
+//! | TableGen  | Rust               |
+//! | --------- | ------------------ |
+//! | `bit`     | `bool`             |
+//! | `bits<n>` | [`BitsInit`]       |
+//! | `int`     | `i64`              |
+//! | `string`  | `&str`             |
+//! | `list<T>` | `Vec<T>`           |
+//! | `dag`     | [`DagInit`]        |
+//! | classes   | [`Record`]         |
+//!
+//! Fields that are unset in the class (e.g. `string summary = ?;`) are
+//! wrapped in an `Option`, which is `None` if the field is still unset in a
+//! def. Elements of lists of lists are [`ListInit`](crate::init::ListInit)s.
+//!
+//! ```rust,no_run
+//! // build.rs
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let out_dir = std::env::var("OUT_DIR")?;
+//!     tblgen::generate_bindings("src/Ops.td", &["Op"], &out_dir)?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! ```rust,ignore
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/Ops.rs"));
+//!
+//! fn summaries(keeper: &tblgen::RecordKeeper) -> Result<Vec<String>, tblgen::Error> {
+//!     Ok(Op::all(keeper)?.iter().map(|op| op.summary.to_string()).collect())
+//! }
+//! ```
+//!
+//! [`BitsInit`]: crate::init::BitsInit
+//! [`DagInit`]: crate::init::DagInit
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    Record, RecordKeeper,
+    backend::{BackendError, TableGenBackend},
+    build::Codegen,
+    schema::FieldSchema,
+    ty::RecTy,
+    util::escape_keyword,
+};
+
+/// Backend that generates Rust structs mirroring the given classes.
+///
+/// See the [module documentation](self) for the generated code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bindings {
+    classes: Vec<String>,
+}
+
+impl Bindings {
+    /// Creates a backend that generates structs for the given classes.
+    pub fn new(classes: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            classes: classes.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TableGenBackend for Bindings {
+    fn name(&self) -> &str {
+        "gen-rust-bindings"
+    }
+
+    fn description(&self) -> &str {
+        "Generate Rust structs mirroring classes"
+    }
+
+    fn run(&self, keeper: &RecordKeeper, output: &mut dyn Write) -> Result<(), BackendError> {
+        write!(output, "// Automatically generated by tblgen")?;
+        if let Some(name) = keeper
+            .input_filename()
+            .ok()
+            .and_then(|name| Path::new(name).file_name())
+        {
+            write!(output, " from {}", name.to_string_lossy())?;
+        }
+        writeln!(output, ", do not edit!")?;
+
+        for class in &self.classes {
+            writeln!(output)?;
+            write_struct(output, keeper.class(class)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a TableGen file and writes structs mirroring the given classes to
+/// a file in the given directory, which is named after the TableGen file (e.g.
+/// `Ops.rs` for `Ops.td`). Returns the path of the written file.
+///
+/// The directory of the TableGen file is added to the include directories.
+/// Use the [`Bindings`] backend with [`Codegen`] to parse files with other
+/// options. Like [`Codegen`], this prints `cargo::rerun-if-changed` for the
+/// TableGen file and every file it includes.
+pub fn generate_bindings(
+    td_path: impl AsRef<Path>,
+    classes: &[&str],
+    out_dir: impl AsRef<Path>,
+) -> Result<PathBuf, BackendError> {
+    let td_path = td_path.as_ref();
+    let mut codegen = Codegen::new(td_path).out_dir(out_dir);
+    if let Some(directory) = td_path.parent() {
+        codegen = codegen.include_directory(directory);
+    }
+    codegen.run_backend(&Bindings::new(classes.iter().copied()))
+}
+
+fn write_struct(output: &mut dyn Write, class: Record) -> Result<(), BackendError> {
+    let schema = class.schema()?;
+    let name = schema.name()?;
+    let fields = schema
+        .fields()
+        .iter()
+        .filter(|field| !field.is_template_arg())
+        .map(|field| {
+            let ty = field.ty().ok_or_else(|| {
+                BackendError::Custom(format!("field {} of {} has no type", field.name(), name))
+            })?;
+            Ok((field, ty))
+        })
+        .collect::<Result<Vec<_>, BackendError>>()?;
+    let ident = escape_keyword(name);
+
+    writeln!(
+        output,
+        "/// Def deriving from the TableGen class `{}`.",
+        name
+    )?;
+    writeln!(output, "#[allow(non_camel_case_types, non_snake_case)]")?;
+    writeln!(output, "#[derive(Debug, Clone, PartialEq, Eq)]")?;
+    writeln!(output, "pub struct {}<'a> {{", ident)?;
+    writeln!(output, "    /// The def.")?;
+    writeln!(output, "    pub record: ::tblgen::Record<'a>,")?;
+    for &(field, ty) in &fields {
+        writeln!(output, "    /// Field `{}` of type `{}`.", field.name(), ty)?;
+        let mut rust_type = rust_type(ty);
+        if is_optional(field, ty) {
+            rust_type = format!("::core::option::Option<{}>", rust_type);
+        }
+        writeln!(
+            output,
+            "    pub {}: {},",
+            field_identifier(field),
+            rust_type
+        )?;
+    }
+    writeln!(output, "}}\n")?;
+
+    writeln!(
+        output,
+        "impl<'a> ::tblgen::FromRecord<'a> for {}<'a> {{",
+        ident
+    )?;
+    writeln!(output, "    fn from_record(")?;
+    writeln!(output, "        record: ::tblgen::Record<'a>,")?;
+    writeln!(
+        output,
+        "    ) -> ::core::result::Result<Self, ::tblgen::Error> {{"
+    )?;
+    writeln!(output, "        ::core::result::Result::Ok(Self {{")?;
+    writeln!(output, "            record,")?;
+    for &(field, ty) in &fields {
+        let value = value_code(field.name(), ty);
+        let value = if is_optional(field, ty) {
+            format!(
+                "if record.is_value_unset({:?}) {{ ::core::option::Option::None }} \
+                 else {{ ::core::option::Option::Some({}) }}",
+                field.name(),
+                value
+            )
+        } else {
+            value
+        };
+        writeln!(
+            output,
+            "            {}: {},",
+            field_identifier(field),
+            value
+        )?;
+    }
+    writeln!(output, "        }})")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "impl<'a> {}<'a> {{", ident)?;
+    writeln!(output, "    /// Name of the TableGen class.")?;
+    writeln!(output, "    pub const CLASS: &'static str = {:?};\n", name)?;
+    writeln!(output, "    /// Returns all defs deriving from the class.")?;
+    writeln!(output, "    pub fn all(")?;
+    writeln!(output, "        keeper: &'a ::tblgen::RecordKeeper<'_>,")?;
+    writeln!(
+        output,
+        "    ) -> ::core::result::Result<::std::vec::Vec<Self>, ::tblgen::Error> {{"
+    )?;
+    writeln!(output, "        keeper")?;
+    writeln!(output, "            .all_derived_definitions(Self::CLASS)?")?;
+    writeln!(
+        output,
+        "            .map(::tblgen::FromRecord::from_record)"
+    )?;
+    writeln!(output, "            .collect()")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "}}")?;
+    Ok(())
+}
+
+/// Returns true if the field is unset in the class and is therefore read as
+/// an `Option`. Bits are never unset as a whole.
+fn is_optional(field: &FieldSchema, ty: RecTy) -> bool {
+    field.default().is_none() && !matches!(ty, RecTy::Bits(_))
+}
+
+fn rust_type(ty: RecTy) -> String {
+    match ty {
+        RecTy::Bit(_) => "bool".into(),
+        RecTy::Bits(_) => "::tblgen::init::BitsInit<'a>".into(),
+        RecTy::Int(_) => "i64".into(),
+        RecTy::String(_) => "&'a str".into(),
+        RecTy::List(ty) => format!(
+            "::std::vec::Vec<{}>",
+            match ty.element_type() {
+                Some(RecTy::List(_)) | None => "::tblgen::init::ListInit<'a>".into(),
+                Some(ty) => rust_type(ty),
+            }
+        ),
+        RecTy::Dag(_) => "::tblgen::init::DagInit<'a>".into(),
+        RecTy::Record(_) => "::tblgen::Record<'a>".into(),
+    }
+}
+
+fn value_code(name: &str, ty: RecTy) -> String {
+    match ty {
+        RecTy::List(_) => format!("record.value({:?})?.list_elements()?", name),
+        _ => format!("record.get({:?})?", name),
+    }
+}
+
+fn field_identifier(field: &FieldSchema) -> String {
+    match field.name() {
+        // Reserved for the def itself.
+        "record" => "record_".into(),
+        name => escape_keyword(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    #[test]
+    fn bindings() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class Base;
+                def ins;
+                class Op<string n> {
+                    string name = n;
+                    string summary = ?;
+                    bit commutative = 0;
+                    bits<4> opcode;
+                    list<int> sizes = [];
+                    list<list<string>> names = [];
+                    dag args = (ins);
+                    Base type = ?;
+                    int record = 0;
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let mut output = Vec::new();
+        Bindings::new(["Op"]).run(&rk, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("pub struct Op<'a> {"));
+        for field in [
+            "pub name: &'a str,",
+            "pub summary: ::core::option::Option<&'a str>,",
+            "pub commutative: bool,",
+            "pub opcode: ::tblgen::init::BitsInit<'a>,",
+            "pub sizes: ::std::vec::Vec<i64>,",
+            "pub names: ::std::vec::Vec<::tblgen::init::ListInit<'a>>,",
+            "pub args: ::tblgen::init::DagInit<'a>,",
+            "pub r#type: ::core::option::Option<::tblgen::Record<'a>>,",
+            "pub record_: i64,",
+            "name: record.get(\"name\")?,",
+            "sizes: record.value(\"sizes\")?.list_elements()?,",
+            "summary: if record.is_value_unset(\"summary\") {",
+            "pub const CLASS: &'static str = \"Op\";",
+        ] {
+            assert!(output.contains(field), "{}", field);
+        }
+        assert!(
+            Bindings::new(["Missing"])
+                .run(&rk, &mut Vec::new())
+                .is_err()
+        );
+    }
+}
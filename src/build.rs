@@ -0,0 +1,215 @@
+//! Helpers to generate code from TableGen files in build scripts.
+//!
+//! ```rust,no_run
+//! // build.rs
+//! use std::io::Write;
+//! use tblgen::build::Codegen;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     Codegen::new("src/Kinds.td")
+//!         .include_directory("include")
+//!         .output("kinds.rs")
+//!         .run(|keeper, output| {
+//!             writeln!(output, "pub const KINDS: &[&str] = &[")?;
+//!             for def in keeper.all_derived_definitions("Kind")? {
+//!                 writeln!(output, "    {:?},", def.name()?)?;
+//!             }
+//!             writeln!(output, "];")?;
+//!             Ok(())
+//!         })?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::{
+    env,
+    ffi::OsStr,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    RecordKeeper, TableGenParser,
+    backend::{self, BackendError, TableGenBackend},
+};
+
+/// Generates a file in `OUT_DIR` from a TableGen file.
+///
+/// Running the generator parses the TableGen file, passes the records to a
+/// callback or backend, writes its output to the output file and prints
+/// `cargo::rerun-if-changed` for the TableGen file and every file it
+/// includes, so that Cargo reruns the build script when any of them changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Codegen {
+    input: PathBuf,
+    include_directories: Vec<PathBuf>,
+    macros: Vec<String>,
+    output: Option<PathBuf>,
+    out_dir: Option<PathBuf>,
+}
+
+impl Codegen {
+    /// Creates a generator for the given TableGen file.
+    pub fn new(input: impl AsRef<Path>) -> Self {
+        Self {
+            input: input.as_ref().into(),
+            include_directories: Vec::new(),
+            macros: Vec::new(),
+            output: None,
+            out_dir: None,
+        }
+    }
+
+    /// Adds a directory to search for included files.
+    pub fn include_directory(mut self, directory: impl AsRef<Path>) -> Self {
+        self.include_directories.push(directory.as_ref().into());
+        self
+    }
+
+    /// Defines a macro for the preprocessor.
+    pub fn define_macro(mut self, name: &str) -> Self {
+        self.macros.push(name.into());
+        self
+    }
+
+    /// Sets the name of the output file, relative to the output directory.
+    ///
+    /// Defaults to the name of the TableGen file with the `rs` extension
+    /// (e.g. `Ops.rs` for `Ops.td`).
+    pub fn output(mut self, name: impl AsRef<Path>) -> Self {
+        self.output = Some(name.as_ref().into());
+        self
+    }
+
+    /// Sets the output directory, which defaults to the `OUT_DIR` environment
+    /// variable set by Cargo.
+    pub fn out_dir(mut self, directory: impl AsRef<Path>) -> Self {
+        self.out_dir = Some(directory.as_ref().into());
+        self
+    }
+
+    /// Runs a callback that writes the output, and returns the path of the
+    /// output file.
+    ///
+    /// The output file is only overwritten if its contents changed, so that
+    /// Cargo does not rebuild the crate unnecessarily.
+    pub fn run(
+        &self,
+        generate: impl FnOnce(&RecordKeeper, &mut dyn Write) -> Result<(), BackendError>,
+    ) -> Result<PathBuf, BackendError> {
+        self.generate(generate, &mut io::stdout())
+    }
+
+    /// Runs a backend, and returns the path of the output file.
+    pub fn run_backend(&self, backend: &dyn TableGenBackend) -> Result<PathBuf, BackendError> {
+        self.run(|keeper, output| backend.run(keeper, output))
+    }
+
+    fn generate(
+        &self,
+        generate: impl FnOnce(&RecordKeeper, &mut dyn Write) -> Result<(), BackendError>,
+        cargo: &mut dyn Write,
+    ) -> Result<PathBuf, BackendError> {
+        let path = match &self.out_dir {
+            Some(directory) => directory.clone(),
+            None => env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or_else(|| BackendError::Custom("OUT_DIR is not set".into()))?,
+        }
+        .join(match &self.output {
+            Some(name) => name.clone(),
+            None => {
+                Path::new(self.input.file_name().unwrap_or(OsStr::new("out"))).with_extension("rs")
+            }
+        });
+
+        let mut parser = TableGenParser::new().add_source_file(&self.input.to_string_lossy());
+        for directory in &self.include_directories {
+            parser = parser.add_include_directory(&directory.to_string_lossy());
+        }
+        for name in &self.macros {
+            parser = parser.define_macro(name);
+        }
+        // Printed before parsing, so that Cargo reruns the build script once
+        // a syntax error is fixed.
+        writeln!(cargo, "cargo::rerun-if-changed={}", self.input.display())?;
+        let keeper = parser.parse()?;
+
+        let mut files = Vec::new();
+        for file in keeper.include_graph().files() {
+            let name = Path::new(file.name());
+            // Skip in-memory sources and the input itself.
+            if name.is_file() && !files.contains(&name) && name != self.input {
+                files.push(name);
+                writeln!(cargo, "cargo::rerun-if-changed={}", name.display())?;
+            }
+        }
+
+        let mut output = Vec::new();
+        generate(&keeper, &mut output)?;
+        backend::write_if_changed(&path, &output)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn codegen() {
+        let directory = env::temp_dir().join("tblgen-build-codegen");
+        let include = directory.join("include");
+        fs::create_dir_all(&include).unwrap();
+        let input = directory.join("Kinds.td");
+        fs::write(
+            &input,
+            "include \"Kind.td\"\n#ifdef B\ndef B : Kind;\n#endif\ndef A : Kind;",
+        )
+        .unwrap();
+        fs::write(include.join("Kind.td"), "class Kind;").unwrap();
+
+        let codegen = Codegen::new(&input)
+            .include_directory(&include)
+            .define_macro("B")
+            .out_dir(&directory);
+        let mut cargo = Vec::new();
+        let path = codegen
+            .generate(
+                |keeper, output| {
+                    for def in keeper.all_derived_definitions("Kind")? {
+                        writeln!(output, "{}", def.name()?)?;
+                    }
+                    Ok(())
+                },
+                &mut cargo,
+            )
+            .unwrap();
+        assert_eq!(path, directory.join("Kinds.rs"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "A\nB\n");
+        let cargo = String::from_utf8(cargo).unwrap();
+        assert_eq!(
+            cargo.lines().collect::<Vec<_>>(),
+            [
+                format!("cargo::rerun-if-changed={}", input.display()),
+                format!(
+                    "cargo::rerun-if-changed={}",
+                    include.join("Kind.td").display()
+                ),
+            ]
+        );
+
+        let path = codegen
+            .output("kinds.txt")
+            .generate(|_, _| Ok(()), &mut Vec::new())
+            .unwrap();
+        assert_eq!(path, directory.join("kinds.txt"));
+        assert!(
+            Codegen::new(directory.join("Missing.td"))
+                .out_dir(&directory)
+                .generate(|_, _| Ok(()), &mut Vec::new())
+                .is_err()
+        );
+    }
+}
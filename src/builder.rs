@@ -0,0 +1,414 @@
+//! Construction of record keepers from Rust, e.g. for unit tests of code
+//! generators.
+//!
+//! ```rust
+//! use tblgen::{RecordKeeperBuilder, builder::{FieldValue, RecordBuilder}};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = RecordKeeperBuilder::new()
+//!     .record(RecordBuilder::class("Op").field("name", "").field("size", 1))
+//!     .record(RecordBuilder::def("i32"))
+//!     .record(
+//!         RecordBuilder::def("AddOp")
+//!             .super_class("Op")
+//!             .field("name", "add")
+//!             .field("operands", FieldValue::dag("ins", [FieldValue::def("i32")])),
+//!     )
+//!     .build()?;
+//! let add = keeper.def("AddOp")?;
+//! assert!(add.subclass_of("Op"));
+//! assert_eq!(add.str_value("name")?, "add");
+//! assert_eq!(add.int_value("size")?, 1);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The records are created with TableGen's record API instead of being
+//! parsed from source code, but resolved like parsed records, e.g. defs
+//! inherit and resolve the fields of their super classes. Records must be
+//! added before they are referenced, and have no source location.
+
+use std::{ffi::c_void, ptr};
+
+use crate::{
+    Error, RecordKeeper, TableGenParser,
+    error::TableGenError,
+    raw::{
+        TableGenRecTyRef, TableGenRecordRef, TableGenStringRef, TableGenTypedInitRef,
+        tableGenBitInitGet, tableGenBitsInitGet, tableGenCodeInitGet, tableGenDagInitGet,
+        tableGenIntInitGet, tableGenListInitGet, tableGenRecordKeeperAddRecord,
+        tableGenRecordKeeperCreate, tableGenRecordKeeperParseType, tableGenStringInitGet,
+    },
+    record_keeper::RecordKeeperRef,
+    string_ref::StringRef,
+    util::print_string_callback,
+};
+
+/// Value of a field of a [`RecordBuilder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldValue {
+    /// A `bit`.
+    Bit(bool),
+    /// A `bits<n>` value with the most significant bit first.
+    Bits(Vec<bool>),
+    /// An `int`.
+    Int(i64),
+    /// A `string`.
+    String(String),
+    /// A `code` string.
+    Code(String),
+    /// A list with the given element type, e.g. `int`.
+    List(String, Vec<FieldValue>),
+    /// A def, whose type is its first super class.
+    Def(String),
+    /// A dag with the given operator and named or unnamed arguments.
+    Dag(String, Vec<(FieldValue, Option<String>)>),
+    /// An unset value (`?`) of the given type.
+    Unset(String),
+}
+
+impl FieldValue {
+    /// Creates a list with the given element type.
+    pub fn list(ty: &str, elements: impl IntoIterator<Item = impl Into<FieldValue>>) -> Self {
+        Self::List(ty.into(), elements.into_iter().map(Into::into).collect())
+    }
+
+    /// Creates a reference to the def with the given name.
+    pub fn def(name: &str) -> Self {
+        Self::Def(name.into())
+    }
+
+    /// Creates a dag with the given operator and unnamed arguments.
+    pub fn dag(operator: &str, args: impl IntoIterator<Item = impl Into<FieldValue>>) -> Self {
+        Self::Dag(
+            operator.into(),
+            args.into_iter().map(|arg| (arg.into(), None)).collect(),
+        )
+    }
+
+    /// Returns the init of this value, or null for an unset value.
+    fn init(&self, keeper: RecordKeeperRef) -> Result<TableGenTypedInitRef, Error> {
+        let raw = keeper.to_raw();
+        Ok(match self {
+            Self::Bit(bit) => unsafe { tableGenBitInitGet(raw, (*bit).into()) },
+            Self::Bits(bits) => {
+                // TableGen stores the least significant bit first.
+                let bits = bits.iter().rev().map(|&bit| bit.into()).collect::<Vec<_>>();
+                unsafe { tableGenBitsInitGet(raw, bits.as_ptr(), bits.len()) }
+            }
+            Self::Int(value) => unsafe { tableGenIntInitGet(raw, *value) },
+            Self::String(value) => unsafe {
+                tableGenStringInitGet(raw, StringRef::from(value.as_str()).to_raw())
+            },
+            Self::Code(value) => unsafe {
+                tableGenCodeInitGet(raw, StringRef::from(value.as_str()).to_raw())
+            },
+            Self::List(ty, elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| element.init(keeper))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let list = unsafe {
+                    tableGenListInitGet(
+                        raw,
+                        elements.as_ptr(),
+                        elements.len(),
+                        parse_type(keeper, ty)?,
+                    )
+                };
+                if list.is_null() {
+                    return Err(invalid(format!("list element does not match type {}", ty)));
+                }
+                list
+            }
+            Self::Def(name) => keeper.def(name)?.def_init().to_raw(),
+            Self::Dag(operator, args) => {
+                let operator = keeper.def(operator)?.def_init().to_raw();
+                let (values, names) = args
+                    .iter()
+                    .map(|(value, name)| {
+                        let name = match name {
+                            Some(name) => unsafe { StringRef::from(name.as_str()).to_raw() },
+                            None => TableGenStringRef {
+                                data: ptr::null(),
+                                len: 0,
+                            },
+                        };
+                        Ok((value.init(keeper)?, name))
+                    })
+                    .collect::<Result<(Vec<_>, Vec<_>), Error>>()?;
+                unsafe {
+                    tableGenDagInitGet(raw, operator, values.as_ptr(), names.as_ptr(), values.len())
+                }
+            }
+            Self::Unset(_) => ptr::null_mut(),
+        })
+    }
+
+    /// Returns the type of a new field with this value, or null if it is the
+    /// type of the init.
+    fn field_type(&self, keeper: RecordKeeperRef) -> Result<TableGenRecTyRef, Error> {
+        match self {
+            Self::Unset(ty) => parse_type(keeper, ty),
+            _ => Ok(ptr::null_mut()),
+        }
+    }
+}
+
+impl From<bool> for FieldValue {
+    fn from(value: bool) -> Self {
+        Self::Bit(value)
+    }
+}
+
+impl From<i32> for FieldValue {
+    fn from(value: i32) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<i64> for FieldValue {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+/// Class or def of a [`RecordKeeperBuilder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordBuilder {
+    class: bool,
+    name: String,
+    super_classes: Vec<String>,
+    fields: Vec<(String, FieldValue)>,
+}
+
+impl RecordBuilder {
+    /// Creates a class with the given name.
+    pub fn class(name: &str) -> Self {
+        Self::new(true, name)
+    }
+
+    /// Creates a def with the given name.
+    pub fn def(name: &str) -> Self {
+        Self::new(false, name)
+    }
+
+    fn new(class: bool, name: &str) -> Self {
+        Self {
+            class,
+            name: name.into(),
+            super_classes: Vec::new(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Adds a direct super class, which must be added to the builder before
+    /// this record.
+    pub fn super_class(mut self, name: &str) -> Self {
+        self.super_classes.push(name.into());
+        self
+    }
+
+    /// Adds a field, or overrides the value of a field of a super class.
+    pub fn field(mut self, name: &str, value: impl Into<FieldValue>) -> Self {
+        self.fields.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// Builder of a [`RecordKeeper`] with classes and defs that are declared in
+/// Rust instead of TableGen source files.
+///
+/// See the [`builder`](crate::builder) module.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordKeeperBuilder {
+    records: Vec<RecordBuilder>,
+}
+
+impl RecordKeeperBuilder {
+    /// Creates a builder without records.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a class or def.
+    pub fn record(mut self, record: RecordBuilder) -> Self {
+        self.records.push(record);
+        self
+    }
+
+    /// Creates a record keeper with the records.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if TableGen rejects the records, e.g. because a super
+    /// class or referenced def is not defined, a name is used twice, or a value
+    /// does not match the type of its field.
+    pub fn build(&self) -> Result<RecordKeeper<'static>, Error> {
+        let keeper =
+            unsafe { RecordKeeper::from_raw(tableGenRecordKeeperCreate(), TableGenParser::new()) };
+        for record in &self.records {
+            add_record(keeper.as_keeper_ref(), record)?;
+        }
+        Ok(keeper)
+    }
+}
+
+fn invalid(message: String) -> Error {
+    TableGenError::InvalidArgument(message).into()
+}
+
+/// Returns the type with the given name in TableGen syntax, e.g. `list<int>`.
+fn parse_type(keeper: RecordKeeperRef, ty: &str) -> Result<TableGenRecTyRef, Error> {
+    let raw =
+        unsafe { tableGenRecordKeeperParseType(keeper.to_raw(), StringRef::from(ty).to_raw()) };
+    if raw.is_null() {
+        Err(invalid(format!("invalid type {}", ty)))
+    } else {
+        Ok(raw)
+    }
+}
+
+fn add_record(keeper: RecordKeeperRef, record: &RecordBuilder) -> Result<(), Error> {
+    let super_classes = record
+        .super_classes
+        .iter()
+        .map(|name| Ok(keeper.class(name)?.to_raw()))
+        .collect::<Result<Vec<TableGenRecordRef>, Error>>()?;
+    let mut names = Vec::new();
+    let mut types = Vec::new();
+    let mut values = Vec::new();
+    for (name, value) in &record.fields {
+        names.push(unsafe { StringRef::from(name.as_str()).to_raw() });
+        types.push(value.field_type(keeper)?);
+        values.push(value.init(keeper)?);
+    }
+
+    let mut data: (_, Result<_, TableGenError>) = (String::new(), Ok(()));
+    let raw = unsafe {
+        tableGenRecordKeeperAddRecord(
+            keeper.to_raw(),
+            StringRef::from(record.name.as_str()).to_raw(),
+            record.class.into(),
+            super_classes.as_ptr(),
+            super_classes.len(),
+            names.as_ptr(),
+            types.as_ptr(),
+            values.as_ptr(),
+            values.len(),
+            Some(print_string_callback),
+            &mut data as *mut _ as *mut c_void,
+        )
+    };
+    if raw.is_null() {
+        data.1?;
+        Err(invalid(data.0))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::DagInit;
+
+    #[test]
+    fn build() {
+        let keeper = RecordKeeperBuilder::new()
+            .record(RecordBuilder::class("Type").field("width", 0))
+            .record(RecordBuilder::def("ins"))
+            .record(
+                RecordBuilder::def("i32")
+                    .super_class("Type")
+                    .field("width", 32),
+            )
+            .record(
+                RecordBuilder::class("Op")
+                    .field("name", "")
+                    .field("flags", FieldValue::Bits(vec![true, false]))
+                    .field("commutative", false),
+            )
+            .record(
+                RecordBuilder::def("AddOp")
+                    .super_class("Op")
+                    .field("name", "add \"x\"\n")
+                    .field("commutative", true)
+                    .field("result", FieldValue::def("i32"))
+                    .field("sizes", FieldValue::list("int", [1i64, 2]))
+                    .field(
+                        "args",
+                        FieldValue::Dag(
+                            "ins".into(),
+                            vec![(FieldValue::def("i32"), Some("lhs".into()))],
+                        ),
+                    )
+                    .field("summary", FieldValue::Unset("string".into())),
+            )
+            .build()
+            .unwrap();
+
+        let add = keeper.def("AddOp").unwrap();
+        assert!(add.subclass_of("Op"));
+        assert_eq!(add.str_value("name"), Ok("add \"x\"\n"));
+        assert_eq!(add.bit_value("commutative"), Ok(true));
+        assert_eq!(add.bits_value("flags"), Ok(vec![false, true]));
+        assert_eq!(add.def_value("result").unwrap().int_value("width"), Ok(32));
+        assert_eq!(add.list_of_ints_value("sizes"), Ok(vec![1, 2]));
+        let args: DagInit = add.value("args").unwrap().try_into().unwrap();
+        assert_eq!(args.name(0), Some("lhs"));
+        assert!(add.is_value_unset("summary"));
+    }
+
+    #[test]
+    fn names() {
+        // Names are not parsed, so they may contain any characters.
+        let keeper = RecordKeeperBuilder::new()
+            .record(RecordBuilder::class("A; def B"))
+            .record(RecordBuilder::def("C } def D {").super_class("A; def B"))
+            .build()
+            .unwrap();
+        assert_eq!(keeper.defs().count(), 1);
+        assert!(keeper.def("C } def D {").unwrap().subclass_of("A; def B"));
+    }
+
+    #[test]
+    fn invalid() {
+        let invalid = [
+            RecordKeeperBuilder::new().record(RecordBuilder::def("B").super_class("C")),
+            RecordKeeperBuilder::new()
+                .record(RecordBuilder::def("B").field("a", FieldValue::def("A"))),
+            RecordKeeperBuilder::new().record(RecordBuilder::def("")),
+            RecordKeeperBuilder::new()
+                .record(RecordBuilder::class("A"))
+                .record(RecordBuilder::def("A")),
+            RecordKeeperBuilder::new()
+                .record(RecordBuilder::def("A"))
+                .record(RecordBuilder::def("B").super_class("A")),
+            RecordKeeperBuilder::new()
+                .record(RecordBuilder::class("A").field("i", 0))
+                .record(RecordBuilder::def("B").super_class("A").field("i", "x")),
+            RecordKeeperBuilder::new()
+                .record(RecordBuilder::def("B").field("l", FieldValue::list("int", ["x"]))),
+            RecordKeeperBuilder::new()
+                .record(RecordBuilder::def("B").field("l", FieldValue::list("list<", [1]))),
+            RecordKeeperBuilder::new()
+                .record(RecordBuilder::def("B").field("s", FieldValue::Unset("Missing".into()))),
+        ];
+        for builder in invalid {
+            assert!(builder.build().is_err(), "{:?}", builder);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::{cell::OnceCell, collections::HashMap};
+
+use crate::{
+    Error, Record, RecordValue,
+    error::{TableGenError, WithLocation},
+    init::FromTypedInit,
+};
+
+/// Record that looks up its fields in a hash map.
+///
+/// LLVM looks up the fields of a record by comparing the name of every field,
+/// which is slow when reading many fields of records with many fields (e.g.
+/// instructions of a target). The hash map is built on the first access of a
+/// field.
+///
+/// ```rust
+/// use tblgen::{CachedRecord, TableGenParser};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let keeper = TableGenParser::new()
+///     .add_source("def A { int size = 32; string name = \"a\"; }")?
+///     .parse()?;
+/// let a = CachedRecord::new(keeper.def("A")?);
+/// assert_eq!(a.get::<i64>("size")?, 32);
+/// assert_eq!(a.get::<&str>("name")?, "a");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachedRecord<'a> {
+    record: Record<'a>,
+    values: OnceCell<HashMap<&'a str, RecordValue<'a>>>,
+}
+
+impl<'a> CachedRecord<'a> {
+    /// Creates a cached record, without building the hash map yet.
+    pub fn new(record: Record<'a>) -> Self {
+        Self {
+            record,
+            values: OnceCell::new(),
+        }
+    }
+
+    /// Returns the underlying record.
+    pub fn record(&self) -> Record<'a> {
+        self.record
+    }
+
+    /// Returns the field with the given name like [`Record::value`].
+    pub fn value(&self, name: &str) -> Result<RecordValue<'a>, Error> {
+        self.values()
+            .get(name)
+            .copied()
+            .ok_or_else(|| TableGenError::MissingValue(name.into()).with_location(self.record))
+    }
+
+    /// Returns true if the record has a field with the given name.
+    pub fn has_field(&self, name: &str) -> bool {
+        self.values().contains_key(name)
+    }
+
+    /// Returns the field with the given name converted to `T` like
+    /// [`Record::get`].
+    pub fn get<T: FromTypedInit<'a>>(&self, name: &str) -> Result<T, Error> {
+        self.record.convert_value(name, self.value(name)?)
+    }
+
+    fn values(&self) -> &HashMap<&'a str, RecordValue<'a>> {
+        self.values.get_or_init(|| {
+            self.record
+                .values()
+                .filter_map(|value| Some((value.name().to_str().ok()?, value)))
+                .collect()
+        })
+    }
+}
+
+impl<'a> From<Record<'a>> for CachedRecord<'a> {
+    fn from(record: Record<'a>) -> Self {
+        Self::new(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TableGenParser, init::ListInit};
+
+    #[test]
+    fn cached_record() {
+        let rk = TableGenParser::new()
+            .add_source("class C<int n> { int size = n; list<int> l = [n]; } def A : C<3>;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = CachedRecord::from(rk.def("A").unwrap());
+        assert_eq!(a.record(), rk.def("A").unwrap());
+        assert!(a.has_field("size"));
+        assert!(!a.has_field("n"));
+        assert_eq!(a.get::<i64>("size"), Ok(3));
+        assert_eq!(a.get::<ListInit>("l").unwrap().len(), 1);
+        assert_eq!(
+            a.value("l").unwrap(),
+            rk.def("A").unwrap().value("l").unwrap()
+        );
+        assert_eq!(
+            a.get::<String>("size").unwrap_err().to_string(),
+            rk.def("A")
+                .unwrap()
+                .get::<String>("size")
+                .unwrap_err()
+                .to_string()
+        );
+        assert!(a.value("missing").is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//! Helpers to generate Rust code from records with [`proc_macro2`].
+//!
+//! Names of records and fields are not always valid Rust identifiers: they can
+//! be keywords (e.g. `type`), contain characters such as `.` or start with a
+//! digit. The functions in this module turn them into identifiers, and turn
+//! inits into literals and token streams.
+//!
+//! ```rust
+//! use tblgen::codegen::{ident, pascal_case_ident, snake_case_ident};
+//!
+//! assert_eq!(ident("type").to_string(), "r#type");
+//! assert_eq!(ident("self").to_string(), "self_");
+//! assert_eq!(ident("v8i16.x").to_string(), "v8i16_x");
+//! assert_eq!(snake_case_ident("AddIOp").to_string(), "add_i_op");
+//! assert_eq!(pascal_case_ident("add_i").to_string(), "AddI");
+//! ```
+
+use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use crate::{
+    Error, Record, TypedInit,
+    error::TableGenError,
+    init::{BitInit, BitsInit},
+    util::escape_keyword,
+};
+
+/// Returns an identifier for the given name.
+///
+/// Characters that are not allowed in identifiers are replaced with `_`, names
+/// starting with a digit are prefixed with `_`, keywords are escaped as raw
+/// identifiers (e.g. `r#type`), and keywords that cannot be raw identifiers
+/// (e.g. `self`) are suffixed with `_`.
+pub fn ident(name: &str) -> Ident {
+    let mut sanitized = name
+        .chars()
+        .map(|c| {
+            if c == '_' || c.is_ascii_alphanumeric() {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if sanitized.is_empty() || sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+
+    let escaped = escape_keyword(&sanitized);
+    match escaped.strip_prefix("r#") {
+        Some(keyword) => Ident::new_raw(keyword, Span::call_site()),
+        None => Ident::new(&escaped, Span::call_site()),
+    }
+}
+
+/// Returns a `snake_case` identifier for the given name (e.g. `add_i_op` for
+/// `AddIOp`).
+pub fn snake_case_ident(name: &str) -> Ident {
+    ident(&to_snake_case(name))
+}
+
+/// Returns a `PascalCase` identifier for the given name (e.g. `AddI` for
+/// `add_i`).
+pub fn pascal_case_ident(name: &str) -> Ident {
+    ident(&to_pascal_case(name))
+}
+
+fn to_snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut output = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let previous = index.checked_sub(1).map(|index| chars[index]);
+            let next = chars.get(index + 1);
+            // Start a new word before an uppercase letter that follows a
+            // lowercase letter or digit, or that starts a word after an
+            // acronym (e.g. `HTTPServer`).
+            if previous.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit())
+                || previous.is_some_and(char::is_uppercase)
+                    && next.is_some_and(|n| n.is_lowercase())
+            {
+                output.push('_');
+            }
+            output.extend(c.to_lowercase());
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+        })
+        .collect()
+}
+
+/// Returns an identifier for the name of the given record.
+pub fn record_ident(record: Record) -> Result<Ident, Error> {
+    Ok(ident(record.name()?))
+}
+
+/// Returns a literal for the given init.
+///
+/// Ints are converted to unsuffixed integer literals, strings and code to
+/// string literals, and `bits` with only known bits to unsuffixed integer
+/// literals of their value.
+pub fn literal(init: TypedInit) -> Result<Literal, Error> {
+    match init {
+        TypedInit::Int(init) => Ok(Literal::i64_unsuffixed(init.try_into()?)),
+        TypedInit::String(init) | TypedInit::Code(init) => {
+            Ok(Literal::string(init.to_str().map_err(TableGenError::from)?))
+        }
+        TypedInit::Bits(init) => Ok(Literal::u64_unsuffixed(bits_value(init)?)),
+        _ => Err(conversion(init, "Literal")),
+    }
+}
+
+fn bits_value(init: BitsInit) -> Result<u64, Error> {
+    let error = || {
+        Error::from(TableGenError::InitConversion {
+            from: "Bits",
+            to: "u64",
+        })
+    };
+    if init.num_bits() > 64 {
+        return Err(error());
+    }
+    (0..init.num_bits()).try_fold(0, |value, index| {
+        let bit = init
+            .bit(index)
+            .and_then(BitInit::as_literal)
+            .ok_or_else(error)?;
+        Ok(value | (u64::from(bit) << index))
+    })
+}
+
+/// Returns a token stream for the given init.
+///
+/// In addition to the [literals](literal), bits are converted to `true` or
+/// `false`, defs to the [identifier](record_ident) of their name, and lists
+/// to arrays of their elements (e.g. `[1, 2]`).
+pub fn tokens(init: TypedInit) -> Result<TokenStream, Error> {
+    match init {
+        TypedInit::Bit(init) => Ok(TokenTree::from(Ident::new(
+            &bool::try_from(init)?.to_string(),
+            Span::call_site(),
+        ))
+        .into()),
+        TypedInit::Def(init) => Ok(TokenTree::from(record_ident(init.into())?).into()),
+        TypedInit::List(init) => {
+            let mut elements = TokenStream::new();
+            for (index, element) in init.iter().enumerate() {
+                if index > 0 {
+                    elements.extend([TokenTree::from(Punct::new(',', Spacing::Alone))]);
+                }
+                elements.extend(tokens(element)?);
+            }
+            Ok(TokenTree::from(Group::new(Delimiter::Bracket, elements)).into())
+        }
+        TypedInit::Int(_) | TypedInit::String(_) | TypedInit::Code(_) | TypedInit::Bits(_) => {
+            Ok(TokenTree::from(literal(init)?).into())
+        }
+        _ => Err(conversion(init, "TokenStream")),
+    }
+}
+
+fn conversion(init: TypedInit, to: &'static str) -> Error {
+    TableGenError::InitConversion {
+        from: init.variant_name(),
+        to,
+    }
+    .into()
+}
+
+/// Implements [`ToTokens`](quote::ToTokens) with the conversion of
+/// [`tokens`], emitting a `compile_error!` if the init cannot be converted.
+#[cfg(feature = "quote")]
+macro_rules! to_tokens {
+    ($type:ident, $variant:ident) => {
+        impl quote::ToTokens for crate::init::$type<'_> {
+            fn to_tokens(&self, stream: &mut TokenStream) {
+                match tokens(TypedInit::$variant(*self)) {
+                    Ok(tokens) => stream.extend(tokens),
+                    Err(error) => {
+                        let message = error.to_string();
+                        stream.extend(quote::quote! { compile_error!(#message) });
+                    }
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "quote")]
+to_tokens!(IntInit, Int);
+#[cfg(feature = "quote")]
+to_tokens!(StringInit, String);
+#[cfg(feature = "quote")]
+to_tokens!(BitInit, Bit);
+#[cfg(feature = "quote")]
+to_tokens!(BitsInit, Bits);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    #[test]
+    fn idents() {
+        assert_eq!(ident("type").to_string(), "r#type");
+        assert_eq!(ident("Self").to_string(), "Self_");
+        assert_eq!(ident("_").to_string(), "__");
+        assert_eq!(ident("8bit").to_string(), "_8bit");
+        assert_eq!(ident("a.b-c").to_string(), "a_b_c");
+        assert_eq!(snake_case_ident("HTTPServer").to_string(), "http_server");
+        assert_eq!(snake_case_ident("Add32rr").to_string(), "add32rr");
+        assert_eq!(snake_case_ident("Mod").to_string(), "r#mod");
+        assert_eq!(pascal_case_ident("add_f_op").to_string(), "AddFOp");
+    }
+
+    #[test]
+    fn init_tokens() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                def A {
+                    int i = -3;
+                    string s = "a\"b";
+                    bit t = 1;
+                    bits<4> b = { 1, 0, 1, 0 };
+                    bits<2> u = { ?, 1 };
+                    list<A> l = [A, A];
+                    dag d = (A);
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        let tokens = |name| tokens(a.value(name).unwrap().init()).map(|t| t.to_string());
+        assert_eq!(tokens("i").unwrap(), "-3");
+        assert_eq!(tokens("s").unwrap(), r#""a\"b""#);
+        assert_eq!(tokens("t").unwrap(), "true");
+        assert_eq!(tokens("b").unwrap(), "10");
+        assert_eq!(tokens("l").unwrap(), "[A , A]");
+        assert!(tokens("u").is_err());
+        assert!(tokens("d").is_err());
+    }
+
+    #[cfg(feature = "quote")]
+    #[test]
+    fn to_tokens() {
+        let rk = TableGenParser::new()
+            .add_source("def A { int i = 3; string s = \"s\"; bits<2> u = { ?, 1 }; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        let i = a.value("i").unwrap().init().as_int().unwrap();
+        let s = a.value("s").unwrap().init().as_string().unwrap();
+        let u = a.bits_init_value("u").unwrap();
+        assert_eq!(quote::quote! { f(#i, #s) }.to_string(), "f (3 , \"s\")");
+        assert!(
+            quote::quote! { #u }
+                .to_string()
+                .starts_with("compile_error !")
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Owned expression trees of dags, e.g. to rewrite patterns.
+//!
+//! [`DagInit::to_expr`] converts a dag to a [`DagExpr`] whose operators,
+//! argument names and values can be modified freely. Nested dags are
+//! converted recursively, and other values are kept as [`TypedInit`]s unless
+//! replaced with TableGen source code. A [`DagExpr`] is displayed in
+//! TableGen dag syntax, so a rewritten pattern can be emitted again.
+//!
+//! ```rust
+//! use tblgen::{TableGenParser, init::DagInit};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source("def add; def mul; def A { dag pattern = (add 1, (mul 2, 3):$m); }")?
+//!     .parse()?;
+//! let dag: DagInit = keeper.def("A")?.value("pattern")?.try_into()?;
+//! let mut expr = dag.to_expr()?;
+//! assert_eq!(expr.to_string(), "(add 1, (mul 2, 3):$m)");
+//!
+//! expr.visit_mut(&mut |expr| {
+//!     if expr.operator == "mul" {
+//!         expr.operator = "shl".into();
+//!     }
+//! });
+//! expr.arg_mut("m").unwrap().name = Some("s".into());
+//! assert_eq!(expr.to_string(), "(add 1, (shl 2, 3):$s)");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{Error, TypedInit, init::DagInit};
+
+/// An owned dag, i.e. an operator applied to arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DagExpr<'a> {
+    /// The name of the operator def.
+    pub operator: String,
+    /// The arguments.
+    pub args: Vec<DagArg<'a>>,
+}
+
+/// An argument of a [`DagExpr`], with an optional name (e.g. `$lhs`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DagArg<'a> {
+    /// The name of the argument, without `$`.
+    pub name: Option<String>,
+    /// The value of the argument.
+    pub value: DagValue<'a>,
+}
+
+/// The value of a [`DagArg`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DagValue<'a> {
+    /// A nested dag.
+    Dag(DagExpr<'a>),
+    /// Any other value of the original dag.
+    Init(TypedInit<'a>),
+    /// TableGen source code, e.g. to insert a value that is not in the
+    /// record keeper.
+    Source(String),
+    /// An unset value (`?`), e.g. for arguments with only a name.
+    Unset,
+}
+
+impl<'a> DagExpr<'a> {
+    /// Creates a dag without arguments.
+    pub fn new(operator: impl Into<String>) -> Self {
+        Self {
+            operator: operator.into(),
+            args: Vec::new(),
+        }
+    }
+
+    pub(crate) fn from_init(dag: DagInit<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            operator: dag.operator()?.name()?.into(),
+            args: dag
+                .args()
+                .map(|(name, value)| {
+                    Ok(DagArg {
+                        name: name.map(Into::into),
+                        value: match value {
+                            TypedInit::Dag(dag) => DagValue::Dag(Self::from_init(dag)?),
+                            TypedInit::Invalid => DagValue::Unset,
+                            value => DagValue::Init(value),
+                        },
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+
+    /// Returns the argument with the given name.
+    pub fn arg(&self, name: &str) -> Option<&DagArg<'a>> {
+        self.args
+            .iter()
+            .find(|arg| arg.name.as_deref() == Some(name))
+    }
+
+    /// Returns the argument with the given name for modification.
+    pub fn arg_mut(&mut self, name: &str) -> Option<&mut DagArg<'a>> {
+        self.args
+            .iter_mut()
+            .find(|arg| arg.name.as_deref() == Some(name))
+    }
+
+    /// Calls a function on this dag and then on all nested dags, in
+    /// pre-order.
+    pub fn visit_mut(&mut self, visit: &mut impl FnMut(&mut Self)) {
+        visit(self);
+        for arg in &mut self.args {
+            if let DagValue::Dag(dag) = &mut arg.value {
+                dag.visit_mut(visit);
+            }
+        }
+    }
+}
+
+impl Display for DagExpr<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "({}", self.operator)?;
+        for (index, arg) in self.args.iter().enumerate() {
+            write!(formatter, "{}{}", if index == 0 { " " } else { ", " }, arg)?;
+        }
+        write!(formatter, ")")
+    }
+}
+
+impl<'a> DagArg<'a> {
+    /// Creates an unnamed argument.
+    pub fn new(value: DagValue<'a>) -> Self {
+        Self { name: None, value }
+    }
+
+    /// Creates a named argument.
+    pub fn named(name: impl Into<String>, value: DagValue<'a>) -> Self {
+        Self {
+            name: Some(name.into()),
+            value,
+        }
+    }
+}
+
+impl Display for DagArg<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.value)?;
+        if let Some(name) = &self.name {
+            write!(formatter, ":${}", name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DagValue<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Dag(dag) => write!(formatter, "{}", dag),
+            Self::Init(init) => write!(formatter, "{}", init),
+            Self::Source(source) => write!(formatter, "{}", source),
+            Self::Unset => write!(formatter, "?"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    #[test]
+    fn to_expr() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                def add; def mul; def ins;
+                def A {
+                    dag pattern = (add "x", (mul ?:$a, [1, 2]), (ins):$i, $b);
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let dag: DagInit = rk
+            .def("A")
+            .unwrap()
+            .value("pattern")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let mut expr = dag.to_expr().unwrap();
+        assert_eq!(expr.to_string(), dag.to_string());
+        assert_eq!(expr.operator, "add");
+        assert_eq!(expr.args.len(), 4);
+        assert_eq!(expr.arg("b").unwrap().value, DagValue::Unset);
+        assert_eq!(
+            expr.arg("i").unwrap().value,
+            DagValue::Dag(DagExpr::new("ins"))
+        );
+        let DagValue::Dag(mul) = &expr.args[1].value else {
+            panic!("expected a dag");
+        };
+        assert_eq!(mul.arg("a").unwrap().value, DagValue::Unset);
+        assert!(matches!(
+            mul.args[1].value,
+            DagValue::Init(TypedInit::List(_))
+        ));
+
+        let mut operators = Vec::new();
+        expr.visit_mut(&mut |expr| operators.push(expr.operator.clone()));
+        assert_eq!(operators, ["add", "mul", "ins"]);
+
+        expr.args.remove(0);
+        expr.arg_mut("b").unwrap().value = DagValue::Source("42".into());
+        expr.args
+            .push(DagArg::named("c", DagValue::Dag(DagExpr::new("mul"))));
+        expr.args
+            .push(DagArg::new(DagValue::Source("\"y\"".into())));
+        assert_eq!(
+            expr.to_string(),
+            "(add (mul ?:$a, [1, 2]), (ins):$i, 42:$b, (mul):$c, \"y\")"
+        );
+    }
+}
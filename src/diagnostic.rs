@@ -0,0 +1,362 @@
+use std::{
+    ffi::c_void,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{
+    error::{ErrorCode, TableGenError, sarif_location},
+    json::Json,
+    raw::{
+        TableGenDiagKind, TableGenDiagnosticRef, TableGenParserRef, tableGenDiagnosticGetColumn,
+        tableGenDiagnosticGetFilename, tableGenDiagnosticGetKind, tableGenDiagnosticGetLine,
+        tableGenDiagnosticGetLineContents, tableGenDiagnosticGetMessage, tableGenDiagnosticPrint,
+        tableGenGetDiagnostic, tableGenGetNumDiagnostics,
+    },
+    string_ref::StringRef,
+    util::print_string_callback,
+};
+
+/// Severity of a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    Error,
+    Warning,
+    Remark,
+    Note,
+}
+
+impl DiagnosticKind {
+    fn from_raw(raw: TableGenDiagKind::Type) -> Self {
+        match raw {
+            TableGenDiagKind::TABLEGEN_DK_WARNING => Self::Warning,
+            TableGenDiagKind::TABLEGEN_DK_REMARK => Self::Remark,
+            TableGenDiagKind::TABLEGEN_DK_NOTE => Self::Note,
+            _ => Self::Error,
+        }
+    }
+
+    pub(crate) fn to_raw(self) -> TableGenDiagKind::Type {
+        match self {
+            Self::Error => TableGenDiagKind::TABLEGEN_DK_ERROR,
+            Self::Warning => TableGenDiagKind::TABLEGEN_DK_WARNING,
+            Self::Remark => TableGenDiagKind::TABLEGEN_DK_REMARK,
+            Self::Note => TableGenDiagKind::TABLEGEN_DK_NOTE,
+        }
+    }
+}
+
+impl Display for DiagnosticKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+            Self::Remark => write!(f, "remark"),
+            Self::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A message reported by TableGen while parsing.
+///
+/// TableGen normally prints these messages to the standard error stream.
+/// Instead, they are collected by the parser and made available through
+/// [`TableGenError::Parse`] and
+/// [`RecordKeeper::diagnostics`](crate::RecordKeeper::diagnostics).
+///
+/// Notes that TableGen emits right after a diagnostic (e.g. "instantiated from
+/// multiclass") are attached to that diagnostic and can be accessed with
+/// [`Diagnostic::notes`].
+///
+/// The [`Display`] implementation formats the diagnostic and its notes the same
+/// way TableGen would print them, including the source line and a caret
+/// pointing at the location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    kind: DiagnosticKind,
+    message: String,
+    filename: String,
+    line: Option<usize>,
+    column: Option<usize>,
+    line_contents: String,
+    rendered: String,
+    notes: Vec<Diagnostic>,
+}
+
+impl Diagnostic {
+    /// # Safety
+    /// The passed pointer should be a valid diagnostic.
+    unsafe fn from_raw(raw: TableGenDiagnosticRef) -> Self {
+        unsafe {
+            let string = |s| String::from_utf8_lossy(StringRef::from_raw(s).into()).into_owned();
+            let position = |n: i32| usize::try_from(n).ok();
+            let message = string(tableGenDiagnosticGetMessage(raw));
+
+            let mut data: (_, Result<_, TableGenError>) = (String::new(), Ok(()));
+            tableGenDiagnosticPrint(
+                raw,
+                Some(print_string_callback),
+                &mut data as *mut _ as *mut c_void,
+            );
+            let rendered = match data.1 {
+                Ok(()) => data.0.trim_end().to_string(),
+                Err(_) => message.clone(),
+            };
+
+            Self {
+                kind: DiagnosticKind::from_raw(tableGenDiagnosticGetKind(raw)),
+                message,
+                filename: string(tableGenDiagnosticGetFilename(raw)),
+                line: position(tableGenDiagnosticGetLine(raw)),
+                column: position(tableGenDiagnosticGetColumn(raw)).map(|c| c + 1),
+                line_contents: string(tableGenDiagnosticGetLineContents(raw)),
+                rendered,
+                notes: Vec::new(),
+            }
+        }
+    }
+
+    /// Collects all diagnostics reported by the last parse of the given
+    /// parser.
+    pub(crate) unsafe fn collect(parser: TableGenParserRef) -> Vec<Self> {
+        let mut diagnostics = Vec::<Self>::new();
+        for i in 0..unsafe { tableGenGetNumDiagnostics(parser) } {
+            let diagnostic = unsafe { Self::from_raw(tableGenGetDiagnostic(parser, i)) };
+            match diagnostics.last_mut() {
+                Some(last) if diagnostic.kind == DiagnosticKind::Note => {
+                    last.notes.push(diagnostic)
+                }
+                _ => diagnostics.push(diagnostic),
+            }
+        }
+        diagnostics
+    }
+
+    /// Returns the severity of the diagnostic.
+    pub fn kind(&self) -> DiagnosticKind {
+        self.kind
+    }
+
+    /// Returns the message, without location information.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the name of the file the diagnostic refers to.
+    ///
+    /// Sources added from strings have an empty name, unless they were added
+    /// with [`add_source_named`](crate::TableGenParser::add_source_named).
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Returns the line number (starting at 1), if known.
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Returns the column number (starting at 1), if known.
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    /// Returns the contents of the source line the diagnostic refers to.
+    pub fn line_contents(&self) -> &str {
+        &self.line_contents
+    }
+
+    /// Returns the notes attached to this diagnostic.
+    pub fn notes(&self) -> &[Diagnostic] {
+        &self.notes
+    }
+
+    /// Returns the diagnostic as a JSON object with the fields `kind`,
+    /// `message`, `filename`, `line`, `column`, `line_contents` and `notes`.
+    ///
+    /// Unknown lines and columns are `null`.
+    pub fn to_json(&self) -> String {
+        self.json().to_string()
+    }
+
+    fn json(&self) -> Json {
+        Json::object([
+            ("kind", self.kind.to_string().into()),
+            ("message", self.message.as_str().into()),
+            ("filename", self.filename.as_str().into()),
+            ("line", self.line.into()),
+            ("column", self.column.into()),
+            ("line_contents", self.line_contents.as_str().into()),
+            ("notes", self.notes.iter().map(Self::json).collect()),
+        ])
+    }
+
+    /// Returns the diagnostic as a SARIF result object. Notes are included as
+    /// related locations.
+    pub(crate) fn to_sarif_result(&self) -> Json {
+        let location = |diagnostic: &Self| {
+            diagnostic.line.map(|line| {
+                sarif_location(&diagnostic.filename, line, diagnostic.column.unwrap_or(1))
+            })
+        };
+        let level = match self.kind {
+            DiagnosticKind::Error => "error",
+            DiagnosticKind::Warning => "warning",
+            DiagnosticKind::Remark | DiagnosticKind::Note => "note",
+        };
+        Json::object([
+            ("ruleId", ErrorCode::Parse.as_str().into()),
+            ("level", level.into()),
+            (
+                "message",
+                Json::object([("text", self.message.as_str().into())]),
+            ),
+            ("locations", location(self).into_iter().collect()),
+            (
+                "relatedLocations",
+                self.notes
+                    .iter()
+                    .filter_map(|note| {
+                        let Json::Object(mut entries) = location(note)? else {
+                            return None;
+                        };
+                        entries.push((
+                            "message".into(),
+                            Json::object([("text", note.message.as_str().into())]),
+                        ));
+                        Some(Json::Object(entries))
+                    })
+                    .collect(),
+            ),
+        ])
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.rendered)?;
+        for note in &self.notes {
+            write!(f, "\n{}", note)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+pub(crate) fn format_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| format!("\n{}", diagnostic))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    #[test]
+    fn parse_error() {
+        let error = TableGenParser::new()
+            .add_source("def A {\n  int i = \"foo\";\n}\n")
+            .unwrap()
+            .parse()
+            .expect_err("invalid tablegen");
+        let TableGenError::Parse(diagnostics) = error.error() else {
+            panic!("expected parse error");
+        };
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.kind(), DiagnosticKind::Error);
+        assert_eq!(diagnostic.line(), Some(2));
+        assert_eq!(diagnostic.line_contents(), "  int i = \"foo\";");
+        assert!(diagnostic.to_string().contains(diagnostic.message()));
+        assert!(error.to_string().contains(diagnostic.message()));
+    }
+
+    #[test]
+    fn json() {
+        let error = TableGenParser::new()
+            .add_source_named("A.td", "def A {\n  int i = \"foo\";\n}\n")
+            .unwrap()
+            .parse()
+            .expect_err("invalid tablegen");
+        let TableGenError::Parse(diagnostics) = error.error() else {
+            panic!("expected parse error");
+        };
+        let json = diagnostics[0].to_json();
+        assert!(json.starts_with(r#"{"kind":"error","message":""#));
+        assert!(json.contains(r#""filename":"A.td","line":2,"column":"#));
+        assert!(json.contains(r#""line_contents":"  int i = \"foo\";","notes":[]}"#));
+
+        let sarif = error.to_sarif();
+        assert!(sarif.contains(r#""version":"2.1.0""#));
+        assert!(sarif.contains(r#""ruleId":"parse","level":"error""#));
+        assert!(sarif.contains(r#""artifactLocation":{"uri":"A.td"},"region":{"startLine":2"#));
+    }
+
+    #[test]
+    fn source_chain() {
+        use std::error::Error;
+
+        // The message of a parse error includes its diagnostics, so they are
+        // not returned as its source.
+        let error = TableGenParser::new()
+            .add_source("def A {\n  int i = \"foo\";\n}\n")
+            .unwrap()
+            .parse()
+            .expect_err("invalid tablegen");
+        assert_eq!(error.category(), crate::error::ErrorCategory::Parse);
+        let TableGenError::Parse(diagnostics) = error.error() else {
+            panic!("expected parse error");
+        };
+        let message = error.to_string();
+        assert_eq!(message.matches(diagnostics[0].message()).count(), 1);
+        assert!(error.source().is_none());
+
+        let error = crate::Error::from(TableGenError::from(
+            String::from_utf8(vec![0xff]).unwrap_err(),
+        ));
+        assert!(error.source().unwrap().is::<std::string::FromUtf8Error>());
+    }
+
+    #[test]
+    fn multiclass_notes() {
+        let error = TableGenParser::new()
+            .add_source("def X_a;\nmulticlass M {\n  def _a;\n}\ndefm X : M;\n")
+            .unwrap()
+            .parse()
+            .expect_err("invalid tablegen");
+        let TableGenError::Parse(diagnostics) = error.error() else {
+            panic!("expected parse error");
+        };
+        assert!(diagnostics.iter().all(|d| d.kind() != DiagnosticKind::Note));
+        let notes = diagnostics[0].notes();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].kind(), DiagnosticKind::Note);
+        assert_eq!(notes[0].message(), "instantiated from multiclass");
+        assert_eq!(notes[0].line(), Some(5));
+        assert!(diagnostics[0].to_string().contains(notes[0].message()));
+    }
+
+    #[test]
+    fn missing_file() {
+        let error = TableGenParser::new()
+            .add_source_file("DoesNotExist.td")
+            .parse()
+            .expect_err("missing file");
+        let TableGenError::Parse(diagnostics) = error.error() else {
+            panic!("expected parse error");
+        };
+        assert_eq!(diagnostics[0].filename(), "DoesNotExist.td");
+        assert_eq!(diagnostics[0].line(), None);
+    }
+
+    #[test]
+    fn no_diagnostics() {
+        let keeper = TableGenParser::new()
+            .add_source("def A;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        assert!(keeper.diagnostics().is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+//! Documentation of records, e.g. to generate reference documentation.
+//!
+//! Descriptions are usually written in code blocks indented like the
+//! surrounding TableGen code:
+//!
+//! ```text
+//! def AddOp : Op<"add"> {
+//!   let summary = "integer addition";
+//!   let description = [{
+//!     Adds two integers.
+//!
+//!     ```mlir
+//!     %0 = arith.addi %a, %b : i32
+//!     ```
+//!   }];
+//! }
+//! ```
+//!
+//! [`Record::doc`](crate::Record::doc) returns such a description without the
+//! common indentation and the surrounding blank lines.
+
+/// The `summary` and `description` fields of a record, see
+/// [`Record::doc`](crate::Record::doc).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Doc {
+    /// The one-line summary, trimmed.
+    pub summary: Option<String>,
+    /// The description, dedented and without leading or trailing blank
+    /// lines.
+    pub description: Option<String>,
+}
+
+impl Doc {
+    pub(crate) fn new(summary: Option<&str>, description: Option<&str>) -> Self {
+        Self {
+            summary: summary
+                .map(|summary| normalize_line_endings(summary).trim().to_owned())
+                .filter(|summary| !summary.is_empty()),
+            description: description
+                .map(dedent)
+                .filter(|description| !description.is_empty()),
+        }
+    }
+}
+
+/// Removes the indentation that all non-blank lines of the given text have
+/// in common, and the leading and trailing blank lines.
+///
+/// Line endings are normalized to `\n`, and trailing whitespace is removed
+/// from every line.
+pub fn dedent(text: &str) -> String {
+    let text = normalize_line_endings(text);
+    let lines = text
+        .lines()
+        .map(str::trim_end)
+        .skip_while(|line| line.is_empty())
+        .collect::<Vec<_>>();
+    let end = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(0, |index| index + 1);
+    let lines = &lines[..end];
+    let indentation = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .iter()
+        .map(|line| line.get(indentation..).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dedent_text() {
+        assert_eq!(
+            dedent("\r\n\n    Adds two integers.\r\n\n      ```mlir  \n    ```\n  "),
+            "Adds two integers.\n\n  ```mlir\n```"
+        );
+        assert_eq!(dedent("single line"), "single line");
+        assert_eq!(dedent("  \n \n"), "");
+        assert_eq!(
+            Doc::new(Some(" sum\r\n"), Some("\n")),
+            Doc {
+                summary: Some("sum".into()),
+                description: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Conversion of records to the layout of `llvm-tblgen --dump-json`.
+//!
+//! - `bit` values are converted to `0` or `1`, `bits` values to arrays of
+//!   bits, ints to integers, strings and code to strings, and lists to arrays,
+//! - unset values are converted to `null`,
+//! - defs, dags, variable bits and any other values are converted to objects
+//!   with a `kind` and a `printable` representation in TableGen syntax,
+//! - records are converted to objects mapping field names to values, with the
+//!   additional keys `!name`, `!anonymous`, `!superclasses` and `!fields`,
+//! - the record keeper is converted to an object mapping def names to
+//!   records, with the additional keys `!instanceof` and
+//!   `!tablegen_json_version`.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    Error, Record, RecordKeeper, TypedInit,
+    error::TableGenError,
+    init::{BitInit, DagInit},
+    json::Json,
+};
+
+pub(crate) fn init(init: TypedInit) -> Result<Json, Error> {
+    Ok(match init {
+        TypedInit::Bit(init) => bit(init),
+        TypedInit::Bits(init) => (0..init.num_bits())
+            // Unset bits are not typed inits and converted to `null`.
+            .map(|index| init.bit(index).map_or(Json::Null, bit))
+            .collect(),
+        TypedInit::Int(init) => match i64::try_from(init) {
+            Ok(value) => value.into(),
+            Err(_) => complex(init),
+        },
+        TypedInit::Code(init) | TypedInit::String(init) => {
+            init.to_str().map_err(TableGenError::from)?.into()
+        }
+        TypedInit::List(init) => init.iter().map(self::init).collect::<Result<_, _>>()?,
+        TypedInit::Dag(init) => dag(init)?,
+        TypedInit::Def(init) => def(Record::from(init))?,
+        TypedInit::Invalid => Json::Null,
+    })
+}
+
+fn bit(init: BitInit) -> Json {
+    if let Some(value) = init.as_literal() {
+        return i64::from(value).into();
+    }
+    match init.as_var_bit() {
+        Some((var, index)) => Json::object([
+            ("kind", "varbit".into()),
+            ("var", var.into()),
+            ("index", index.into()),
+            ("printable", init.to_string().into()),
+        ]),
+        None => complex(init),
+    }
+}
+
+fn def(record: Record) -> Result<Json, Error> {
+    Ok(Json::object([
+        ("kind", "def".into()),
+        ("def", record.name()?.into()),
+        ("printable", record.def_init().to_string().into()),
+    ]))
+}
+
+fn dag(init: DagInit) -> Result<Json, Error> {
+    Ok(Json::object([
+        ("kind", "dag".into()),
+        ("operator", def(init.operator()?)?),
+        (
+            "args",
+            init.args()
+                .map(|(name, value)| Ok(Json::Array(vec![self::init(value)?, name.into()])))
+                .collect::<Result<_, Error>>()?,
+        ),
+        ("printable", init.to_string().into()),
+    ]))
+}
+
+/// Converts an init that has no JSON equivalent, such as a reference to a
+/// template argument.
+fn complex(init: impl ToString) -> Json {
+    Json::object([
+        ("kind", "complex".into()),
+        ("printable", init.to_string().into()),
+    ])
+}
+
+pub(crate) fn record(record: Record) -> Result<Json, Error> {
+    let mut entries = Vec::new();
+    let mut fields = Vec::new();
+    for value in record.values().filter(|value| !value.is_template_arg()) {
+        let name = value.name().to_str().map_err(TableGenError::from)?;
+        if value.is_nonconcrete_ok() {
+            fields.push(Json::from(name));
+        }
+        entries.push((name.into(), init(value.init())?));
+    }
+
+    entries.extend([
+        ("!name".into(), record.name()?.into()),
+        ("!anonymous".into(), record.anonymous().into()),
+        (
+            "!superclasses".into(),
+            super_classes(record)
+                .into_iter()
+                .map(|class| Ok(Json::from(class.name()?)))
+                .collect::<Result<_, Error>>()?,
+        ),
+        ("!fields".into(), Json::Array(fields)),
+    ]);
+    Ok(Json::Object(entries))
+}
+
+/// Returns all super classes of a record, with the super classes of each class
+/// preceding the class itself.
+pub(crate) fn super_classes(record: Record) -> Vec<Record> {
+    fn visit<'a>(record: Record<'a>, classes: &mut Vec<Record<'a>>) {
+        for class in record.direct_super_classes() {
+            if !classes.contains(&class) {
+                visit(class, classes);
+                // Older LLVM versions already return all super classes.
+                if !classes.contains(&class) {
+                    classes.push(class);
+                }
+            }
+        }
+    }
+
+    let mut classes = Vec::new();
+    visit(record, &mut classes);
+    classes
+}
+
+pub(crate) fn keeper(keeper: &RecordKeeper) -> Result<Json, Error> {
+    let mut instances = BTreeMap::<&str, Vec<Json>>::new();
+    for (name, _) in keeper.classes() {
+        instances.insert(name.map_err(TableGenError::from)?, Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for (name, def) in keeper.defs() {
+        let name = name.map_err(TableGenError::from)?;
+        entries.push((name.into(), record(def)?));
+        for class in super_classes(def) {
+            instances
+                .entry(class.name()?)
+                .or_default()
+                .push(name.into());
+        }
+    }
+
+    entries.extend([
+        (
+            "!instanceof".into(),
+            Json::Object(
+                instances
+                    .into_iter()
+                    .map(|(class, defs)| (class.into(), Json::Array(defs)))
+                    .collect(),
+            ),
+        ),
+        ("!tablegen_json_version".into(), 1i64.into()),
+    ]);
+    Ok(Json::Object(entries))
+}
@@ -0,0 +1,210 @@
+//! Extraction of enums from the conventions used to describe them in TableGen
+//! files.
+//!
+//! - MLIR enum attributes (e.g. `I32EnumAttr` with `I32EnumAttrCase`s) are
+//!   defs deriving from `EnumAttrInfo`, with the cases in the `enumerants`
+//!   field. Each case has a `symbol` and an int `value`.
+//! - Enum attributes wrapping an `EnumAttrInfo` (e.g. `EnumAttr`) refer to
+//!   it in the `enumInfo` field.
+//! - `GenericEnum`s of `llvm/TableGen/SearchableTable.td` (see
+//!   [`searchable_tables`](crate::searchable_tables)).
+//! - Any class whose defs have an int field with the value of the case, and
+//!   optionally a string field with its name.
+//!
+//! ```rust
+//! use tblgen::{TableGenParser, enums::ExtractedEnum};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source(
+//!         r#"
+//!         class Color<int v> { int value = v; }
+//!         def Red : Color<1>;
+//!         def Green : Color<2>;
+//!         "#,
+//!     )?
+//!     .parse()?;
+//! let color = ExtractedEnum::from_class(&keeper, "Color", "value", None)?;
+//! assert_eq!(color.name, "Color");
+//! assert_eq!(color.cases, [("Green".into(), 2), ("Red".into(), 1)]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    Error, Record, RecordKeeper,
+    error::{TableGenError, WithLocation},
+    searchable_tables::GenericEnum,
+};
+
+/// An enum with named integer cases.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExtractedEnum {
+    /// The name of the enum.
+    pub name: String,
+    /// The names and values of the cases.
+    pub cases: Vec<(String, i64)>,
+}
+
+impl ExtractedEnum {
+    /// Extracts an MLIR enum attribute, i.e. a def deriving from
+    /// `EnumAttrInfo` (e.g. `I32EnumAttr`) or a def with an `enumInfo` field
+    /// referring to one (e.g. `EnumAttr`).
+    ///
+    /// The name of the enum is the name of its C++ class (`className`), and
+    /// the cases are in the order of their declaration.
+    pub fn from_enum_attr(record: Record) -> Result<Self, Error> {
+        let info = if record.subclass_of("EnumAttrInfo") {
+            record
+        } else if record.has_field("enumInfo") {
+            record.def_value("enumInfo")?
+        } else {
+            return Err(TableGenError::InitConversion {
+                from: "Record",
+                to: "ExtractedEnum",
+            }
+            .with_location(record));
+        };
+
+        Ok(Self {
+            name: info.str_value("className")?.into(),
+            cases: info
+                .list_of_defs_value("enumerants")?
+                .into_iter()
+                .map(|case| Ok((case.str_value("symbol")?.into(), case.int_value("value")?)))
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+
+    /// Extracts a `GenericEnum` of `llvm/TableGen/SearchableTable.td`.
+    ///
+    /// The cases are in the order of their values.
+    pub fn from_generic_enum(generic: GenericEnum, keeper: &RecordKeeper) -> Result<Self, Error> {
+        let mut cases = generic
+            .entries(keeper)?
+            .into_iter()
+            .map(|entry| (entry.name().to_owned(), entry.value()))
+            .collect::<Vec<_>>();
+        cases.sort_by_key(|&(_, value)| value);
+        Ok(Self {
+            name: generic.name()?.into(),
+            cases,
+        })
+    }
+
+    /// Extracts an enum named after a class, with a case for each def
+    /// deriving from it in name order.
+    ///
+    /// The value of each case is read from the given int field, and its name
+    /// from the given string field or from the name of the def.
+    pub fn from_class(
+        keeper: &RecordKeeper,
+        class: &str,
+        value_field: &str,
+        name_field: Option<&str>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            name: class.into(),
+            cases: keeper
+                .all_derived_definitions(class)?
+                .map(|record| {
+                    let name = match name_field {
+                        Some(field) => record.str_value(field)?,
+                        None => record.name()?,
+                    };
+                    Ok((name.into(), record.int_value(value_field)?))
+                })
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+}
+
+/// Returns all MLIR enum attributes of the given record keeper, i.e. the defs
+/// deriving from `EnumAttrInfo`, in name order.
+pub fn enum_attrs(keeper: &RecordKeeper) -> Result<Vec<ExtractedEnum>, Error> {
+    keeper
+        .all_derived_definitions_if_defined("EnumAttrInfo")
+        .map(ExtractedEnum::from_enum_attr)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TableGenParser, searchable_tables::generic_enums};
+
+    #[test]
+    fn enum_attr() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class EnumAttrCaseInfo<string sym, int intVal, string strVal> {
+                    string symbol = sym; int value = intVal; string str = strVal;
+                }
+                class I32EnumAttrCase<string sym, int val, string str = sym>
+                    : EnumAttrCaseInfo<sym, val, str>;
+                class EnumAttrInfo<string name, list<EnumAttrCaseInfo> cases> {
+                    string className = name; list<EnumAttrCaseInfo> enumerants = cases;
+                }
+                class I32EnumAttr<string name, string summary, list<I32EnumAttrCase> cases>
+                    : EnumAttrInfo<name, cases>;
+                class EnumAttr<EnumAttrInfo info> { EnumAttrInfo enumInfo = info; }
+
+                def CmpIPredicate : I32EnumAttr<"CmpIPredicate", "", [
+                    I32EnumAttrCase<"eq", 0>, I32EnumAttrCase<"ne", 1>, I32EnumAttrCase<"slt", 2>
+                ]>;
+                def CmpIPredicateAttr : EnumAttr<CmpIPredicate>;
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let expected = ExtractedEnum {
+            name: "CmpIPredicate".into(),
+            cases: vec![("eq".into(), 0), ("ne".into(), 1), ("slt".into(), 2)],
+        };
+        assert_eq!(
+            ExtractedEnum::from_enum_attr(rk.def("CmpIPredicateAttr").unwrap()).unwrap(),
+            expected
+        );
+        assert_eq!(enum_attrs(&rk).unwrap(), [expected]);
+        assert!(ExtractedEnum::from_enum_attr(rk.def("CmpIPredicate").unwrap()).is_ok());
+        assert!(ExtractedEnum::from_enum_attr(rk.class("I32EnumAttrCase").unwrap()).is_err());
+    }
+
+    #[test]
+    fn generic_and_class() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class GenericEnum {
+                    string FilterClass; string NameField = ""; string ValueField = "";
+                }
+                class Kind<string n, int v> { string Name = n; int Value = v; }
+                def B : Kind<"second", 2>;
+                def A : Kind<"first", 3>;
+                def Kinds : GenericEnum {
+                    let FilterClass = "Kind"; let NameField = "Name"; let ValueField = "Value";
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let generic = generic_enums(&rk).unwrap()[0];
+        assert_eq!(
+            ExtractedEnum::from_generic_enum(generic, &rk).unwrap(),
+            ExtractedEnum {
+                name: "Kinds".into(),
+                cases: vec![("second".into(), 2), ("first".into(), 3)],
+            }
+        );
+        assert_eq!(
+            ExtractedEnum::from_class(&rk, "Kind", "Value", Some("Name"))
+                .unwrap()
+                .cases,
+            [("first".into(), 3), ("second".into(), 2)]
+        );
+        assert!(ExtractedEnum::from_class(&rk, "Kind", "Missing", None).is_err());
+    }
+}
@@ -37,11 +37,11 @@
 //!     .parse()?;
 //! if let Err(e) = keeper.def("A").unwrap().string_value("i") {
 //!     println!("{}", e);
-//!     // invalid conversion from Int to alloc::string::String
+//!     // 3:17: field 'i' of def 'A': invalid conversion from Int to alloc::string::String
 //!
 //!     println!("{}", e.add_source_info(keeper.source_info()));
-//!     // error: invalid conversion from Int to alloc::string::String
-//!     //   int a = test;
+//!     // error: field 'i' of def 'A': invalid conversion from Int to alloc::string::String
+//!     //   int i = 5;
 //!     //       ^
 //! }
 //! # Ok(())
@@ -54,29 +54,47 @@
 //! If it does not match, the error will be printed without information about
 //! the TableGen source file.
 //!
+//! Errors can be told apart without matching messages by their
+//! [`ErrorCode`], which belongs to one of the broad [`ErrorCategory`]s
+//! (parse errors, failed accesses to records and internal errors). Errors of
+//! the categories that are commonly handled separately can be converted into
+//! their own types: [`ParseError`], [`LookupError`], [`TypeMismatchError`] and
+//! [`SourceLocationError`].
+//!
 //! Custom error types that implement [`std::error::Error`] also implement
 //! [`WithLocation`]. That way, a [`SourceLocation`] can be attached to any
 //! error by calling [`with_location`](`WithLocation::with_location`).
 
 use std::{
+    cmp::Ordering,
     convert::Infallible,
-    ffi::{NulError, c_void},
-    fmt::{self, Display, Formatter},
+    ffi::{NulError, c_char, c_void},
+    fmt::{self, Debug, Display, Formatter},
     str::Utf8Error,
     string::FromUtf8Error,
 };
 
 use crate::{
-    SourceInfo, TableGenParser,
+    Record, SourceInfo, TableGenParser,
+    diagnostic::{Diagnostic, format_diagnostics},
+    json::Json,
     raw::{
-        TableGenDiagKind::TABLEGEN_DK_ERROR, TableGenSourceLocationRef, tableGenPrintError,
-        tableGenSourceLocationClone, tableGenSourceLocationFree, tableGenSourceLocationNull,
+        TableGenDiagKind::TABLEGEN_DK_ERROR, TableGenSourceLocationRef, TableGenStringRef,
+        tableGenFindSourceBuffer, tableGenFindSourceBufferName, tableGenPrintError,
+        tableGenSourceLocationClone, tableGenSourceLocationFree, tableGenSourceLocationGet,
+        tableGenSourceLocationGetPointer, tableGenSourceLocationNull,
+        tableGenSourceLocationReadBuffer, tableGenSourceLocationSize,
     },
     string_ref::StringRef,
-    util::print_string_callback,
+    util::{caret_indent, print_string_callback, raw_fn},
 };
 
 /// Enum of TableGen errors.
+///
+/// Errors caused by another error expose it through
+/// [`source`](std::error::Error::source), e.g. invalid UTF-8 strings return
+/// the [`Utf8Error`] or [`FromUtf8Error`]. The message of
+/// [`TableGenError::Parse`] includes its diagnostics.
 #[non_exhaustive]
 #[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
 pub enum TableGenError {
@@ -88,8 +106,8 @@ pub enum TableGenError {
     InvalidUtf8Str(#[from] Utf8Error),
     #[error("invalid UTF-8 string")]
     InvalidUtf8String(#[from] FromUtf8Error),
-    #[error("failed to parse TableGen source")]
-    Parse,
+    #[error("failed to parse TableGen source{}", format_diagnostics(.0))]
+    Parse(Vec<Diagnostic>),
     #[error("expected field {0} in record")]
     MissingValue(String),
     #[error("expected def {0}")]
@@ -101,27 +119,448 @@ pub enum TableGenError {
         from: &'static str,
         to: &'static str,
     },
+    #[error("field {field} declared as {declared}, requested {requested}")]
+    FieldTypeMismatch {
+        field: String,
+        declared: String,
+        requested: String,
+    },
     #[error("invalid source location")]
     InvalidSourceLocation,
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
     #[error("infallible")]
     Infallible(#[from] Infallible),
 }
 
+/// Stable code identifying the kind of a [`TableGenError`].
+///
+/// Unlike the error messages, codes are not changed between releases, so tools
+/// can match on them (or on [`ErrorCode::as_str`]) to handle specific failures.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// The source could not be passed to TableGen.
+    InvalidSource,
+    /// A string is not valid UTF-8.
+    InvalidUtf8,
+    /// TableGen failed to parse the sources.
+    Parse,
+    /// A record does not have the requested field.
+    MissingValue,
+    /// A def does not exist.
+    MissingDef,
+    /// A class does not exist.
+    MissingClass,
+    /// A value has a different type than requested.
+    TypeMismatch,
+    /// A source location does not belong to the given source info.
+    InvalidSourceLocation,
+    /// An argument passed to this crate is invalid.
+    InvalidArgument,
+}
+
+impl ErrorCode {
+    /// Returns the code as a string (e.g. `"missing-value"`).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::InvalidSource => "invalid-source",
+            Self::InvalidUtf8 => "invalid-utf8",
+            Self::Parse => "parse",
+            Self::MissingValue => "missing-value",
+            Self::MissingDef => "missing-def",
+            Self::MissingClass => "missing-class",
+            Self::TypeMismatch => "type-mismatch",
+            Self::InvalidSourceLocation => "invalid-source-location",
+            Self::InvalidArgument => "invalid-argument",
+        }
+    }
+
+    /// Returns the category of this code.
+    pub fn category(self) -> ErrorCategory {
+        match self {
+            Self::Parse => ErrorCategory::Parse,
+            Self::InvalidUtf8
+            | Self::MissingValue
+            | Self::MissingDef
+            | Self::MissingClass
+            | Self::TypeMismatch => ErrorCategory::Access,
+            Self::InvalidSource | Self::InvalidSourceLocation | Self::InvalidArgument => {
+                ErrorCategory::Internal
+            }
+        }
+    }
+}
+
+/// Broad category of a [`TableGenError`], e.g. to convert errors of each
+/// category into a different error type of a dependent crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// TableGen reported errors while parsing the sources (see
+    /// [`TableGenError::Parse`]).
+    Parse,
+    /// A record, field or value does not exist or does not have the requested
+    /// type. These errors are caused by the contents of the sources.
+    Access,
+    /// The crate was used incorrectly (e.g. invalid arguments or source
+    /// information that does not belong to an error) or TableGen failed
+    /// internally.
+    Internal,
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Details of a failed conversion, returned by
+/// [`TableGenError::as_conversion`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Conversion<'a> {
+    /// Name of the field, if known.
+    pub field: Option<&'a str>,
+    /// Kind of the value (e.g. `Int`) or declared type of the field (e.g.
+    /// `list<int>`).
+    pub found: &'a str,
+    /// Requested Rust type.
+    pub expected: &'a str,
+}
+
+impl TableGenError {
+    /// Returns the name of the missing field if this is a
+    /// [`TableGenError::MissingValue`] error.
+    pub fn as_missing_value(&self) -> Option<&str> {
+        match self {
+            Self::MissingValue(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the missing def if this is a
+    /// [`TableGenError::MissingDef`] error.
+    pub fn as_missing_def(&self) -> Option<&str> {
+        match self {
+            Self::MissingDef(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the missing class if this is a
+    /// [`TableGenError::MissingClass`] error.
+    pub fn as_missing_class(&self) -> Option<&str> {
+        match self {
+            Self::MissingClass(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Returns the details of a failed conversion if this is a
+    /// [`TableGenError::InitConversion`] or
+    /// [`TableGenError::FieldTypeMismatch`] error.
+    pub fn as_conversion(&self) -> Option<Conversion<'_>> {
+        match self {
+            Self::InitConversion { from, to } => Some(Conversion {
+                field: None,
+                found: from,
+                expected: to,
+            }),
+            Self::FieldTypeMismatch {
+                field,
+                declared,
+                requested,
+            } => Some(Conversion {
+                field: Some(field),
+                found: declared,
+                expected: requested,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the diagnostics if this is a [`TableGenError::Parse`] error.
+    pub fn as_parse(&self) -> Option<&[Diagnostic]> {
+        match self {
+            Self::Parse(diagnostics) => Some(diagnostics),
+            _ => None,
+        }
+    }
+
+    /// Returns the category of this error.
+    pub fn category(&self) -> ErrorCategory {
+        self.code().category()
+    }
+
+    /// Returns the code of this error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::InvalidSource | Self::InvalidSourceString(_) => ErrorCode::InvalidSource,
+            Self::InvalidUtf8Str(_) | Self::InvalidUtf8String(_) => ErrorCode::InvalidUtf8,
+            Self::Parse(_) => ErrorCode::Parse,
+            Self::MissingValue(_) => ErrorCode::MissingValue,
+            Self::MissingDef(_) => ErrorCode::MissingDef,
+            Self::MissingClass(_) => ErrorCode::MissingClass,
+            Self::InitConversion { .. } | Self::FieldTypeMismatch { .. } => ErrorCode::TypeMismatch,
+            Self::InvalidSourceLocation => ErrorCode::InvalidSourceLocation,
+            Self::InvalidArgument(_) => ErrorCode::InvalidArgument,
+            Self::Infallible(infallible) => match *infallible {},
+        }
+    }
+}
+
+/// Errors reported by TableGen while parsing the sources.
+///
+/// This and the other category types ([`LookupError`], [`TypeMismatchError`]
+/// and [`SourceLocationError`]) are obtained from a [`TableGenError`] (or an
+/// [`Error`], keeping its location) with `try_from`, which returns the
+/// original error if it belongs to a different category. That way, dependent
+/// crates can convert each category into their own error types:
+///
+/// ```rust
+/// use tblgen::{
+///     TableGenParser,
+///     error::{Error, LookupError, SourceError},
+/// };
+///
+/// enum MyError {
+///     Missing(SourceError<LookupError>),
+///     Other(Error),
+/// }
+///
+/// impl From<Error> for MyError {
+///     fn from(error: Error) -> Self {
+///         match SourceError::<LookupError>::try_from(error) {
+///             Ok(error) => Self::Missing(error),
+///             Err(error) => Self::Other(error),
+///         }
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let keeper = TableGenParser::new().add_source("def A;")?.parse()?;
+/// let error = MyError::from(keeper.def("B").unwrap_err());
+/// assert!(matches!(
+///     error,
+///     MyError::Missing(error) if *error.error() == LookupError::MissingDef("B".into())
+/// ));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError(Vec<Diagnostic>);
+
+impl ParseError {
+    /// Creates a parse error from the diagnostics reported by TableGen.
+    pub fn new(diagnostics: Vec<Diagnostic>) -> Self {
+        Self(diagnostics)
+    }
+
+    /// Returns the diagnostics reported by TableGen.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.0
+    }
+}
+
+/// A record or field that does not exist.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LookupError {
+    MissingValue(String),
+    MissingDef(String),
+    MissingClass(String),
+}
+
+/// A value that does not have the requested type.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeMismatchError {
+    InitConversion {
+        from: &'static str,
+        to: &'static str,
+    },
+    FieldTypeMismatch {
+        field: String,
+        declared: String,
+        requested: String,
+    },
+}
+
+/// A source location that does not belong to the given source info.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocationError;
+
+impl From<ParseError> for TableGenError {
+    fn from(value: ParseError) -> Self {
+        Self::Parse(value.0)
+    }
+}
+
+impl TryFrom<TableGenError> for ParseError {
+    type Error = TableGenError;
+
+    fn try_from(value: TableGenError) -> Result<Self, Self::Error> {
+        match value {
+            TableGenError::Parse(diagnostics) => Ok(Self(diagnostics)),
+            error => Err(error),
+        }
+    }
+}
+
+impl From<LookupError> for TableGenError {
+    fn from(value: LookupError) -> Self {
+        match value {
+            LookupError::MissingValue(name) => Self::MissingValue(name),
+            LookupError::MissingDef(name) => Self::MissingDef(name),
+            LookupError::MissingClass(name) => Self::MissingClass(name),
+        }
+    }
+}
+
+impl TryFrom<TableGenError> for LookupError {
+    type Error = TableGenError;
+
+    fn try_from(value: TableGenError) -> Result<Self, Self::Error> {
+        match value {
+            TableGenError::MissingValue(name) => Ok(Self::MissingValue(name)),
+            TableGenError::MissingDef(name) => Ok(Self::MissingDef(name)),
+            TableGenError::MissingClass(name) => Ok(Self::MissingClass(name)),
+            error => Err(error),
+        }
+    }
+}
+
+impl From<TypeMismatchError> for TableGenError {
+    fn from(value: TypeMismatchError) -> Self {
+        match value {
+            TypeMismatchError::InitConversion { from, to } => Self::InitConversion { from, to },
+            TypeMismatchError::FieldTypeMismatch {
+                field,
+                declared,
+                requested,
+            } => Self::FieldTypeMismatch {
+                field,
+                declared,
+                requested,
+            },
+        }
+    }
+}
+
+impl TryFrom<TableGenError> for TypeMismatchError {
+    type Error = TableGenError;
+
+    fn try_from(value: TableGenError) -> Result<Self, Self::Error> {
+        match value {
+            TableGenError::InitConversion { from, to } => Ok(Self::InitConversion { from, to }),
+            TableGenError::FieldTypeMismatch {
+                field,
+                declared,
+                requested,
+            } => Ok(Self::FieldTypeMismatch {
+                field,
+                declared,
+                requested,
+            }),
+            error => Err(error),
+        }
+    }
+}
+
+impl From<SourceLocationError> for TableGenError {
+    fn from(_: SourceLocationError) -> Self {
+        Self::InvalidSourceLocation
+    }
+}
+
+impl TryFrom<TableGenError> for SourceLocationError {
+    type Error = TableGenError;
+
+    fn try_from(value: TableGenError) -> Result<Self, Self::Error> {
+        match value {
+            TableGenError::InvalidSourceLocation => Ok(Self),
+            error => Err(error),
+        }
+    }
+}
+
+macro_rules! category_error {
+    ($type:ty) => {
+        impl Display for $type {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                write!(f, "{}", TableGenError::from(self.clone()))
+            }
+        }
+
+        impl From<$type> for Error {
+            fn from(value: $type) -> Self {
+                TableGenError::from(value).into()
+            }
+        }
+
+        impl From<SourceError<$type>> for Error {
+            fn from(value: SourceError<$type>) -> Self {
+                value.map_error(TableGenError::from)
+            }
+        }
+
+        impl TryFrom<Error> for SourceError<$type> {
+            type Error = Error;
+
+            fn try_from(value: Error) -> Result<Self, Self::Error> {
+                split_error(value.map_error(<$type>::try_from))
+            }
+        }
+    };
+}
+
+category_error!(ParseError);
+category_error!(LookupError);
+category_error!(TypeMismatchError);
+category_error!(SourceLocationError);
+
+impl std::error::Error for ParseError {}
+
+impl std::error::Error for LookupError {}
+
+impl std::error::Error for TypeMismatchError {}
+
+impl std::error::Error for SourceLocationError {}
+
 /// A location in a TableGen source file.
-#[derive(Debug, PartialEq, Eq)]
+///
+/// Locations are equal if they point at the same positions, and are ordered
+/// by their positions within a source buffer. Locations in different buffers
+/// are ordered arbitrarily, but consistently. Records instantiated from a
+/// multiclass are ordered by their location in the multiclass first and then
+/// by the locations of the `defm`s.
+#[derive(Eq)]
 pub struct SourceLocation {
     raw: TableGenSourceLocationRef,
 }
 
-// SourceLocation is a read-only llvm::ArrayRef, which should be thread-safe.
+// SourceLocation is only read, and its source buffers are read while holding a
+// lock that keeps the parser owning them alive.
 unsafe impl Sync for SourceLocation {}
 unsafe impl Send for SourceLocation {}
 
 impl SourceLocation {
-    /// # Safety
-    /// The passed pointer should be a valid table gen source location.
-    pub unsafe fn from_raw(raw: TableGenSourceLocationRef) -> Self {
-        Self { raw }
+    raw_fn! {
+        /// Creates a location from a raw object, which it takes ownership of.
+        ///
+        /// # Safety
+        ///
+        /// The raw object must be a valid source location that is not owned by
+        /// anything else.
+        unsafe fn from_raw(raw: TableGenSourceLocationRef) -> Self {
+            Self { raw }
+        }
+    }
+
+    raw_fn! {
+        /// Returns the raw object, which is still owned by this location.
+        fn to_raw(&self) -> TableGenSourceLocationRef {
+            self.raw
+        }
     }
 
     /// Returns a [`SourceLocation`] for an undetermined location in the
@@ -133,6 +572,110 @@ impl SourceLocation {
             }
         }
     }
+
+    /// Returns the number of locations. Records instantiated from a
+    /// multiclass have additional locations for each `defm`.
+    pub(crate) fn len(&self) -> usize {
+        unsafe { tableGenSourceLocationSize(self.raw) }
+    }
+
+    /// Returns a new [`SourceLocation`] that only contains the location at
+    /// the given index.
+    pub(crate) fn get(&self, index: usize) -> SourceLocation {
+        unsafe { Self::from_raw(tableGenSourceLocationGet(self.raw, index)) }
+    }
+
+    /// Returns the pointers into the source buffers of all locations.
+    fn pointers(&self) -> impl Iterator<Item = *const c_char> + '_ {
+        (0..self.len()).map(|index| unsafe { tableGenSourceLocationGetPointer(self.raw, index) })
+    }
+
+    /// Calls the given function with the name of the source buffer containing
+    /// the location at the given index, the contents of this buffer and the
+    /// offset of the location in it.
+    ///
+    /// The buffer is owned by the parser, so it is only borrowed while the
+    /// record keeper the location was obtained from cannot be dropped. This
+    /// returns `None` once it has been dropped.
+    pub(crate) fn read_buffer<T, F: FnOnce(&[u8], &[u8], usize) -> T>(
+        &self,
+        index: usize,
+        read: F,
+    ) -> Option<T> {
+        unsafe extern "C" fn callback<T, F: FnOnce(&[u8], &[u8], usize) -> T>(
+            name: TableGenStringRef,
+            buffer: TableGenStringRef,
+            offset: usize,
+            data: *mut c_void,
+        ) {
+            let (read, result) = unsafe { &mut *(data as *mut (Option<F>, Option<T>)) };
+            if let Some(read) = read.take() {
+                unsafe {
+                    *result = Some(read(
+                        StringRef::from_raw(name).into(),
+                        StringRef::from_raw(buffer).into(),
+                        offset,
+                    ));
+                }
+            }
+        }
+
+        let mut data = (Some(read), None);
+        unsafe {
+            tableGenSourceLocationReadBuffer(
+                self.raw,
+                index,
+                Some(callback::<T, F>),
+                &mut data as *mut _ as *mut c_void,
+            );
+        }
+        data.1
+    }
+
+    /// Returns an excerpt of the source buffer containing the location at the
+    /// given index, which is copied from the buffer.
+    ///
+    /// Returns `None` once the record keeper the location was obtained from
+    /// has been dropped.
+    pub(crate) fn excerpt(&self, index: usize) -> Option<SourceExcerpt> {
+        self.read_buffer(index, SourceExcerpt::new)
+    }
+
+    /// Returns the source buffer of the given parser that contains the first
+    /// location, together with the offset of the location in this buffer.
+    ///
+    /// Returns `None` if the record keeper the location was obtained from has
+    /// been dropped, even if the parser reused its memory.
+    pub(crate) fn buffer_in<'p>(&self, parser: &'p TableGenParser) -> Option<(&'p [u8], usize)> {
+        let mut buffer = TableGenStringRef {
+            data: std::ptr::null(),
+            len: 0,
+        };
+        let mut offset = 0;
+        unsafe {
+            if tableGenFindSourceBuffer(parser.raw, self.raw, &mut buffer, &mut offset) > 0 {
+                Some((StringRef::from_raw(buffer).into(), offset))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the name of the source buffer of the given parser that contains
+    /// the first location.
+    pub(crate) fn buffer_name_in<'p>(&self, parser: &'p TableGenParser) -> Option<&'p [u8]> {
+        let mut name = TableGenStringRef {
+            data: std::ptr::null(),
+            len: 0,
+        };
+        unsafe {
+            if tableGenFindSourceBufferName(parser.raw, self.raw, &mut name) > 0 {
+                Some(StringRef::from_raw(name).into())
+            } else {
+                None
+            }
+        }
+    }
 }
 
 impl Clone for SourceLocation {
@@ -141,22 +684,271 @@ impl Clone for SourceLocation {
     }
 }
 
+impl Debug for SourceLocation {
+    /// Formats the positions as `file:line:column`, or `?` if the record
+    /// keeper of the location has been dropped.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "SourceLocation(")?;
+        for index in 0..self.len() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            match self.excerpt(index) {
+                Some(excerpt) => write!(f, "{}", excerpt)?,
+                None => write!(f, "?")?,
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+impl PartialEq for SourceLocation {
+    fn eq(&self, other: &Self) -> bool {
+        self.pointers().eq(other.pointers())
+    }
+}
+
+impl PartialOrd for SourceLocation {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SourceLocation {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.pointers().cmp(other.pointers())
+    }
+}
+
 impl Drop for SourceLocation {
     fn drop(&mut self) {
         unsafe { tableGenSourceLocationFree(self.raw) }
     }
 }
 
+/// A range of a TableGen source buffer from a start location (inclusive) to
+/// an end location (exclusive).
+///
+/// For example, the range between a record and the next record in the same
+/// file contains the body of the record, which allows editors to find the
+/// record that encloses a location.
+///
+/// ```rust
+/// use tblgen::{TableGenParser, error::SourceRange};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let keeper = TableGenParser::new()
+///     .add_source("class A { int x = 1; }\ndef B: A;\n")?
+///     .parse()?;
+/// let a = keeper.class("A")?;
+/// let b = keeper.def("B")?;
+/// let range = SourceRange::new(a, b);
+/// assert!(range.contains(a.value("x")?));
+/// assert!(!range.contains(b));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceRange {
+    start: SourceLocation,
+    end: SourceLocation,
+}
+
+impl SourceRange {
+    /// Creates a range from the given start and end locations, which should
+    /// be in the same source buffer.
+    pub fn new(start: impl SourceLoc, end: impl SourceLoc) -> Self {
+        Self {
+            start: start.source_location(),
+            end: end.source_location(),
+        }
+    }
+
+    /// Returns the start location.
+    pub fn start(&self) -> &SourceLocation {
+        &self.start
+    }
+
+    /// Returns the end location.
+    pub fn end(&self) -> &SourceLocation {
+        &self.end
+    }
+
+    /// Returns true if the first position of the given location is within
+    /// this range.
+    ///
+    /// Since source buffers are contiguous, a location between the start and
+    /// the end is always in the same buffer. Empty locations (see
+    /// [`SourceLocation::none`]) are never contained in a range.
+    pub fn contains(&self, location: impl SourceLoc) -> bool {
+        let location = location.source_location();
+        match (
+            self.start.pointers().next(),
+            self.end.pointers().next(),
+            location.pointers().next(),
+        ) {
+            (Some(start), Some(end), Some(pointer)) => start <= pointer && pointer < end,
+            _ => false,
+        }
+    }
+}
+
+/// Excerpt of a TableGen source file, which is copied into an error by
+/// [`SourceError::with_owned_source_info`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceExcerpt {
+    file: String,
+    line: usize,
+    column: usize,
+    line_contents: String,
+}
+
+impl SourceExcerpt {
+    fn new(file: &[u8], buffer: &[u8], offset: usize) -> Self {
+        let line_start = buffer[..offset]
+            .iter()
+            .rposition(|&c| c == b'\n')
+            .map_or(0, |i| i + 1);
+        let line_end = buffer[offset..]
+            .iter()
+            .position(|&c| c == b'\n')
+            .map_or(buffer.len(), |i| offset + i);
+        let line_contents = String::from_utf8_lossy(&buffer[line_start..line_end]);
+        Self {
+            file: String::from_utf8_lossy(file).into_owned(),
+            line: buffer[..line_start].iter().filter(|&&c| c == b'\n').count() + 1,
+            column: offset - line_start + 1,
+            line_contents: line_contents.trim_end_matches('\r').into(),
+        }
+    }
+
+    /// Returns the name of the file.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Returns the line (starting at 1).
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the column (starting at 1).
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Returns the contents of the line.
+    pub fn line_contents(&self) -> &str {
+        &self.line_contents
+    }
+
+    /// Formats the given message the way TableGen prints errors, including the
+    /// line and a caret pointing at the column.
+    ///
+    /// The caret is aligned by the display width of the characters before the
+    /// column, so that it points at the right character in lines that contain
+    /// non-ASCII characters.
+    fn render(&self, f: &mut Formatter, message: &str) -> fmt::Result {
+        let prefix = self
+            .line_contents
+            .get(..self.column - 1)
+            .unwrap_or(&self.line_contents);
+        write!(
+            f,
+            "{}: error: {}\n{}\n{}^",
+            self,
+            message,
+            self.line_contents,
+            caret_indent(prefix)
+        )
+    }
+}
+
+impl Display for SourceExcerpt {
+    /// Formats the position as `file:line:column`, or `line:column` for
+    /// unnamed sources.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if !self.file.is_empty() {
+            write!(f, "{}:", self.file)?;
+        }
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// The record field in which an error occurred.
+///
+/// Conversions of record fields attach this to their errors, which are then
+/// displayed as e.g. `field 'summary' of def 'AddOp': invalid conversion from
+/// Int to String`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FieldContext {
+    record: String,
+    class: bool,
+    field: String,
+}
+
+impl FieldContext {
+    pub(crate) fn new(record: Record, field: &str) -> Self {
+        Self::named(
+            &String::from_utf8_lossy(record.name_bytes()),
+            record.is_class(),
+            field,
+        )
+    }
+
+    pub(crate) fn named(record: &str, class: bool, field: &str) -> Self {
+        Self {
+            record: record.into(),
+            class,
+            field: field.into(),
+        }
+    }
+
+    /// Returns the name of the record.
+    pub fn record(&self) -> &str {
+        &self.record
+    }
+
+    /// Returns true if the record is a class.
+    pub fn is_class(&self) -> bool {
+        self.class
+    }
+
+    /// Returns the name of the field.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+}
+
+impl Display for FieldContext {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "field '{}' of {} '{}'",
+            self.field,
+            if self.class { "class" } else { "def" },
+            self.record
+        )
+    }
+}
+
 /// A wrapper around error types which includes a [`SourceLocation`].
 ///
 /// This error is used to describe erros in the TableGen source file at a
 /// certain location.
 ///
 /// By calling `add_source_info`, information about the TableGen source file at
-/// the [`SourceLocation`] will be included in this error.
+/// the [`SourceLocation`] will be included in this error. Otherwise, the error
+/// message is prefixed with `file:line:column` as long as the record keeper the
+/// error was obtained from is alive.
+///
+/// The wrapped error is returned by [`source`](std::error::Error::source), so
+/// its own causes can be found by following the chain.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SourceError<E> {
     location: SourceLocation,
+    field: Option<Box<FieldContext>>,
+    excerpt: Option<Box<SourceExcerpt>>,
     message: Option<String>,
     error: E,
 }
@@ -167,6 +959,8 @@ impl<E: std::error::Error> SourceError<E> {
         Self {
             location,
             error,
+            field: None,
+            excerpt: None,
             message: None,
         }
     }
@@ -175,6 +969,21 @@ impl<E: std::error::Error> SourceError<E> {
         &self.location
     }
 
+    /// Returns the record field in which the error occurred, if known.
+    pub fn field(&self) -> Option<&FieldContext> {
+        self.field.as_deref()
+    }
+
+    /// Sets the record field in which the error occurred.
+    ///
+    /// Any source information that was previously attached with
+    /// [`SourceError::add_source_info`] will be removed.
+    pub fn set_field(mut self, field: FieldContext) -> Self {
+        self.field = Some(Box::new(field));
+        self.message = None;
+        self
+    }
+
     pub fn error(&self) -> &E {
         &self.error
     }
@@ -187,6 +996,8 @@ impl<E: std::error::Error> SourceError<E> {
         SourceError {
             error,
             message: None,
+            field: self.field,
+            excerpt: self.excerpt,
             location: self.location,
         }
     }
@@ -194,9 +1005,11 @@ impl<E: std::error::Error> SourceError<E> {
     /// Replaces the location.
     ///
     /// Any source information that was previously attached with
-    /// [`SourceError::add_source_info`] will be removed.
+    /// [`SourceError::add_source_info`] or
+    /// [`SourceError::with_owned_source_info`] will be removed.
     pub fn set_location(mut self, location: impl SourceLoc) -> Self {
         self.location = location.source_location();
+        self.excerpt = None;
         self
     }
 
@@ -208,11 +1021,44 @@ impl<E: std::error::Error> SourceError<E> {
         self.message = Some(Self::create_message(
             info.0,
             &self.location,
-            &format!("{}", self.error),
+            &self.description(),
         ));
         self
     }
 
+    /// Copies an excerpt of the TableGen source file at the given
+    /// [`SourceLocation`] into this error.
+    ///
+    /// Unlike [`SourceError::add_source_info`], the message is not created by
+    /// LLVM, but the excerpt is kept so that the error can still be rendered
+    /// with the source line and a caret (and exported with
+    /// [`SourceError::to_sarif`]) after the record keeper has been dropped.
+    ///
+    /// If the location does not belong to the given source info, any excerpt
+    /// that was previously copied is removed.
+    pub fn with_owned_source_info(mut self, info: &SourceInfo) -> Self {
+        self.excerpt = self
+            .location
+            .buffer_in(info.0)
+            .zip(self.location.buffer_name_in(info.0))
+            .map(|((buffer, offset), name)| Box::new(SourceExcerpt::new(name, buffer, offset)));
+        self
+    }
+
+    /// Returns the source excerpt that was copied into this error by
+    /// [`SourceError::with_owned_source_info`].
+    pub fn excerpt(&self) -> Option<&SourceExcerpt> {
+        self.excerpt.as_deref()
+    }
+
+    /// Returns the error message without source information.
+    fn description(&self) -> String {
+        match &self.field {
+            Some(field) => format!("{}: {}", field, self.error),
+            None => self.error.to_string(),
+        }
+    }
+
     fn create_message(parser: &TableGenParser, location: &SourceLocation, message: &str) -> String {
         let mut data: (_, Result<_, TableGenError>) = (String::new(), Ok(()));
         let res = unsafe {
@@ -235,19 +1081,200 @@ impl<E: std::error::Error> SourceError<E> {
     }
 }
 
+impl<E> SourceError<E> {
+    /// Converts the inner error without changing the message, e.g. into an
+    /// error of the same category.
+    fn map_error<F>(self, f: impl FnOnce(E) -> F) -> SourceError<F> {
+        SourceError {
+            error: f(self.error),
+            message: self.message,
+            field: self.field,
+            excerpt: self.excerpt,
+            location: self.location,
+        }
+    }
+}
+
+/// Splits an error with a converted inner error into the converted error or
+/// the original one.
+fn split_error<T, E>(error: SourceError<Result<T, E>>) -> Result<SourceError<T>, SourceError<E>> {
+    let SourceError {
+        location,
+        field,
+        excerpt,
+        message,
+        error,
+    } = error;
+    match error {
+        Ok(error) => Ok(SourceError {
+            location,
+            field,
+            excerpt,
+            message,
+            error,
+        }),
+        Err(error) => Err(SourceError {
+            location,
+            field,
+            excerpt,
+            message,
+            error,
+        }),
+    }
+}
+
+impl SourceError<TableGenError> {
+    /// Returns the code of the inner error.
+    pub fn code(&self) -> ErrorCode {
+        self.error.code()
+    }
+
+    /// Returns the category of the inner error.
+    pub fn category(&self) -> ErrorCategory {
+        self.error.category()
+    }
+
+    /// Returns the name of the missing field if the inner error is a
+    /// [`TableGenError::MissingValue`] error.
+    ///
+    /// ```rust
+    /// use tblgen::TableGenParser;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let keeper = TableGenParser::new().add_source("def A;")?.parse()?;
+    /// let size = match keeper.def("A")?.int_value("size") {
+    ///     Err(error) if error.as_missing_value() == Some("size") => 0,
+    ///     result => result?,
+    /// };
+    /// assert_eq!(size, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn as_missing_value(&self) -> Option<&str> {
+        self.error.as_missing_value()
+    }
+
+    /// Returns the name of the missing def if the inner error is a
+    /// [`TableGenError::MissingDef`] error.
+    pub fn as_missing_def(&self) -> Option<&str> {
+        self.error.as_missing_def()
+    }
+
+    /// Returns the name of the missing class if the inner error is a
+    /// [`TableGenError::MissingClass`] error.
+    pub fn as_missing_class(&self) -> Option<&str> {
+        self.error.as_missing_class()
+    }
+
+    /// Returns the details of a failed conversion (see
+    /// [`TableGenError::as_conversion`]). The field name is also taken from
+    /// the [`FieldContext`] of this error.
+    pub fn as_conversion(&self) -> Option<Conversion<'_>> {
+        self.error.as_conversion().map(|conversion| Conversion {
+            field: conversion
+                .field
+                .or(self.field.as_ref().map(|field| field.field())),
+            ..conversion
+        })
+    }
+
+    /// Returns the diagnostics if the inner error is a
+    /// [`TableGenError::Parse`] error.
+    pub fn as_parse(&self) -> Option<&[Diagnostic]> {
+        self.error.as_parse()
+    }
+
+    /// Returns this error as a [SARIF](https://sarifweb.azurewebsites.net/)
+    /// 2.1.0 log, which can be used to annotate TableGen source files in CI
+    /// systems and code review tools.
+    ///
+    /// Parse errors result in one SARIF result for each [`Diagnostic`]. Other
+    /// errors result in a single result, which only includes the location if
+    /// an excerpt was added with [`SourceError::with_owned_source_info`].
+    pub fn to_sarif(&self) -> String {
+        let results = match &self.error {
+            TableGenError::Parse(diagnostics) => diagnostics
+                .iter()
+                .map(Diagnostic::to_sarif_result)
+                .collect(),
+            error => vec![Json::object([
+                ("ruleId", error.code().as_str().into()),
+                ("level", "error".into()),
+                (
+                    "message",
+                    Json::object([("text", self.description().into())]),
+                ),
+                (
+                    "locations",
+                    self.excerpt
+                        .as_deref()
+                        .map(|excerpt| sarif_location(&excerpt.file, excerpt.line, excerpt.column))
+                        .into_iter()
+                        .collect(),
+                ),
+            ])],
+        };
+        Json::object([
+            (
+                "$schema",
+                "https://json.schemastore.org/sarif-2.1.0.json".into(),
+            ),
+            ("version", "2.1.0".into()),
+            (
+                "runs",
+                Json::Array(vec![Json::object([
+                    (
+                        "tool",
+                        Json::object([(
+                            "driver",
+                            Json::object([
+                                ("name", env!("CARGO_PKG_NAME").into()),
+                                ("version", env!("CARGO_PKG_VERSION").into()),
+                                ("informationUri", env!("CARGO_PKG_REPOSITORY").into()),
+                            ]),
+                        )]),
+                    ),
+                    ("results", Json::Array(results)),
+                ])]),
+            ),
+        ])
+        .to_string()
+    }
+}
+
+/// Returns a SARIF location object.
+pub(crate) fn sarif_location(file: &str, line: usize, column: usize) -> Json {
+    Json::object([(
+        "physicalLocation",
+        Json::object([
+            ("artifactLocation", Json::object([("uri", file.into())])),
+            (
+                "region",
+                Json::object([("startLine", line.into()), ("startColumn", column.into())]),
+            ),
+        ]),
+    )])
+}
+
 impl<E: std::error::Error> Display for SourceError<E> {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         if let Some(message) = self.message.as_ref() {
             write!(f, "{}", message)
+        } else if let Some(excerpt) = self.excerpt.as_ref() {
+            excerpt.render(f, &self.description())
+        } else if let Some(excerpt) = self.location.excerpt(0) {
+            write!(f, "{}: {}", excerpt, self.description())
         } else {
-            write!(f, "{}", self.error)
+            write!(f, "{}", self.description())
         }
     }
 }
 
+// The message includes the message of the inner error, so its source is
+// returned instead of the inner error itself.
 impl<E: std::error::Error + 'static> std::error::Error for SourceError<E> {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        Some(&self.error)
+        self.error.source()
     }
 }
 
@@ -257,6 +1284,145 @@ impl From<TableGenError> for SourceError<TableGenError> {
     }
 }
 
+/// Collects the errors of a series of fallible operations, so that all of them
+/// can be reported at once instead of stopping at the first one.
+///
+/// ```rust
+/// use tblgen::{TableGenParser, error::ErrorCollector};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let keeper = TableGenParser::new()
+///     .add_source("def A { int size = 4; }")?
+///     .parse()?;
+/// let a = keeper.def("A")?;
+///
+/// let mut errors = ErrorCollector::new();
+/// let name = errors.take_or_default(a.string_value("name"));
+/// let size = errors.take_or_default(a.string_value("size"));
+/// let alignment = errors.take_or_default(a.int_value("alignment"));
+///
+/// let errors = errors.finish((name, size, alignment)).unwrap_err();
+/// assert_eq!(errors.len(), 3);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorCollector {
+    errors: Vec<Error>,
+}
+
+impl ErrorCollector {
+    /// Creates an empty collector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value of the given result, or records the error and
+    /// returns `None`.
+    pub fn take<T>(&mut self, result: Result<T, Error>) -> Option<T> {
+        result.map_err(|error| self.push(error)).ok()
+    }
+
+    /// Returns the value of the given result, or records the error and
+    /// returns the default value.
+    pub fn take_or_default<T: Default>(&mut self, result: Result<T, Error>) -> T {
+        self.take(result).unwrap_or_default()
+    }
+
+    /// Records the given error.
+    pub fn push(&mut self, error: Error) {
+        self.errors.push(error);
+    }
+
+    /// Returns the recorded errors.
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// Returns true if no errors were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the given value if no errors were recorded, or all recorded
+    /// errors otherwise.
+    pub fn finish<T>(self, value: T) -> Result<T, Errors> {
+        if self.errors.is_empty() {
+            Ok(value)
+        } else {
+            Err(Errors(self.errors))
+        }
+    }
+}
+
+/// Non-empty list of errors returned by [`ErrorCollector::finish`].
+///
+/// The [`Display`] implementation prints each error on a separate line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Errors(Vec<Error>);
+
+impl Errors {
+    /// Returns the number of errors.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns an iterator over the errors.
+    pub fn iter(&self) -> std::slice::Iter<'_, Error> {
+        self.0.iter()
+    }
+
+    /// Adds information about the TableGen source file to all errors (see
+    /// [`SourceError::add_source_info`]).
+    pub fn add_source_info(self, info: SourceInfo) -> Self {
+        Self(
+            self.0
+                .into_iter()
+                .map(|error| error.add_source_info(info))
+                .collect(),
+        )
+    }
+}
+
+impl Display for Errors {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (index, error) in self.0.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Errors {}
+
+impl IntoIterator for Errors {
+    type Item = Error;
+    type IntoIter = std::vec::IntoIter<Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Errors {
+    type Item = &'a Error;
+    type IntoIter = std::slice::Iter<'a, Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl From<Errors> for Vec<Error> {
+    fn from(errors: Errors) -> Self {
+        errors.0
+    }
+}
+
 pub trait WithLocation: std::error::Error + Sized {
     /// Creates a [`SourceError`] wrapper.
     fn with_location<L: SourceLoc>(self, location: L) -> SourceError<Self> {
@@ -0,0 +1,167 @@
+use std::{fmt, sync::Arc};
+
+use crate::raw::{
+    TableGenIncludeCacheRef, tableGenIncludeCacheCachesIncludes, tableGenIncludeCacheClear,
+    tableGenIncludeCacheCreate, tableGenIncludeCacheFree, tableGenIncludeCacheSize,
+};
+
+/// Cache of TableGen source files that can be shared between parsers.
+///
+/// Source files added with
+/// [`add_source_file`](crate::TableGenParser::add_source_file) are loaded
+/// through the cache, and so are files pulled in by `include` directives if
+/// [`IncludeCache::caches_includes`] returns true. Files are identified by
+/// their resolved path, and read again if their size or modification time
+/// changed.
+///
+/// Cloning an [`IncludeCache`] is cheap and returns a handle to the same cache.
+///
+/// ```rust,no_run
+/// use tblgen::{IncludeCache, TableGenParser};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let cache = IncludeCache::new();
+/// for file in ["A.td", "B.td"] {
+///     // Headers included by both files are only read once.
+///     let keeper = TableGenParser::new()
+///         .with_include_cache(&cache)
+///         .add_source_file(file)
+///         .parse()?;
+///     println!("{}: {} defs", file, keeper.defs().count());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct IncludeCache(Arc<RawIncludeCache>);
+
+struct RawIncludeCache(TableGenIncludeCacheRef);
+
+// The C++ cache guards all accesses with a mutex.
+unsafe impl Send for RawIncludeCache {}
+unsafe impl Sync for RawIncludeCache {}
+
+impl Drop for RawIncludeCache {
+    fn drop(&mut self) {
+        unsafe { tableGenIncludeCacheFree(self.0) }
+    }
+}
+
+impl IncludeCache {
+    /// Creates a new, empty cache.
+    pub fn new() -> Self {
+        Self(Arc::new(RawIncludeCache(unsafe {
+            tableGenIncludeCacheCreate()
+        })))
+    }
+
+    /// Returns true if files pulled in by `include` directives are loaded
+    /// through the cache.
+    ///
+    /// TableGen opens included files through the virtual file system of its
+    /// source manager, which older LLVM versions do not have. With those
+    /// versions, only files passed to the parser directly are cached.
+    pub fn caches_includes() -> bool {
+        unsafe { tableGenIncludeCacheCachesIncludes() > 0 }
+    }
+
+    /// Returns the number of cached files.
+    pub fn len(&self) -> usize {
+        unsafe { tableGenIncludeCacheSize(self.0.0) }
+    }
+
+    /// Returns true if no files are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached files.
+    ///
+    /// Parsers that already loaded files from the cache are not affected.
+    pub fn clear(&self) {
+        unsafe { tableGenIncludeCacheClear(self.0.0) }
+    }
+
+    pub(crate) fn to_raw(&self) -> TableGenIncludeCacheRef {
+        self.0.0
+    }
+}
+
+impl Default for IncludeCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for IncludeCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IncludeCache {{ len: {} }}", self.len())
+    }
+}
+
+impl PartialEq for IncludeCache {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for IncludeCache {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+    use std::fs::{self, File};
+
+    #[test]
+    fn share_included_file() {
+        let dir = std::env::temp_dir().join(format!("tblgen-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Common.td"), "def Old;").unwrap();
+        fs::write(dir.join("A.td"), "include \"Common.td\"\ndef A;").unwrap();
+        fs::write(dir.join("B.td"), "include \"Common.td\"\ndef B;").unwrap();
+        let parse = |cache: &IncludeCache, file: &str| {
+            TableGenParser::new()
+                .with_include_cache(cache)
+                .add_include_directory(dir.to_str().unwrap())
+                .add_source_file(file)
+                .parse()
+                .expect("valid tablegen")
+        };
+
+        let cache = IncludeCache::new();
+        assert!(cache.is_empty());
+        let rk = parse(&cache, "A.td");
+        assert!(rk.def("A").is_ok());
+        assert!(rk.def("Old").is_ok());
+        let included = IncludeCache::caches_includes();
+        assert_eq!(cache.len(), if included { 2 } else { 1 });
+
+        // Replace the shared include with contents of the same size and keep
+        // its modification time, so that only a cached copy has the old def.
+        let common = dir.join("Common.td");
+        let modified = fs::metadata(&common).unwrap().modified().unwrap();
+        fs::write(&common, "def New;").unwrap();
+        File::options()
+            .write(true)
+            .open(&common)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        let rk = parse(&cache, "B.td");
+        assert!(rk.def("B").is_ok());
+        assert_eq!(rk.def("Old").is_ok(), included);
+        assert_eq!(rk.def("New").is_ok(), !included);
+        assert_eq!(cache.len(), if included { 3 } else { 2 });
+
+        // Files are read again once they change.
+        fs::write(dir.join("A.td"), "include \"Common.td\"\ndef A2;").unwrap();
+        let rk = parse(&cache, "A.td");
+        assert!(rk.def("A2").is_ok());
+        assert!(rk.def("A").is_err());
+
+        cache.clear();
+        assert!(cache.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -22,17 +22,20 @@ use crate::{
         TableGenRecTyKind, TableGenTypedInitRef, tableGenBitInitGetValue, tableGenBitInitIsVarBit,
         tableGenBitsInitGetBitInit, tableGenBitsInitGetNumBits, tableGenDagRecordArgName,
         tableGenDagRecordGet, tableGenDagRecordGetArgNo, tableGenDagRecordNumArgs,
-        tableGenDagRecordOperator, tableGenDefInitGetValue, tableGenInitDump, tableGenInitPrint,
-        tableGenInitRecType, tableGenIntInitGetValue, tableGenListInitGetElementType,
-        tableGenListRecordGet, tableGenListRecordNumElements, tableGenStringInitGetValue,
-        tableGenVarBitInitGetBitNum, tableGenVarBitInitGetVarName,
+        tableGenDagRecordOperator, tableGenDagRecordOperatorInit, tableGenDefInitGetValue,
+        tableGenInitDump, tableGenInitGetRecTy, tableGenInitPrint, tableGenInitRecType,
+        tableGenIntInitGetValue, tableGenListInitGetElementType, tableGenListRecordGet,
+        tableGenListRecordNumElements, tableGenStringInitGetValue, tableGenVarBitInitGetBitNum,
+        tableGenVarBitInitGetVarName,
     },
     string_ref::StringRef,
-    util::print_callback,
+    ty::RecTy,
+    util::{print_callback, raw_fn},
 };
 use paste::paste;
 
 use crate::{
+    dag::DagExpr,
     error::{Error, TableGenError},
     record::Record,
 };
@@ -59,7 +62,7 @@ pub enum TypedInit<'a> {
 }
 
 impl TypedInit<'_> {
-    fn variant_name(&self) -> &'static str {
+    pub(crate) fn variant_name(&self) -> &'static str {
         match self {
             TypedInit::Bit(_) => "Bit",
             TypedInit::Bits(_) => "Bits",
@@ -163,6 +166,7 @@ macro_rules! try_into {
 }
 
 try_into!(Bit, BitInit<'a>, bool);
+try_into!(Bits, BitsInit<'a>, BitsInit<'a>);
 try_into!(Bits, BitsInit<'a>, Vec<BitInit<'a>>);
 try_into!(Bits, BitsInit<'a>, Vec<bool>);
 try_into!(Int, IntInit<'a>, i64);
@@ -204,6 +208,54 @@ impl<'a> TryFrom<TypedInit<'a>> for &'a str {
     }
 }
 
+/// Trait for Rust types that can be obtained from a [`TypedInit`] of a certain
+/// TableGen type.
+///
+/// See [`Record::get`](crate::Record::get).
+pub trait FromTypedInit<'a>: Sized {
+    /// Returns true if values of the given declared type can be converted to
+    /// this type.
+    fn accepts(ty: RecTy<'a>) -> bool;
+
+    /// Converts the given init to this type.
+    fn from_typed_init(init: TypedInit<'a>) -> Result<Self, Error>;
+}
+
+macro_rules! from_typed_init {
+    ($type:ty, $pattern:pat) => {
+        impl<'a> FromTypedInit<'a> for $type {
+            fn accepts(ty: RecTy<'a>) -> bool {
+                matches!(ty, $pattern)
+            }
+
+            fn from_typed_init(init: TypedInit<'a>) -> Result<Self, Error> {
+                Self::try_from(init)
+            }
+        }
+    };
+}
+
+from_typed_init!(bool, RecTy::Bit(_));
+from_typed_init!(BitsInit<'a>, RecTy::Bits(_));
+from_typed_init!(Vec<bool>, RecTy::Bits(_));
+from_typed_init!(Vec<BitInit<'a>>, RecTy::Bits(_));
+from_typed_init!(i64, RecTy::Int(_));
+from_typed_init!(Record<'a>, RecTy::Record(_));
+from_typed_init!(ListInit<'a>, RecTy::List(_));
+from_typed_init!(DagInit<'a>, RecTy::Dag(_));
+from_typed_init!(String, RecTy::String(_));
+from_typed_init!(&'a str, RecTy::String(_));
+
+impl<'a> FromTypedInit<'a> for TypedInit<'a> {
+    fn accepts(_: RecTy<'a>) -> bool {
+        true
+    }
+
+    fn from_typed_init(init: TypedInit<'a>) -> Result<Self, Error> {
+        Ok(init)
+    }
+}
+
 impl<'a> TypedInit<'a> {
     as_inner!(bit, Bit, BitInit);
     as_inner!(bits, Bits, BitsInit);
@@ -214,25 +266,48 @@ impl<'a> TypedInit<'a> {
     as_inner!(dag, Dag, DagInit);
     as_inner!(def, Def, DefInit);
 
-    /// Creates a new init from a raw object.
-    ///
-    /// # Safety
-    ///
-    /// The raw object must be valid.
-    #[allow(non_upper_case_globals)]
-    pub unsafe fn from_raw(init: TableGenTypedInitRef) -> Self {
-        use TableGenRecTyKind::*;
-
-        match unsafe { tableGenInitRecType(init) } {
-            TableGenBitRecTyKind => Self::Bit(unsafe { BitInit::from_raw(init) }),
-            TableGenBitsRecTyKind => Self::Bits(unsafe { BitsInit::from_raw(init) }),
-            TableGenCodeRecTyKind => Self::Code(unsafe { StringInit::from_raw(init) }),
-            TableGenIntRecTyKind => TypedInit::Int(unsafe { IntInit::from_raw(init) }),
-            TableGenStringRecTyKind => Self::String(unsafe { StringInit::from_raw(init) }),
-            TableGenListRecTyKind => TypedInit::List(unsafe { ListInit::from_raw(init) }),
-            TableGenDagRecTyKind => TypedInit::Dag(unsafe { DagInit::from_raw(init) }),
-            TableGenRecordRecTyKind => Self::Def(unsafe { DefInit::from_raw(init) }),
-            _ => Self::Invalid,
+    /// Returns the type of this init, or `None` if it is invalid.
+    pub fn rec_ty(self) -> Option<RecTy<'a>> {
+        unsafe { RecTy::from_raw(tableGenInitGetRecTy(self.to_raw()?)) }
+    }
+
+    raw_fn! {
+        /// Returns the raw object, or `None` if the init is invalid.
+        fn to_raw(self) -> Option<TableGenTypedInitRef> {
+            Some(match self {
+                Self::Bit(init) => init.raw,
+                Self::Bits(init) => init.raw,
+                Self::Code(init) | Self::String(init) => init.raw,
+                Self::Int(init) => init.raw,
+                Self::List(init) => init.raw,
+                Self::Dag(init) => init.raw,
+                Self::Def(init) => init.raw,
+                Self::Invalid => return None,
+            })
+        }
+    }
+
+    raw_fn! {
+        /// Creates a new init from a raw object.
+        ///
+        /// # Safety
+        ///
+        /// The raw object must be valid.
+        #[allow(non_upper_case_globals)]
+        unsafe fn from_raw(init: TableGenTypedInitRef) -> Self {
+            use TableGenRecTyKind::*;
+
+            match unsafe { tableGenInitRecType(init) } {
+                TableGenBitRecTyKind => Self::Bit(unsafe { BitInit::from_raw(init) }),
+                TableGenBitsRecTyKind => Self::Bits(unsafe { BitsInit::from_raw(init) }),
+                TableGenCodeRecTyKind => Self::Code(unsafe { StringInit::from_raw(init) }),
+                TableGenIntRecTyKind => TypedInit::Int(unsafe { IntInit::from_raw(init) }),
+                TableGenStringRecTyKind => Self::String(unsafe { StringInit::from_raw(init) }),
+                TableGenListRecTyKind => TypedInit::List(unsafe { ListInit::from_raw(init) }),
+                TableGenDagRecTyKind => TypedInit::Dag(unsafe { DagInit::from_raw(init) }),
+                TableGenRecordRecTyKind => Self::Def(unsafe { DefInit::from_raw(init) }),
+                _ => Self::Invalid,
+            }
         }
     }
 }
@@ -246,15 +321,24 @@ macro_rules! init {
         }
 
         impl<'a> $name<'a> {
-            /// Creates a new init from a raw object.
-            ///
-            /// # Safety
-            ///
-            /// The raw object must be valid.
-            pub unsafe fn from_raw(raw: TableGenTypedInitRef) -> Self {
-                Self {
-                    raw,
-                    _reference: PhantomData,
+            raw_fn! {
+                /// Creates a new init from a raw object.
+                ///
+                /// # Safety
+                ///
+                /// The raw object must be valid.
+                unsafe fn from_raw(raw: TableGenTypedInitRef) -> Self {
+                    Self {
+                        raw,
+                        _reference: PhantomData,
+                    }
+                }
+            }
+
+            raw_fn! {
+                /// Returns the raw object.
+                fn to_raw(self) -> TableGenTypedInitRef {
+                    self.raw
                 }
             }
 
@@ -355,10 +439,17 @@ impl<'a> TryFrom<BitInit<'a>> for bool {
 
 init!(BitsInit);
 
-impl<'a> From<BitsInit<'a>> for Vec<BitInit<'a>> {
-    fn from(value: BitsInit<'a>) -> Self {
+impl<'a> TryFrom<BitsInit<'a>> for Vec<BitInit<'a>> {
+    type Error = TableGenError;
+
+    fn try_from(value: BitsInit<'a>) -> Result<Self, Self::Error> {
         (0..value.num_bits())
-            .map(|i| value.bit(i).expect("index within range"))
+            .map(|i| {
+                value.bit(i).ok_or(TableGenError::InitConversion {
+                    from: "UnsetInit",
+                    to: "BitInit",
+                })
+            })
             .collect()
     }
 }
@@ -384,13 +475,15 @@ impl<'a> TryFrom<BitsInit<'a>> for Vec<bool> {
 impl<'a> From<BitsInit<'a>> for Vec<Option<bool>> {
     fn from(value: BitsInit<'a>) -> Self {
         (0..value.num_bits())
-            .map(|i| value.bit(i).expect("index within range").as_literal())
+            .map(|i| value.bit(i).and_then(BitInit::as_literal))
             .collect()
     }
 }
 
 impl<'a> BitsInit<'a> {
     /// Returns the bit at the given index.
+    ///
+    /// Returns `None` if the index is out of range or the bit is unset (`?`).
     pub fn bit(self, index: usize) -> Option<BitInit<'a>> {
         let bit = unsafe { tableGenBitsInitGetBitInit(self.raw, index) };
         if !bit.is_null() {
@@ -465,7 +558,7 @@ impl<'a> StringInit<'a> {
 
     /// Gets the string init as a slice of bytes.
     pub fn as_bytes(self) -> &'a [u8] {
-        unsafe { StringRef::from_raw(tableGenStringInitGetValue(self.raw)) }.into()
+        unsafe { StringRef::from_raw(tableGenStringInitGetValue(self.raw)) }.as_bytes()
     }
 }
 
@@ -497,8 +590,27 @@ impl<'a> DagInit<'a> {
     }
 
     /// Returns the operator of the dag as a [`Record`].
-    pub fn operator(self) -> Record<'a> {
-        unsafe { Record::from_raw(tableGenDagRecordOperator(self.raw)) }
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operator is not a def, e.g. a template argument
+    /// in a dag of a class (see [`DagInit::operator_init`]).
+    pub fn operator(self) -> Result<Record<'a>, Error> {
+        let def = unsafe { tableGenDagRecordOperator(self.raw) };
+        if def.is_null() {
+            Err(TableGenError::InitConversion {
+                from: self.operator_init().variant_name(),
+                to: "Record",
+            }
+            .into())
+        } else {
+            Ok(unsafe { Record::from_raw(def) })
+        }
+    }
+
+    /// Returns the operator of the dag, which is usually a def.
+    pub fn operator_init(self) -> TypedInit<'a> {
+        unsafe { TypedInit::from_raw(tableGenDagRecordOperatorInit(self.raw)) }
     }
 
     /// Returns the number of arguments for this dag.
@@ -522,6 +634,12 @@ impl<'a> DagInit<'a> {
         }
     }
 
+    /// Returns the dag as an owned expression tree, e.g. to rewrite it and
+    /// print it back in TableGen syntax.
+    pub fn to_expr(self) -> Result<DagExpr<'a>, Error> {
+        DagExpr::from_init(self)
+    }
+
     /// Returns the argument at the given index.
     pub fn get(self, index: usize) -> Option<TypedInit<'a>> {
         let value = unsafe { tableGenDagRecordGet(self.raw, index) };
@@ -533,6 +651,25 @@ impl<'a> DagInit<'a> {
     }
 }
 
+impl<'a> IntoIterator for DagInit<'a> {
+    type Item = (Option<&'a str>, TypedInit<'a>);
+    type IntoIter = DagIter<'a>;
+
+    /// Returns an iterator over the arguments (see [`DagInit::args`]).
+    fn into_iter(self) -> Self::IntoIter {
+        self.args()
+    }
+}
+
+impl<'a> IntoIterator for &DagInit<'a> {
+    type Item = (Option<&'a str>, TypedInit<'a>);
+    type IntoIter = DagIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.args()
+    }
+}
+
 /// Iterator over the arguments of a [`DagInit`].
 #[derive(Debug, Clone)]
 pub struct DagIter<'a> {
@@ -630,6 +767,25 @@ impl<'a> ListInit<'a> {
     }
 }
 
+impl<'a> IntoIterator for ListInit<'a> {
+    type Item = TypedInit<'a>;
+    type IntoIter = ListIter<'a>;
+
+    /// Returns an iterator over the elements (see [`ListInit::iter`]).
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &ListInit<'a> {
+    type Item = TypedInit<'a>;
+    type IntoIter = ListIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 /// Iterator over the elements of a [`ListInit`].
 #[derive(Debug, Clone)]
 pub struct ListIter<'a> {
@@ -707,7 +863,7 @@ mod tests {
                     .expect("def A exists")
                     .value("a")
                     .expect("field a exists");
-                assert_eq!(a.init.try_into(), Ok($expected));
+                assert_eq!(a.init().try_into(), Ok($expected));
             }
         };
     }
@@ -750,7 +906,7 @@ mod tests {
             .try_into()
             .expect("is dag init");
         assert_eq!(a.num_args(), 2);
-        assert_eq!(a.operator().name(), Ok("ins"));
+        assert_eq!(a.operator().unwrap().name(), Ok("ins"));
         let mut args = a.args();
         assert_eq!(
             args.clone().next().map(|(name, init)| (
@@ -766,6 +922,20 @@ mod tests {
             )),
             Some((Some("src2"), Ok("test".into())))
         );
+
+        let rk = TableGenParser::new()
+            .add_source("class Op; class C<Op o> { dag d = (o); }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let d: DagInit = rk
+            .class("C")
+            .unwrap()
+            .value("d")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(d.operator().is_err());
     }
 
     #[test]
@@ -916,6 +1086,31 @@ mod tests {
         assert!(iter.next_back().is_none());
     }
 
+    #[test]
+    fn into_iter() {
+        let rk = TableGenParser::new()
+            .add_source(
+                "def op; def A; def B;
+                 def R { list<int> l = [1, 2]; dag d = (op A:$a, B:$b); }",
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let r = rk.def("R").unwrap();
+        let list: ListInit = r.value("l").unwrap().try_into().unwrap();
+        let dag: DagInit = r.value("d").unwrap().try_into().unwrap();
+        assert!(list.into_iter().eq(list.iter()));
+        assert!((&list).into_iter().eq(list.iter()));
+        assert!(dag.into_iter().eq(dag.args()));
+        assert!((&dag).into_iter().eq(dag.args()));
+
+        let mut pairs = Vec::new();
+        for ((name, _), element) in dag.into_iter().zip(&list) {
+            pairs.push((name, i64::try_from(element).unwrap()));
+        }
+        assert_eq!(pairs, [(Some("a"), 1), (Some("b"), 2)]);
+    }
+
     #[test]
     fn varbit() {
         // Access the class template before parameter substitution.
@@ -930,7 +1125,7 @@ mod tests {
             .expect("class Foo exists")
             .value("val")
             .expect("field val exists")
-            .init
+            .init()
             .as_bits()
             .expect("is BitsInit");
         assert_eq!(bits.num_bits(), 4);
@@ -958,13 +1153,36 @@ mod tests {
             .expect("class Foo exists")
             .value("val")
             .expect("field val exists")
-            .init
+            .init()
             .as_bits()
             .expect("is BitsInit");
         let result = Vec::<bool>::try_from(bits);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn unset_bits() {
+        let rk = TableGenParser::new()
+            .add_source("def A { bits<2> b = { ?, 1 }; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let bits: BitsInit = rk
+            .def("A")
+            .expect("def A exists")
+            .value("b")
+            .expect("field b exists")
+            .init()
+            .as_bits()
+            .expect("is BitsInit");
+        assert!(bits.bit(1).is_none());
+        assert!(bits.bit(2).is_none());
+        assert!(Vec::<BitInit>::try_from(bits).is_err());
+        assert!(Vec::<bool>::try_from(bits).is_err());
+        let optional: Vec<Option<bool>> = bits.into();
+        assert_eq!(optional, vec![Some(true), None]);
+    }
+
     #[test]
     fn empty_list() {
         let rk = TableGenParser::new()
@@ -1000,7 +1218,7 @@ mod tests {
             .expect("def A exists")
             .value("a")
             .expect("field a exists")
-            .init
+            .init()
             .as_bits()
             .expect("is BitsInit");
         for i in 0..4 {
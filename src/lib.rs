@@ -29,6 +29,53 @@
 //! The `TABLEGEN_<version>_PREFIX` environment variable can be used to specify
 //! a custom directory of the LLVM installation.
 //!
+//! To reuse the C++ wrapper of an earlier build (e.g. cached in CI), the
+//! `TABLEGEN_<version>_CTABLEGEN_DIR` environment variable can point to a
+//! directory with the prebuilt `CTableGen` static library, and
+//! `TABLEGEN_<version>_BINDINGS` to the generated `bindings.rs` file. Both
+//! must be built from the same version of this crate and of LLVM.
+//!
+//! When cross-compiling, `TABLEGEN_<version>_PREFIX` refers to the LLVM
+//! installation of the target. If its `llvm-config` cannot run on the host,
+//! `TABLEGEN_HOST_PREFIX` can refer to a host installation of the same LLVM
+//! version, whose `llvm-config` is run instead. Bindings are generated for the
+//! target with the sysroot of its C compiler, or with `TABLEGEN_SYSROOT` if
+//! it is set.
+//!
+//! The static LLVM libraries are linked if they are available, and the shared
+//! library (e.g. `libLLVM.so`) otherwise. The `force-static` feature requires
+//! the static libraries, while the `dynamic-linking` feature always links the
+//! shared library, e.g. for distributions without static LLVM libraries.
+//!
+//! The `vendored` feature compiles the TableGen parser and the LLVM libraries
+//! it depends on from the LLVM sources in `TABLEGEN_<version>_SOURCE_DIR`
+//! (e.g. a checkout of `llvm-project`) instead, so that neither an LLVM
+//! installation nor `llvm-config` is needed.
+//!
+//! # Optional Features
+//!
+//! - `codegen`: adds the `codegen` module with helpers to generate Rust code
+//!   with `proc_macro2`.
+//! - `quote`: implements `quote::ToTokens` for ints, strings, bits and `bits`
+//!   inits, to interpolate them into `quote!` templates.
+//! - `serde`: implements `serde::Serialize` for [`RecordKeeper`], [`Record`],
+//!   [`RecordValue`] and [`TypedInit`], in the layout of the output of
+//!   `llvm-tblgen --dump-json`.
+//! - `derive`: adds the [`FromRecord`] derive macro to map records onto
+//!   structs.
+//! - `minijinja`: adds the `template` module to render `minijinja` templates
+//!   with records.
+//! - `sqlite`: adds `RecordKeeper::export_sqlite` to export records to a
+//!   SQLite database with `rusqlite`.
+//! - `raw`: documents the [`raw`] bindings of the C API and covers them by
+//!   semantic versioning, and adds `from_raw` and `to_raw` to the wrapper
+//!   types.
+//! - `archive`: adds `Snapshot::to_archive` to write records in the format
+//!   of the `tblgen-archive` crate, which reads them without linking LLVM.
+//! - `access`: adds the `access` module, which implements the traits of the
+//!   `tblgen-access` crate for records, so that code generators also run with
+//!   JSON dumps of records loaded by that crate without linking LLVM.
+//!
 //! # Examples
 //!
 //! The following example parse simple TableGen code provided as a `&str` and
@@ -49,7 +96,7 @@
 //! assert_eq!(keeper.classes().next().unwrap().0, Ok("A"));
 //! assert_eq!(keeper.defs().next().unwrap().0, Ok("D"));
 //! assert_eq!(
-//!     keeper.all_derived_definitions("A").next().unwrap().name(),
+//!     keeper.all_derived_definitions("A")?.next().unwrap().name(),
 //!     Ok("D")
 //! );
 //! # Ok(())
@@ -104,17 +151,65 @@
 //! this crate is not stable. Furthermore, the safe wrapper does not provide a
 //! stable interface either, since this crate is still in early development.
 
+#[cfg(feature = "access")]
+pub mod access;
+pub mod backend;
+mod binary;
+pub mod bindings;
+pub mod build;
+pub mod builder;
+mod cached_record;
+#[cfg(feature = "codegen")]
+pub mod codegen;
+pub mod dag;
+/// Diagnostics reported by TableGen.
+pub mod diagnostic;
+pub mod doc;
+mod dump;
+pub mod enums;
 pub mod error;
+/// Cache of TableGen source files shared between parsers.
+pub mod include_cache;
 pub mod init;
+mod json;
+pub mod ods;
+/// Command line options compatible with `llvm-tblgen`.
+pub mod options;
+/// Progress reporting while parsing.
+pub mod progress;
+pub mod query;
 /// TableGen records and record values.
 pub mod record;
 /// TableGen record keeper.
 pub mod record_keeper;
+pub mod schema;
+pub mod searchable_tables;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod snapshot;
+/// Source files loaded by TableGen.
+pub mod source;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 mod string_ref;
+mod table;
+pub mod target;
+mod td;
+#[cfg(feature = "minijinja")]
+pub mod template;
+pub mod testing;
+pub mod ty;
 mod util;
+mod yaml;
 
-/// This module contains raw bindings for TableGen. Note that these bindings are
-/// unstable and can change at any time.
+/// This module contains raw bindings for the C API of TableGen.
+///
+/// With the `raw` feature, these bindings are documented and follow semantic
+/// versioning like the rest of this crate, and the wrapper types have public
+/// `from_raw` and `to_raw` functions, so that other crates can pass TableGen
+/// objects across the FFI boundary. Without it, the bindings are hidden and
+/// can change at any time, and the wrapper types cannot be converted.
+#[cfg_attr(not(feature = "raw"), doc(hidden))]
 #[allow(non_upper_case_globals)]
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
@@ -123,34 +218,52 @@ pub mod raw {
 }
 
 use std::{
-    ffi::{CStr, CString},
+    ffi::{CStr, CString, c_void},
     marker::PhantomData,
-    sync::Mutex,
+    thread::{self, JoinHandle},
 };
 
+pub use bindings::generate_bindings;
+pub use builder::RecordKeeperBuilder;
+pub use cached_record::CachedRecord;
+pub use diagnostic::Diagnostic;
+use diagnostic::DiagnosticKind;
 pub use error::Error;
-use error::TableGenError;
+use error::{SourceLoc, TableGenError, WithLocation};
+pub use include_cache::IncludeCache;
 pub use init::TypedInit;
-pub use record::{Record, RecordValue};
+pub use options::TableGenOptions;
+pub use progress::ParseProgress;
+pub use record::{FieldKey, FromRecord, Record, RecordValue};
+#[cfg(feature = "derive")]
+pub use tblgen_derive::FromRecord;
+
+// Allows the derive macros to refer to `::tblgen` in tests of this crate.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as tblgen;
 pub use record_keeper::RecordKeeper;
 
+use progress::ProgressCallback;
 use raw::{
-    TableGenParserRef, tableGenAddIncludeDirectory, tableGenAddSource, tableGenAddSourceFile,
-    tableGenFree, tableGenGet, tableGenParse,
+    TableGenParserRef, tableGenAddIncludeDirectory, tableGenAddPrelude, tableGenAddSource,
+    tableGenAddSourceCopy, tableGenAddSourceFile, tableGenAssign, tableGenClearSources,
+    tableGenClone, tableGenDefineMacro, tableGenFree, tableGenGet, tableGenParse,
+    tableGenPrintError, tableGenSetIncludeCache, tableGenSetMemoryMapFiles,
+    tableGenSetWarnOnUnusedTemplateArgs,
 };
 use string_ref::StringRef;
-
-// TableGen only exposes `TableGenParseFile` in its API.
-// However, this function uses global state and therefore it is not thread safe.
-// Until they remove this hack, we have to deal with it ourselves.
-static TABLEGEN_PARSE_LOCK: Mutex<()> = Mutex::new(());
+use util::print_string_callback;
 
 /// Builder struct that parses TableGen source files and builds a
 /// [`RecordKeeper`].
+///
+/// Cloning a parser copies its configuration, so that the same sources can be
+/// parsed multiple times (see [`TableGenParser::parse_ref`]).
 #[derive(Debug, PartialEq, Eq)]
 pub struct TableGenParser<'s> {
     raw: TableGenParserRef,
-    source_strings: Vec<CString>,
+    include_cache: Option<IncludeCache>,
+    progress: Option<ProgressCallback>,
     _source_ref: PhantomData<&'s str>,
 }
 
@@ -165,7 +278,8 @@ impl<'s> TableGenParser<'s> {
     pub fn new() -> Self {
         Self {
             raw: unsafe { tableGenGet() },
-            source_strings: Vec::new(),
+            include_cache: None,
+            progress: None,
             _source_ref: PhantomData,
         }
     }
@@ -176,12 +290,104 @@ impl<'s> TableGenParser<'s> {
         self
     }
 
-    /// Reads TableGen source code from the file at the given path.
+    /// Loads source files and, where supported, included files through the
+    /// given [`IncludeCache`], which can be shared with other parsers.
+    pub fn with_include_cache(mut self, cache: &IncludeCache) -> Self {
+        unsafe { tableGenSetIncludeCache(self.raw, cache.to_raw()) }
+        self.include_cache = Some(cache.clone());
+        self
+    }
+
+    /// Adds TableGen source code that is parsed before the main source, e.g.
+    /// to provide definitions that are missing in included files.
+    ///
+    /// The prelude is parsed as if it was included at the start of the main
+    /// source, so line numbers reported for the main source are not affected.
+    /// The string is copied. Multiple preludes are parsed in the order in
+    /// which they were added.
+    pub fn add_prelude(self, source: &str) -> Self {
+        unsafe { tableGenAddPrelude(self.raw, StringRef::from(source).to_raw()) }
+        self
+    }
+
+    /// Defines a preprocessor macro with the given name, like the `-D` option
+    /// of `llvm-tblgen`.
+    pub fn define_macro(self, name: &str) -> Self {
+        unsafe { tableGenDefineMacro(self.raw, StringRef::from(name).to_raw()) }
+        self
+    }
+
+    /// Enables or disables warnings about unused template arguments, which are
+    /// enabled by default.
+    pub fn warn_on_unused_template_args(self, enabled: bool) -> Self {
+        unsafe { tableGenSetWarnOnUnusedTemplateArgs(self.raw, enabled.into()) }
+        self
+    }
+
+    /// Enables or disables memory-mapping of source files, which is disabled
+    /// by default.
+    ///
+    /// Mapping large generated files instead of reading them into heap
+    /// buffers reduces the peak memory usage of parsing. Only files of at
+    /// least 16 KiB whose size is not a multiple of the page size are mapped,
+    /// since the lexer requires a null terminator after the source. A mapped
+    /// file must not be modified until the [`RecordKeeper`] is dropped.
+    /// Files pulled in by `include` directives are always loaded by LLVM,
+    /// which maps them under the same conditions.
+    pub fn memory_map_files(self, enabled: bool) -> Self {
+        unsafe { tableGenSetMemoryMapFiles(self.raw, enabled.into()) }
+        self
+    }
+
+    /// Sets a callback that is invoked with the [`ParseProgress`] of each
+    /// parse operation.
+    ///
+    /// The callback is invoked for each loaded source, when TableGen starts
+    /// parsing, and when parsing has finished. TableGen itself does not
+    /// report progress while it is parsing, not even for included files, so
+    /// the callback is additionally invoked as a heartbeat every 100 ms in
+    /// the meantime. A heartbeat only updates [`ParseProgress::elapsed`], so
+    /// that command line tools can show that they are still working on large
+    /// sources. Heartbeats happen on a separate thread, but never after the
+    /// call for the finished parse.
+    ///
+    /// If the callback panics, it is not invoked again and the panic is
+    /// resumed once TableGen has returned.
+    ///
+    /// ```rust
+    /// use tblgen::TableGenParser;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let keeper = TableGenParser::new()
+    ///     .add_source("def A;")?
+    ///     .on_progress(|progress| {
+    ///         eprintln!("{:?}: {} bytes", progress.phase(), progress.bytes())
+    ///     })
+    ///     .parse()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_progress(mut self, callback: impl FnMut(&ParseProgress) + Send + 'static) -> Self {
+        self.progress = Some(ProgressCallback::new(callback));
+        self
+    }
+
+    /// Reads TableGen source code from the file at the given path, or from the
+    /// standard input if the path is `-`.
     pub fn add_source_file(self, source: &str) -> Self {
         unsafe { tableGenAddSourceFile(self.raw, StringRef::from(source).to_raw()) }
         self
     }
 
+    /// Reads TableGen source code from the standard input.
+    ///
+    /// This is equivalent to `add_source_file("-")`, which is how
+    /// `llvm-tblgen` refers to the standard input. The input is read when the
+    /// sources are parsed, so it can only be parsed once.
+    pub fn add_source_stdin(self) -> Self {
+        self.add_source_file("-")
+    }
+
     /// Adds the given TableGen source string.
     ///
     /// The string must be null-terminated and is not copied, hence it is
@@ -196,42 +402,242 @@ impl<'s> TableGenParser<'s> {
 
     /// Adds the given TableGen source string.
     ///
-    /// The string is copied into a null-terminated [`CString`].
-    pub fn add_source(mut self, source: &str) -> Result<Self, Error> {
-        let string = CString::new(source).map_err(TableGenError::from)?;
-        self.source_strings.push(string);
-        if unsafe {
-            tableGenAddSource(
+    /// The string is copied by the parser.
+    pub fn add_source(self, source: &str) -> Result<Self, Error> {
+        self.add_source_named("", source)
+    }
+
+    /// Adds the given TableGen source string under the given name.
+    ///
+    /// The name is used instead of a file name in diagnostics, e.g. to refer
+    /// to the tool that generated the source. The string is copied by the
+    /// parser, which appends the null terminator required by the lexer.
+    pub fn add_source_named(self, name: &str, source: &str) -> Result<Self, Error> {
+        if source.contains('\0') {
+            // Only build a `CString` for its error.
+            let error = CString::new(source).unwrap_err();
+            return Err(TableGenError::from(error).into());
+        }
+        unsafe {
+            tableGenAddSourceCopy(
                 self.raw,
-                self.source_strings.last().expect("not empty").as_ptr(),
-            ) > 0
-        } {
-            Ok(self)
-        } else {
-            Err(TableGenError::InvalidSource.into())
+                StringRef::from(source).to_raw(),
+                StringRef::from(name).to_raw(),
+            )
         }
+        Ok(self)
     }
 
     pub fn source_info(&self) -> SourceInfo<'_> {
         SourceInfo(self)
     }
 
+    /// Removes all source files and source strings that were added to the
+    /// parser.
+    ///
+    /// Include directories, preludes and the include cache are kept, so that
+    /// the parser can be reused for new sources, e.g. after a failed parse.
+    pub fn reset(&mut self) {
+        unsafe { tableGenClearSources(self.raw) }
+    }
+
+    /// Parses the TableGen source files and returns a [`RecordKeeper`] without
+    /// consuming the parser.
+    ///
+    /// This is equivalent to `self.clone().parse()`.
+    pub fn parse_ref(&self) -> Result<RecordKeeper<'s>, Error> {
+        self.clone().parse()
+    }
+
     /// Parses the TableGen source files and returns a [`RecordKeeper`].
     ///
-    /// Due to limitations of TableGen, parsing TableGen is not thread-safe.
-    /// In order to provide thread-safety, this method ensures that any
-    /// concurrent parse operations are executed sequentially.
+    /// Errors reported by TableGen are not printed, but returned as
+    /// [`Diagnostic`]s in [`TableGenError::Parse`](error::TableGenError::Parse).
+    ///
+    /// Different parsers can parse concurrently on different threads. Only
+    /// the final step that runs the TableGen parser is executed sequentially,
+    /// since TableGen parses into a global source manager.
+    ///
+    /// # Process exit
+    ///
+    /// Accessors that TableGen implements with fatal errors (e.g.
+    /// [`DagInit::operator`](init::DagInit::operator) and
+    /// [`RecordKeeper::all_derived_definitions`]) return errors instead.
+    /// However, some errors that TableGen reports while folding and resolving
+    /// values during parsing still print a message and exit the process,
+    /// for example:
+    ///
+    /// - `!cast` to a record that is not defined, e.g. `!cast<A>("Missing")`
+    ///   in the body of a def
+    /// - division by zero in `!div`
+    /// - a resolved value that does not match the type of its field
+    ///
+    /// Parse sources that may contain such errors in a separate process.
     pub fn parse(self) -> Result<RecordKeeper<'s>, Error> {
-        unsafe {
-            let guard = TABLEGEN_PARSE_LOCK.lock().unwrap();
-            let keeper = tableGenParse(self.raw);
-            let res = if !keeper.is_null() {
-                Ok(RecordKeeper::from_raw(keeper, self))
-            } else {
-                Err(TableGenError::Parse.into())
-            };
-            drop(guard);
-            res
+        self.parse_or_return().map_err(|(_, error)| error)
+    }
+
+    /// Parses the TableGen source files into the given [`Workspace`] and
+    /// returns a reference to the resulting [`RecordKeeper`].
+    ///
+    /// The workspace keeps the memory used by the previous parse (e.g. the
+    /// configuration of the parser and buffers for diagnostics) and reuses it
+    /// for the next parse, which reduces allocations in tools that parse the
+    /// same sources repeatedly. The record keeper of the previous parse is
+    /// dropped.
+    ///
+    /// ```rust
+    /// use tblgen::{TableGenParser, Workspace};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut workspace = Workspace::new();
+    /// for source in ["def A;", "def B;"] {
+    ///     let keeper = TableGenParser::new()
+    ///         .add_source(source)?
+    ///         .parse_into(&mut workspace)?;
+    ///     assert_eq!(keeper.defs().count(), 1);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse_into<'w>(
+        &self,
+        workspace: &'w mut Workspace<'s>,
+    ) -> Result<&'w RecordKeeper<'s>, Error> {
+        let mut parser = match (workspace.keeper.take(), workspace.parser.take()) {
+            (Some(keeper), _) => keeper.into_parser(),
+            (None, Some(parser)) => parser,
+            (None, None) => TableGenParser::new(),
+        };
+        parser.assign(self);
+        match parser.parse_or_return() {
+            Ok(keeper) => Ok(workspace.keeper.insert(keeper)),
+            Err((parser, error)) => {
+                workspace.parser = Some(parser);
+                Err(error)
+            }
+        }
+    }
+
+    /// Parses the TableGen source files and returns the parser if parsing
+    /// fails.
+    #[allow(clippy::result_large_err)]
+    fn parse_or_return(self) -> Result<RecordKeeper<'s>, (Self, Error)> {
+        let (keeper, panic) = unsafe {
+            match &self.progress {
+                Some(progress) => progress.run(self.raw, || tableGenParse(self.raw)),
+                None => (tableGenParse(self.raw), None),
+            }
+        };
+        let result = if !keeper.is_null() {
+            Ok(unsafe { RecordKeeper::from_raw(keeper, self) })
+        } else {
+            let error = TableGenError::Parse(unsafe { Diagnostic::collect(self.raw) }).into();
+            Err((self, error))
+        };
+        // A panic of the progress callback is resumed once the record keeper
+        // or parser is owned, so that it is dropped while unwinding.
+        if let Some(payload) = panic {
+            std::panic::resume_unwind(payload);
+        }
+        result
+    }
+
+    /// Copies the configuration of the given parser into this parser, reusing
+    /// already allocated memory.
+    fn assign(&mut self, other: &Self) {
+        unsafe { tableGenAssign(self.raw, other.raw) }
+        self.include_cache.clone_from(&other.include_cache);
+        self.progress.clone_from(&other.progress);
+    }
+}
+
+impl TableGenParser<'static> {
+    /// Parses the TableGen source files on a new thread and returns a handle
+    /// to the result.
+    ///
+    /// Parsing large TableGen files (e.g. target descriptions) can take several
+    /// seconds. This method allows to do other work in the meantime. Parsers
+    /// on different threads load their source files concurrently, but take
+    /// turns while TableGen parses them, since TableGen parses into a global
+    /// source manager.
+    ///
+    /// In an async context, `parse` can also be called on a thread pool for
+    /// blocking operations, e.g. with
+    /// `tokio::task::spawn_blocking(move || parser.parse())`.
+    ///
+    /// ```rust
+    /// use tblgen::TableGenParser;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let handle = TableGenParser::new().add_source("def A;")?.spawn();
+    /// // ...
+    /// let keeper = handle.join()?;
+    /// assert!(keeper.def("A").is_ok());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spawn(self) -> ParseHandle {
+        ParseHandle(thread::spawn(move || self.parse()))
+    }
+}
+
+// The parser owns its C++ counterpart, which does not depend on the thread it
+// was created on.
+unsafe impl Send for TableGenParser<'_> {}
+
+/// Memory that is reused by repeated parse operations.
+///
+/// See [`TableGenParser::parse_into`].
+#[derive(Debug, Default)]
+pub struct Workspace<'s> {
+    parser: Option<TableGenParser<'s>>,
+    keeper: Option<RecordKeeper<'s>>,
+}
+
+impl<'s> Workspace<'s> {
+    /// Creates a new, empty workspace.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the [`RecordKeeper`] of the last parse, if it was successful.
+    pub fn keeper(&self) -> Option<&RecordKeeper<'s>> {
+        self.keeper.as_ref()
+    }
+}
+
+/// Handle to a TableGen parse operation running on another thread.
+///
+/// See [`TableGenParser::spawn`].
+#[derive(Debug)]
+pub struct ParseHandle(JoinHandle<Result<RecordKeeper<'static>, Error>>);
+
+impl ParseHandle {
+    /// Returns true if the parse operation has finished.
+    pub fn is_finished(&self) -> bool {
+        self.0.is_finished()
+    }
+
+    /// Waits for the parse operation to finish and returns its result.
+    ///
+    /// If the parsing thread panicked, the panic is propagated.
+    pub fn join(self) -> Result<RecordKeeper<'static>, Error> {
+        match self.0.join() {
+            Ok(result) => result,
+            Err(payload) => std::panic::resume_unwind(payload),
+        }
+    }
+}
+
+impl<'s> Clone for TableGenParser<'s> {
+    fn clone(&self) -> Self {
+        Self {
+            // The clone shares the copied source strings of the parser.
+            raw: unsafe { tableGenClone(self.raw) },
+            include_cache: self.include_cache.clone(),
+            progress: self.progress.clone(),
+            _source_ref: PhantomData,
         }
     }
 }
@@ -250,3 +656,451 @@ impl Drop for TableGenParser<'_> {
 /// [`RecordKeeper::source_info`](RecordKeeper::source_info).
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct SourceInfo<'a>(pub(crate) &'a TableGenParser<'a>);
+
+impl SourceInfo<'_> {
+    /// Returns the line of source code at the given location, together with
+    /// `context_lines` lines before and after it.
+    ///
+    /// The snippet does not end with a line terminator. This allows
+    /// applications to format their own diagnostics.
+    ///
+    /// ```rust
+    /// use tblgen::TableGenParser;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let keeper = TableGenParser::new()
+    ///     .add_source("class A;\ndef B: A;\ndef C: A;\n")?
+    ///     .parse()?;
+    /// let b = keeper.def("B")?;
+    /// assert_eq!(keeper.source_info().snippet(b, 0)?, "def B: A;");
+    /// assert_eq!(
+    ///     keeper.source_info().snippet(b, 1)?,
+    ///     "class A;\ndef B: A;\ndef C: A;"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the location does not belong to this source, or if
+    /// the source is not valid UTF-8.
+    pub fn snippet(&self, location: impl SourceLoc, context_lines: usize) -> Result<String, Error> {
+        let location = location.source_location();
+        let Some((buffer, offset)) = location.buffer_in(self.0) else {
+            return Err(TableGenError::InvalidSourceLocation.with_location(location));
+        };
+
+        let mut start = buffer[..offset]
+            .iter()
+            .rposition(|&c| c == b'\n')
+            .map_or(0, |i| i + 1);
+        for _ in 0..context_lines {
+            if start == 0 {
+                break;
+            }
+            start = buffer[..start - 1]
+                .iter()
+                .rposition(|&c| c == b'\n')
+                .map_or(0, |i| i + 1);
+        }
+
+        let mut end = offset;
+        for line in 0..=context_lines {
+            match buffer[end..].iter().position(|&c| c == b'\n') {
+                Some(i) if line < context_lines && end + i + 1 < buffer.len() => end += i + 1,
+                Some(i) => {
+                    end += i;
+                    break;
+                }
+                None => {
+                    end = buffer.len();
+                    break;
+                }
+            }
+        }
+
+        let snippet = std::str::from_utf8(&buffer[start..end])
+            .map_err(|e| TableGenError::from(e).with_location(location))?;
+        Ok(snippet.strip_suffix('\r').unwrap_or(snippet).into())
+    }
+
+    /// Formats a message at the given location like the diagnostics of
+    /// TableGen itself, i.e. with the file name, line and column, the line
+    /// of source code and a caret.
+    ///
+    /// For records instantiated from a multiclass, notes with the locations
+    /// of the `defm`s are appended. This allows backends to report their own
+    /// errors in the source files.
+    ///
+    /// ```rust
+    /// use tblgen::{TableGenParser, diagnostic::DiagnosticKind};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let keeper = TableGenParser::new()
+    ///     .add_source_named("ops.td", "class Op;\ndef B: Op;\n")?
+    ///     .parse()?;
+    /// let message = keeper.source_info().format_diagnostic(
+    ///     keeper.def("B")?,
+    ///     DiagnosticKind::Warning,
+    ///     "op without results",
+    /// )?;
+    /// assert!(message.starts_with("ops.td:2:"));
+    /// assert!(message.contains("warning: op without results"));
+    /// assert!(message.contains("def B: Op;"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the location does not belong to this source, or if
+    /// the source is not valid UTF-8.
+    pub fn format_diagnostic(
+        &self,
+        location: impl SourceLoc,
+        kind: DiagnosticKind,
+        message: &str,
+    ) -> Result<String, Error> {
+        let location = location.source_location();
+        let mut data: (_, Result<_, TableGenError>) = (String::new(), Ok(()));
+        let printed = unsafe {
+            tableGenPrintError(
+                self.0.raw,
+                location.to_raw(),
+                kind.to_raw(),
+                StringRef::from(message).to_raw(),
+                Some(print_string_callback),
+                &mut data as *mut _ as *mut c_void,
+            )
+        };
+        if printed == 0 {
+            return Err(TableGenError::InvalidSourceLocation.with_location(location));
+        }
+        data.1.map_err(|error| error.with_location(location))?;
+        Ok(data.0)
+    }
+
+    /// Prints an error at the given location to the standard error stream,
+    /// like `PrintError` of TableGen.
+    ///
+    /// See [`SourceInfo::format_diagnostic`].
+    pub fn print_error(&self, location: impl SourceLoc, message: &str) -> Result<(), Error> {
+        self.print(location, DiagnosticKind::Error, message)
+    }
+
+    /// Prints a warning at the given location to the standard error stream,
+    /// like `PrintWarning` of TableGen.
+    ///
+    /// See [`SourceInfo::format_diagnostic`].
+    pub fn print_warning(&self, location: impl SourceLoc, message: &str) -> Result<(), Error> {
+        self.print(location, DiagnosticKind::Warning, message)
+    }
+
+    /// Prints a note at the given location to the standard error stream, like
+    /// `PrintNote` of TableGen.
+    ///
+    /// See [`SourceInfo::format_diagnostic`].
+    pub fn print_note(&self, location: impl SourceLoc, message: &str) -> Result<(), Error> {
+        self.print(location, DiagnosticKind::Note, message)
+    }
+
+    fn print(
+        &self,
+        location: impl SourceLoc,
+        kind: DiagnosticKind,
+        message: &str,
+    ) -> Result<(), Error> {
+        eprint!("{}", self.format_diagnostic(location, kind, message)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ref() {
+        let parser = TableGenParser::new().add_source("def A;").unwrap();
+        let first = parser.parse_ref().expect("valid tablegen");
+        let second = parser.parse_ref().expect("valid tablegen");
+        assert!(first.def("A").is_ok());
+        assert!(second.def("A").is_ok());
+        assert!(parser.parse().expect("valid tablegen").def("A").is_ok());
+    }
+
+    #[test]
+    fn prelude() {
+        let error = TableGenParser::new()
+            .add_prelude("class B;\ndefvar x = 1;")
+            .add_source("def A: B {\n  int i = x;\n}\ndef C: D;\n")
+            .unwrap()
+            .parse()
+            .expect_err("invalid tablegen");
+        let TableGenError::Parse(diagnostics) = error.error() else {
+            panic!("expected parse error");
+        };
+        assert_eq!(diagnostics[0].line(), Some(4));
+
+        let rk = TableGenParser::new()
+            .add_prelude("class B;\ndefvar x = 1;")
+            .add_source("def A: B {\n  int i = x;\n}\n")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        assert_eq!(rk.def("A").unwrap().int_value("i"), Ok(1));
+    }
+
+    #[test]
+    fn named_source() {
+        let error = TableGenParser::new()
+            .add_source_named("generated.td", "def A: B;")
+            .unwrap()
+            .parse()
+            .expect_err("invalid tablegen");
+        let TableGenError::Parse(diagnostics) = error.error() else {
+            panic!("expected parse error");
+        };
+        assert_eq!(diagnostics[0].filename(), "generated.td");
+        assert!(diagnostics[0].to_string().starts_with("generated.td:1:"));
+    }
+
+    #[test]
+    fn copied_source() {
+        let source = String::from("def A;");
+        let parser = TableGenParser::new().add_source(&source).unwrap();
+        drop(source);
+        assert!(
+            parser
+                .clone()
+                .parse()
+                .expect("valid tablegen")
+                .def("A")
+                .is_ok()
+        );
+        assert!(matches!(
+            TableGenParser::new().add_source("def A;\0").map(drop),
+            Err(error) if matches!(error.error(), TableGenError::InvalidSourceString(_))
+        ));
+    }
+
+    #[test]
+    fn memory_map_files() {
+        let path = std::env::temp_dir().join(format!("tblgen-mmap-{}.td", std::process::id()));
+        let source = (0..2000)
+            .map(|index| format!("def A{} {{ int i = {}; }}\n", index, index))
+            .collect::<String>();
+        std::fs::write(&path, source).unwrap();
+        let parser = TableGenParser::new()
+            .add_source_file(path.to_str().unwrap())
+            .memory_map_files(true);
+        let rk = parser.clone().parse().expect("valid tablegen");
+        assert_eq!(rk.def("A1999").unwrap().int_value("i"), Ok(1999));
+        assert_eq!(rk.defs().count(), 2000);
+        drop(rk);
+        assert!(parser.memory_map_files(false).parse().is_ok());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn spawn() {
+        fn assert_send<T: Send>() {}
+        assert_send::<TableGenParser>();
+        assert_send::<RecordKeeper>();
+        assert_send::<ParseHandle>();
+
+        let handle = TableGenParser::new().add_source("def A;").unwrap().spawn();
+        assert!(handle.join().expect("valid tablegen").def("A").is_ok());
+    }
+
+    #[test]
+    fn concurrent_parses() {
+        let handles = (0..8)
+            .map(|index| {
+                thread::spawn(move || {
+                    let source = if index % 2 == 0 {
+                        format!("class C; def D{} : C;", index)
+                    } else {
+                        format!("def D{} : Missing;", index)
+                    };
+                    TableGenParser::new()
+                        .add_source_named(&format!("{}.td", index), &source)
+                        .unwrap()
+                        .parse()
+                })
+            })
+            .collect::<Vec<_>>();
+        for (index, handle) in handles.into_iter().enumerate() {
+            let result = handle.join().unwrap();
+            if index % 2 == 0 {
+                let keeper = result.expect("valid tablegen");
+                assert_eq!(keeper.defs().count(), 1);
+                assert!(keeper.def(&format!("D{}", index)).is_ok());
+            } else {
+                let error = result.expect_err("invalid tablegen");
+                let TableGenError::Parse(diagnostics) = error.error() else {
+                    panic!("expected parse error");
+                };
+                assert_eq!(diagnostics[0].filename(), format!("{}.td", index));
+            }
+        }
+    }
+
+    #[test]
+    fn snippet() {
+        let rk = TableGenParser::new()
+            .add_source("def A;\n\ndef B;\ndef C;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let info = rk.source_info();
+        assert_eq!(info.snippet(rk.def("A").unwrap(), 1).unwrap(), "def A;\n");
+        assert_eq!(info.snippet(rk.def("B").unwrap(), 0).unwrap(), "def B;");
+        assert_eq!(
+            info.snippet(rk.def("C").unwrap(), 5).unwrap(),
+            "def A;\n\ndef B;\ndef C;"
+        );
+
+        let other = TableGenParser::new()
+            .add_source("def D;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        assert!(info.snippet(other.def("D").unwrap(), 0).is_err());
+        assert!(info.snippet(error::SourceLocation::none(), 0).is_err());
+    }
+
+    #[test]
+    fn shared_source_location() {
+        let parse = || {
+            TableGenParser::new()
+                .add_source_raw(c"def A;")
+                .unwrap()
+                .parse()
+                .expect("valid tablegen")
+        };
+        // Both parsers borrow the same source.
+        let first = parse();
+        let second = parse();
+        let location = second.def("A").unwrap().source_location();
+        drop(first);
+        assert_eq!(
+            second.source_info().snippet(location.clone(), 0).unwrap(),
+            "def A;"
+        );
+        assert!(!format!("{:?}", location).contains('?'));
+
+        // The location must not be found in the sources of a new parser,
+        // which borrows the same memory.
+        drop(second);
+        let third = parse();
+        assert_eq!(format!("{:?}", location), "SourceLocation(?)");
+        assert!(third.source_info().snippet(location, 0).is_err());
+    }
+
+    #[test]
+    fn format_diagnostic() {
+        let rk = TableGenParser::new()
+            .add_source_named("a.td", "multiclass M { def _x; }\ndefm A: M;\n")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let info = rk.source_info();
+        let message = info
+            .format_diagnostic(rk.def("A_x").unwrap(), DiagnosticKind::Error, "invalid op")
+            .unwrap();
+        assert!(message.starts_with("a.td:1:"));
+        assert!(message.contains("error: invalid op"));
+        assert!(message.contains("a.td:2:"));
+        assert!(message.contains("note: initiated from multiclass"));
+
+        let other = TableGenParser::new()
+            .add_source("def D;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        assert!(
+            info.format_diagnostic(other.def("D").unwrap(), DiagnosticKind::Note, "")
+                .is_err()
+        );
+        assert!(info.print_warning(other.def("D").unwrap(), "").is_err());
+    }
+
+    #[test]
+    fn source_location_order() {
+        use error::{SourceLoc, SourceRange};
+
+        let rk = TableGenParser::new()
+            .add_source("class A { int x = 1; }\ndef B: A;\ndef C: A;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.class("A").unwrap();
+        let b = rk.def("B").unwrap();
+        let c = rk.def("C").unwrap();
+        assert!(a.source_location() < b.source_location());
+        assert!(b.source_location() < c.source_location());
+        assert_eq!(b.source_location(), b.source_location().clone());
+        assert!(error::SourceLocation::none() < a.source_location());
+
+        let range = SourceRange::new(a, b);
+        assert!(range.contains(a));
+        assert!(range.contains(a.value("x").unwrap()));
+        assert!(!range.contains(b));
+        assert!(!range.contains(c));
+        assert!(!range.contains(error::SourceLocation::none()));
+    }
+
+    #[test]
+    fn parse_into() {
+        let mut workspace = Workspace::new();
+        let parser = TableGenParser::new().add_source("def A;").unwrap();
+        assert!(parser.parse_into(&mut workspace).unwrap().def("A").is_ok());
+
+        let parser = TableGenParser::new().add_source("def B: C;").unwrap();
+        assert!(parser.parse_into(&mut workspace).is_err());
+        assert!(workspace.keeper().is_none());
+
+        let parser = TableGenParser::new().add_source("def B;").unwrap();
+        let keeper = parser.parse_into(&mut workspace).unwrap();
+        assert!(keeper.def("A").is_err());
+        assert!(keeper.def("B").is_ok());
+    }
+
+    #[test]
+    fn fatal_errors() {
+        // Accessors that exit the process in TableGen return errors.
+        let rk = TableGenParser::new()
+            .add_source("class Op; class C<Op o> { dag d = (o); }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let d: init::DagInit = rk
+            .class("C")
+            .unwrap()
+            .value("d")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(d.operator().is_err());
+        let error = rk.all_derived_definitions("Missing").err().unwrap();
+        assert!(matches!(error.error(), TableGenError::MissingClass(_)));
+
+        // Fatal errors while folding values during parsing are not converted
+        // and still exit the process, e.g. `def A { int i = !div(1, 0); }` or
+        // `class A; def B { A a = !cast<A>("Missing"); }`, so they are not
+        // parsed here.
+    }
+
+    #[test]
+    fn reset_after_failure() {
+        let mut parser = TableGenParser::new().add_source("def A: B;").unwrap();
+        assert!(parser.parse_ref().is_err());
+
+        parser.reset();
+        let parser = parser.add_source("class B; def A: B;").unwrap();
+        let rk = parser.parse_ref().expect("valid tablegen");
+        assert!(rk.def("A").unwrap().subclass_of("B"));
+    }
+}
@@ -0,0 +1,1366 @@
+//! Typed views over MLIR Operation Definition Specification (ODS) records.
+//!
+//! The wrappers in this module follow the classes of `mlir/IR/OpBase.td`
+//! (e.g. `Op`, `Dialect` and `Arg`) and the ODS documentation. They do not
+//! require the MLIR `.td` files to be of a specific version, but only use
+//! fields that have been stable across releases.
+//!
+//! ```rust
+//! use tblgen::{TableGenParser, ods::Operator};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source(
+//!         r#"
+//!         class Dialect { string name = ""; }
+//!         class TypeConstraint;
+//!         def I32 : TypeConstraint;
+//!         def ins; def outs; def region; def successor;
+//!         class Op<Dialect d, string mnemonic> {
+//!             Dialect opDialect = d; string opName = mnemonic;
+//!             string summary = ""; string description = "";
+//!             dag arguments = (ins); dag results = (outs);
+//!             dag regions = (region); dag successors = (successor);
+//!             list<int> traits = [];
+//!         }
+//!         def Arith : Dialect { let name = "arith"; }
+//!         def AddIOp : Op<Arith, "addi"> {
+//!             let arguments = (ins I32:$lhs, I32:$rhs);
+//!             let results = (outs I32:$result);
+//!         }
+//!         "#,
+//!     )?
+//!     .parse()?;
+//! let op = Operator::try_from(keeper.def("AddIOp")?)?;
+//! assert_eq!(op.full_name()?, "arith.addi");
+//! assert_eq!(op.operands()?[1].name(), Some("rhs"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    Error, Record, RecordKeeper,
+    error::{TableGenError, WithLocation},
+};
+
+/// An operation, i.e. a def deriving from the `Op` class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Operator<'a> {
+    record: Record<'a>,
+}
+
+impl<'a> TryFrom<Record<'a>> for Operator<'a> {
+    type Error = Error;
+
+    fn try_from(record: Record<'a>) -> Result<Self, Self::Error> {
+        if record.subclass_of("Op") {
+            Ok(Self { record })
+        } else {
+            Err(TableGenError::InitConversion {
+                from: "Record",
+                to: "Operator",
+            }
+            .with_location(record))
+        }
+    }
+}
+
+impl<'a> Operator<'a> {
+    /// Returns the underlying record.
+    pub fn record(self) -> Record<'a> {
+        self.record
+    }
+
+    /// Returns the dialect the operation belongs to.
+    pub fn dialect(self) -> Result<Dialect<'a>, Error> {
+        Ok(Dialect {
+            record: self.record.def_value("opDialect")?,
+        })
+    }
+
+    /// Returns the name of the operation without the dialect prefix (e.g.
+    /// `addi`).
+    pub fn op_name(self) -> Result<&'a str, Error> {
+        self.record.str_value("opName")
+    }
+
+    /// Returns the full name of the operation, prefixed with the name of its
+    /// dialect (e.g. `arith.addi`).
+    pub fn full_name(self) -> Result<String, Error> {
+        let dialect = self.dialect()?.name()?;
+        let name = self.op_name()?;
+        Ok(if dialect.is_empty() {
+            name.into()
+        } else {
+            format!("{}.{}", dialect, name)
+        })
+    }
+
+    /// Returns the one-line summary of the operation.
+    pub fn summary(self) -> Result<&'a str, Error> {
+        self.record.str_value("summary")
+    }
+
+    /// Returns the description of the operation.
+    pub fn description(self) -> Result<&'a str, Error> {
+        self.record.str_value("description")
+    }
+
+    /// Returns the declarative assembly format, if any.
+    pub fn assembly_format(self) -> Result<Option<&'a str>, Error> {
+        Ok(optional_str(self.record, "assemblyFormat")?.filter(|format| !format.is_empty()))
+    }
+
+    /// Returns the operands and attributes of the `arguments` dag in order.
+    pub fn arguments(self) -> Result<Vec<NamedConstraint<'a>>, Error> {
+        self.constraints("arguments")
+    }
+
+    /// Returns the operands of the operation, i.e. the arguments that are not
+    /// attributes.
+    pub fn operands(self) -> Result<Vec<NamedConstraint<'a>>, Error> {
+        Ok(self
+            .arguments()?
+            .into_iter()
+            .filter(|argument| !argument.is_attribute())
+            .collect())
+    }
+
+    /// Returns the attributes of the operation.
+    pub fn attributes(self) -> Result<Vec<NamedConstraint<'a>>, Error> {
+        Ok(self
+            .arguments()?
+            .into_iter()
+            .filter(|argument| argument.is_attribute())
+            .collect())
+    }
+
+    /// Returns the results of the `results` dag.
+    pub fn results(self) -> Result<Vec<NamedConstraint<'a>>, Error> {
+        self.constraints("results")
+    }
+
+    /// Returns the regions of the `regions` dag.
+    pub fn regions(self) -> Result<Vec<NamedConstraint<'a>>, Error> {
+        self.constraints("regions")
+    }
+
+    /// Returns the successors of the `successors` dag.
+    pub fn successors(self) -> Result<Vec<NamedConstraint<'a>>, Error> {
+        self.constraints("successors")
+    }
+
+    /// Returns the traits and interfaces of the operation.
+    pub fn traits(self) -> Result<Vec<Record<'a>>, Error> {
+        self.record.list_of_defs_value("traits")
+    }
+
+    /// Returns true if the operation has the trait or interface with the given
+    /// name.
+    ///
+    /// Native traits are matched by their C++ name (e.g. `Commutative`), all
+    /// other traits by the name of their def (e.g. `Pure`).
+    pub fn has_trait(self, name: &str) -> Result<bool, Error> {
+        Ok(self.traits()?.into_iter().any(|record| {
+            record.name().is_ok_and(|record| record == name)
+                || (record.subclass_of("NativeTrait")
+                    && record.str_value("trait").is_ok_and(|record| record == name))
+        }))
+    }
+
+    /// Returns the traits and interfaces of the operation, with the traits of
+    /// `TraitList`s flattened and duplicates removed like `mlir-tblgen` does.
+    pub fn op_traits(self) -> Result<Vec<OpTrait<'a>>, Error> {
+        fn flatten<'a>(
+            records: Vec<Record<'a>>,
+            traits: &mut Vec<OpTrait<'a>>,
+        ) -> Result<(), Error> {
+            for record in records {
+                if record.subclass_of("TraitList") {
+                    flatten(record.list_of_defs_value("traits")?, traits)?;
+                } else {
+                    let record = OpTrait::try_from(record)?;
+                    if !traits.contains(&record) {
+                        traits.push(record);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        let mut traits = Vec::new();
+        flatten(self.traits()?, &mut traits)?;
+        Ok(traits)
+    }
+
+    fn constraints(self, field: &str) -> Result<Vec<NamedConstraint<'a>>, Error> {
+        Ok(self
+            .record
+            .value(field)?
+            .dag_args::<Record>()?
+            .into_iter()
+            .map(|(name, record)| NamedConstraint::new(name, record))
+            .collect())
+    }
+}
+
+/// Returns all operations of the given record keeper.
+pub fn operators<'a>(keeper: &'a RecordKeeper) -> Result<Vec<Operator<'a>>, Error> {
+    Ok(keeper
+        .all_derived_definitions("Op")?
+        .map(|record| Operator { record })
+        .collect())
+}
+
+/// A dialect, i.e. a def deriving from the `Dialect` class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Dialect<'a> {
+    record: Record<'a>,
+}
+
+impl<'a> TryFrom<Record<'a>> for Dialect<'a> {
+    type Error = Error;
+
+    fn try_from(record: Record<'a>) -> Result<Self, Self::Error> {
+        if record.subclass_of("Dialect") {
+            Ok(Self { record })
+        } else {
+            Err(TableGenError::InitConversion {
+                from: "Record",
+                to: "Dialect",
+            }
+            .with_location(record))
+        }
+    }
+}
+
+impl<'a> Dialect<'a> {
+    /// Returns the underlying record.
+    pub fn record(self) -> Record<'a> {
+        self.record
+    }
+
+    /// Returns the name of the dialect (e.g. `arith`).
+    pub fn name(self) -> Result<&'a str, Error> {
+        self.record.str_value("name")
+    }
+
+    /// Returns the C++ namespace of the dialect, if specified.
+    pub fn cpp_namespace(self) -> Result<Option<&'a str>, Error> {
+        optional_str(self.record, "cppNamespace")
+    }
+
+    /// Returns the one-line summary of the dialect, if specified.
+    pub fn summary(self) -> Result<Option<&'a str>, Error> {
+        optional_str(self.record, "summary")
+    }
+
+    /// Returns the description of the dialect, if specified.
+    pub fn description(self) -> Result<Option<&'a str>, Error> {
+        optional_str(self.record, "description")
+    }
+
+    /// Returns the C++ class names of the dialects that this dialect depends
+    /// on (e.g. `arith::ArithDialect`).
+    pub fn dependent_dialects(self) -> Result<Vec<&'a str>, Error> {
+        if self.record.has_field("dependentDialects") {
+            self.record.list_of_strings_value("dependentDialects")
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Returns the operations of the dialect in name order.
+    pub fn operations(self, keeper: &'a RecordKeeper) -> Result<Vec<Operator<'a>>, Error> {
+        let mut operations = Vec::new();
+        for operation in operators(keeper)? {
+            if operation.dialect()? == self {
+                operations.push(operation);
+            }
+        }
+        Ok(operations)
+    }
+
+    /// Returns the attribute definitions of the dialect, i.e. the defs
+    /// deriving from `AttrDef`, in name order.
+    pub fn attributes(self, keeper: &'a RecordKeeper) -> Result<Vec<Record<'a>>, Error> {
+        self.definitions(keeper, "AttrDef")
+    }
+
+    /// Returns the type definitions of the dialect, i.e. the defs deriving
+    /// from `TypeDef`, in name order.
+    pub fn types(self, keeper: &'a RecordKeeper) -> Result<Vec<Record<'a>>, Error> {
+        self.definitions(keeper, "TypeDef")
+    }
+
+    fn definitions(self, keeper: &'a RecordKeeper, class: &str) -> Result<Vec<Record<'a>>, Error> {
+        let mut definitions = Vec::new();
+        for record in keeper.all_derived_definitions_if_defined(class) {
+            if record.def_value("dialect")? == self.record {
+                definitions.push(record);
+            }
+        }
+        Ok(definitions)
+    }
+}
+
+/// Returns all dialects of the given record keeper.
+pub fn dialects<'a>(keeper: &'a RecordKeeper) -> Result<Vec<Dialect<'a>>, Error> {
+    Ok(keeper
+        .all_derived_definitions("Dialect")?
+        .map(|record| Dialect { record })
+        .collect())
+}
+
+/// A named entry of the `arguments`, `results`, `regions` or `successors` dag
+/// of an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NamedConstraint<'a> {
+    name: Option<&'a str>,
+    constraint: Record<'a>,
+    description: Option<Record<'a>>,
+}
+
+impl<'a> NamedConstraint<'a> {
+    fn new(name: Option<&'a str>, record: Record<'a>) -> Self {
+        // Arguments decorated with `Arg<constraint, description, effects>`
+        // (a subclass of `OpVariable`) wrap the actual constraint.
+        match record.def_value("constraint") {
+            Ok(constraint) if record.subclass_of("OpVariable") || record.subclass_of("Arg") => {
+                Self {
+                    name,
+                    constraint,
+                    description: Some(record),
+                }
+            }
+            _ => Self {
+                name,
+                constraint: record,
+                description: None,
+            },
+        }
+    }
+
+    /// Returns the name of the entry (e.g. `lhs` for `I32:$lhs`).
+    pub fn name(self) -> Option<&'a str> {
+        self.name
+    }
+
+    /// Returns the constraint, e.g. a type constraint, an attribute or a
+    /// region constraint.
+    pub fn constraint(self) -> Record<'a> {
+        self.constraint
+    }
+
+    /// Returns the description of an argument decorated with `Arg`.
+    pub fn description(self) -> Option<&'a str> {
+        self.description
+            .and_then(|record| record.str_value("summary").ok())
+            .filter(|summary| !summary.is_empty())
+    }
+
+    /// Returns true if the constraint is an attribute.
+    pub fn is_attribute(self) -> bool {
+        self.constraint.subclass_of("Attr")
+    }
+
+    /// Returns the constraint as an attribute, if it is one.
+    pub fn attr(self) -> Option<Attr<'a>> {
+        Attr::try_from(self.constraint).ok()
+    }
+
+    /// Returns the constraint as a type constraint, if it is one.
+    pub fn type_constraint(self) -> Option<TypeConstraint<'a>> {
+        TypeConstraint::try_from(self.constraint).ok()
+    }
+
+    /// Returns true if the constraint is variadic, e.g. `Variadic<I32>` or
+    /// `VariadicRegion<AnyRegion>`.
+    pub fn is_variadic(self) -> bool {
+        [
+            "Variadic",
+            "VariadicOfVariadic",
+            "VariadicRegion",
+            "VariadicSuccessor",
+        ]
+        .iter()
+        .any(|class| self.constraint.subclass_of(class))
+    }
+
+    /// Returns true if the constraint is optional, e.g. `Optional<I32>` or
+    /// `OptionalAttr<I32Attr>`.
+    pub fn is_optional(self) -> bool {
+        ["Optional", "OptionalAttr"]
+            .iter()
+            .any(|class| self.constraint.subclass_of(class))
+            || (self.is_attribute() && self.constraint.bit_value("isOptional").is_ok_and(|bit| bit))
+    }
+}
+
+/// Returns the value of a string field that may be missing or unset.
+fn optional_str<'a>(record: Record<'a>, name: &str) -> Result<Option<&'a str>, Error> {
+    if record.has_field(name) {
+        record.optional_str_value(name)
+    } else {
+        Ok(None)
+    }
+}
+
+macro_rules! constraint {
+    ($name:ident, $class:literal) => {
+        impl<'a> TryFrom<Record<'a>> for $name<'a> {
+            type Error = Error;
+
+            fn try_from(record: Record<'a>) -> Result<Self, Self::Error> {
+                if record.subclass_of($class) {
+                    Ok(Self { record })
+                } else {
+                    Err(TableGenError::InitConversion {
+                        from: "Record",
+                        to: stringify!($name),
+                    }
+                    .with_location(record))
+                }
+            }
+        }
+
+        impl<'a> $name<'a> {
+            /// Returns the underlying record.
+            pub fn record(self) -> Record<'a> {
+                self.record
+            }
+        }
+    };
+}
+
+/// A type constraint, i.e. a def deriving from the `TypeConstraint` class
+/// (e.g. `I32` or `Variadic<AnyType>`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeConstraint<'a> {
+    record: Record<'a>,
+}
+
+constraint!(TypeConstraint, "TypeConstraint");
+
+impl<'a> TypeConstraint<'a> {
+    /// Returns the one-line summary of the constraint.
+    pub fn summary(self) -> Result<&'a str, Error> {
+        self.record.str_value("summary")
+    }
+
+    /// Returns the predicate that values must satisfy.
+    pub fn predicate(self) -> Result<Pred<'a>, Error> {
+        Pred::try_from(self.record.def_value("predicate")?)
+    }
+
+    /// Returns the C++ type of values (e.g. `::mlir::IntegerType`).
+    pub fn cpp_type(self) -> Result<&'a str, Error> {
+        // Older MLIR versions name the field `cppClassName`.
+        match optional_str(self.record, "cppType")? {
+            Some(cpp_type) => Ok(cpp_type),
+            None => self.record.str_value("cppClassName"),
+        }
+    }
+}
+
+/// An attribute constraint, i.e. a def deriving from the `Attr` class (e.g.
+/// `I32Attr` or `DefaultValuedAttr<I32Attr, "0">`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Attr<'a> {
+    record: Record<'a>,
+}
+
+constraint!(Attr, "Attr");
+
+impl<'a> Attr<'a> {
+    /// Returns the one-line summary of the attribute.
+    pub fn summary(self) -> Result<&'a str, Error> {
+        self.record.str_value("summary")
+    }
+
+    /// Returns the predicate that attributes must satisfy.
+    pub fn predicate(self) -> Result<Pred<'a>, Error> {
+        Pred::try_from(self.record.def_value("predicate")?)
+    }
+
+    /// Returns the C++ type used to store the attribute (e.g.
+    /// `::mlir::IntegerAttr`).
+    pub fn storage_type(self) -> Result<Option<&'a str>, Error> {
+        optional_str(self.record, "storageType")
+    }
+
+    /// Returns the C++ type returned by the attribute getter (e.g.
+    /// `::llvm::APInt`).
+    pub fn return_type(self) -> Result<Option<&'a str>, Error> {
+        optional_str(self.record, "returnType")
+    }
+
+    /// Returns the C++ expression converting from the storage type to the
+    /// return type, with `$_self` as a placeholder of the stored attribute.
+    pub fn convert_from_storage(self) -> Result<Option<&'a str>, Error> {
+        optional_str(self.record, "convertFromStorage")
+    }
+
+    /// Returns the C++ expression building a constant attribute, if any.
+    pub fn const_builder_call(self) -> Result<Option<&'a str>, Error> {
+        optional_str(self.record, "constBuilderCall")
+    }
+
+    /// Returns the default value of the attribute as a C++ expression (e.g.
+    /// for `DefaultValuedAttr`).
+    pub fn default_value(self) -> Result<Option<&'a str>, Error> {
+        optional_str(self.record, "defaultValue")
+    }
+
+    /// Returns true if the attribute is optional.
+    pub fn is_optional(self) -> bool {
+        self.record.bit_value("isOptional").is_ok_and(|bit| bit)
+    }
+
+    /// Returns the attribute this attribute is derived from (e.g. `I32Attr`
+    /// for `DefaultValuedAttr<I32Attr, "0">`), if any.
+    pub fn base_attr(self) -> Option<Attr<'a>> {
+        self.record
+            .optional_def_value("baseAttr")
+            .and_then(|record| Attr::try_from(record).ok())
+    }
+}
+
+/// A predicate, i.e. a def deriving from the `Pred` class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pred<'a> {
+    record: Record<'a>,
+}
+
+constraint!(Pred, "Pred");
+
+impl<'a> Pred<'a> {
+    /// Returns the C++ expression of a `CPred`, e.g.
+    /// `($_self.isInteger(32))`.
+    pub fn expression(self) -> Result<Option<&'a str>, Error> {
+        optional_str(self.record, "predExpr")
+    }
+
+    /// Returns the name of the combiner of a `CombinedPred` (e.g.
+    /// `PredCombinerAnd` for `And<[...]>`).
+    pub fn combiner(self) -> Result<Option<&'a str>, Error> {
+        if !self.record.subclass_of("CombinedPred") {
+            return Ok(None);
+        }
+        self.record.def_value("kind")?.name().map(Some)
+    }
+
+    /// Returns the predicates combined by a `CombinedPred`.
+    pub fn children(self) -> Result<Vec<Pred<'a>>, Error> {
+        if !self.record.subclass_of("CombinedPred") {
+            return Ok(Vec::new());
+        }
+        self.record
+            .list_of_defs_value("children")?
+            .into_iter()
+            .map(Pred::try_from)
+            .collect()
+    }
+
+    /// Returns the condition of the predicate as a C++ expression, combining
+    /// the expressions of all `CPred`s like `mlir-tblgen` does.
+    pub fn condition(self) -> Result<String, Error> {
+        if let Some(expression) = self.expression()? {
+            return Ok(expression.into());
+        }
+        let Some(combiner) = self.combiner()? else {
+            return Err(TableGenError::InitConversion {
+                from: "Pred",
+                to: "CPred",
+            }
+            .with_location(self.record));
+        };
+
+        let children = self
+            .children()?
+            .into_iter()
+            .map(Pred::condition)
+            .collect::<Result<Vec<_>, _>>()?;
+        let child = || {
+            children.first().cloned().ok_or_else(|| {
+                TableGenError::MissingValue("children".into()).with_location(self.record)
+            })
+        };
+        Ok(match combiner {
+            "PredCombinerAnd" if children.is_empty() => "true".into(),
+            "PredCombinerOr" if children.is_empty() => "false".into(),
+            "PredCombinerAnd" => format!("({})", children.join(" && ")),
+            "PredCombinerOr" => format!("({})", children.join(" || ")),
+            "PredCombinerNot" => format!("!({})", child()?),
+            "PredCombinerSubstLeaves" => child()?.replace(
+                self.record.str_value("pattern")?,
+                self.record.str_value("replacement")?,
+            ),
+            "PredCombinerConcat" => format!(
+                "{}{}{}",
+                self.record.str_value("prefix")?,
+                child()?,
+                self.record.str_value("suffix")?
+            ),
+            _ => return Err(unknown_combiner(self.record, combiner)),
+        })
+    }
+
+    /// Returns the predicate as an expression tree, e.g. to translate it to
+    /// another language than C++.
+    ///
+    /// Like in `mlir-tblgen`, the substitutions of `SubstLeaves` are applied
+    /// to the expressions of the `CPred`s they contain, so the tree only has
+    /// `CPred` leaves and `And`, `Or`, `Neg` and `Concat` nodes.
+    pub fn to_expr(self) -> Result<PredExpr, Error> {
+        self.expr(&mut Vec::new())
+    }
+
+    fn expr(self, substitutions: &mut Vec<(&'a str, &'a str)>) -> Result<PredExpr, Error> {
+        if let Some(expression) = self.expression()? {
+            // Applied from the innermost to the outermost `SubstLeaves`.
+            let expression = substitutions.iter().rev().fold(
+                expression.to_owned(),
+                |expression, (pattern, replacement)| expression.replace(pattern, replacement),
+            );
+            return Ok(PredExpr::Code(CodeFragment::parse(&expression)));
+        }
+        let Some(combiner) = self.combiner()? else {
+            return Err(TableGenError::InitConversion {
+                from: "Pred",
+                to: "CPred",
+            }
+            .with_location(self.record));
+        };
+
+        let children = self.children()?;
+        let child = |substitutions: &mut Vec<_>| {
+            children
+                .first()
+                .ok_or_else(|| {
+                    TableGenError::MissingValue("children".into()).with_location(self.record)
+                })?
+                .expr(substitutions)
+                .map(Box::new)
+        };
+        let all = |substitutions: &mut Vec<_>| {
+            children
+                .iter()
+                .map(|child| child.expr(substitutions))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(match combiner {
+            "PredCombinerAnd" => PredExpr::And(all(substitutions)?),
+            "PredCombinerOr" => PredExpr::Or(all(substitutions)?),
+            "PredCombinerNot" => PredExpr::Not(child(substitutions)?),
+            "PredCombinerSubstLeaves" => {
+                substitutions.push((
+                    self.record.str_value("pattern")?,
+                    self.record.str_value("replacement")?,
+                ));
+                let child = child(substitutions);
+                substitutions.pop();
+                *child?
+            }
+            "PredCombinerConcat" => PredExpr::Concat {
+                prefix: self.record.str_value("prefix")?.into(),
+                child: child(substitutions)?,
+                suffix: self.record.str_value("suffix")?.into(),
+            },
+            _ => return Err(unknown_combiner(self.record, combiner)),
+        })
+    }
+}
+
+fn unknown_combiner(record: Record, combiner: &str) -> Error {
+    TableGenError::InvalidArgument(format!("unknown predicate combiner {}", combiner))
+        .with_location(record)
+}
+
+/// An expression tree of a predicate, see [`Pred::to_expr`].
+///
+/// It is displayed as the C++ expression returned by [`Pred::condition`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PredExpr {
+    /// The C++ expression of a `CPred`.
+    Code(Vec<CodeFragment>),
+    /// The conjunction of predicates (`And`), true if empty.
+    And(Vec<PredExpr>),
+    /// The disjunction of predicates (`Or`), false if empty.
+    Or(Vec<PredExpr>),
+    /// The negation of a predicate (`Neg`).
+    Not(Box<PredExpr>),
+    /// A predicate surrounded by a prefix and a suffix (`Concat`).
+    Concat {
+        /// The code before the predicate.
+        prefix: String,
+        /// The predicate.
+        child: Box<PredExpr>,
+        /// The code after the predicate.
+        suffix: String,
+    },
+}
+
+impl PredExpr {
+    /// Renders the expression as C++, replacing each placeholder with the
+    /// result of the given function, which receives its name without `$`
+    /// (e.g. `_self`).
+    pub fn render(&self, placeholder: &mut impl FnMut(&str) -> String) -> String {
+        let all = |children: &[PredExpr], placeholder: &mut _, operator| {
+            children
+                .iter()
+                .map(|child| child.render(placeholder))
+                .collect::<Vec<_>>()
+                .join(operator)
+        };
+        match self {
+            Self::Code(fragments) => fragments
+                .iter()
+                .map(|fragment| match fragment {
+                    CodeFragment::Text(text) => text.clone(),
+                    CodeFragment::Placeholder(name) => placeholder(name),
+                })
+                .collect(),
+            Self::And(children) if children.is_empty() => "true".into(),
+            Self::Or(children) if children.is_empty() => "false".into(),
+            Self::And(children) => format!("({})", all(children, placeholder, " && ")),
+            Self::Or(children) => format!("({})", all(children, placeholder, " || ")),
+            Self::Not(child) => format!("!({})", child.render(placeholder)),
+            Self::Concat {
+                prefix,
+                child,
+                suffix,
+            } => format!("{}{}{}", prefix, child.render(placeholder), suffix),
+        }
+    }
+
+    /// Returns the names of the placeholders used in the expression, without
+    /// `$` and in order of first use.
+    pub fn placeholders(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.visit_placeholders(&mut names);
+        names
+    }
+
+    fn visit_placeholders<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Self::Code(fragments) => {
+                for fragment in fragments {
+                    match fragment {
+                        CodeFragment::Placeholder(name) if !names.contains(&name.as_str()) => {
+                            names.push(name)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Self::And(children) | Self::Or(children) => {
+                for child in children {
+                    child.visit_placeholders(names);
+                }
+            }
+            Self::Not(child) | Self::Concat { child, .. } => child.visit_placeholders(names),
+        }
+    }
+}
+
+impl Display for PredExpr {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{}",
+            self.render(&mut |name| format!("${}", name))
+        )
+    }
+}
+
+/// A fragment of the C++ expression of a `CPred`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CodeFragment {
+    /// Verbatim C++ code.
+    Text(String),
+    /// A placeholder substituted by `mlir-tblgen`, e.g. `$_self` or the name
+    /// of an operand like `$lhs`, without `$`.
+    Placeholder(String),
+}
+
+impl CodeFragment {
+    /// Splits C++ code into text and placeholders.
+    pub fn parse(code: &str) -> Vec<Self> {
+        let mut fragments = Vec::new();
+        let mut text = String::new();
+        let mut rest = code;
+        while let Some(index) = rest.find('$') {
+            text.push_str(&rest[..index]);
+            rest = &rest[index + 1..];
+            let length = rest
+                .find(|character: char| !(character.is_ascii_alphanumeric() || character == '_'))
+                .unwrap_or(rest.len());
+            if length == 0 {
+                text.push('$');
+                continue;
+            }
+            if !text.is_empty() {
+                fragments.push(Self::Text(std::mem::take(&mut text)));
+            }
+            fragments.push(Self::Placeholder(rest[..length].into()));
+            rest = &rest[length..];
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            fragments.push(Self::Text(text));
+        }
+        fragments
+    }
+}
+
+/// The kind of an [`OpTrait`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TraitKind {
+    /// A trait implemented in C++ (`NativeTrait`, e.g.
+    /// `NativeOpTrait<"Commutative">` or `ParamNativeOpTrait<"HasParent",
+    /// "FuncOp">`).
+    Native,
+    /// A trait whose C++ name is generated by `mlir-tblgen`
+    /// (`GenInternalTrait`, e.g. `AttrSizedOperandSegments`).
+    Internal,
+    /// A trait verified with a predicate (`PredTrait`, e.g. `PredOpTrait`).
+    Predicate,
+    /// An interface (`Interface`, e.g. `OpInterface`).
+    Interface,
+    /// An interface whose methods are declared by the operation
+    /// (`DeclareOpInterfaceMethods`).
+    DeclaredInterface,
+    /// Any other trait.
+    Other,
+}
+
+/// A trait or interface of an operation, i.e. a def deriving from the `Trait`
+/// class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OpTrait<'a> {
+    record: Record<'a>,
+}
+
+constraint!(OpTrait, "Trait");
+
+impl<'a> OpTrait<'a> {
+    /// Returns the kind of the trait.
+    pub fn kind(self) -> TraitKind {
+        if self.record.subclass_of("DeclareInterfaceMethods") {
+            TraitKind::DeclaredInterface
+        } else if self.record.subclass_of("Interface") {
+            TraitKind::Interface
+        } else if self.record.subclass_of("PredTrait") {
+            TraitKind::Predicate
+        } else if self.record.subclass_of("GenInternalTrait") {
+            TraitKind::Internal
+        } else if self.record.subclass_of("NativeTrait") {
+            TraitKind::Native
+        } else {
+            TraitKind::Other
+        }
+    }
+
+    /// Returns the name of the trait without C++ namespace and parameters,
+    /// e.g. `HasParent` for `ParamNativeOpTrait<"HasParent", "FuncOp">` or
+    /// `InferTypeOpInterface`.
+    ///
+    /// Predicate and other traits are named after their def.
+    pub fn name(self) -> Result<&'a str, Error> {
+        Ok(match self.kind() {
+            TraitKind::Native => {
+                let name = self.record.str_value("trait")?;
+                name.split('<').next().unwrap_or(name).trim()
+            }
+            TraitKind::Internal => {
+                let name = self.record.str_value("trait")?;
+                name.rsplit("::").next().unwrap_or(name)
+            }
+            TraitKind::Interface | TraitKind::DeclaredInterface => {
+                self.record.str_value("cppInterfaceName")?
+            }
+            TraitKind::Predicate | TraitKind::Other => self.record.name()?,
+        })
+    }
+
+    /// Returns the fully qualified C++ name of the trait, e.g.
+    /// `::mlir::OpTrait::HasParent<FuncOp>::Impl` or
+    /// `::mlir::InferTypeOpInterface`, or `None` for predicate and other
+    /// traits.
+    pub fn cpp_name(self) -> Result<Option<String>, Error> {
+        let (namespace, name) = match self.kind() {
+            TraitKind::Native => (
+                optional_str(self.record, "cppNamespace")?,
+                self.record.str_value("trait")?,
+            ),
+            TraitKind::Internal => (None, self.record.str_value("trait")?),
+            TraitKind::Interface | TraitKind::DeclaredInterface => (
+                optional_str(self.record, "cppNamespace")?,
+                self.record.str_value("cppInterfaceName")?,
+            ),
+            TraitKind::Predicate | TraitKind::Other => return Ok(None),
+        };
+        Ok(Some(match namespace {
+            Some(namespace) if !namespace.is_empty() => format!("{}::{}", namespace, name),
+            _ => name.into(),
+        }))
+    }
+
+    /// Returns the parameters of the trait, i.e. the C++ template arguments of
+    /// native traits (e.g. `FuncOp` for `ParamNativeOpTrait<"HasParent",
+    /// "FuncOp">`) or the methods that are always overridden by declared
+    /// interfaces.
+    pub fn parameters(self) -> Result<Vec<&'a str>, Error> {
+        Ok(match self.kind() {
+            TraitKind::Native => template_arguments(self.record.str_value("trait")?),
+            TraitKind::DeclaredInterface => self
+                .record
+                .list_of_strings_value("alwaysOverriddenMethods")?,
+            _ => Vec::new(),
+        })
+    }
+
+    /// Returns the predicate of a predicate trait.
+    pub fn predicate(self) -> Result<Option<Pred<'a>>, Error> {
+        if self.kind() != TraitKind::Predicate {
+            return Ok(None);
+        }
+        Pred::try_from(self.record.def_value("predicate")?).map(Some)
+    }
+}
+
+/// Returns the top-level template arguments of a C++ name, e.g. `A` and
+/// `B<C, D>` for `T<A, B<C, D>>::Impl`.
+fn template_arguments(name: &str) -> Vec<&str> {
+    let Some(start) = name.find('<') else {
+        return Vec::new();
+    };
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut argument = start + 1;
+    for (index, character) in name.char_indices().skip_while(|&(index, _)| index <= start) {
+        match character {
+            '<' | '(' => depth += 1,
+            ',' if depth == 0 => {
+                arguments.push(name[argument..index].trim());
+                argument = index + 1;
+            }
+            '>' | ')' if depth == 0 => {
+                arguments.push(name[argument..index].trim());
+                break;
+            }
+            '>' | ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    arguments.retain(|argument| !argument.is_empty());
+    arguments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    const OP_BASE: &str = r#"
+        class Dialect { string name = ""; string cppNamespace = ""; }
+        class Trait;
+        class NativeTrait<string name> : Trait { string trait = name; }
+        def Commutative : NativeTrait<"Commutative">;
+        def Pure : Trait;
+        class TypeConstraint;
+        class Type : TypeConstraint;
+        class Variadic<Type type> : TypeConstraint;
+        class Attr { bit isOptional = 0; }
+        class OptionalAttr<Attr attr> : Attr { let isOptional = 1; }
+        class Arg<TypeConstraint c, string desc = ""> {
+            TypeConstraint constraint = c; string summary = desc;
+        }
+        class Region;
+        def AnyRegion : Region;
+        class Successor;
+        def AnySuccessor : Successor;
+        def I32 : Type;
+        def I32Attr : Attr;
+        def ins; def outs; def region; def successor;
+        class Op<Dialect d, string mnemonic, list<Trait> props = []> {
+            Dialect opDialect = d; string opName = mnemonic;
+            string summary = ""; string description = "";
+            dag arguments = (ins); dag results = (outs);
+            dag regions = (region); dag successors = (successor);
+            list<Trait> traits = props;
+            string assemblyFormat = ?;
+        }
+        def Test : Dialect { let name = "test"; let cppNamespace = "::test"; }
+    "#;
+
+    fn parse(source: &str) -> RecordKeeper<'static> {
+        TableGenParser::new()
+            .add_source(&format!("{}{}", OP_BASE, source))
+            .unwrap()
+            .parse()
+            .expect("valid tablegen")
+    }
+
+    #[test]
+    fn operator() {
+        let rk = parse(
+            r#"
+            def AddOp : Op<Test, "add", [Commutative, Pure]> {
+                let summary = "addition";
+                let arguments = (ins Arg<I32, "left">:$lhs, Variadic<I32>:$rhs,
+                                     OptionalAttr<I32Attr>:$flag);
+                let results = (outs I32:$result);
+                let regions = (region AnyRegion:$body);
+                let successors = (successor AnySuccessor:$next);
+                let assemblyFormat = "$lhs `,` $rhs attr-dict";
+            }
+            "#,
+        );
+        let op = Operator::try_from(rk.def("AddOp").unwrap()).unwrap();
+        assert_eq!(op.full_name().unwrap(), "test.add");
+        assert_eq!(
+            op.dialect().unwrap().cpp_namespace().unwrap(),
+            Some("::test")
+        );
+        assert_eq!(op.summary().unwrap(), "addition");
+        assert_eq!(
+            op.assembly_format().unwrap(),
+            Some("$lhs `,` $rhs attr-dict")
+        );
+
+        let operands = op.operands().unwrap();
+        assert_eq!(operands.len(), 2);
+        assert_eq!(operands[0].name(), Some("lhs"));
+        assert_eq!(operands[0].constraint(), rk.def("I32").unwrap());
+        assert_eq!(operands[0].description(), Some("left"));
+        assert!(operands[1].is_variadic());
+        let attributes = op.attributes().unwrap();
+        assert_eq!(attributes[0].name(), Some("flag"));
+        assert!(attributes[0].is_optional());
+
+        assert_eq!(op.results().unwrap()[0].name(), Some("result"));
+        assert_eq!(op.regions().unwrap()[0].name(), Some("body"));
+        assert_eq!(op.successors().unwrap()[0].name(), Some("next"));
+        assert!(op.has_trait("Commutative").unwrap());
+        assert!(op.has_trait("Pure").unwrap());
+        assert!(!op.has_trait("Terminator").unwrap());
+        assert_eq!(operators(&rk).unwrap(), [op]);
+    }
+
+    #[test]
+    fn op_traits() {
+        let rk = parse(
+            r#"
+            class Pred;
+            class CPred<code pred> : Pred { code predExpr = "(" # pred # ")"; }
+            class NativeOpTrait<string name> : NativeTrait<name> {
+                string cppNamespace = "::mlir::OpTrait";
+            }
+            class ParamNativeOpTrait<string prop, string params>
+                : NativeOpTrait<prop # "<" # params # ">::Impl">;
+            class GenInternalTrait<string prop> : Trait {
+                string trait = "::mlir::OpTrait::" # prop;
+            }
+            class PredTrait<string descr, Pred pred> : Trait {
+                string summary = descr; Pred predicate = pred;
+            }
+            class TraitList<list<Trait> props> : Trait { list<Trait> traits = props; }
+            class Interface<string name> {
+                string cppInterfaceName = name; string cppNamespace = "::mlir";
+            }
+            class OpInterface<string name> : Interface<name>, NativeTrait<name # "::Trait">;
+            class DeclareInterfaceMethods<list<string> methods = []> {
+                list<string> alwaysOverriddenMethods = methods;
+            }
+            class DeclareOpInterfaceMethods<OpInterface interface, list<string> methods = []>
+                : DeclareInterfaceMethods<methods>, OpInterface<interface.cppInterfaceName>;
+            def InferTypeOpInterface : OpInterface<"InferTypeOpInterface">;
+            def IsolatedFromAbove : NativeOpTrait<"IsIsolatedFromAbove">;
+            def TraitOp : Op<Test, "trait", [
+                Commutative, IsolatedFromAbove,
+                TraitList<[
+                    ParamNativeOpTrait<"HasParent", "FuncOp, Wrapper<A, B>">,
+                    GenInternalTrait<"AttrSizedOperandSegments">
+                ]>,
+                PredTrait<"true", CPred<"true">>, InferTypeOpInterface,
+                DeclareOpInterfaceMethods<InferTypeOpInterface, ["inferReturnTypes"]>,
+                Pure, Commutative
+            ]>;
+            "#,
+        );
+        let op = Operator::try_from(rk.def("TraitOp").unwrap()).unwrap();
+        let traits = op.op_traits().unwrap();
+        let describe = |index: usize| {
+            let op_trait = traits[index];
+            (
+                op_trait.kind(),
+                op_trait.name().unwrap(),
+                op_trait.cpp_name().unwrap(),
+                op_trait.parameters().unwrap(),
+            )
+        };
+        assert_eq!(traits.len(), 8);
+        assert_eq!(
+            describe(0),
+            (
+                TraitKind::Native,
+                "Commutative",
+                Some("Commutative".into()),
+                vec![]
+            )
+        );
+        assert_eq!(
+            describe(1),
+            (
+                TraitKind::Native,
+                "IsIsolatedFromAbove",
+                Some("::mlir::OpTrait::IsIsolatedFromAbove".into()),
+                vec![]
+            )
+        );
+        assert_eq!(
+            describe(2),
+            (
+                TraitKind::Native,
+                "HasParent",
+                Some("::mlir::OpTrait::HasParent<FuncOp, Wrapper<A, B>>::Impl".into()),
+                vec!["FuncOp", "Wrapper<A, B>"]
+            )
+        );
+        assert_eq!(
+            describe(3),
+            (
+                TraitKind::Internal,
+                "AttrSizedOperandSegments",
+                Some("::mlir::OpTrait::AttrSizedOperandSegments".into()),
+                vec![]
+            )
+        );
+        assert_eq!(describe(4).0, TraitKind::Predicate);
+        assert_eq!(describe(4).2, None);
+        assert_eq!(
+            traits[4].predicate().unwrap().unwrap().condition().unwrap(),
+            "(true)"
+        );
+        assert_eq!(
+            describe(5),
+            (
+                TraitKind::Interface,
+                "InferTypeOpInterface",
+                Some("::mlir::InferTypeOpInterface".into()),
+                vec![]
+            )
+        );
+        assert_eq!(
+            describe(6),
+            (
+                TraitKind::DeclaredInterface,
+                "InferTypeOpInterface",
+                Some("::mlir::InferTypeOpInterface".into()),
+                vec!["inferReturnTypes"]
+            )
+        );
+        assert_eq!(describe(7), (TraitKind::Other, "Pure", None, vec![]));
+        assert_eq!(traits[7].predicate().unwrap(), None);
+    }
+
+    #[test]
+    fn constraints() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class Pred;
+                class CPred<code pred> : Pred { code predExpr = "(" # pred # ")"; }
+                class PredCombinerKind;
+                def PredCombinerAnd : PredCombinerKind;
+                def PredCombinerNot : PredCombinerKind;
+                class CombinedPred<PredCombinerKind k, list<Pred> c> : Pred {
+                    PredCombinerKind kind = k; list<Pred> children = c;
+                }
+                class And<list<Pred> c> : CombinedPred<PredCombinerAnd, c>;
+                class Neg<Pred p> : CombinedPred<PredCombinerNot, [p]>;
+                class Constraint<Pred p, string desc = ""> {
+                    Pred predicate = p; string summary = desc;
+                }
+                class TypeConstraint<Pred p, string desc = "", string t = "::mlir::Type">
+                    : Constraint<p, desc> { string cppType = t; }
+                class Attr<Pred p, string desc = ""> : Constraint<p, desc> {
+                    code storageType = ?; code returnType = ?;
+                    code convertFromStorage = "$_self"; string defaultValue = ?;
+                    bit isOptional = 0; Attr baseAttr = ?;
+                }
+                class DefaultValuedAttr<Attr attr, string val>
+                    : Attr<attr.predicate, attr.summary> {
+                    let storageType = attr.storageType;
+                    let defaultValue = val;
+                    let baseAttr = attr;
+                }
+                def I32 : TypeConstraint<CPred<"$_self.isInteger(32)">, "i32",
+                                         "::mlir::IntegerType">;
+                def I32Attr : Attr<And<[CPred<"a">, Neg<CPred<"b">>]>, "i32 attr"> {
+                    let storageType = [{ ::mlir::IntegerAttr }];
+                    let returnType = [{ ::llvm::APInt }];
+                }
+                def DefaultI32Attr : DefaultValuedAttr<I32Attr, "0">;
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+
+        let i32 = TypeConstraint::try_from(rk.def("I32").unwrap()).unwrap();
+        assert_eq!(i32.summary().unwrap(), "i32");
+        assert_eq!(i32.cpp_type().unwrap(), "::mlir::IntegerType");
+        assert_eq!(
+            i32.predicate().unwrap().condition().unwrap(),
+            "($_self.isInteger(32))"
+        );
+
+        let attr = Attr::try_from(rk.def("I32Attr").unwrap()).unwrap();
+        assert_eq!(attr.storage_type().unwrap(), Some(" ::mlir::IntegerAttr "));
+        assert_eq!(attr.return_type().unwrap(), Some(" ::llvm::APInt "));
+        assert_eq!(attr.convert_from_storage().unwrap(), Some("$_self"));
+        assert_eq!(attr.default_value().unwrap(), None);
+        let predicate = attr.predicate().unwrap();
+        assert_eq!(predicate.combiner().unwrap(), Some("PredCombinerAnd"));
+        assert_eq!(predicate.children().unwrap().len(), 2);
+        assert_eq!(predicate.condition().unwrap(), "((a) && !((b)))");
+
+        let default = Attr::try_from(rk.def("DefaultI32Attr").unwrap()).unwrap();
+        assert_eq!(default.default_value().unwrap(), Some("0"));
+        assert_eq!(default.base_attr(), Some(attr));
+        assert!(Attr::try_from(rk.def("I32").unwrap()).is_err());
+    }
+
+    #[test]
+    fn predicate_expr() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class Pred;
+                class CPred<code pred> : Pred { code predExpr = "(" # pred # ")"; }
+                class PredCombinerKind;
+                def PredCombinerAnd : PredCombinerKind;
+                def PredCombinerOr : PredCombinerKind;
+                def PredCombinerNot : PredCombinerKind;
+                def PredCombinerSubstLeaves : PredCombinerKind;
+                def PredCombinerConcat : PredCombinerKind;
+                class CombinedPred<PredCombinerKind k, list<Pred> c> : Pred {
+                    PredCombinerKind kind = k; list<Pred> children = c;
+                }
+                class Or<list<Pred> c> : CombinedPred<PredCombinerOr, c>;
+                class Neg<Pred p> : CombinedPred<PredCombinerNot, [p]>;
+                class SubstLeaves<string pat, string repl, Pred p>
+                    : CombinedPred<PredCombinerSubstLeaves, [p]> {
+                    string pattern = pat; string replacement = repl;
+                }
+                class Concat<string pre, Pred p, string suf>
+                    : CombinedPred<PredCombinerConcat, [p]> {
+                    string prefix = pre; string suffix = suf;
+                }
+                def IsI32 : CPred<"$_self.isInteger(32)">;
+                def P : Or<[
+                    SubstLeaves<"$_self", "$_self.getType()", IsI32>,
+                    Neg<Concat<"foo(", CPred<"$lhs == $$x">, ")">>
+                ]>;
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+
+        let pred = Pred::try_from(rk.def("P").unwrap()).unwrap();
+        let expr = pred.to_expr().unwrap();
+        let code = |fragments: &[&str]| {
+            PredExpr::Code(
+                fragments
+                    .iter()
+                    .map(|fragment| match fragment.strip_prefix('$') {
+                        Some(name) => CodeFragment::Placeholder(name.into()),
+                        None => CodeFragment::Text((*fragment).into()),
+                    })
+                    .collect(),
+            )
+        };
+        assert_eq!(
+            expr,
+            PredExpr::Or(vec![
+                code(&["(", "$_self", ".getType().isInteger(32))"]),
+                PredExpr::Not(Box::new(PredExpr::Concat {
+                    prefix: "foo(".into(),
+                    child: Box::new(code(&["(", "$lhs", " == $", "$x", ")"])),
+                    suffix: ")".into(),
+                })),
+            ])
+        );
+        assert_eq!(expr.to_string(), pred.condition().unwrap());
+        assert_eq!(expr.placeholders(), ["_self", "lhs", "x"]);
+        assert_eq!(
+            expr.render(&mut |name| name.to_uppercase()),
+            "((_SELF.getType().isInteger(32)) || !(foo((LHS == $X))))"
+        );
+        assert_eq!(PredExpr::And(Vec::new()).to_string(), "true");
+        assert_eq!(
+            CodeFragment::parse("$ $_op"),
+            [
+                CodeFragment::Text("$ ".into()),
+                CodeFragment::Placeholder("_op".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn dialect() {
+        let rk = parse(
+            r#"
+            class AttrDef<Dialect d> { Dialect dialect = d; }
+            class TypeDef<Dialect d> { Dialect dialect = d; }
+            class FullDialect : Dialect {
+                string summary = ?; list<string> dependentDialects = [];
+            }
+            def Other : FullDialect {
+                let name = "other";
+                let summary = "other dialect";
+                let dependentDialects = ["test::TestDialect"];
+            }
+            def AddOp : Op<Test, "add">;
+            def SubOp : Op<Test, "sub">;
+            def MulOp : Op<Other, "mul">;
+            def TestAttr : AttrDef<Test>;
+            def OtherType : TypeDef<Other>;
+            "#,
+        );
+        let test = Dialect::try_from(rk.def("Test").unwrap()).unwrap();
+        let other = Dialect::try_from(rk.def("Other").unwrap()).unwrap();
+        assert_eq!(dialects(&rk).unwrap(), [other, test]);
+        assert_eq!(test.summary().unwrap(), None);
+        assert!(test.dependent_dialects().unwrap().is_empty());
+        assert_eq!(other.summary().unwrap(), Some("other dialect"));
+        assert_eq!(other.dependent_dialects().unwrap(), ["test::TestDialect"]);
+
+        let names = |dialect: Dialect| {
+            dialect
+                .operations(&rk)
+                .unwrap()
+                .into_iter()
+                .map(|op| op.op_name().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(test), ["add", "sub"]);
+        assert_eq!(names(other), ["mul"]);
+        assert_eq!(test.attributes(&rk).unwrap(), [rk.def("TestAttr").unwrap()]);
+        assert!(test.types(&rk).unwrap().is_empty());
+        assert_eq!(other.types(&rk).unwrap(), [rk.def("OtherType").unwrap()]);
+        assert!(Dialect::try_from(rk.def("AddOp").unwrap()).is_err());
+    }
+
+    #[test]
+    fn not_an_operator() {
+        let rk = parse("");
+        assert!(Operator::try_from(rk.def("I32").unwrap()).is_err());
+    }
+}
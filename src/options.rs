@@ -0,0 +1,214 @@
+use crate::{Error, TableGenParser, error::TableGenError};
+
+/// Options of a TableGen invocation, parsed from `llvm-tblgen` command line
+/// arguments.
+///
+/// This allows existing build rules that invoke `llvm-tblgen` to be ported
+/// with minimal changes. The following options are understood:
+///
+/// - `-I <dir>`: adds an include directory
+/// - `-D <name>`: defines a preprocessor macro
+/// - `-o <file>`: sets the output file
+/// - `-d <file>`: sets the dependency file
+/// - `--no-warn-on-unused-template-args`: disables warnings about unused
+///   template arguments
+/// - a positional argument: sets the input file
+///
+/// As in `llvm-tblgen`, options can start with one or two dashes, and values
+/// can be passed as a separate argument, after `=`, or (for `-I` and `-D`)
+/// directly after the option name. All other options (e.g. backend
+/// options such as `-gen-op-decls`) are collected in
+/// [`other_args`](TableGenOptions::other_args).
+///
+/// ```rust
+/// use tblgen::TableGenOptions;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let options = TableGenOptions::from_args(["-I", "include", "-DDEBUG", "Ops.td"])?;
+/// assert_eq!(options.input_filename.as_deref(), Some("Ops.td"));
+/// let parser = options.parser();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableGenOptions {
+    /// The input file (`-` for standard input).
+    pub input_filename: Option<String>,
+    /// The include directories (`-I`).
+    pub include_directories: Vec<String>,
+    /// The defined preprocessor macros (`-D`).
+    pub macros: Vec<String>,
+    /// The output file (`-o`).
+    pub output_filename: Option<String>,
+    /// The dependency file (`-d`).
+    pub dependency_filename: Option<String>,
+    /// Whether warnings about unused template arguments are disabled.
+    pub no_warn_on_unused_template_args: bool,
+    /// All arguments that are not understood, in their original order.
+    pub other_args: Vec<String>,
+}
+
+impl TableGenOptions {
+    /// Parses the given command line arguments, without the program name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an option is missing its value or if more than one
+    /// input file is given.
+    pub fn from_args<I, S>(args: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut options = Self::default();
+        let mut args = args.into_iter().map(Into::into);
+        let mut positional_only = false;
+
+        while let Some(arg) = args.next() {
+            let option = match arg.strip_prefix("--").or_else(|| arg.strip_prefix('-')) {
+                Some(option) if !positional_only && !option.is_empty() => option,
+                _ if !positional_only && arg == "--" => {
+                    positional_only = true;
+                    continue;
+                }
+                _ => {
+                    if options.input_filename.is_some() {
+                        return Err(TableGenError::InvalidArgument(format!(
+                            "more than one input file: {}",
+                            arg
+                        ))
+                        .into());
+                    }
+                    options.input_filename = Some(arg);
+                    continue;
+                }
+            };
+            let (name, value) = match option.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (option, None),
+            };
+
+            let list = match name {
+                "I" => Some(&mut options.include_directories),
+                "D" => Some(&mut options.macros),
+                _ => None,
+            };
+            if let Some(list) = list {
+                list.push(Self::value(&arg, value, &mut args)?);
+                continue;
+            }
+
+            match name {
+                "o" => options.output_filename = Some(Self::value(&arg, value, &mut args)?),
+                "d" => options.dependency_filename = Some(Self::value(&arg, value, &mut args)?),
+                "no-warn-on-unused-template-args" if value.is_none() => {
+                    options.no_warn_on_unused_template_args = true
+                }
+                _ => {
+                    // Prefix options, e.g. `-Iinclude`.
+                    if let Some(dir) = option.strip_prefix('I') {
+                        options.include_directories.push(dir.into());
+                    } else if let Some(macro_name) = option.strip_prefix('D') {
+                        options.macros.push(macro_name.into());
+                    } else {
+                        options.other_args.push(arg);
+                    }
+                }
+            }
+        }
+
+        Ok(options)
+    }
+
+    fn value(
+        arg: &str,
+        value: Option<String>,
+        args: &mut impl Iterator<Item = String>,
+    ) -> Result<String, Error> {
+        value.or_else(|| args.next()).ok_or_else(|| {
+            TableGenError::InvalidArgument(format!("missing value for {}", arg)).into()
+        })
+    }
+
+    /// Creates a [`TableGenParser`] that is configured with these options.
+    ///
+    /// Options that do not affect parsing (e.g. the output file) are ignored.
+    pub fn parser<'s>(&self) -> TableGenParser<'s> {
+        let mut parser = TableGenParser::new()
+            .warn_on_unused_template_args(!self.no_warn_on_unused_template_args);
+        for directory in &self.include_directories {
+            parser = parser.add_include_directory(directory);
+        }
+        for name in &self.macros {
+            parser = parser.define_macro(name);
+        }
+        if let Some(file) = &self.input_filename {
+            parser = parser.add_source_file(file);
+        }
+        parser
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn from_args() {
+        let options = TableGenOptions::from_args([
+            "-I",
+            "a",
+            "-Ib",
+            "--I=c",
+            "-DX",
+            "-D",
+            "Y",
+            "-o",
+            "out.inc",
+            "--d=out.d",
+            "--no-warn-on-unused-template-args",
+            "-gen-op-decls",
+            "Ops.td",
+        ])
+        .unwrap();
+        assert_eq!(
+            options,
+            TableGenOptions {
+                input_filename: Some("Ops.td".into()),
+                include_directories: vec!["a".into(), "b".into(), "c".into()],
+                macros: vec!["X".into(), "Y".into()],
+                output_filename: Some("out.inc".into()),
+                dependency_filename: Some("out.d".into()),
+                no_warn_on_unused_template_args: true,
+                other_args: vec!["-gen-op-decls".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_args() {
+        assert!(TableGenOptions::from_args(["-I"]).is_err());
+        assert!(TableGenOptions::from_args(["A.td", "B.td"]).is_err());
+        let options = TableGenOptions::from_args(["--", "-A.td"]).unwrap();
+        assert_eq!(options.input_filename.as_deref(), Some("-A.td"));
+    }
+
+    #[test]
+    fn parser() {
+        let dir = std::env::temp_dir().join(format!("tblgen-options-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("Options.td"),
+            "#ifdef X\ndef A;\n#else\ndef B;\n#endif\n",
+        )
+        .unwrap();
+
+        let options =
+            TableGenOptions::from_args(["-I", dir.to_str().unwrap(), "-DX", "Options.td"]).unwrap();
+        let rk = options.parser().parse().expect("valid tablegen");
+        assert!(rk.def("A").is_ok());
+        assert!(rk.def("B").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
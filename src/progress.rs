@@ -0,0 +1,324 @@
+use std::{
+    any::Any,
+    ffi::c_void,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::raw::{TableGenParsePhase, TableGenParserRef, tableGenSetProgressCallback};
+
+/// Interval of the heartbeat while TableGen parses the sources.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Phase of a parse operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParsePhase {
+    /// The sources passed to the parser are being loaded.
+    Loading,
+    /// TableGen is parsing the sources.
+    Parsing,
+    /// Parsing has finished (successfully or not).
+    Finished,
+}
+
+/// Progress of a parse operation.
+///
+/// See [`TableGenParser::on_progress`](crate::TableGenParser::on_progress).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseProgress {
+    phase: ParsePhase,
+    buffers: usize,
+    bytes: usize,
+    elapsed: Duration,
+}
+
+impl ParseProgress {
+    /// Returns the current phase.
+    pub fn phase(&self) -> ParsePhase {
+        self.phase
+    }
+
+    /// Returns the number of source buffers that were loaded so far,
+    /// including the prelude.
+    ///
+    /// TableGen does not report the files pulled in by `include` directives
+    /// while it is parsing, so they are only counted once parsing has
+    /// finished. Heartbeats during the [`ParsePhase::Parsing`] phase repeat
+    /// the count from the start of the phase.
+    pub fn buffers(&self) -> usize {
+        self.buffers
+    }
+
+    /// Returns the total size of the loaded source buffers in bytes.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Returns the time since the parse operation started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+type Callback = dyn FnMut(&ParseProgress) + Send;
+
+/// Payload of a panic in a progress callback.
+pub(crate) type Panic = Box<dyn Any + Send>;
+
+/// Shared progress callback of a parser.
+#[derive(Clone)]
+pub(crate) struct ProgressCallback(Arc<Mutex<Callback>>);
+
+/// State of a parse operation that is shared by the TableGen callback and the
+/// heartbeat thread.
+struct ProgressState<'a> {
+    callback: &'a ProgressCallback,
+    start: Instant,
+    // Progress is reported while this lock is held, so that a heartbeat is
+    // never reported after the progress of a later phase.
+    reports: Mutex<Reports>,
+}
+
+#[derive(Default)]
+struct Reports {
+    last: Option<ParseProgress>,
+    finished: bool,
+    panic: Option<Panic>,
+}
+
+impl ProgressState<'_> {
+    /// Reports the given progress, which was reported by TableGen.
+    fn update(&self, progress: ParseProgress) {
+        let mut reports = self.lock();
+        reports.last = Some(progress);
+        reports.finished |= progress.phase == ParsePhase::Finished;
+        self.report(&mut reports, &progress);
+    }
+
+    /// Reports a heartbeat if TableGen is parsing, which repeats the last
+    /// progress with an updated elapsed time.
+    fn heartbeat(&self) {
+        let mut reports = self.lock();
+        if reports.finished {
+            return;
+        }
+        if let Some(progress) = reports.last.filter(|p| p.phase == ParsePhase::Parsing) {
+            let progress = ParseProgress {
+                elapsed: self.start.elapsed(),
+                ..progress
+            };
+            self.report(&mut reports, &progress);
+        }
+    }
+
+    /// Stops the heartbeat.
+    fn finish(&self) {
+        self.lock().finished = true;
+    }
+
+    /// Calls the callback unless it panicked before. A panic is caught, since
+    /// it must not unwind into TableGen, and is resumed once parsing returns.
+    fn report(&self, reports: &mut Reports, progress: &ParseProgress) {
+        if reports.panic.is_some() {
+            return;
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            (self
+                .callback
+                .0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner))(progress)
+        }));
+        reports.panic = result.err();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Reports> {
+        self.reports.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ProgressCallback {
+    pub(crate) fn new(callback: impl FnMut(&ParseProgress) + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(callback)))
+    }
+
+    /// Calls `parse` while reporting the progress of the given parser.
+    ///
+    /// TableGen does not report anything while it is parsing, so a separate
+    /// thread reports a heartbeat until `parse` returns.
+    ///
+    /// If the callback panics, it is not called again and the payload of the
+    /// panic is returned together with the result of `parse`, so that the
+    /// caller can clean up before resuming the panic.
+    ///
+    /// # Safety
+    /// The passed pointer should be a valid parser, which is parsed by
+    /// `parse`.
+    pub(crate) unsafe fn run<T>(
+        &self,
+        parser: TableGenParserRef,
+        parse: impl FnOnce() -> T,
+    ) -> (T, Option<Panic>) {
+        let state = ProgressState {
+            callback: self,
+            start: Instant::now(),
+            reports: Mutex::default(),
+        };
+        let done = AtomicBool::new(false);
+
+        unsafe {
+            tableGenSetProgressCallback(
+                parser,
+                Some(progress_callback),
+                &state as *const _ as *mut c_void,
+            )
+        };
+        let result = thread::scope(|scope| {
+            let heartbeat = scope.spawn(|| {
+                loop {
+                    thread::park_timeout(HEARTBEAT_INTERVAL);
+                    if done.load(Ordering::Acquire) {
+                        break;
+                    }
+                    state.heartbeat();
+                }
+            });
+            let result = parse();
+            state.finish();
+            done.store(true, Ordering::Release);
+            heartbeat.thread().unpark();
+            result
+        });
+        unsafe { tableGenSetProgressCallback(parser, None, std::ptr::null_mut()) };
+
+        (result, state.lock().panic.take())
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_tuple("ProgressCallback")
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for ProgressCallback {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ProgressCallback {}
+
+unsafe extern "C" fn progress_callback(
+    phase: TableGenParsePhase::Type,
+    buffers: usize,
+    bytes: usize,
+    data: *mut c_void,
+) {
+    let state = unsafe { &*(data as *const ProgressState) };
+    let phase = match phase {
+        TableGenParsePhase::TABLEGEN_PARSE_LOADING => ParsePhase::Loading,
+        TableGenParsePhase::TABLEGEN_PARSE_PARSING => ParsePhase::Parsing,
+        _ => ParsePhase::Finished,
+    };
+    let progress = ParseProgress {
+        phase,
+        buffers,
+        bytes,
+        elapsed: state.start.elapsed(),
+    };
+    state.update(progress);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    #[test]
+    fn on_progress() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let rk = TableGenParser::new()
+            .add_source("def A;")
+            .unwrap()
+            .add_source_named("B.td", "def B;")
+            .unwrap()
+            .on_progress({
+                let reports = reports.clone();
+                move |progress| reports.lock().unwrap().push(*progress)
+            })
+            .parse()
+            .expect("valid tablegen");
+        assert!(rk.def("A").is_ok());
+
+        let reports = reports.lock().unwrap();
+        let phases = reports.iter().map(|p| p.phase()).collect::<Vec<_>>();
+        assert_eq!(phases[..2], [ParsePhase::Loading, ParsePhase::Loading]);
+        assert_eq!(phases.last(), Some(&ParsePhase::Finished));
+        assert!(
+            phases[2..phases.len() - 1]
+                .iter()
+                .all(|phase| *phase == ParsePhase::Parsing)
+        );
+
+        let finished = reports.last().unwrap();
+        assert_eq!(finished.buffers(), 2);
+        assert_eq!(finished.bytes(), "def A;".len() + "def B;".len());
+    }
+
+    #[test]
+    fn no_heartbeat_after_finished() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let callback = ProgressCallback::new({
+            let reports = reports.clone();
+            move |progress| reports.lock().unwrap().push(progress.phase())
+        });
+        let state = ProgressState {
+            callback: &callback,
+            start: Instant::now(),
+            reports: Mutex::default(),
+        };
+        let progress = |phase| ParseProgress {
+            phase,
+            buffers: 1,
+            bytes: 0,
+            elapsed: Duration::ZERO,
+        };
+
+        state.heartbeat();
+        state.update(progress(ParsePhase::Loading));
+        state.heartbeat();
+        state.update(progress(ParsePhase::Parsing));
+        state.heartbeat();
+        state.update(progress(ParsePhase::Finished));
+        state.heartbeat();
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [
+                ParsePhase::Loading,
+                ParsePhase::Parsing,
+                ParsePhase::Parsing,
+                ParsePhase::Finished
+            ]
+        );
+    }
+
+    #[test]
+    fn panic_in_callback() {
+        let parser = TableGenParser::new()
+            .add_source("def A;")
+            .unwrap()
+            .on_progress(|_| panic!("progress"));
+        let payload = panic::catch_unwind(AssertUnwindSafe(|| parser.parse()))
+            .expect_err("callback panicked");
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"progress"));
+    }
+}
@@ -0,0 +1,351 @@
+//! Queries that select values of records by paths, e.g. for REPLs and
+//! debugging tools.
+//!
+//! A query is a sequence of steps separated by dots. The first step selects
+//! records: the defs deriving from a class, a single def, or all defs (`*`).
+//! The following steps select
+//!
+//! - fields of records by name, or all fields (`*`),
+//! - elements of lists by index, or all elements (`*`),
+//! - arguments of dags by name or index, or all arguments (`*`).
+//!
+//! Every step can be followed by filters `[field=value]`, which keep the
+//! records whose field is rendered as `value` (strings without quotes).
+//!
+//! ```rust
+//! use tblgen::TableGenParser;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source(
+//!         r#"
+//!         class Op<string d, list<int> s> { string dialect = d; list<int> sizes = s; }
+//!         def AddOp: Op<"arith", [1, 2]>;
+//!         def CallOp: Op<"func", [3]>;
+//!         "#,
+//!     )?
+//!     .parse()?;
+//! let matches = keeper.select("Op[dialect=arith].sizes.*")?;
+//! let paths: Vec<_> = matches.iter().map(|m| m.path()).collect();
+//! assert_eq!(paths, ["AddOp.sizes.0", "AddOp.sizes.1"]);
+//! assert_eq!(matches[1].init().to_string(), "2");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    Error, Record, RecordValue, TypedInit,
+    error::{SourceLoc, SourceLocation, TableGenError},
+    record_keeper::RecordKeeperRef,
+};
+
+/// A value selected by a [`Query`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<'a> {
+    path: String,
+    init: TypedInit<'a>,
+    location: SourceLocation,
+}
+
+impl<'a> Match<'a> {
+    /// Returns the path of the value, e.g. `AddOp.sizes.0`, with the names
+    /// and indices that wildcards matched.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the value.
+    pub fn init(&self) -> TypedInit<'a> {
+        self.init
+    }
+
+    /// Returns the location of the record or field of the value. Elements of
+    /// lists and dags have the location of their field.
+    pub fn location(&self) -> &SourceLocation {
+        &self.location
+    }
+}
+
+impl SourceLoc for &Match<'_> {
+    fn source_location(self) -> SourceLocation {
+        self.location.clone()
+    }
+}
+
+/// Selector of a step of a [`Query`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Selector {
+    Name(String),
+    Index(usize),
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    selector: Selector,
+    filters: Vec<(String, String)>,
+}
+
+/// A parsed query (see the [module documentation](self)).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    steps: Vec<Step>,
+}
+
+impl Query {
+    /// Parses a query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query is empty or has invalid filters.
+    pub fn parse(query: &str) -> Result<Self, Error> {
+        let invalid =
+            |reason: &str| TableGenError::InvalidArgument(format!("query {query:?}: {reason}"));
+        let mut steps = Vec::new();
+        for step in split_steps(query) {
+            let (selector, mut rest) = step.split_at(step.find('[').unwrap_or(step.len()));
+            let selector = match selector.trim() {
+                "" => return Err(invalid("empty step").into()),
+                "*" => Selector::Wildcard,
+                name => match name.parse() {
+                    Ok(index) => Selector::Index(index),
+                    Err(_) => Selector::Name(name.into()),
+                },
+            };
+            let mut filters = Vec::new();
+            while !rest.is_empty() {
+                let end = rest
+                    .find(']')
+                    .filter(|_| rest.starts_with('['))
+                    .ok_or_else(|| invalid("expected [field=value]"))?;
+                let (field, value) = rest[1..end]
+                    .split_once('=')
+                    .ok_or_else(|| invalid("expected = in filter"))?;
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .unwrap_or(value);
+                filters.push((field.trim().into(), value.into()));
+                rest = &rest[end + 1..];
+            }
+            steps.push(Step { selector, filters });
+        }
+        Ok(Self { steps })
+    }
+
+    /// Returns the values of the given record keeper that match this query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the first step is an index, or a name that is
+    /// neither a class nor a def.
+    pub fn select<'a>(&self, keeper: RecordKeeperRef<'a>) -> Result<Vec<Match<'a>>, Error> {
+        let (first, steps) = self.steps.split_first().expect("queries are not empty");
+        let records: Vec<Record<'a>> = match &first.selector {
+            Selector::Wildcard => keeper.defs().map(|(_, def)| def).collect(),
+            Selector::Name(name) => match keeper.all_derived_definitions(name) {
+                Ok(defs) => defs.collect(),
+                Err(_) => vec![keeper.def(name)?],
+            },
+            Selector::Index(_) => {
+                return Err(TableGenError::InvalidArgument(
+                    "queries must start with a class or def".into(),
+                )
+                .into());
+            }
+        };
+        let mut matches: Vec<_> = records
+            .into_iter()
+            .map(|record| Match {
+                path: String::from_utf8_lossy(record.name_bytes()).into_owned(),
+                init: TypedInit::Def(record.def_init()),
+                location: record.source_location(),
+            })
+            .filter(|m| filter(m, &first.filters))
+            .collect();
+        for step in steps {
+            matches = matches
+                .iter()
+                .flat_map(|m| children(m, &step.selector))
+                .filter(|m| filter(m, &step.filters))
+                .collect();
+        }
+        Ok(matches)
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(query: &str) -> Result<Self, Error> {
+        Self::parse(query)
+    }
+}
+
+/// Splits a query at the dots that are not part of a filter.
+fn split_steps(query: &str) -> Vec<&str> {
+    let mut steps = Vec::new();
+    let (mut start, mut depth) = (0, 0);
+    for (index, c) in query.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            '.' if depth == 0 => {
+                steps.push(&query[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    steps.push(&query[start..]);
+    steps
+}
+
+/// Returns true if the value is a record whose fields match all filters.
+fn filter(m: &Match, filters: &[(String, String)]) -> bool {
+    if filters.is_empty() {
+        return true;
+    }
+    let TypedInit::Def(def) = m.init else {
+        return false;
+    };
+    let record = Record::from(def);
+    filters.iter().all(|(field, value)| {
+        record.value(field.as_str()).is_ok_and(|v| match v.init() {
+            TypedInit::String(init) | TypedInit::Code(init) => init.as_bytes() == value.as_bytes(),
+            init => init.to_string() == *value,
+        })
+    })
+}
+
+/// Returns the values that the selector selects in the given value.
+fn children<'a>(m: &Match<'a>, selector: &Selector) -> Vec<Match<'a>> {
+    let child = |segment: &dyn Display, init, location| Match {
+        path: format!("{}.{}", m.path, segment),
+        init,
+        location,
+    };
+    let field = |value: RecordValue<'a>| {
+        child(
+            &String::from_utf8_lossy(value.name().as_bytes()),
+            value.init(),
+            value.source_location(),
+        )
+    };
+    match (m.init, selector) {
+        (TypedInit::Def(def), Selector::Name(name)) => Record::from(def)
+            .value(name.as_str())
+            .map(field)
+            .into_iter()
+            .collect(),
+        (TypedInit::Def(def), Selector::Wildcard) => {
+            Record::from(def).values().map(field).collect()
+        }
+        (TypedInit::List(list), Selector::Index(index)) => list
+            .get(*index)
+            .map(|init| child(index, init, m.location.clone()))
+            .into_iter()
+            .collect(),
+        (TypedInit::List(list), Selector::Wildcard) => list
+            .iter()
+            .enumerate()
+            .map(|(index, init)| child(&index, init, m.location.clone()))
+            .collect(),
+        (TypedInit::Dag(dag), Selector::Name(name)) => dag
+            .arg_no(name)
+            .and_then(|index| dag.get(index))
+            .map(|init| child(name, init, m.location.clone()))
+            .into_iter()
+            .collect(),
+        (TypedInit::Dag(dag), Selector::Index(index)) => dag
+            .get(*index)
+            .map(|init| child(index, init, m.location.clone()))
+            .into_iter()
+            .collect(),
+        (TypedInit::Dag(dag), Selector::Wildcard) => dag
+            .args()
+            .enumerate()
+            .map(|(index, (name, init))| match name {
+                Some(name) => child(&name, init, m.location.clone()),
+                None => child(&index, init, m.location.clone()),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    #[test]
+    fn parse() {
+        let query = Query::parse("Op[dialect=arith][n=\"a.b\"].results.*.0").unwrap();
+        assert_eq!(query.steps.len(), 4);
+        assert_eq!(
+            query.steps[0].filters,
+            [
+                ("dialect".into(), "arith".into()),
+                ("n".into(), "a.b".into())
+            ]
+        );
+        assert_eq!(query.steps[2].selector, Selector::Wildcard);
+        assert_eq!(query.steps[3].selector, Selector::Index(0));
+
+        assert!(Query::parse("").is_err());
+        assert!(Query::parse("Op..x").is_err());
+        assert!(Query::parse("Op[x]").is_err());
+        assert!(Query::parse("Op[x=1").is_err());
+        assert!(Query::parse("Op[x=1]y").is_err());
+    }
+
+    #[test]
+    fn select() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                def ins; def I32 { string type = "i32"; } def F32 { string type = "f32"; }
+                class Op<string d, dag a> { string dialect = d; dag args = a; }
+                def AddOp: Op<"arith", (ins I32:$lhs, F32:$rhs)>;
+                def CallOp: Op<"func", (ins I32)>;
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let paths = |query: &str| -> Vec<String> {
+            rk.select(query)
+                .unwrap()
+                .iter()
+                .map(|m| format!("{}={}", m.path(), m.init()))
+                .collect()
+        };
+        assert_eq!(
+            paths("Op[dialect=arith].args.*.type"),
+            ["AddOp.args.lhs.type=\"i32\"", "AddOp.args.rhs.type=\"f32\""]
+        );
+        assert_eq!(
+            paths("Op.args.0"),
+            ["AddOp.args.0=I32", "CallOp.args.0=I32"]
+        );
+        assert_eq!(paths("CallOp.args.*"), ["CallOp.args.0=I32"]);
+        assert_eq!(paths("*[type=f32]"), ["F32=F32"]);
+        assert_eq!(paths("Op.missing"), Vec::<String>::new());
+        assert!(rk.select("Missing").is_err());
+        assert!(rk.select("0.x").is_err());
+
+        let m = &rk.select("AddOp.dialect").unwrap()[0];
+        assert_eq!(
+            m.location(),
+            &rk.def("AddOp")
+                .unwrap()
+                .value("dialect")
+                .unwrap()
+                .source_location()
+        );
+    }
+}
@@ -9,34 +9,42 @@
 // except according to those terms.
 
 use paste::paste;
-use std::{ffi::c_void, marker::PhantomData};
+use std::{ffi::c_void, marker::PhantomData, path::PathBuf};
 
 use crate::raw::{
     TableGenRecTyKind::TableGenInvalidRecTyKind as RawInvalidRecTyKind, TableGenRecordRef,
-    TableGenRecordValRef, tableGenIntArrayFree, tableGenRecordDump, tableGenRecordGetDefInit,
-    tableGenRecordGetFieldType, tableGenRecordGetFirstValue, tableGenRecordGetID,
+    TableGenRecordValRef, TableGenTypedInitRef, tableGenIntArrayFree, tableGenRecordDump,
+    tableGenRecordGetDefInit, tableGenRecordGetFieldType, tableGenRecordGetID,
     tableGenRecordGetLoc, tableGenRecordGetName, tableGenRecordGetNameInit,
-    tableGenRecordGetNumSuperClasses, tableGenRecordGetNumTemplateArgs,
+    tableGenRecordGetNumSuperClasses, tableGenRecordGetNumTemplateArgs, tableGenRecordGetRecords,
     tableGenRecordGetSuperClass, tableGenRecordGetTemplateArgName, tableGenRecordGetValue,
     tableGenRecordGetValueAsBit, tableGenRecordGetValueAsBitsInit, tableGenRecordGetValueAsDag,
     tableGenRecordGetValueAsDef, tableGenRecordGetValueAsInt, tableGenRecordGetValueAsListInit,
     tableGenRecordGetValueAsListOfDefs, tableGenRecordGetValueAsListOfInts,
     tableGenRecordGetValueAsListOfStrings, tableGenRecordGetValueAsOptionalDef,
     tableGenRecordGetValueAsOptionalString, tableGenRecordGetValueAsString,
-    tableGenRecordHasDirectSuperClass, tableGenRecordIsAnonymous, tableGenRecordIsClass,
-    tableGenRecordIsSubclassOf, tableGenRecordIsValueUnset, tableGenRecordPrint,
-    tableGenRecordRecTyGetClass, tableGenRecordRecTyGetNumClasses, tableGenRecordRecTyIsSubClassOf,
-    tableGenRecordValDump, tableGenRecordValGetBitsWidth, tableGenRecordValGetListElementType,
-    tableGenRecordValGetLoc, tableGenRecordValGetNameInit, tableGenRecordValGetValue,
-    tableGenRecordValIsNonconcreteOK, tableGenRecordValIsTemplateArg, tableGenRecordValNext,
-    tableGenRecordValPrint, tableGenStringRefArrayFree,
+    tableGenRecordGetValueByInit, tableGenRecordGetValues, tableGenRecordGetValuesArray,
+    tableGenRecordGetValuesChunk, tableGenRecordHasDirectSuperClass, tableGenRecordIsAnonymous,
+    tableGenRecordIsClass, tableGenRecordIsSubclassOf, tableGenRecordIsValueUnset,
+    tableGenRecordPrint, tableGenRecordRecTyGetClass, tableGenRecordRecTyGetNumClasses,
+    tableGenRecordRecTyIsSubClassOf, tableGenRecordValDump, tableGenRecordValGetBitsWidth,
+    tableGenRecordValGetListElementType, tableGenRecordValGetLoc, tableGenRecordValGetNameInit,
+    tableGenRecordValGetRecTy, tableGenRecordValGetValue, tableGenRecordValIsNonconcreteOK,
+    tableGenRecordValIsTemplateArg, tableGenRecordValIsUnset, tableGenRecordValPrint,
+    tableGenStringRefArrayFree,
 };
 
 use crate::{
-    error::{Error, SourceLoc, SourceLocation, TableGenError, WithLocation},
-    init::{BitInit, BitsInit, DagInit, DefInit, ListInit, StringInit, TypedInit},
+    SourceInfo,
+    doc::Doc,
+    error::{Error, FieldContext, SourceLoc, SourceLocation, TableGenError, WithLocation},
+    init::{BitInit, BitsInit, DagInit, DefInit, FromTypedInit, ListInit, StringInit, TypedInit},
+    record_keeper::RecordKeeperRef,
+    schema::Schema,
     string_ref::StringRef,
-    util::print_callback,
+    td,
+    ty::RecTy,
+    util::{print_callback, raw_fn, short_type_name},
 };
 use std::fmt::{self, Debug, Display, Formatter};
 
@@ -81,27 +89,63 @@ impl std::hash::Hash for Record<'_> {
     }
 }
 
+impl<'a> IntoIterator for Record<'a> {
+    type Item = RecordValue<'a>;
+    type IntoIter = RecordValueIter<'a>;
+
+    /// Returns an iterator over the fields of the record (see
+    /// [`Record::values`]).
+    fn into_iter(self) -> Self::IntoIter {
+        self.values()
+    }
+}
+
 macro_rules! record_value {
     ($(#[$attr:meta])* $name:ident, $type:ty) => {
         paste! {
             $(#[$attr])*
             pub fn [<$name _value>](self, name: &str) -> Result<$type, Error> {
-                self.value(name)?.try_into()
+                self.value(name)?
+                    .try_into()
+                    .map_err(|e: Error| e.set_field(FieldContext::new(self, name)))
+            }
+        }
+    };
+}
+
+macro_rules! optional_value {
+    ($name:ident, $type:ty) => {
+        paste! {
+            #[doc = "Returns the field with the given name like [`Record::" $name "_value`], or"]
+            #[doc = "`None` if its value is unset (`?`)."]
+            pub fn [<optional_ $name _value>](self, name: &str) -> Result<Option<$type>, Error> {
+                self.value(name)?
+                    .try_into()
+                    .map_err(|e: Error| e.set_field(FieldContext::new(self, name)))
             }
         }
     };
 }
 
 impl<'a> Record<'a> {
-    /// Creates a record from a raw object.
-    ///
-    /// # Safety
-    ///
-    /// The raw object must be valid.
-    pub unsafe fn from_raw(ptr: TableGenRecordRef) -> Record<'a> {
-        Record {
-            raw: ptr,
-            _reference: PhantomData,
+    raw_fn! {
+        /// Creates a record from a raw object.
+        ///
+        /// # Safety
+        ///
+        /// The raw object must be valid.
+        unsafe fn from_raw(ptr: TableGenRecordRef) -> Record<'a> {
+            Record {
+                raw: ptr,
+                _reference: PhantomData,
+            }
+        }
+    }
+
+    raw_fn! {
+        /// Returns the raw object.
+        fn to_raw(self) -> TableGenRecordRef {
+            self.raw
         }
     }
 
@@ -109,7 +153,8 @@ impl<'a> Record<'a> {
     ///
     /// # Errors
     ///
-    /// Returns an error if the name is not a valid UTF-8 string.
+    /// Returns an error if the name is not a valid UTF-8 string. Use
+    /// [`Record::name_bytes`] to get such names.
     pub fn name(self) -> Result<&'a str, Error> {
         unsafe { StringRef::from_raw(tableGenRecordGetName(self.raw)) }
             .try_into()
@@ -117,6 +162,12 @@ impl<'a> Record<'a> {
             .map_err(|e| e.with_location(self))
     }
 
+    /// Returns the name of the record as a slice of bytes, which is not
+    /// required to be valid UTF-8.
+    pub fn name_bytes(self) -> &'a [u8] {
+        unsafe { StringRef::from_raw(tableGenRecordGetName(self.raw)) }.as_bytes()
+    }
+
     record_value!(
         /// Returns the field with the given name converted to a [`Vec<bool>`]
         /// if this field is of type [`BitsInit`](crate::init::BitsInit).
@@ -145,15 +196,118 @@ impl<'a> Record<'a> {
         string,
         String
     );
+    optional_value!(bit, bool);
+    optional_value!(bits, Vec<bool>);
+    optional_value!(int, i64);
+    optional_value!(string, String);
+    optional_value!(code, String);
+    optional_value!(dag, DagInit<'a>);
+    optional_value!(list_init, ListInit<'a>);
+
+    /// Returns the field with the given name converted to `T`.
+    ///
+    /// Unlike the typed accessors (e.g. [`Record::int_value`]), this checks
+    /// the declared type of the field first, so that the error describes the
+    /// mismatch (e.g. "field x declared as list<int>, requested String")
+    /// instead of the kind of the current value.
+    ///
+    /// ```rust
+    /// use tblgen::{TableGenParser, init::ListInit};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let keeper = TableGenParser::new()
+    ///     .add_source("def A { int i = 1; list<int> l = [1, 2]; }")?
+    ///     .parse()?;
+    /// let a = keeper.def("A")?;
+    /// assert_eq!(a.get::<i64>("i")?, 1);
+    /// assert_eq!(a.get::<ListInit>("l")?.len(), 2);
+    /// assert_eq!(
+    ///     a.get::<String>("l").unwrap_err().to_string(),
+    ///     "1:30: field l declared as list<int>, requested String"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get<T: FromTypedInit<'a>>(self, name: &str) -> Result<T, Error> {
+        self.convert_value(name, self.value(name)?)
+    }
+
+    /// Converts the given field of this record to `T` like [`Record::get`].
+    pub(crate) fn convert_value<T: FromTypedInit<'a>>(
+        self,
+        name: &str,
+        value: RecordValue<'a>,
+    ) -> Result<T, Error> {
+        match value.rec_ty() {
+            Some(ty) if !T::accepts(ty) => Err(TableGenError::FieldTypeMismatch {
+                field: name.into(),
+                declared: ty.to_string(),
+                requested: short_type_name::<T>(),
+            }
+            .with_location(value)),
+            _ => T::from_typed_init(value.init()).map_err(|e| {
+                e.set_location(value)
+                    .set_field(FieldContext::new(self, name))
+            }),
+        }
+    }
 
     /// Returns a [`RecordValue`] for the field with the given name.
-    pub fn value<'n>(self, name: &'n str) -> Result<RecordValue<'a>, Error> {
-        let value = unsafe { tableGenRecordGetValue(self.raw, StringRef::from(name).to_raw()) };
+    ///
+    /// The name is a string or a [`FieldKey`], which is faster to look up
+    /// repeatedly.
+    pub fn value(self, name: impl FieldName) -> Result<RecordValue<'a>, Error> {
+        let value = name.lookup(self);
         if !value.is_null() {
             Ok(unsafe { RecordValue::from_raw(value) })
         } else {
-            Err(TableGenError::MissingValue(String::from(name)).with_location(self))
+            Err(TableGenError::MissingValue(String::from(name.field_name())).with_location(self))
+        }
+    }
+
+    /// Returns [`RecordValue`]s for the fields with the given names, in the
+    /// same order.
+    ///
+    /// All fields are looked up with a single call into TableGen, which is
+    /// faster than calling [`Record::value`] for each field when extracting
+    /// many fields from many records.
+    pub fn values_for(self, names: &[&str]) -> Result<Vec<RecordValue<'a>>, Error> {
+        let raw_names = names
+            .iter()
+            .map(|&name| unsafe { StringRef::from(name).to_raw() })
+            .collect::<Vec<_>>();
+        let mut values = vec![std::ptr::null_mut(); names.len()];
+        let mut name_inits = vec![std::ptr::null_mut(); names.len()];
+        let mut inits = vec![std::ptr::null_mut(); names.len()];
+        let found = unsafe {
+            tableGenRecordGetValues(
+                self.raw,
+                raw_names.as_ptr(),
+                names.len(),
+                values.as_mut_ptr(),
+                name_inits.as_mut_ptr(),
+                inits.as_mut_ptr(),
+            )
+        };
+        if found == 0 {
+            // The shim leaves the values of missing fields null.
+            let name = values
+                .iter()
+                .position(|value| value.is_null())
+                .map_or("", |index| names[index]);
+            return Err(TableGenError::MissingValue(name.into()).with_location(self));
         }
+        Ok(values
+            .into_iter()
+            .zip(name_inits)
+            .zip(inits)
+            .map(|((value, name), init)| unsafe { RecordValue::from_raw_parts(value, name, init) })
+            .collect())
+    }
+
+    /// Returns the record keeper that owns this record.
+    pub fn record_keeper(self) -> RecordKeeperRef<'a> {
+        unsafe { RecordKeeperRef::from_raw(tableGenRecordGetRecords(self.raw)) }
     }
 
     /// Returns true if the record is anonymous.
@@ -174,6 +328,26 @@ impl<'a> Record<'a> {
         RecordValueIter::new(self)
     }
 
+    /// Returns a cursor over the raw fields of the record, for consumers
+    /// with extreme throughput needs.
+    ///
+    /// Unlike [`Record::values`], the cursor does not wrap the fields and
+    /// only calls into TableGen once. The raw fields can be passed to the
+    /// functions of the [`raw`](crate::raw) module or wrapped with
+    /// [`RecordValue::from_raw`].
+    pub fn raw_cursor(self) -> RawFieldCursor<'a> {
+        let mut remaining = 0;
+        let mut stride = 0;
+        let current =
+            unsafe { tableGenRecordGetValuesArray(self.raw, &mut remaining, &mut stride) };
+        RawFieldCursor {
+            current,
+            remaining,
+            stride,
+            _reference: PhantomData,
+        }
+    }
+
     /// Returns `true` if the record has a field with the given name.
     pub fn has_field(self, name: &str) -> bool {
         let v = unsafe { tableGenRecordGetValue(self.raw, StringRef::from(name).to_raw()) };
@@ -270,6 +444,24 @@ impl<'a> Record<'a> {
         }
     }
 
+    /// Returns the string or code value of the field with the given name as
+    /// a slice of bytes.
+    ///
+    /// Unlike [`Record::str_value`], this does not fail if the value is not
+    /// valid UTF-8 (e.g. if it contains escaped raw bytes).
+    pub fn string_bytes_value(self, name: &str) -> Result<&'a [u8], Error> {
+        let value = self.value(name)?;
+        match value.init() {
+            TypedInit::String(init) | TypedInit::Code(init) => Ok(init.as_bytes()),
+            init => Err(TableGenError::InitConversion {
+                from: init.variant_name(),
+                to: std::any::type_name::<&[u8]>(),
+            }
+            .with_location(value)
+            .set_field(FieldContext::new(self, name))),
+        }
+    }
+
     /// Returns the bit (boolean) value of the field with the given name.
     pub fn bit_value(self, name: &str) -> Result<bool, Error> {
         let mut out: i8 = 0;
@@ -480,6 +672,298 @@ impl<'a> Record<'a> {
     pub fn type_is_subclass_of(self, class: Record<'a>) -> bool {
         unsafe { tableGenRecordRecTyIsSubClassOf(self.raw, class.raw) > 0 }
     }
+
+    /// Returns the multiclass that defined this record, if it was instantiated
+    /// by a `defm`.
+    ///
+    /// If the record was defined by a multiclass that was instantiated in
+    /// another multiclass, the innermost multiclass and `defm` are returned.
+    ///
+    /// TableGen does not keep multiclasses after parsing, but it appends the
+    /// location of the multiclass in each `defm` to the locations of the
+    /// records that the `defm` instantiates. The origin is read from these
+    /// locations.
+    pub fn defining_multiclass(self) -> Option<MulticlassOrigin> {
+        let location = self.source_location();
+        if location.len() < 2 {
+            return None;
+        }
+        let name = identifier_at(&location, 1)?;
+        let defm_name = name_template_at(&location, 0)
+            .and_then(|template| resolve_name(&template, self.name_bytes()));
+        Some(MulticlassOrigin {
+            name: String::from_utf8_lossy(&name).into_owned(),
+            defm_name: defm_name.map(|name| String::from_utf8_lossy(name).into_owned()),
+            defm_location: location.get(1),
+        })
+    }
+
+    /// Returns the path of the file that defined this record, as it was
+    /// resolved by TableGen.
+    ///
+    /// The record must belong to the given [`SourceInfo`]. For records that
+    /// were defined in an in-memory source, the name of that source is
+    /// returned (see
+    /// [`TableGenParser::add_source_named`](crate::TableGenParser::add_source_named)).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record does not belong to the given source info.
+    pub fn source_file(self, info: &SourceInfo) -> Result<PathBuf, Error> {
+        let location = self.source_location();
+        let Some(name) = location.buffer_name_in(info.0) else {
+            return Err(TableGenError::InvalidSourceLocation.with_location(location));
+        };
+        let name = str::from_utf8(name).map_err(|e| TableGenError::from(e).with_location(self))?;
+        Ok(PathBuf::from(name))
+    }
+
+    /// Returns TableGen source code that declares this record.
+    ///
+    /// Defs are declared with their direct super classes and all of their
+    /// resolved fields. Classes are declared with their direct super classes,
+    /// but without fields or template arguments. The records referenced by
+    /// this record are not declared (see [`RecordKeeper::to_td`]).
+    ///
+    /// [`RecordKeeper::to_td`]: crate::RecordKeeper::to_td
+    pub fn to_td(self) -> Result<String, Error> {
+        let mut output = String::new();
+        td::record(&mut output, self)?;
+        Ok(output)
+    }
+
+    /// Returns a description of the fields of this record, with their
+    /// declared types and default values.
+    pub fn schema(self) -> Result<Schema<'a>, Error> {
+        Schema::new(self)
+    }
+
+    /// Returns the documentation of this record from its `summary` and
+    /// `description` fields, which may be missing or unset.
+    ///
+    /// The description is dedented, so that code blocks (`[{ ... }]`) that
+    /// are indented like the surrounding code can be rendered as Markdown.
+    /// See the [`doc`](crate::doc) module.
+    pub fn doc(self) -> Result<Doc, Error> {
+        let field = |name| {
+            if self.has_field(name) {
+                self.optional_str_value(name)
+            } else {
+                Ok(None)
+            }
+        };
+        Ok(Doc::new(field("summary")?, field("description")?))
+    }
+}
+
+/// Trait for Rust types that can be obtained from a [`Record`], e.g. to map
+/// the defs deriving from a class onto structs.
+///
+/// With the `derive` feature, the trait can be derived for structs with named
+/// fields, which are then read with [`Record::get`]:
+///
+/// ```ignore
+/// use tblgen::FromRecord;
+///
+/// #[derive(FromRecord)]
+/// struct Op<'a> {
+///     name: &'a str,
+///     // Reads the `summary` field.
+///     #[tblgen(field = "summary")]
+///     description: String,
+///     // Defaults to 0 if the field is missing or unset.
+///     #[tblgen(default)]
+///     size: i64,
+/// }
+/// ```
+pub trait FromRecord<'a>: Sized {
+    /// Converts the given record to this type.
+    fn from_record(record: Record<'a>) -> Result<Self, Error>;
+}
+
+impl<'a> FromRecord<'a> for Record<'a> {
+    fn from_record(record: Record<'a>) -> Result<Self, Error> {
+        Ok(record)
+    }
+}
+
+/// The multiclass that defined a record and the `defm` that instantiated it.
+///
+/// See [`Record::defining_multiclass`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MulticlassOrigin {
+    name: String,
+    defm_name: Option<String>,
+    defm_location: SourceLocation,
+}
+
+impl MulticlassOrigin {
+    /// Returns the name of the multiclass.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the name of the `defm` with `NAME` resolved, e.g. `X` for the
+    /// def `X_a` defined as `def _a` in the multiclass.
+    ///
+    /// The name is derived from the name template of the def in the
+    /// multiclass, e.g. `X` for the def `X_y` defined as `def NAME # "_y"`.
+    /// Identifiers other than `NAME` in the template are read as literal
+    /// text. Returns `None` if the template is not made of identifiers and
+    /// strings pasted with `#` (e.g. `def !strconcat(NAME, "_y")`), or if the
+    /// name of the def does not match it.
+    pub fn defm_name(&self) -> Option<&str> {
+        self.defm_name.as_deref()
+    }
+
+    /// Returns the location of the multiclass in the `defm` that instantiated
+    /// the record (e.g. of `M` in `defm X : M;`).
+    pub fn defm_location(&self) -> &SourceLocation {
+        &self.defm_location
+    }
+}
+
+/// Returns the identifier at the location with the given index, which is
+/// empty if the location does not point at an identifier.
+fn identifier_at(location: &SourceLocation, index: usize) -> Option<Vec<u8>> {
+    location
+        .read_buffer(index, |_, buffer, offset| {
+            let rest = buffer.get(offset..)?;
+            let len = rest
+                .iter()
+                .position(|&c| !(c.is_ascii_alphanumeric() || c == b'_'))
+                .unwrap_or(rest.len());
+            Some(rest[..len].to_vec())
+        })
+        .flatten()
+}
+
+/// A piece of the name template of a def in a multiclass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NamePiece {
+    /// A reference to `NAME`, the name of the `defm`.
+    Name,
+    Text(Vec<u8>),
+}
+
+/// Returns the name template of the def at the location with the given index,
+/// i.e. the identifiers and strings pasted with `#` after the `def` keyword.
+///
+/// Returns `None` if the name is not made of such pieces.
+fn name_template_at(location: &SourceLocation, index: usize) -> Option<Vec<NamePiece>> {
+    fn skip_whitespace(rest: &[u8]) -> &[u8] {
+        let len = rest
+            .iter()
+            .position(|c| !c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        &rest[len..]
+    }
+
+    fn identifier(rest: &[u8]) -> (&[u8], &[u8]) {
+        let len = rest
+            .iter()
+            .position(|&c| !(c.is_ascii_alphanumeric() || c == b'_'))
+            .unwrap_or(rest.len());
+        rest.split_at(len)
+    }
+
+    fn piece(rest: &[u8]) -> Option<(NamePiece, &[u8])> {
+        if let Some(mut rest) = rest.strip_prefix(b"\"") {
+            let mut text = Vec::new();
+            loop {
+                let (&c, next) = rest.split_first()?;
+                rest = next;
+                match c {
+                    b'"' => return Some((NamePiece::Text(text), rest)),
+                    b'\\' => {
+                        let (&escaped, next) = rest.split_first()?;
+                        rest = next;
+                        text.push(match escaped {
+                            b'n' => b'\n',
+                            b't' => b'\t',
+                            b'\\' | b'\'' | b'"' => escaped,
+                            _ => return None,
+                        });
+                    }
+                    b'\n' => return None,
+                    _ => text.push(c),
+                }
+            }
+        }
+        match identifier(rest) {
+            (b"", _) => None,
+            (b"NAME", rest) => Some((NamePiece::Name, rest)),
+            (text, rest) => Some((NamePiece::Text(text.to_vec()), rest)),
+        }
+    }
+
+    location
+        .read_buffer(index, |_, buffer, offset| {
+            // The location points at the name of the def if it is an
+            // identifier, and at the `def` keyword otherwise.
+            let mut rest = buffer.get(offset..)?;
+            if let (b"def", next) = identifier(rest) {
+                rest = next;
+            }
+            let mut template = Vec::new();
+            loop {
+                let (piece, next) = piece(skip_whitespace(rest))?;
+                template.push(piece);
+                rest = skip_whitespace(next);
+                match rest.strip_prefix(b"#") {
+                    Some(next) => rest = next,
+                    None => return Some(template),
+                }
+            }
+        })
+        .flatten()
+}
+
+/// Returns the value of `NAME` for which the given name template resolves to
+/// the given name.
+///
+/// Like TableGen, templates that do not refer to `NAME` are prefixed with it.
+fn resolve_name<'n>(template: &[NamePiece], name: &'n [u8]) -> Option<&'n [u8]> {
+    let prefixed;
+    let template = if template.contains(&NamePiece::Name) {
+        template
+    } else {
+        prefixed = [&[NamePiece::Name], template].concat();
+        &prefixed
+    };
+    let count = template
+        .iter()
+        .filter(|piece| **piece == NamePiece::Name)
+        .count();
+    let text_len = template
+        .iter()
+        .map(|piece| match piece {
+            NamePiece::Name => 0,
+            NamePiece::Text(text) => text.len(),
+        })
+        .sum::<usize>();
+    let name_len = name.len().checked_sub(text_len)?;
+    if name_len % count != 0 {
+        return None;
+    }
+    let name_len = name_len / count;
+
+    let mut defm_name = None;
+    let mut rest = name;
+    for piece in template {
+        match piece {
+            NamePiece::Name => {
+                let (value, next) = rest.split_at(name_len);
+                if defm_name.is_some_and(|defm_name| defm_name != value) {
+                    return None;
+                }
+                defm_name = Some(value);
+                rest = next;
+            }
+            NamePiece::Text(text) => rest = rest.strip_prefix(text.as_slice())?,
+        }
+    }
+    defm_name
 }
 
 impl SourceLoc for Record<'_> {
@@ -494,7 +978,21 @@ macro_rules! try_into {
             type Error = Error;
 
             fn try_from(record_value: RecordValue<'a>) -> Result<Self, Self::Error> {
-                Self::try_from(record_value.init).map_err(|e| e.set_location(record_value))
+                Self::try_from(record_value.init()).map_err(|e| e.set_location(record_value))
+            }
+        }
+
+        impl<'a> TryFrom<RecordValue<'a>> for Option<$type> {
+            type Error = Error;
+
+            /// Converts the value like the conversion to the inner type, but
+            /// returns `None` if the value is unset (`?`).
+            fn try_from(record_value: RecordValue<'a>) -> Result<Self, Self::Error> {
+                if record_value.is_unset() {
+                    Ok(None)
+                } else {
+                    record_value.try_into().map(Some)
+                }
             }
         }
     };
@@ -512,21 +1010,142 @@ try_into!(&'a str);
 
 impl<'a> From<RecordValue<'a>> for TypedInit<'a> {
     fn from(value: RecordValue<'a>) -> Self {
-        value.init
+        value.init()
+    }
+}
+
+/// Name of a field to look up with [`Record::value`], i.e. a string or a
+/// [`FieldKey`].
+pub trait FieldName {
+    #[doc(hidden)]
+    fn lookup(&self, record: Record) -> TableGenRecordValRef;
+
+    #[doc(hidden)]
+    fn field_name(&self) -> &str;
+}
+
+impl FieldName for str {
+    fn lookup(&self, record: Record) -> TableGenRecordValRef {
+        unsafe { tableGenRecordGetValue(record.raw, StringRef::from(self).to_raw()) }
+    }
+
+    fn field_name(&self) -> &str {
+        self
+    }
+}
+
+impl FieldName for String {
+    fn lookup(&self, record: Record) -> TableGenRecordValRef {
+        self.as_str().lookup(record)
+    }
+
+    fn field_name(&self) -> &str {
+        self
+    }
+}
+
+impl<T: FieldName + ?Sized> FieldName for &T {
+    fn lookup(&self, record: Record) -> TableGenRecordValRef {
+        (**self).lookup(record)
+    }
+
+    fn field_name(&self) -> &str {
+        (**self).field_name()
+    }
+}
+
+/// Name of a field that is converted for TableGen once, so that looking it
+/// up in thousands of records with [`Record::value`] does not convert it
+/// for every lookup.
+///
+/// A key is created with [`RecordKeeper::field_key`](crate::RecordKeeper::field_key)
+/// and only finds fields in the records of that record keeper.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FieldKey<'a> {
+    name: &'a str,
+    init: TableGenTypedInitRef,
+}
+
+impl<'a> FieldKey<'a> {
+    raw_fn! {
+        /// Creates a key from the `StringInit` of the name.
+        ///
+        /// # Safety
+        ///
+        /// The raw object must be a valid `StringInit` of a valid UTF-8 string.
+        unsafe fn from_raw(init: TableGenTypedInitRef) -> Self {
+            Self {
+                name: unsafe { StringInit::from_raw(init) }
+                    .to_str()
+                    .unwrap_or_default(),
+                init,
+            }
+        }
+    }
+
+    raw_fn! {
+        /// Returns the raw `StringInit` of the name.
+        fn to_raw(self) -> TableGenTypedInitRef {
+            self.init
+        }
+    }
+
+    /// Returns the name of the field.
+    pub fn name(self) -> &'a str {
+        self.name
+    }
+}
+
+impl Debug for FieldKey<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.debug_tuple("FieldKey").field(&self.name).finish()
+    }
+}
+
+impl FieldName for FieldKey<'_> {
+    fn lookup(&self, record: Record) -> TableGenRecordValRef {
+        unsafe { tableGenRecordGetValueByInit(record.raw, self.init) }
+    }
+
+    fn field_name(&self) -> &str {
+        self.name
     }
 }
 
 /// Struct that represents a field of a [`Record`].
 ///
 /// Can be converted into a Rust type using the [`TryInto`] trait.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// The name and value of the field are only wrapped when they are accessed,
+/// so that e.g. filtering fields by name does not query their values.
+#[derive(Clone, Copy)]
 pub struct RecordValue<'a> {
     raw: TableGenRecordValRef,
-    pub name: StringInit<'a>,
-    pub init: TypedInit<'a>,
+    /// The name, or null if it has not been fetched yet.
+    name_raw: TableGenTypedInitRef,
+    /// The value, or null if it has not been fetched yet (or is unset).
+    init_raw: TableGenTypedInitRef,
     _reference: PhantomData<&'a TableGenRecordRef>,
 }
 
+impl Debug for RecordValue<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("RecordValue")
+            .field("name", &self.name())
+            .field("init", &self.init())
+            .finish()
+    }
+}
+
+impl PartialEq for RecordValue<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl Eq for RecordValue<'_> {}
+
 impl Display for RecordValue<'_> {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         let mut data = (formatter, Ok(()));
@@ -549,23 +1168,65 @@ impl std::hash::Hash for RecordValue<'_> {
     }
 }
 
-impl RecordValue<'_> {
-    /// Creates a record from a raw object.
+impl<'a> RecordValue<'a> {
+    raw_fn! {
+        /// Creates a record from a raw object.
+        ///
+        /// # Safety
+        ///
+        /// The raw object must be valid.
+        unsafe fn from_raw(ptr: TableGenRecordValRef) -> Self {
+            unsafe { Self::from_raw_parts(ptr, std::ptr::null_mut(), std::ptr::null_mut()) }
+        }
+    }
+
+    raw_fn! {
+        /// Returns the raw object.
+        fn to_raw(self) -> TableGenRecordValRef {
+            self.raw
+        }
+    }
+
+    /// Creates a record value from a raw object and its already fetched name
+    /// and value, which are fetched on access if null.
     ///
     /// # Safety
     ///
-    /// The raw object must be valid.
-    pub unsafe fn from_raw(ptr: TableGenRecordValRef) -> Self {
-        let name = unsafe { StringInit::from_raw(tableGenRecordValGetNameInit(ptr)) };
-        let value = unsafe { TypedInit::from_raw(tableGenRecordValGetValue(ptr)) };
+    /// The raw objects must be valid, and the name and value must be those
+    /// of the record value.
+    pub(crate) unsafe fn from_raw_parts(
+        ptr: TableGenRecordValRef,
+        name: TableGenTypedInitRef,
+        init: TableGenTypedInitRef,
+    ) -> Self {
         Self {
-            name,
-            init: value,
             raw: ptr,
+            name_raw: name,
+            init_raw: init,
             _reference: PhantomData,
         }
     }
 
+    /// Returns the name of the field.
+    pub fn name(self) -> StringInit<'a> {
+        let raw = if self.name_raw.is_null() {
+            unsafe { tableGenRecordValGetNameInit(self.raw) }
+        } else {
+            self.name_raw
+        };
+        unsafe { StringInit::from_raw(raw) }
+    }
+
+    /// Returns the value of the field.
+    pub fn init(self) -> TypedInit<'a> {
+        let raw = if self.init_raw.is_null() {
+            unsafe { tableGenRecordValGetValue(self.raw) }
+        } else {
+            self.init_raw
+        };
+        unsafe { TypedInit::from_raw(raw) }
+    }
+
     /// Dumps this record value to stderr (for debugging).
     pub fn dump(self) {
         unsafe { tableGenRecordValDump(self.raw) }
@@ -581,12 +1242,50 @@ impl RecordValue<'_> {
         unsafe { tableGenRecordValIsNonconcreteOK(self.raw) > 0 }
     }
 
+    /// Returns true if the value is unset (`?`), including bits whose bits
+    /// are all unset.
+    pub fn is_unset(self) -> bool {
+        unsafe { tableGenRecordValIsUnset(self.raw) > 0 }
+    }
+
     /// If this field is bits-typed, returns the bit width.
     pub fn bits_width(self) -> Option<usize> {
         let w = unsafe { tableGenRecordValGetBitsWidth(self.raw) };
         if w == 0 { None } else { Some(w) }
     }
 
+    /// Returns the declared type of this field.
+    pub fn rec_ty(self) -> Option<RecTy<'a>> {
+        unsafe { RecTy::from_raw(tableGenRecordValGetRecTy(self.raw)) }
+    }
+
+    /// Returns the elements of this list field converted to `T`.
+    ///
+    /// Unlike converting the elements of the [`ListInit`] directly, errors
+    /// include the location of this field.
+    pub fn list_elements<T: FromTypedInit<'a>>(self) -> Result<Vec<T>, Error> {
+        ListInit::try_from(self)?
+            .iter()
+            .map(|element| T::from_typed_init(element).map_err(|e| e.set_location(self)))
+            .collect()
+    }
+
+    /// Returns the arguments of this dag field converted to `T`, together with
+    /// their names.
+    ///
+    /// Unlike converting the arguments of the [`DagInit`] directly, errors
+    /// include the location of this field.
+    pub fn dag_args<T: FromTypedInit<'a>>(self) -> Result<Vec<(Option<&'a str>, T)>, Error> {
+        DagInit::try_from(self)?
+            .args()
+            .map(|(name, arg)| {
+                T::from_typed_init(arg)
+                    .map(|arg| (name, arg))
+                    .map_err(|e| e.set_location(self))
+            })
+            .collect()
+    }
+
     /// If this field is list-typed, returns the element type kind.
     pub fn list_element_type(self) -> Option<crate::raw::TableGenRecTyKind::Type> {
         let k = unsafe { tableGenRecordValGetListElementType(self.raw) };
@@ -605,21 +1304,60 @@ impl SourceLoc for RecordValue<'_> {
 }
 
 /// Iterator over the fields of a [`Record`].
+///
+/// Fields are fetched in chunks together with their names and values, so
+/// that iterating over all fields of a record does not cross the FFI
+/// boundary for every field.
 #[derive(Debug, Clone)]
 pub struct RecordValueIter<'a> {
     record: TableGenRecordRef,
-    current: TableGenRecordValRef,
+    offset: usize,
+    values: Vec<TableGenRecordValRef>,
+    name_inits: Vec<TableGenTypedInitRef>,
+    inits: Vec<TableGenTypedInitRef>,
+    index: usize,
     _reference: PhantomData<&'a TableGenRecordRef>,
 }
 
 impl<'a> RecordValueIter<'a> {
+    const CHUNK_SIZE: usize = 64;
+
     fn new(record: Record<'a>) -> RecordValueIter<'a> {
+        RecordValueIter {
+            record: record.raw,
+            offset: 0,
+            values: Vec::new(),
+            name_inits: Vec::new(),
+            inits: Vec::new(),
+            index: 0,
+            _reference: PhantomData,
+        }
+    }
+
+    /// Fetches the next chunk of fields, and returns false if there are no
+    /// fields left.
+    fn fetch(&mut self) -> bool {
+        self.values.clear();
+        self.name_inits.clear();
+        self.inits.clear();
+        self.values.reserve(Self::CHUNK_SIZE);
+        self.name_inits.reserve(Self::CHUNK_SIZE);
+        self.inits.reserve(Self::CHUNK_SIZE);
+        self.index = 0;
         unsafe {
-            RecordValueIter {
-                record: record.raw,
-                current: tableGenRecordGetFirstValue(record.raw),
-                _reference: PhantomData,
-            }
+            let count = tableGenRecordGetValuesChunk(
+                self.record,
+                self.offset,
+                self.values.as_mut_ptr(),
+                self.name_inits.as_mut_ptr(),
+                self.inits.as_mut_ptr(),
+                Self::CHUNK_SIZE,
+            );
+            self.values.set_len(count);
+            self.name_inits.set_len(count);
+            self.inits.set_len(count);
+            self.offset += count;
+            count > 0
         }
     }
 }
@@ -628,17 +1366,57 @@ impl<'a> Iterator for RecordValueIter<'a> {
     type Item = RecordValue<'a>;
 
     fn next(&mut self) -> Option<RecordValue<'a>> {
-        if self.current.is_null() {
+        if self.index == self.values.len() && !self.fetch() {
             return None;
         }
-        let res = unsafe { RecordValue::from_raw(self.current) };
-        self.current = unsafe { tableGenRecordValNext(self.record, self.current) };
-        Some(res)
+        let index = self.index;
+        self.index += 1;
+        Some(unsafe {
+            RecordValue::from_raw_parts(
+                self.values[index],
+                self.name_inits[index],
+                self.inits[index],
+            )
+        })
     }
 }
 
 impl std::iter::FusedIterator for RecordValueIter<'_> {}
 
+/// Cursor over the raw fields of a [`Record`].
+///
+/// The fields of a record are stored in an array, which the cursor walks
+/// without calling into TableGen. See [`Record::raw_cursor`].
+#[derive(Debug, Clone)]
+pub struct RawFieldCursor<'a> {
+    current: TableGenRecordValRef,
+    remaining: usize,
+    stride: usize,
+    _reference: PhantomData<&'a TableGenRecordRef>,
+}
+
+impl Iterator for RawFieldCursor<'_> {
+    type Item = TableGenRecordValRef;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let current = self.current;
+        self.current = current.wrapping_byte_add(self.stride);
+        self.remaining -= 1;
+        Some(current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for RawFieldCursor<'_> {}
+
+impl std::iter::FusedIterator for RawFieldCursor<'_> {}
+
 /// Iterator over the template argument names of a [`Record`].
 #[derive(Debug, Clone)]
 pub struct TemplateArgIter<'a> {
@@ -734,7 +1512,558 @@ impl std::iter::FusedIterator for SuperClassIter<'_> {}
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::TableGenParser;
+    use crate::{
+        TableGenParser,
+        error::{
+            Conversion, ErrorCategory, ErrorCode, ErrorCollector, LookupError, ParseError,
+            SourceError, SourceLocation, SourceLocationError, TypeMismatchError,
+        },
+        raw::tableGenSourceLocationClone,
+        record_keeper::RecordKeeperRef,
+        ty::RecTy,
+    };
+
+    #[test]
+    fn defining_multiclass() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                multiclass M<int n = 1> {
+                  def _a { string s = "}"; }
+                }
+                multiclass N {
+                  defm _b : M;
+                }
+                multiclass P {
+                  def NAME;
+                  def "x" # NAME;
+                }
+                multiclass Q<string s> {
+                  def NAME # "_x";
+                  def NAME#_y;
+                  def NAME # s # NAME;
+                  def !strconcat(NAME, "_z");
+                }
+                defm X : M;
+                defm Y : N;
+                defm W : P;
+                defm V : Q<"_s">;
+                def Z;
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let origin = |name| rk.def(name).unwrap().defining_multiclass().unwrap();
+        assert_eq!(origin("X_a").name(), "M");
+        assert_eq!(origin("X_a").defm_name(), Some("X"));
+        assert_eq!(origin("Y_b_a").name(), "M");
+        assert_eq!(origin("Y_b_a").defm_name(), Some("Y_b"));
+        assert_eq!(origin("W").name(), "P");
+        assert_eq!(origin("W").defm_name(), Some("W"));
+        assert_eq!(origin("xW").defm_name(), Some("W"));
+        assert_eq!(origin("V_x").defm_name(), Some("V"));
+        assert_eq!(origin("V_y").defm_name(), Some("V"));
+        assert_eq!(origin("V_sV").defm_name(), None);
+        assert_eq!(origin("V_z").name(), "Q");
+        assert_eq!(origin("V_z").defm_name(), None);
+        let defm = origin("X_a").defm_location().clone();
+        assert_eq!(identifier_at(&defm, 0), Some(b"M".to_vec()));
+        assert_eq!(rk.def("Z").unwrap().defining_multiclass(), None);
+    }
+
+    #[test]
+    fn get() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class C;
+                def D: C;
+                def A {
+                    bit b = 1;
+                    bits<2> bs = 0b10;
+                    int i = 3;
+                    string s = "s";
+                    list<int> l = [1];
+                    dag d = (ins);
+                    C r = D;
+                    int u = ?;
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        assert_eq!(a.get::<bool>("b"), Ok(true));
+        assert_eq!(a.get::<Vec<bool>>("bs"), Ok(vec![false, true]));
+        assert_eq!(a.get::<i64>("i"), Ok(3));
+        assert_eq!(a.get::<&str>("s"), Ok("s"));
+        assert_eq!(a.get::<ListInit>("l").map(ListInit::len), Ok(1));
+        assert!(a.get::<DagInit>("d").is_ok());
+        assert_eq!(a.get::<Record>("r"), Ok(rk.def("D").unwrap()));
+        assert!(matches!(a.get::<TypedInit>("i"), Ok(TypedInit::Int(_))));
+
+        let error = a.get::<String>("l").unwrap_err();
+        assert_eq!(
+            error.error(),
+            &TableGenError::FieldTypeMismatch {
+                field: "l".into(),
+                declared: "list<int>".into(),
+                requested: "String".into(),
+            }
+        );
+        assert!(a.get::<bool>("i").is_err());
+        assert!(a.get::<i64>("u").is_err());
+        assert!(a.get::<i64>("x").is_err());
+    }
+
+    #[test]
+    fn field_key() {
+        let rk = TableGenParser::new()
+            .add_source("def A { int a = 1; } def B { int a = 2; int b = 3; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let key = rk.field_key("a");
+        assert_eq!(key.name(), "a");
+        assert_eq!(rk.field_key("a"), key);
+        for (_, def) in rk.defs() {
+            assert_eq!(def.value(key), def.value("a"));
+        }
+        let missing = rk.field_key("b");
+        assert_eq!(
+            rk.def("A").unwrap().value(missing).unwrap_err().error(),
+            &TableGenError::MissingValue("b".into())
+        );
+        assert!(rk.def("B").unwrap().value(missing).is_ok());
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        let rk = TableGenParser::new()
+            .add_source("def A { int a = 1; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let keeper = rk.as_keeper_ref();
+        assert_eq!(
+            unsafe { RecordKeeperRef::from_raw(keeper.to_raw()) },
+            keeper
+        );
+        let a = rk.def("A").unwrap();
+        assert_eq!(unsafe { Record::from_raw(a.to_raw()) }, a);
+        let value = a.value("a").unwrap();
+        assert_eq!(unsafe { RecordValue::from_raw(value.to_raw()) }, value);
+        let init = value.init();
+        assert_eq!(unsafe { TypedInit::from_raw(init.to_raw().unwrap()) }, init);
+        let name = value.name();
+        assert_eq!(unsafe { StringInit::from_raw(name.to_raw()) }, name);
+        assert_eq!(TypedInit::Invalid.to_raw(), None);
+        let key = rk.field_key("a");
+        assert_eq!(unsafe { FieldKey::from_raw(key.to_raw()) }, key);
+        let ty = init.rec_ty().unwrap();
+        assert_eq!(unsafe { RecTy::from_raw(ty.to_raw()) }, Some(ty));
+        let location = a.source_location();
+        let copy =
+            unsafe { SourceLocation::from_raw(tableGenSourceLocationClone(location.to_raw())) };
+        assert_eq!(copy.len(), location.len());
+    }
+
+    #[test]
+    fn optional_values() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                def A {
+                    int i = ?;
+                    int j = 2;
+                    bits<2> b = ?;
+                    bits<2> c = { 1, ? };
+                    string s = ?;
+                    dag d = ?;
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        assert_eq!(a.optional_int_value("i"), Ok(None));
+        assert_eq!(a.optional_int_value("j"), Ok(Some(2)));
+        assert_eq!(a.optional_bits_value("b"), Ok(None));
+        assert!(a.optional_bits_value("c").is_err());
+        assert_eq!(a.optional_string_value("s"), Ok(None));
+        assert!(a.optional_dag_value("d").unwrap().is_none());
+        assert!(a.optional_string_value("j").is_err());
+        assert!(a.optional_int_value("x").is_err());
+
+        assert!(a.value("i").unwrap().is_unset());
+        assert!(!a.value("c").unwrap().is_unset());
+        let j: Option<i64> = a.value("j").unwrap().try_into().unwrap();
+        assert_eq!(j, Some(2));
+        let i: Result<i64, _> = a.value("i").unwrap().try_into();
+        assert!(i.is_err());
+    }
+
+    #[test]
+    fn debug() {
+        let rk = TableGenParser::new()
+            .add_source_named("a.td", "def A {\n  list<int> a = [1, 2];\n}")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        let value = a.value("a").unwrap();
+        assert_eq!(
+            format!("{:?}", value.init()),
+            "TypedInit(List(ListInit([1, 2])))"
+        );
+        assert_eq!(format!("{:?}", rk.field_key("a")), "FieldKey(\"a\")");
+        assert_eq!(
+            format!("{:?}", a.source_location()),
+            "SourceLocation(a.td:1:5)"
+        );
+        assert_eq!(
+            format!("{:?}", value.source_location()),
+            "SourceLocation(a.td:2:13)"
+        );
+        assert_eq!(format!("{:?}", SourceLocation::none()), "SourceLocation()");
+        assert!(format!("{:?}", rk.as_keeper_ref()).starts_with("RecordKeeperRef {"));
+    }
+
+    #[test]
+    fn values_for() {
+        let rk = TableGenParser::new()
+            .add_source("def A { int a = 1; string b = \"b\"; int c = ?; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        let values = a.values_for(&["c", "a", "b"]).unwrap();
+        assert_eq!(
+            values,
+            [a.value("c"), a.value("a"), a.value("b")].map(Result::unwrap)
+        );
+        assert_eq!(values[1].name().to_str(), Ok("a"));
+        assert_eq!(String::try_from(values[2].init()), Ok("b".into()));
+        assert_eq!(a.values_for(&[]), Ok(vec![]));
+        assert_eq!(
+            a.values_for(&["a", "x", "y"]).unwrap_err().error(),
+            &TableGenError::MissingValue("x".into())
+        );
+    }
+
+    #[test]
+    fn bytes() {
+        let path = std::env::temp_dir().join(format!("tblgen-bytes-{}.td", std::process::id()));
+        std::fs::write(
+            &path,
+            b"def A {\n  string s = \"\xff\";\n  code c = [{ c }];\n  int i = 1;\n}\n",
+        )
+        .unwrap();
+        let rk = TableGenParser::new()
+            .add_source_file(path.to_str().unwrap())
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        assert_eq!(a.name_bytes(), b"A");
+        assert_eq!(a.string_bytes_value("s"), Ok(&b"\xff"[..]));
+        assert!(a.str_value("s").is_err());
+        assert_eq!(a.string_bytes_value("c"), Ok(&b" c "[..]));
+        let error = a.string_bytes_value("i").unwrap_err();
+        let field = error.field().expect("field context");
+        assert_eq!((field.record(), field.field()), ("A", "i"));
+        assert!(!field.is_class());
+        assert!(a.string_bytes_value("x").is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn located_elements() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                def ins;
+                def A {
+                    list<int> l = [1, 2];
+                    list<string> s = ["a"];
+                    dag d = (ins 1, "a":$x);
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        let l = a.value("l").unwrap();
+        assert_eq!(l.list_elements::<i64>(), Ok(vec![1, 2]));
+        let offset = |location: &SourceLocation| location.read_buffer(0, |_, _, offset| offset);
+        let error = l.list_elements::<String>().unwrap_err();
+        assert_eq!(offset(error.location()), offset(&l.source_location()));
+
+        let s = a.value("s").unwrap();
+        assert_eq!(s.list_elements::<&str>(), Ok(vec!["a"]));
+        assert!(s.dag_args::<i64>().is_err());
+
+        let d = a.value("d").unwrap();
+        let args = d.dag_args::<TypedInit>().unwrap();
+        assert_eq!(args.len(), 2);
+        assert_eq!(args[1].0, Some("x"));
+        let error = d.dag_args::<i64>().unwrap_err();
+        assert_eq!(offset(error.location()), offset(&d.source_location()));
+    }
+
+    #[test]
+    fn error_code() {
+        let rk = TableGenParser::new()
+            .add_source("def A { int i = 1; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        let error = a.string_value("x").unwrap_err();
+        assert_eq!(error.code(), ErrorCode::MissingValue);
+        assert_eq!(error.code().as_str(), "missing-value");
+        assert_eq!(
+            a.string_value("i").unwrap_err().code(),
+            ErrorCode::TypeMismatch
+        );
+        assert_eq!(
+            a.get::<String>("i").unwrap_err().code(),
+            ErrorCode::TypeMismatch
+        );
+        assert_eq!(rk.def("B").unwrap_err().code(), ErrorCode::MissingDef);
+        assert_eq!(error.category(), ErrorCategory::Access);
+    }
+
+    #[test]
+    fn downcast_error() {
+        let rk = TableGenParser::new()
+            .add_source("def A { int i = 1; list<int> l = []; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        let error = a.int_value("x").unwrap_err();
+        assert_eq!(error.as_missing_value(), Some("x"));
+        assert_eq!(error.as_conversion(), None);
+        assert_eq!(rk.def("B").unwrap_err().as_missing_def(), Some("B"));
+        assert_eq!(rk.class("C").unwrap_err().as_missing_class(), Some("C"));
+
+        let conversion = Conversion {
+            field: Some("i"),
+            found: "Int",
+            expected: "alloc::string::String",
+        };
+        let error = a.string_value("i").unwrap_err();
+        assert_eq!(error.as_conversion(), Some(conversion));
+        assert_eq!(error.error().as_conversion().unwrap().field, None);
+        let error = a.get::<i64>("l").unwrap_err();
+        assert_eq!(
+            error.as_conversion(),
+            Some(Conversion {
+                field: Some("l"),
+                found: "list<int>",
+                expected: "i64",
+            })
+        );
+        assert_eq!(error.as_missing_value(), None);
+    }
+
+    #[test]
+    fn category_error() {
+        let rk = TableGenParser::new()
+            .add_source("def A { int i = 1; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        let error = a.string_value("x").unwrap_err();
+        let message = error.to_string();
+        let location = error.location().clone();
+        let lookup = SourceError::<LookupError>::try_from(error).unwrap();
+        assert_eq!(lookup.error(), &LookupError::MissingValue("x".into()));
+        assert_eq!(lookup.to_string(), message);
+        assert_eq!(lookup.location(), &location);
+        let error = Error::from(lookup);
+        assert_eq!(error.to_string(), message);
+
+        let error = SourceError::<TypeMismatchError>::try_from(error).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::MissingValue);
+        let mismatch =
+            SourceError::<TypeMismatchError>::try_from(a.string_value("i").unwrap_err()).unwrap();
+        assert!(matches!(
+            mismatch.error(),
+            TypeMismatchError::InitConversion { from: "Int", .. }
+        ));
+        assert!(ParseError::try_from(TableGenError::InvalidSourceLocation).is_err());
+        assert_eq!(
+            SourceLocationError::try_from(TableGenError::InvalidSourceLocation),
+            Ok(SourceLocationError)
+        );
+        assert_eq!(
+            SourceLocationError.to_string(),
+            TableGenError::InvalidSourceLocation.to_string()
+        );
+    }
+
+    #[test]
+    fn owned_source_info() {
+        let rk = TableGenParser::new()
+            .add_source_named("A.td", "def A {\n  int i = 1;\r\n}\n")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let error = rk
+            .def("A")
+            .unwrap()
+            .string_value("i")
+            .unwrap_err()
+            .with_owned_source_info(&rk.source_info());
+        drop(rk);
+
+        let excerpt = error.excerpt().expect("excerpt");
+        assert_eq!(
+            (excerpt.file(), excerpt.line(), excerpt.column()),
+            ("A.td", 2, 7)
+        );
+        assert_eq!(excerpt.line_contents(), "  int i = 1;");
+        assert_eq!(
+            error.to_string(),
+            "A.td:2:7: error: field 'i' of def 'A': invalid conversion from Int to \
+             alloc::string::String\n  int i = 1;\n      ^"
+        );
+        assert!(
+            error
+                .to_sarif()
+                .contains(r#""region":{"startLine":2,"startColumn":7}"#)
+        );
+
+        let rk = TableGenParser::new()
+            .add_source("def B { string s = \"ü日\"; int i = 1; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let error = rk
+            .def("B")
+            .unwrap()
+            .string_value("i")
+            .unwrap_err()
+            .with_owned_source_info(&rk.source_info());
+        let caret = error.to_string().lines().last().unwrap().to_string();
+        assert_eq!(
+            caret.len(),
+            "def B { string s = \"ü日\"; int ".chars().count() + 2
+        );
+
+        let other = TableGenParser::new()
+            .add_source("def B;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        assert!(
+            error
+                .with_owned_source_info(&other.source_info())
+                .excerpt()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn error_collector() {
+        let rk = TableGenParser::new()
+            .add_source("def A { int i = 1; string s = \"s\"; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+
+        let mut errors = ErrorCollector::new();
+        assert_eq!(errors.take(a.int_value("i")), Some(1));
+        assert_eq!(errors.take_or_default(a.str_value("s")), "s");
+        assert!(errors.is_empty());
+        assert_eq!(errors.clone().finish(()), Ok(()));
+
+        assert_eq!(errors.take(a.int_value("s")), None);
+        assert_eq!(errors.take_or_default(a.str_value("x")), "");
+        let errors = errors.finish(()).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors.iter().next().unwrap().code(),
+            ErrorCode::MissingValue
+        );
+        assert_eq!(errors.to_string().lines().count(), 2);
+    }
+
+    #[test]
+    fn display_location() {
+        let rk = TableGenParser::new()
+            .add_source_named("A.td", "def A {\n  int i = 1;\n}\n")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let error = rk.def("A").unwrap().string_value("i").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "A.td:2:7: field 'i' of def 'A': invalid conversion from Int to alloc::string::String"
+        );
+        drop(rk);
+        assert_eq!(
+            error.to_string(),
+            "field 'i' of def 'A': invalid conversion from Int to alloc::string::String"
+        );
+    }
+
+    #[test]
+    fn sarif() {
+        let rk = TableGenParser::new()
+            .add_source_named("A.td", "def A {\n  int i = 1;\n}\n")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let error = rk.def("A").unwrap().string_value("i").unwrap_err();
+        let sarif = error.to_sarif();
+        assert!(sarif.contains(r#""ruleId":"type-mismatch","level":"error""#));
+        assert!(sarif.contains(r#""text":"field 'i' of def 'A': invalid conversion"#));
+        assert!(sarif.contains(r#""locations":[]"#));
+
+        let sarif = error.with_owned_source_info(&rk.source_info()).to_sarif();
+        assert!(sarif.contains(
+            r#""artifactLocation":{"uri":"A.td"},"region":{"startLine":2,"startColumn":7}"#
+        ));
+    }
+
+    #[test]
+    fn source_file() {
+        let dir = std::env::temp_dir().join(format!("tblgen-source-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("A.td"), "def A;").unwrap();
+
+        let rk = TableGenParser::new()
+            .add_include_directory(dir.to_str().unwrap())
+            .add_source_named("Main.td", "include \"A.td\"\ndef B;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let info = rk.source_info();
+        assert_eq!(
+            rk.def("A").unwrap().source_file(&info).unwrap(),
+            dir.join("A.td")
+        );
+        assert_eq!(
+            rk.def("B").unwrap().source_file(&info).unwrap(),
+            PathBuf::from("Main.td")
+        );
+
+        let other = TableGenParser::new()
+            .add_source("def C;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        assert!(other.def("C").unwrap().source_file(&info).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
     #[test]
     fn record() {
@@ -787,8 +2116,8 @@ mod tests {
         assert_eq!(
             a.value("size")
                 .and_then(|v| {
-                    assert!(v.name.to_str() == Ok("size"));
-                    v.init.as_int().map_err(|e| e.set_location(v))
+                    assert!(v.name().to_str() == Ok("size"));
+                    v.init().as_int().map_err(|e| e.set_location(v))
                 })
                 .and_then(|i| {
                     i64::try_from(i)
@@ -815,14 +2144,20 @@ mod tests {
         let a = rk.def("A").expect("def A exists");
         let values = a.values();
         assert_eq!(values.clone().count(), 2);
+        assert!(a.into_iter().eq(a.values()));
+        let mut names = Vec::new();
+        for v in a {
+            names.push(v.name().to_str().unwrap());
+        }
+        assert_eq!(names, ["a", "n"]);
         for v in values {
-            match v.init {
+            match v.init() {
                 TypedInit::Int(i) => {
-                    assert_eq!(v.name.to_str(), Ok("a"));
+                    assert_eq!(v.name().to_str(), Ok("a"));
                     assert_eq!(i64::try_from(i).unwrap(), 5);
                 }
                 TypedInit::String(i) => {
-                    assert_eq!(v.name.to_str(), Ok("n"));
+                    assert_eq!(v.name().to_str(), Ok("n"));
                     assert_eq!(i.to_str(), Ok("hello"));
                 }
                 _ => panic!("unexpected type"),
@@ -845,6 +2180,51 @@ mod tests {
         assert!(iter.next().is_none());
     }
 
+    #[test]
+    fn many_values() {
+        let source = (0..150)
+            .map(|index| format!("int f{} = {};", index, index))
+            .collect::<String>();
+        let rk = TableGenParser::new()
+            .add_source(&format!("def A {{ {} }}", source))
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        let values = a.values().collect::<Vec<_>>();
+        assert_eq!(values.len(), 150);
+        for (index, value) in values.into_iter().enumerate() {
+            assert_eq!(value, a.value(format!("f{}", index)).unwrap());
+            assert_eq!(i64::try_from(value.init()).ok(), Some(index as i64));
+        }
+        let mut iter = a.values().skip(149);
+        assert!(iter.next().is_some());
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn raw_cursor() {
+        let rk = TableGenParser::new()
+            .add_source("class C<int n> { int a = n; string b = \"b\"; } def A : C<1>; def E;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        for record in [rk.class("C").unwrap(), rk.def("A").unwrap()] {
+            let cursor = record.raw_cursor();
+            assert_eq!(cursor.len(), record.values().count());
+            assert_eq!(
+                cursor
+                    .map(|raw| unsafe { RecordValue::from_raw(raw) })
+                    .collect::<Vec<_>>(),
+                record.values().collect::<Vec<_>>()
+            );
+        }
+        let mut cursor = rk.def("E").unwrap().raw_cursor();
+        assert_eq!(cursor.len(), 0);
+        assert!(cursor.next().is_none());
+    }
+
     #[test]
     fn print_error() {
         let rk = TableGenParser::new()
@@ -876,7 +2256,7 @@ mod tests {
             assert_eq!(
                 msg,
                 r#"
-                  error: invalid conversion from Int to alloc::string::String
+                  error: field 'a' of def 'A': invalid conversion from Int to alloc::string::String
                     int a = test;
                         ^
                 "#
@@ -884,7 +2264,9 @@ mod tests {
             );
             #[cfg(feature = "llvm22-0")]
             assert!(
-                msg.contains("error: invalid conversion from Int to alloc::string::String"),
+                msg.contains(
+                    "error: field 'a' of def 'A': invalid conversion from Int to alloc::string::String"
+                ),
                 "unexpected error message: {msg}"
             );
 
@@ -893,7 +2275,7 @@ mod tests {
             assert_eq!(
                 format!("{}", e).trim(),
                 r#"
-                  invalid conversion from Int to alloc::string::String
+                  field 'a' of def 'A': invalid conversion from Int to alloc::string::String
                 "#
                 .trim()
             );
@@ -906,7 +2288,7 @@ mod tests {
                 .expect("valid tablegen");
             assert_eq!(
                 format!("{}", e.add_source_info(rk.source_info())).trim(),
-                "invalid conversion from Int to alloc::string::String\nfailed to print source information: invalid source location"
+                "field 'a' of def 'A': invalid conversion from Int to alloc::string::String\nfailed to print source information: invalid source location"
                 .trim()
             );
         } else {
@@ -1099,4 +2481,78 @@ mod tests {
         assert!(iter.next().is_none());
         assert!(iter.next_back().is_none());
     }
+
+    #[test]
+    fn doc() {
+        let rk = TableGenParser::new()
+            .add_source(
+                "class Op { string summary = ?; string description = \"\"; }
+                 def A : Op {
+                     let summary = \" addition \";
+                     let description = [{
+                         Adds integers.
+
+                           %0 = add %a, %b
+                     }];
+                 }
+                 def B : Op;
+                 def C;",
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        assert_eq!(
+            rk.def("A").unwrap().doc(),
+            Ok(Doc {
+                summary: Some("addition".into()),
+                description: Some("Adds integers.\n\n  %0 = add %a, %b".into()),
+            })
+        );
+        assert_eq!(rk.def("B").unwrap().doc(), Ok(Doc::default()));
+        assert_eq!(rk.def("C").unwrap().doc(), Ok(Doc::default()));
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_from_record() {
+        use crate::FromRecord;
+
+        #[derive(FromRecord)]
+        struct Op<'a> {
+            r#type: &'a str,
+            #[tblgen(field = "summary")]
+            description: String,
+            #[tblgen(default)]
+            size: i64,
+            #[tblgen(default)]
+            extra: Vec<bool>,
+            parent: Record<'a>,
+        }
+
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class Op<string t, int s = ?> {
+                    string type = t; string summary = ""; int size = s; Op parent = ?;
+                }
+                def Root : Op<"r">;
+                def A : Op<"a"> { let summary = "first"; let parent = Root; }
+                def B : Op<"b", 2> { let parent = A; }
+                def C;
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = Op::from_record(rk.def("A").unwrap()).unwrap();
+        assert_eq!(a.r#type, "a");
+        assert_eq!(a.description, "first");
+        assert_eq!(a.size, 0);
+        assert!(a.extra.is_empty());
+        assert_eq!(a.parent, rk.def("Root").unwrap());
+        let b = Op::from_record(rk.def("B").unwrap()).unwrap();
+        assert_eq!(b.size, 2);
+        assert_eq!(b.parent, rk.def("A").unwrap());
+        assert!(Op::from_record(rk.def("C").unwrap()).is_err());
+    }
 }
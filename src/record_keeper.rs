@@ -8,44 +8,70 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::{fmt, marker::PhantomData};
-
-#[cfg(any(
-    feature = "llvm18-0",
-    feature = "llvm19-0",
-    feature = "llvm20-0",
-    feature = "llvm21-0",
-    feature = "llvm22-0"
-))]
-use crate::error::TableGenError;
+use std::{collections::HashMap, ffi::c_void, fmt, io, marker::PhantomData, mem::ManuallyDrop};
+
 #[cfg(any(feature = "llvm16-0", feature = "llvm17-0"))]
-use crate::error::{SourceLocation, TableGenError, WithLocation};
+use crate::error::SourceLocation;
 use crate::{
-    Error, SourceInfo, TableGenParser,
+    Diagnostic, Error, SourceInfo, TableGenParser, binary, dump,
+    error::{TableGenError, WithLocation},
     init::TypedInit,
+    query::{Match, Query},
     raw::{
-        TableGenRecordKeeperIteratorRef, TableGenRecordKeeperRef, TableGenRecordVectorRef,
-        tableGenRecordKeeperFree, tableGenRecordKeeperGetAllDerivedDefinitions,
-        tableGenRecordKeeperGetAllDerivedDefinitionsIfDefined, tableGenRecordKeeperGetClass,
+        TableGenRecordKeeperIteratorRef, TableGenRecordKeeperRef, TableGenRecordRef,
+        TableGenRecordVectorRef, TableGenStringRef, TableGenTypedInitRef, tableGenBitInitGet,
+        tableGenIntInitGet, tableGenRecordKeeperDumpJson, tableGenRecordKeeperFree,
+        tableGenRecordKeeperGetAllDerivedDefinitions,
+        tableGenRecordKeeperGetAllDerivedDefinitionsIfDefined,
+        tableGenRecordKeeperGetAllDerivedDefinitionsOf, tableGenRecordKeeperGetClass,
         tableGenRecordKeeperGetDef, tableGenRecordKeeperGetFirstClass,
         tableGenRecordKeeperGetFirstDef, tableGenRecordKeeperGetGlobal,
-        tableGenRecordKeeperGetInputFilename, tableGenRecordKeeperGetNextClass,
-        tableGenRecordKeeperGetNextDef, tableGenRecordKeeperItemGetName,
-        tableGenRecordKeeperItemGetRecord, tableGenRecordKeeperIteratorClone,
-        tableGenRecordKeeperIteratorFree, tableGenRecordVectorFree, tableGenRecordVectorGet,
-        tableGenRecordVectorSize,
+        tableGenRecordKeeperGetInputFilename, tableGenRecordKeeperInstantiateClass,
+        tableGenRecordKeeperIteratorClone, tableGenRecordKeeperIteratorFree,
+        tableGenRecordKeeperIteratorNextChunk, tableGenRecordKeeperPrint, tableGenRecordVectorFree,
+        tableGenRecordVectorGet, tableGenRecordVectorSize, tableGenStringInitGet,
     },
-    record::Record,
+    record::{FieldKey, Record},
+    schema::Schema,
+    snapshot::Snapshot,
+    source::IncludeGraph,
     string_ref::StringRef,
+    table, td,
+    util::{print_string_callback, raw_fn},
+    yaml,
 };
 
+/// A backend of `llvm-tblgen` that is part of the TableGen library and can be
+/// run with [`RecordKeeper::run_backend`].
+///
+/// Backends of specific targets or of MLIR (e.g. `-gen-searchable-tables` or
+/// `-gen-op-decls`) are only part of the `llvm-tblgen` and `mlir-tblgen`
+/// executables and are not available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Backend<'a> {
+    /// Prints all classes and defs in TableGen syntax (`-print-records`,
+    /// the default backend).
+    PrintRecords,
+    /// Dumps all defs as JSON (`-dump-json`).
+    DumpJson,
+    /// Prints the names of all defs deriving from the given class
+    /// (`-print-enums -class=<class>`).
+    PrintEnums(&'a str),
+}
+
 /// Struct that holds all records from a TableGen file.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(PartialEq, Eq)]
 pub struct RecordKeeper<'s> {
     raw: TableGenRecordKeeperRef,
     pub(crate) parser: TableGenParser<'s>,
 }
 
+// The record keeper owns all records and does not depend on the thread it was
+// created on. It is not `Sync`, since LLVM may lazily modify records when
+// they are accessed.
+unsafe impl Send for RecordKeeper<'_> {}
+
 impl<'s> RecordKeeper<'s> {
     pub(crate) unsafe fn from_raw(
         raw: TableGenRecordKeeperRef,
@@ -54,22 +80,511 @@ impl<'s> RecordKeeper<'s> {
         RecordKeeper { raw, parser }
     }
 
+    /// Frees all records and returns the parser, so that it can be reused.
+    pub(crate) fn into_parser(self) -> TableGenParser<'s> {
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            tableGenRecordKeeperFree(this.raw);
+            std::ptr::read(&this.parser)
+        }
+    }
+
     /// Returns an iterator over all classes.
     ///
     /// The iterator yields tuples of type `(String, Record)`.
     pub fn classes(&self) -> NamedRecordIter<'_, IsClass> {
-        unsafe { NamedRecordIter::from_raw(tableGenRecordKeeperGetFirstClass(self.raw)) }
+        self.as_keeper_ref().classes()
     }
 
     /// Returns an iterator over all definitions.
     ///
     /// The iterator yields tuples of type `(String, Record)`.
     pub fn defs(&self) -> NamedRecordIter<'_, IsDef> {
-        unsafe { NamedRecordIter::from_raw(tableGenRecordKeeperGetFirstDef(self.raw)) }
+        self.as_keeper_ref().defs()
     }
 
     /// Returns the class with the given name.
     pub fn class(&self, name: &str) -> Result<Record<'_>, Error> {
+        self.as_keeper_ref().class(name)
+    }
+
+    /// Returns the definition with the given name.
+    pub fn def(&self, name: &str) -> Result<Record<'_>, Error> {
+        self.as_keeper_ref().def(name)
+    }
+
+    /// Returns a map of all definitions by name.
+    ///
+    /// See [`RecordKeeperRef::defs_map`].
+    pub fn defs_map(&self) -> HashMap<String, Record<'_>> {
+        self.as_keeper_ref().defs_map()
+    }
+
+    /// Returns a description of the fields of the class with the given name.
+    ///
+    /// See [`Record::schema`].
+    pub fn class_schema(&self, name: &str) -> Result<Schema<'_>, Error> {
+        self.class(name)?.schema()
+    }
+
+    /// Returns an iterator over all definitions that derive from the class with
+    /// the given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the class is not defined.
+    pub fn all_derived_definitions(&self, name: &str) -> Result<RecordIter<'_>, Error> {
+        self.as_keeper_ref().all_derived_definitions(name)
+    }
+
+    /// Returns an iterator over all definitions that derive from the class with
+    /// the given name. Returns an empty iterator if the class is not defined.
+    pub fn all_derived_definitions_if_defined(&self, name: &str) -> RecordIter<'_> {
+        self.as_keeper_ref()
+            .all_derived_definitions_if_defined(name)
+    }
+
+    /// Returns the values that match the given query, e.g.
+    /// `Op[dialect=arith].sizes.*`.
+    ///
+    /// See the [`query`](crate::query) module for the syntax.
+    pub fn select(&self, query: &str) -> Result<Vec<Match<'_>>, Error> {
+        self.as_keeper_ref().select(query)
+    }
+
+    /// Returns an iterator over all definitions that derive from the given
+    /// class.
+    ///
+    /// See [`RecordKeeperRef::all_derived_definitions_of`].
+    pub fn all_derived_definitions_of<'a>(&'a self, class: Record<'a>) -> RecordIter<'a> {
+        self.as_keeper_ref().all_derived_definitions_of(class)
+    }
+
+    /// Instantiates a class with the given template arguments, and adds the
+    /// resulting def to the record keeper.
+    ///
+    /// Like for `def Name : Class<args...>;`, missing trailing arguments take
+    /// their default values, all fields of the def are resolved and the
+    /// assertions of the class are checked. The def is also returned by
+    /// [`all_derived_definitions`](Self::all_derived_definitions) afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the class is not defined, if a def with the same
+    /// name already exists, if the arguments do not match the template
+    /// arguments of the class, if a `!cast` refers to an undefined record, if
+    /// a `!div` divides by zero or if an assertion fails.
+    ///
+    /// Other errors that TableGen reports while folding values exit the
+    /// process, like when parsing (see [`TableGenParser::parse`]).
+    pub fn instantiate_class(
+        &mut self,
+        class: &str,
+        name: &str,
+        args: &[TemplateArg],
+    ) -> Result<Record<'_>, Error> {
+        let keeper = self.as_keeper_ref();
+        let class = keeper.class(class)?;
+        let args = args
+            .iter()
+            .map(|arg| unsafe {
+                Ok(match *arg {
+                    TemplateArg::Bit(value) => tableGenBitInitGet(self.raw, value.into()),
+                    TemplateArg::Int(value) => tableGenIntInitGet(self.raw, value),
+                    TemplateArg::String(value) => {
+                        tableGenStringInitGet(self.raw, StringRef::from(value).to_raw())
+                    }
+                    TemplateArg::Def(name) => keeper.def(name)?.def_init().to_raw(),
+                })
+            })
+            .collect::<Result<Vec<TableGenTypedInitRef>, Error>>()?;
+
+        let mut data: (_, Result<_, TableGenError>) = (String::new(), Ok(()));
+        let def = unsafe {
+            tableGenRecordKeeperInstantiateClass(
+                self.raw,
+                class.to_raw(),
+                StringRef::from(name).to_raw(),
+                args.as_ptr(),
+                args.len(),
+                Some(print_string_callback),
+                &mut data as *mut _ as *mut c_void,
+            )
+        };
+        if def.is_null() {
+            data.1?;
+            Err(TableGenError::InvalidArgument(data.0).with_location(class))
+        } else {
+            Ok(unsafe { Record::from_raw(def) })
+        }
+    }
+
+    /// Copies all classes and defs into a [`Snapshot`] that does not borrow
+    /// the record keeper.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a name or string is not valid UTF-8.
+    pub fn snapshot(&self) -> Result<Snapshot, Error> {
+        Snapshot::new(self)
+    }
+
+    /// Returns a cursor over all raw definitions.
+    ///
+    /// See [`RecordKeeperRef::raw_cursor`].
+    pub fn raw_cursor(&self) -> RawRecordCursor<'_> {
+        self.as_keeper_ref().raw_cursor()
+    }
+
+    /// Returns a [`RecordKeeperRef`] that borrows this record keeper.
+    pub fn as_keeper_ref(&self) -> RecordKeeperRef<'_> {
+        RecordKeeperRef {
+            raw: self.raw,
+            _reference: PhantomData,
+        }
+    }
+
+    pub fn source_info(&self) -> SourceInfo<'_> {
+        SourceInfo(&self.parser)
+    }
+
+    /// Returns the diagnostics (e.g. warnings) that were reported while parsing.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        unsafe { Diagnostic::collect(self.parser.raw) }
+    }
+
+    /// Returns the graph of `include` directives of all files that were loaded
+    /// while parsing.
+    pub fn include_graph(&self) -> IncludeGraph {
+        unsafe { IncludeGraph::from_parser(self.parser.raw) }
+    }
+
+    /// Returns the input filename.
+    pub fn input_filename(&self) -> Result<&str, Error> {
+        let raw = unsafe { tableGenRecordKeeperGetInputFilename(self.raw) };
+        unsafe { StringRef::from_raw(raw) }
+            .try_into()
+            .map_err(|e: std::str::Utf8Error| TableGenError::from(e).into())
+    }
+
+    /// Returns a key for the field with the given name.
+    ///
+    /// See [`RecordKeeperRef::field_key`].
+    pub fn field_key(&self, name: &str) -> FieldKey<'_> {
+        self.as_keeper_ref().field_key(name)
+    }
+
+    /// Returns the global variable with the given name, if it exists.
+    pub fn global(&self, name: &str) -> Option<TypedInit<'_>> {
+        self.as_keeper_ref().global(name)
+    }
+
+    /// Writes all defs as a YAML document, e.g. to review changes of the
+    /// parsed records in a diff.
+    ///
+    /// The document has the same layout as the output of `llvm-tblgen
+    /// --dump-json`, with the keys of all objects sorted by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if a name or
+    /// string is not valid UTF-8.
+    pub fn to_yaml(&self, mut writer: impl io::Write) -> io::Result<()> {
+        let value = dump::keeper(self)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        yaml::write(&value, &mut writer)
+    }
+
+    /// Writes all defs in the MessagePack format, e.g. to hand the parsed
+    /// records to tools written in other languages.
+    ///
+    /// The document has the same layout as the output of `llvm-tblgen
+    /// --dump-json`, but is smaller and faster to decode.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if a name or
+    /// string is not valid UTF-8.
+    pub fn to_msgpack(&self, mut writer: impl io::Write) -> io::Result<()> {
+        let value = dump::keeper(self)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        binary::write_msgpack(&value, &mut writer)
+    }
+
+    /// Writes all defs in the CBOR format.
+    ///
+    /// The document has the same layout as the one written by
+    /// [`to_msgpack`](Self::to_msgpack).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if a name or
+    /// string is not valid UTF-8.
+    pub fn to_cbor(&self, mut writer: impl io::Write) -> io::Result<()> {
+        let value = dump::keeper(self)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        binary::write_cbor(&value, &mut writer)
+    }
+
+    /// Runs a backend of `llvm-tblgen` and returns its output.
+    ///
+    /// ```rust
+    /// use tblgen::{TableGenParser, record_keeper::Backend};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let keeper = TableGenParser::new()
+    ///     .add_source("class A; def X : A; def Y : A;")?
+    ///     .parse()?;
+    /// assert_eq!(keeper.run_backend(Backend::PrintEnums("A"))?, "X, Y, \n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_backend(&self, backend: Backend) -> Result<String, Error> {
+        let print = match backend {
+            Backend::PrintRecords => tableGenRecordKeeperPrint,
+            Backend::DumpJson => tableGenRecordKeeperDumpJson,
+            Backend::PrintEnums(class) => {
+                // The backend is part of the `llvm-tblgen` executable, so it
+                // is implemented here with the same output.
+                let mut output = String::new();
+                for record in self.all_derived_definitions(class)? {
+                    output.push_str(record.name()?);
+                    output.push_str(", ");
+                }
+                output.push('\n');
+                return Ok(output);
+            }
+        };
+
+        let mut data: (_, Result<_, TableGenError>) = (String::new(), Ok(()));
+        unsafe {
+            print(
+                self.raw,
+                Some(print_string_callback),
+                &mut data as *mut _ as *mut c_void,
+            );
+        }
+        data.1?;
+        Ok(data.0)
+    }
+
+    /// Writes the given fields of all defs deriving from a class as CSV, e.g.
+    /// to review instruction tables in a spreadsheet.
+    ///
+    /// The first row contains the column names, which are `name` followed by
+    /// the given fields. Each following row contains the name of a def and the
+    /// values of its fields: bits and ints are written as numbers (bits with
+    /// only known bits as binary literals, e.g. `0b0110`), strings without
+    /// quotes, defs as their names, and other values in TableGen syntax.
+    /// Missing and unset fields are left empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if the class
+    /// does not exist or a name or string is not valid UTF-8.
+    pub fn export_table(
+        &self,
+        class: &str,
+        fields: &[&str],
+        mut writer: impl io::Write,
+    ) -> io::Result<()> {
+        table::write(self, class, fields, ',', &mut writer)
+    }
+
+    /// Writes the given fields of all defs deriving from a class as TSV.
+    ///
+    /// See [`export_table`](Self::export_table) for the layout of the table.
+    pub fn export_table_tsv(
+        &self,
+        class: &str,
+        fields: &[&str],
+        mut writer: impl io::Write,
+    ) -> io::Result<()> {
+        table::write(self, class, fields, '\t', &mut writer)
+    }
+
+    /// Exports all defs to a SQLite database at the given path, with a table
+    /// for each class and a row for each def deriving from it.
+    ///
+    /// Each table has a `name` column and a column for each field of the
+    /// class that is not a template argument. Columns are typed after the
+    /// declared type of their field:
+    ///
+    /// - `bit`, `int` and `bits` of up to 63 bits are stored as integers,
+    /// - strings and code are stored as text, and defs as their names,
+    /// - all other values (e.g. lists and dags) are stored as JSON text in the
+    ///   layout of `llvm-tblgen --dump-json`, so that they can be queried with
+    ///   the JSON functions of SQLite.
+    ///
+    /// Unset values and values that cannot be stored in their column (e.g.
+    /// `bits` with unknown bits) are stored as `NULL`. Existing tables with the
+    /// same names as classes are replaced.
+    ///
+    /// For example, the encodings of the instructions of a target can be
+    /// queried with `SELECT name, Inst FROM Instruction`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`rusqlite::Error::ToSqlConversionFailure`] if a name or string
+    /// is not valid UTF-8, and any error reported by SQLite.
+    #[cfg(feature = "sqlite")]
+    pub fn export_sqlite(&self, path: impl AsRef<std::path::Path>) -> rusqlite::Result<()> {
+        crate::sqlite::write(self, &mut rusqlite::Connection::open(path)?)
+    }
+
+    /// Returns TableGen source code that declares all classes and defs.
+    ///
+    /// Parsing the output yields defs with the same fields and super classes,
+    /// e.g. to filter or transform records before passing them to the
+    /// backends of `llvm-tblgen`. See [`Record::to_td`] for the declarations
+    /// of each record.
+    pub fn to_td(&self) -> Result<String, Error> {
+        let mut output = String::new();
+        td::keeper(&mut output, self)?;
+        Ok(output)
+    }
+
+    /// Writes the inheritance graph of all classes in the DOT language of
+    /// Graphviz, with an edge from each class to its direct super classes.
+    ///
+    /// If `include_defs` is true, defs are included as elliptic nodes with
+    /// edges to their direct super classes.
+    pub fn class_hierarchy_dot(
+        &self,
+        mut writer: impl io::Write,
+        include_defs: bool,
+    ) -> io::Result<()> {
+        writeln!(writer, "digraph classes {{")?;
+        writeln!(writer, "  rankdir=BT;")?;
+        writeln!(writer, "  node [shape=box];")?;
+        for (_, class) in self.classes() {
+            writeln!(writer, "  {};", dot_id(class))?;
+        }
+        if include_defs {
+            for (_, def) in self.defs() {
+                writeln!(writer, "  {} [shape=ellipse];", dot_id(def))?;
+            }
+        }
+
+        let defs = include_defs.then(|| self.defs()).into_iter().flatten();
+        for (_, record) in self.classes().chain(defs) {
+            // Older LLVM versions also return indirect super classes.
+            for class in record
+                .direct_super_classes()
+                .filter(|&class| record.has_direct_super_class(class))
+            {
+                writeln!(writer, "  {} -> {};", dot_id(record), dot_id(class))?;
+            }
+        }
+        writeln!(writer, "}}")
+    }
+}
+
+/// Returns the quoted name of a record as a DOT identifier.
+fn dot_id(record: Record) -> String {
+    let name = String::from_utf8_lossy(record.name_bytes());
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl fmt::Debug for RecordKeeper<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordKeeper")
+            .field(
+                "input_filename",
+                &self.as_keeper_ref().input_filename_lossy(),
+            )
+            .field("parser", &self.parser)
+            .finish()
+    }
+}
+
+impl Drop for RecordKeeper<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            tableGenRecordKeeperFree(self.raw);
+        }
+    }
+}
+
+/// Reference to a [`RecordKeeper`].
+///
+/// This allows to look up records from objects that refer to the record keeper
+/// that owns them, e.g. [`RecTy::record_keeper`](crate::ty::RecTy::record_keeper).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct RecordKeeperRef<'a> {
+    raw: TableGenRecordKeeperRef,
+    _reference: PhantomData<&'a TableGenRecordKeeperRef>,
+}
+
+impl<'a> RecordKeeperRef<'a> {
+    raw_fn! {
+        /// Creates a record keeper reference from a raw object.
+        ///
+        /// # Safety
+        ///
+        /// The raw object must be valid.
+        unsafe fn from_raw(raw: TableGenRecordKeeperRef) -> Self {
+            Self {
+                raw,
+                _reference: PhantomData,
+            }
+        }
+    }
+
+    raw_fn! {
+        /// Returns the raw object.
+        fn to_raw(self) -> TableGenRecordKeeperRef {
+            self.raw
+        }
+    }
+
+    /// Returns the input filename, replacing invalid UTF-8.
+    fn input_filename_lossy(self) -> std::borrow::Cow<'a, str> {
+        let raw = unsafe { tableGenRecordKeeperGetInputFilename(self.raw) };
+        String::from_utf8_lossy(unsafe { StringRef::from_raw(raw) }.as_bytes())
+    }
+
+    /// Returns an iterator over all classes.
+    ///
+    /// The iterator yields tuples of type `(String, Record)`.
+    pub fn classes(self) -> NamedRecordIter<'a, IsClass> {
+        unsafe { NamedRecordIter::from_raw(tableGenRecordKeeperGetFirstClass(self.raw)) }
+    }
+
+    /// Returns an iterator over all definitions.
+    ///
+    /// The iterator yields tuples of type `(String, Record)`.
+    pub fn defs(self) -> NamedRecordIter<'a, IsDef> {
+        unsafe { NamedRecordIter::from_raw(tableGenRecordKeeperGetFirstDef(self.raw)) }
+    }
+
+    /// Returns a map of all definitions by name, for consumers that look up
+    /// many definitions by computed names.
+    ///
+    /// Unlike [`RecordKeeperRef::def`], lookups in the map do not cross the
+    /// FFI boundary. Names that are not valid UTF-8 are converted lossily.
+    pub fn defs_map(self) -> HashMap<String, Record<'a>> {
+        self.defs()
+            .map(|(name, def)| match name {
+                Ok(name) => (name.to_owned(), def),
+                Err(_) => (String::from_utf8_lossy(def.name_bytes()).into_owned(), def),
+            })
+            .collect()
+    }
+
+    /// Returns a cursor over all raw definitions, for consumers with extreme
+    /// throughput needs.
+    ///
+    /// Unlike [`RecordKeeperRef::defs`], the cursor neither wraps the
+    /// definitions nor checks that their names are valid UTF-8. The raw
+    /// records can be passed to the functions of the [`raw`](crate::raw)
+    /// module or wrapped with [`Record::from_raw`].
+    pub fn raw_cursor(self) -> RawRecordCursor<'a> {
+        RawRecordCursor(self.defs())
+    }
+
+    /// Returns the class with the given name.
+    pub fn class(self, name: &str) -> Result<Record<'a>, Error> {
         unsafe {
             let class = tableGenRecordKeeperGetClass(self.raw, StringRef::from(name).to_raw());
             if class.is_null() {
@@ -81,7 +596,7 @@ impl<'s> RecordKeeper<'s> {
     }
 
     /// Returns the definition with the given name.
-    pub fn def(&self, name: &str) -> Result<Record<'_>, Error> {
+    pub fn def(self, name: &str) -> Result<Record<'a>, Error> {
         unsafe {
             let def = tableGenRecordKeeperGetDef(self.raw, StringRef::from(name).to_raw());
             if def.is_null() {
@@ -94,18 +609,24 @@ impl<'s> RecordKeeper<'s> {
 
     /// Returns an iterator over all definitions that derive from the class with
     /// the given name.
-    pub fn all_derived_definitions(&self, name: &str) -> RecordIter<'_> {
-        unsafe {
-            RecordIter::from_raw_vector(tableGenRecordKeeperGetAllDerivedDefinitions(
-                self.raw,
-                StringRef::from(name).to_raw(),
-            ))
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the class is not defined.
+    pub fn all_derived_definitions(self, name: &str) -> Result<RecordIter<'a>, Error> {
+        let vector = unsafe {
+            tableGenRecordKeeperGetAllDerivedDefinitions(self.raw, StringRef::from(name).to_raw())
+        };
+        if vector.is_null() {
+            Err(TableGenError::MissingClass(name.into()).into())
+        } else {
+            Ok(unsafe { RecordIter::from_raw_vector(vector) })
         }
     }
 
     /// Returns an iterator over all definitions that derive from the class with
     /// the given name. Returns an empty iterator if the class is not defined.
-    pub fn all_derived_definitions_if_defined(&self, name: &str) -> RecordIter<'_> {
+    pub fn all_derived_definitions_if_defined(self, name: &str) -> RecordIter<'a> {
         unsafe {
             RecordIter::from_raw_vector(tableGenRecordKeeperGetAllDerivedDefinitionsIfDefined(
                 self.raw,
@@ -114,20 +635,42 @@ impl<'s> RecordKeeper<'s> {
         }
     }
 
-    pub fn source_info(&self) -> SourceInfo<'_> {
-        SourceInfo(&self.parser)
+    /// Returns an iterator over all definitions that derive from the given
+    /// class.
+    ///
+    /// Unlike [`all_derived_definitions`](Self::all_derived_definitions), this
+    /// does not look up the class by name, e.g. for classes obtained from
+    /// [`Record::direct_super_classes`]. Returns an empty iterator if the
+    /// record is a def, or if it belongs to another record keeper.
+    pub fn all_derived_definitions_of(self, class: Record<'a>) -> RecordIter<'a> {
+        unsafe {
+            RecordIter::from_raw_vector(tableGenRecordKeeperGetAllDerivedDefinitionsOf(
+                self.raw,
+                class.to_raw(),
+            ))
+        }
     }
 
-    /// Returns the input filename.
-    pub fn input_filename(&self) -> Result<&str, Error> {
-        let raw = unsafe { tableGenRecordKeeperGetInputFilename(self.raw) };
-        unsafe { StringRef::from_raw(raw) }
-            .try_into()
-            .map_err(|e: std::str::Utf8Error| TableGenError::from(e).into())
+    /// Returns the values that match the given query.
+    ///
+    /// See the [`query`](crate::query) module for the syntax.
+    pub fn select(self, query: &str) -> Result<Vec<Match<'a>>, Error> {
+        Query::parse(query)?.select(self)
+    }
+
+    /// Returns a key for the field with the given name, which is faster to
+    /// look up in many records with [`Record::value`] than the name itself.
+    pub fn field_key(self, name: &str) -> FieldKey<'a> {
+        unsafe {
+            FieldKey::from_raw(tableGenStringInitGet(
+                self.raw,
+                StringRef::from(name).to_raw(),
+            ))
+        }
     }
 
     /// Returns the global variable with the given name, if it exists.
-    pub fn global(&self, name: &str) -> Option<TypedInit<'_>> {
+    pub fn global(self, name: &str) -> Option<TypedInit<'a>> {
         let ptr =
             unsafe { tableGenRecordKeeperGetGlobal(self.raw, StringRef::from(name).to_raw()) };
         if ptr.is_null() {
@@ -138,83 +681,149 @@ impl<'s> RecordKeeper<'s> {
     }
 }
 
-impl Drop for RecordKeeper<'_> {
-    fn drop(&mut self) {
-        unsafe {
-            tableGenRecordKeeperFree(self.raw);
-        }
+impl fmt::Debug for RecordKeeperRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordKeeperRef")
+            .field("input_filename", &self.input_filename_lossy())
+            .finish()
     }
 }
 
-#[doc(hidden)]
-pub struct IsClass;
-#[doc(hidden)]
-pub struct IsDef;
+impl<'a> From<&'a RecordKeeper<'_>> for RecordKeeperRef<'a> {
+    fn from(keeper: &'a RecordKeeper<'_>) -> Self {
+        keeper.as_keeper_ref()
+    }
+}
 
-trait NextRecord {
-    unsafe fn next(raw: &mut TableGenRecordKeeperIteratorRef);
+/// Template argument of [`RecordKeeper::instantiate_class`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateArg<'a> {
+    /// A `bit` value.
+    Bit(bool),
+    /// An `int` value.
+    Int(i64),
+    /// A `string` or `code` value.
+    String(&'a str),
+    /// The def with the given name.
+    Def(&'a str),
 }
 
-impl NextRecord for IsClass {
-    unsafe fn next(raw: &mut TableGenRecordKeeperIteratorRef) {
-        unsafe {
-            tableGenRecordKeeperGetNextClass(raw);
-        }
+impl From<bool> for TemplateArg<'_> {
+    fn from(value: bool) -> Self {
+        Self::Bit(value)
     }
 }
 
-impl NextRecord for IsDef {
-    unsafe fn next(raw: &mut TableGenRecordKeeperIteratorRef) {
-        unsafe {
-            tableGenRecordKeeperGetNextDef(raw);
-        }
+impl From<i64> for TemplateArg<'_> {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
     }
 }
 
+impl<'a> From<&'a str> for TemplateArg<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::String(value)
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct IsClass;
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct IsDef;
+
 /// Iterator over named records (classes or definitions) in a [`RecordKeeper`].
+///
+/// Records are fetched in chunks, so that iterating over large record
+/// keepers does not cross the FFI boundary for every record.
 #[derive(Debug)]
 pub struct NamedRecordIter<'a, T> {
     raw: TableGenRecordKeeperIteratorRef,
+    names: Vec<TableGenStringRef>,
+    records: Vec<TableGenRecordRef>,
+    index: usize,
     _kind: PhantomData<&'a T>,
 }
 
 impl<T> NamedRecordIter<'_, T> {
+    const CHUNK_SIZE: usize = 256;
+
     unsafe fn from_raw(raw: TableGenRecordKeeperIteratorRef) -> Self {
         NamedRecordIter {
             raw,
+            names: Vec::new(),
+            records: Vec::new(),
+            index: 0,
             _kind: PhantomData,
         }
     }
+
+    /// Fetches the next chunk of records, and returns false if there are no
+    /// records left.
+    fn fetch(&mut self) -> bool {
+        if self.raw.is_null() {
+            return false;
+        }
+        self.names.clear();
+        self.records.clear();
+        self.names.reserve(Self::CHUNK_SIZE);
+        self.records.reserve(Self::CHUNK_SIZE);
+        self.index = 0;
+        unsafe {
+            let count = tableGenRecordKeeperIteratorNextChunk(
+                self.raw,
+                self.names.as_mut_ptr(),
+                self.records.as_mut_ptr(),
+                Self::CHUNK_SIZE,
+            );
+            self.names.set_len(count);
+            self.records.set_len(count);
+            if count == 0 {
+                tableGenRecordKeeperIteratorFree(self.raw);
+                self.raw = std::ptr::null_mut();
+            }
+            count > 0
+        }
+    }
+
+    fn next_raw(&mut self) -> Option<(TableGenStringRef, TableGenRecordRef)> {
+        if self.index == self.records.len() && !self.fetch() {
+            return None;
+        }
+        let index = self.index;
+        self.index += 1;
+        Some((self.names[index], self.records[index]))
+    }
 }
 
-impl<'a, T: NextRecord> Iterator for NamedRecordIter<'a, T> {
+impl<'a, T> Iterator for NamedRecordIter<'a, T> {
     type Item = (Result<&'a str, std::str::Utf8Error>, Record<'a>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let current = if self.raw.is_null() {
-            return None;
-        } else {
-            unsafe {
-                Some((
-                    StringRef::from_raw(tableGenRecordKeeperItemGetName(self.raw)).try_into(),
-                    Record::from_raw(tableGenRecordKeeperItemGetRecord(self.raw)),
-                ))
-            }
-        };
-        unsafe { T::next(&mut self.raw) };
-        current
+        let (name, record) = self.next_raw()?;
+        unsafe {
+            Some((
+                StringRef::from_raw(name).try_into(),
+                Record::from_raw(record),
+            ))
+        }
     }
 }
 
 impl<T> Clone for NamedRecordIter<'_, T> {
     fn clone(&self) -> Self {
-        if self.raw.is_null() {
-            return Self {
-                raw: std::ptr::null_mut(),
-                _kind: PhantomData,
-            };
+        Self {
+            raw: if self.raw.is_null() {
+                std::ptr::null_mut()
+            } else {
+                unsafe { tableGenRecordKeeperIteratorClone(self.raw) }
+            },
+            names: self.names.clone(),
+            records: self.records.clone(),
+            index: self.index,
+            _kind: PhantomData,
         }
-        unsafe { Self::from_raw(tableGenRecordKeeperIteratorClone(self.raw)) }
     }
 }
 
@@ -224,7 +833,23 @@ impl<T> Drop for NamedRecordIter<'_, T> {
     }
 }
 
-impl<T: NextRecord> std::iter::FusedIterator for NamedRecordIter<'_, T> {}
+impl<T> std::iter::FusedIterator for NamedRecordIter<'_, T> {}
+
+/// Cursor over the raw definitions of a [`RecordKeeper`].
+///
+/// See [`RecordKeeperRef::raw_cursor`].
+#[derive(Debug, Clone)]
+pub struct RawRecordCursor<'a>(NamedRecordIter<'a, IsDef>);
+
+impl Iterator for RawRecordCursor<'_> {
+    type Item = TableGenRecordRef;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_raw().map(|(_, record)| record)
+    }
+}
+
+impl std::iter::FusedIterator for RawRecordCursor<'_> {}
 
 /// Iterator over records derived from a given class in a [`RecordKeeper`].
 pub struct RecordIter<'a> {
@@ -303,6 +928,7 @@ impl Drop for RecordIter<'_> {
 
 #[cfg(test)]
 mod test {
+    use super::{Backend, Record, TemplateArg};
     use crate::TableGenParser;
 
     #[test]
@@ -329,6 +955,127 @@ mod test {
         assert!(rk.defs().map(|i| i.0.unwrap()).eq(["D1", "D2", "D3"]));
     }
 
+    #[test]
+    fn instantiate_class() {
+        let mut rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class Base { int base = 1; }
+                class Op<string n, int size, bit flag = 0, int twice = !mul(size, 2)>
+                    : Base {
+                    string name = n;
+                    int width = twice;
+                    bit commutative = flag;
+                    Base parent = ?;
+                }
+                class Child<string p> { Base parent = !cast<Base>(p); }
+                class Ratio<int d> { int ratio = !div(16, d); }
+                class Checked<int size> {
+                    assert !gt(size, 0), "size must be positive";
+                }
+                def Existing : Base;
+                def Other;
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let def = rk
+            .instantiate_class("Op", "Add", &["add".into(), 16.into()])
+            .unwrap();
+        assert_eq!(def.name(), Ok("Add"));
+        assert_eq!(def.str_value("name"), Ok("add"));
+        assert_eq!(def.int_value("width"), Ok(32));
+        assert_eq!(def.bit_value("commutative"), Ok(false));
+        assert_eq!(def.int_value("base"), Ok(1));
+        assert!(def.subclass_of("Base"));
+        let def = rk.def("Add").unwrap();
+        assert_eq!(
+            rk.all_derived_definitions("Op")
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [def]
+        );
+
+        let def = rk
+            .instantiate_class(
+                "Op",
+                "Mul",
+                &["mul".into(), 8.into(), true.into(), 1.into()],
+            )
+            .unwrap();
+        assert_eq!(def.int_value("width"), Ok(1));
+        assert_eq!(def.bit_value("commutative"), Ok(true));
+        assert_eq!(rk.all_derived_definitions("Base").unwrap().count(), 3);
+
+        let def = rk
+            .instantiate_class("Child", "Sub", &[TemplateArg::String("Existing")])
+            .unwrap();
+        assert_eq!(def.def_value("parent").unwrap().name(), Ok("Existing"));
+        let def = rk.instantiate_class("Checked", "Div", &[4.into()]).unwrap();
+        assert_eq!(def.name(), Ok("Div"));
+        let def = rk.instantiate_class("Ratio", "Half", &[2.into()]).unwrap();
+        assert_eq!(def.int_value("ratio"), Ok(8));
+
+        let invalid: [(&str, &str, &[TemplateArg]); 10] = [
+            ("Op", "Add", &["add".into(), 16.into()]),
+            ("Op", "Rem", &["rem".into()]),
+            ("Op", "Rem", &[16.into(), 16.into()]),
+            ("Op", "", &["rem".into(), 16.into()]),
+            ("Base", "Rem", &[TemplateArg::Def("Existing")]),
+            ("Child", "Rem", &[TemplateArg::Def("Missing")]),
+            ("Child", "Rem", &["Missing".into()]),
+            ("Child", "Rem", &["Other".into()]),
+            ("Checked", "Rem", &[0.into()]),
+            ("Ratio", "Rem", &[0.into()]),
+        ];
+        for (class, name, args) in invalid {
+            assert!(rk.instantiate_class(class, name, args).is_err());
+        }
+        assert!(rk.instantiate_class("Missing", "Rem", &[]).is_err());
+        assert!(rk.def("Rem").is_err());
+    }
+
+    #[test]
+    fn many_defs() {
+        let source = (0..1000)
+            .map(|index| format!("def D{:04};", index))
+            .collect::<String>();
+        let rk = TableGenParser::new()
+            .add_source(&source)
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let mut defs = rk.defs();
+        assert_eq!(defs.clone().count(), 1000);
+        for index in 0..300 {
+            let (name, def) = defs.next().unwrap();
+            assert_eq!(name, Ok(format!("D{:04}", index).as_str()));
+            assert_eq!(def.name().ok(), name.ok());
+        }
+        let rest = defs.clone();
+        assert_eq!(defs.next().unwrap().0, Ok("D0300"));
+        assert_eq!(rest.map(|(name, _)| name.unwrap()).last(), Some("D0999"));
+        assert_eq!(defs.count(), 699);
+        assert_eq!(rk.classes().count(), 0);
+    }
+
+    #[test]
+    fn raw_cursor() {
+        let rk = TableGenParser::new()
+            .add_source("class C; def B : C; def A; def D : C;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        assert_eq!(
+            rk.raw_cursor()
+                .map(|raw| unsafe { Record::from_raw(raw) })
+                .collect::<Vec<_>>(),
+            rk.defs().map(|(_, def)| def).collect::<Vec<_>>()
+        );
+        assert_eq!(rk.raw_cursor().count(), 3);
+    }
+
     #[test]
     fn derived_defs() {
         let rk = TableGenParser::new()
@@ -346,10 +1093,50 @@ mod test {
             .unwrap()
             .parse()
             .expect("valid tablegen");
-        let a = rk.all_derived_definitions("A");
+        let a = rk.all_derived_definitions("A").unwrap();
         assert!(a.map(|i| i.name().unwrap().to_string()).eq(["D1", "D2"]));
-        let b = rk.all_derived_definitions("B");
+        let b = rk.all_derived_definitions("B").unwrap();
         assert!(b.map(|i| i.name().unwrap().to_string()).eq(["D2", "D3"]));
+        assert!(rk.all_derived_definitions("X").is_err());
+    }
+
+    #[test]
+    fn defs_map() {
+        let rk = TableGenParser::new()
+            .add_source("class A; def B: A; def C;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let defs = rk.defs_map();
+        assert_eq!(defs.len(), 2);
+        assert_eq!(defs["B"], rk.def("B").unwrap());
+        assert_eq!(defs["C"], rk.def("C").unwrap());
+        assert!(!defs.contains_key("A"));
+    }
+
+    #[test]
+    fn derived_defs_of() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class A;
+                class B: A;
+
+                def D1: A;
+                def D2: B;
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let b = rk.class("B").unwrap();
+        let a = b.direct_super_classes().next().unwrap();
+        let defs = rk.all_derived_definitions_of(a);
+        assert!(defs.map(|i| i.name().unwrap().to_string()).eq(["D1", "D2"]));
+        let defs = rk.all_derived_definitions_of(b);
+        assert!(defs.map(|i| i.name().unwrap().to_string()).eq(["D2"]));
+        let def = rk.def("D1").unwrap();
+        assert_eq!(rk.all_derived_definitions_of(def).count(), 0);
     }
 
     #[test]
@@ -407,7 +1194,7 @@ mod test {
             .unwrap()
             .parse()
             .expect("valid tablegen");
-        let mut iter = rk.all_derived_definitions("A");
+        let mut iter = rk.all_derived_definitions("A").unwrap();
         assert_eq!(iter.size_hint(), (3, Some(3)));
         assert_eq!(iter.len(), 3);
         iter.next();
@@ -489,7 +1276,7 @@ mod test {
             .parse()
             .expect("valid tablegen");
         // Collect from both ends alternately.
-        let mut iter = rk.all_derived_definitions("A");
+        let mut iter = rk.all_derived_definitions("A").unwrap();
         assert_eq!(iter.next().unwrap().name().unwrap(), "D1");
         assert_eq!(iter.next_back().unwrap().name().unwrap(), "D4");
         assert_eq!(iter.next().unwrap().name().unwrap(), "D2");
@@ -505,7 +1292,7 @@ mod test {
             .unwrap()
             .parse()
             .expect("valid tablegen");
-        let mut iter = rk.all_derived_definitions("A");
+        let mut iter = rk.all_derived_definitions("A").unwrap();
         assert_eq!(iter.len(), 3);
         iter.next();
         assert_eq!(iter.len(), 2);
@@ -516,4 +1303,205 @@ mod test {
         assert!(iter.next().is_none());
         assert!(iter.next_back().is_none());
     }
+
+    #[test]
+    fn yaml() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"class A; def D : A { int i = 1; bits<2> b = { 0, 1 }; string s = "x"; }"#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let mut output = Vec::new();
+        rk.to_yaml(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                "\"!instanceof\":\n",
+                "  A: [D]\n",
+                "\"!tablegen_json_version\": 1\n",
+                "D:\n",
+                "  \"!anonymous\": false\n",
+                "  \"!fields\": []\n",
+                "  \"!name\": D\n",
+                "  \"!superclasses\": [A]\n",
+                "  b: [1, 0]\n",
+                "  i: 1\n",
+                "  s: x\n",
+            )
+        );
+    }
+
+    #[test]
+    fn binary() {
+        let rk = TableGenParser::new()
+            .add_source("class A; def D : A { int i = 1; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let mut msgpack = Vec::new();
+        rk.to_msgpack(&mut msgpack).unwrap();
+        let mut cbor = Vec::new();
+        rk.to_cbor(&mut cbor).unwrap();
+        // A map with the def and the `!instanceof` and `!tablegen_json_version`
+        // entries.
+        assert_eq!(msgpack[0], 0x83);
+        assert_eq!(cbor[0], 0xa3);
+        assert!(msgpack.len() < rk.run_backend(Backend::DumpJson).unwrap().len());
+        assert!(msgpack.windows(3).any(|bytes| bytes == [0xa1, b'i', 0x01]));
+        assert!(cbor.windows(3).any(|bytes| bytes == [0x61, b'i', 0x01]));
+    }
+
+    #[test]
+    fn class_hierarchy_dot() {
+        let rk = TableGenParser::new()
+            .add_source("class A; class B : A; class C : B; def D : C;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let dot = |include_defs| {
+            let mut output = Vec::new();
+            rk.class_hierarchy_dot(&mut output, include_defs).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        assert_eq!(
+            dot(false),
+            concat!(
+                "digraph classes {\n",
+                "  rankdir=BT;\n",
+                "  node [shape=box];\n",
+                "  \"A\";\n",
+                "  \"B\";\n",
+                "  \"C\";\n",
+                "  \"B\" -> \"A\";\n",
+                "  \"C\" -> \"B\";\n",
+                "}\n",
+            )
+        );
+        let with_defs = dot(true);
+        assert!(with_defs.contains("  \"D\" [shape=ellipse];\n"));
+        assert!(with_defs.contains("  \"D\" -> \"C\";\n"));
+        assert!(!with_defs.contains("  \"D\" -> \"B\";\n"));
+    }
+
+    #[test]
+    fn to_td() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class A<int x> { int i = x; string s = "a\"b"; }
+                class B : A<2>;
+                def X;
+                def D : B { list<X> l = [X]; dag d = (X 1:$a); bits<2> b = { 0, ? }; }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let td = rk.to_td().unwrap();
+        assert!(td.starts_with("class A;\nclass B : A;\n"));
+        let rk = TableGenParser::new()
+            .add_source(&td)
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let d = rk.def("D").unwrap();
+        assert!(d.subclass_of("A"));
+        assert_eq!(d.int_value("i").unwrap(), 2);
+        assert_eq!(d.str_value("s").unwrap(), "a\"b");
+        assert_eq!(d.list_of_defs_value("l").unwrap(), [rk.def("X").unwrap()]);
+        assert_eq!(d.dag_value("d").unwrap().name(0), Some("a"));
+        let bits: Vec<Option<bool>> = d.bits_init_value("b").unwrap().into();
+        assert_eq!(bits, [None, Some(false)]);
+    }
+
+    #[test]
+    fn export_table() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class I<string asm> { string AsmString = asm; bits<4> Inst = ?; }
+                def R0;
+                def ADD : I<"add a, b"> { let Inst = 0b0110; bit b = 1; }
+                def MOV : I<"mov"> { R0 Reg = R0; }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let fields = ["AsmString", "Inst", "b", "Reg"];
+        let mut csv = Vec::new();
+        rk.export_table("I", &fields, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            concat!(
+                "name,AsmString,Inst,b,Reg\n",
+                "ADD,\"add a, b\",0b0110,1,\n",
+                "MOV,mov,,,R0\n",
+            )
+        );
+        let mut tsv = Vec::new();
+        rk.export_table_tsv("I", &["AsmString"], &mut tsv).unwrap();
+        assert_eq!(tsv, b"name\tAsmString\nADD\tadd a, b\nMOV\tmov\n");
+        assert!(rk.export_table("J", &fields, Vec::new()).is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn export_sqlite() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class I<bits<4> inst> { bits<4> Inst = inst; list<int> L = [1, 2]; }
+                def R0;
+                def ADD : I<0b0110> { R0 Reg = R0; }
+                def MOV : I<{ ?, 0, 0, 1 }>;
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let path = std::env::temp_dir().join("tblgen-export-sqlite.db");
+        rk.export_sqlite(&path).unwrap();
+        rk.export_sqlite(&path).unwrap();
+
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        let mut statement = connection
+            .prepare("SELECT name, Inst, json_extract(L, '$[1]') FROM I ORDER BY name")
+            .unwrap();
+        let rows = statement
+            .query_map((), |row| {
+                Ok((
+                    row.get::<String>(0)?,
+                    row.get::<Option<i64>>(1)?,
+                    row.get::<i64>(2)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, [("ADD".into(), Some(6), 2), ("MOV".into(), None, 2)]);
+    }
+
+    #[test]
+    fn run_backend() {
+        let rk = TableGenParser::new()
+            .add_source("class A { int i = 1; } def X : A; def Y : A;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let records = rk.run_backend(Backend::PrintRecords).unwrap();
+        assert!(records.contains("class A {"));
+        assert!(records.contains("def X {\t// A\n  int i = 1;\n}\n"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&rk.run_backend(Backend::DumpJson).unwrap()).unwrap();
+        assert_eq!(json["!instanceof"]["A"], serde_json::json!(["X", "Y"]));
+        assert_eq!(
+            rk.run_backend(Backend::PrintEnums("A")).unwrap(),
+            "X, Y, \n"
+        );
+        assert!(rk.run_backend(Backend::PrintEnums("B")).is_err());
+    }
 }
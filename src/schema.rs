@@ -0,0 +1,188 @@
+//! Descriptions of the fields of classes, e.g. to generate typed accessors.
+//!
+//! ```rust
+//! use tblgen::TableGenParser;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source("class Op<string n> { string name = n; list<int> sizes = []; }")?
+//!     .parse()?;
+//! let schema = keeper.class_schema("Op")?;
+//! for field in schema.fields() {
+//!     println!("{}: {:?}", field.name(), field.ty().map(|ty| ty.to_string()));
+//! }
+//! assert_eq!(schema.field("sizes").unwrap().ty().unwrap().to_string(), "list<int>");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, Record, RecordValue, TypedInit, error::TableGenError, ty::RecTy};
+
+/// Description of the fields of a class or def.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schema<'a> {
+    record: Record<'a>,
+    fields: Vec<FieldSchema<'a>>,
+}
+
+impl<'a> Schema<'a> {
+    pub(crate) fn new(record: Record<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            record,
+            fields: record
+                .values()
+                .map(FieldSchema::new)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Returns the described record.
+    pub fn record(&self) -> Record<'a> {
+        self.record
+    }
+
+    /// Returns the name of the described record.
+    pub fn name(&self) -> Result<&'a str, Error> {
+        self.record.name()
+    }
+
+    /// Returns the direct super classes of the described record.
+    pub fn super_classes(&self) -> Vec<Record<'a>> {
+        // Older LLVM versions also return indirect super classes.
+        self.record
+            .direct_super_classes()
+            .filter(|&class| self.record.has_direct_super_class(class))
+            .collect()
+    }
+
+    /// Returns all fields, starting with the template arguments, in the order
+    /// of their declaration.
+    pub fn fields(&self) -> &[FieldSchema<'a>] {
+        &self.fields
+    }
+
+    /// Returns the field with the given name.
+    pub fn field(&self, name: &str) -> Option<&FieldSchema<'a>> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Returns the template arguments.
+    pub fn template_args(&self) -> impl Iterator<Item = &FieldSchema<'a>> {
+        self.fields.iter().filter(|field| field.template_arg)
+    }
+}
+
+/// Description of a field of a class or def.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSchema<'a> {
+    value: RecordValue<'a>,
+    name: &'a str,
+    template_arg: bool,
+}
+
+impl<'a> FieldSchema<'a> {
+    fn new(value: RecordValue<'a>) -> Result<Self, Error> {
+        let name = value.name().to_str().map_err(TableGenError::from)?;
+        let template_arg = value.is_template_arg();
+        Ok(Self {
+            value,
+            // Template arguments are qualified with the name of their class
+            // (e.g. `Op:n`) or multiclass (e.g. `M::n`).
+            name: match name.rsplit_once(':') {
+                Some((_, name)) if template_arg => name,
+                _ => name,
+            },
+            template_arg,
+        })
+    }
+
+    /// Returns the name of the field, without the class prefix of template
+    /// arguments.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the declared type of the field.
+    pub fn ty(&self) -> Option<RecTy<'a>> {
+        self.value.rec_ty()
+    }
+
+    /// Returns the default value of the field, or `None` if it is unset.
+    ///
+    /// The default value is not necessarily a literal: it may for example
+    /// refer to template arguments (e.g. `int size = n;`).
+    pub fn default(&self) -> Option<TypedInit<'a>> {
+        match self.value.init() {
+            TypedInit::Invalid => None,
+            TypedInit::Bits(init)
+                if (0..init.num_bits()).all(|index| init.bit(index).is_none()) =>
+            {
+                None
+            }
+            init => Some(init),
+        }
+    }
+
+    /// Returns true if the field is a template argument.
+    pub fn is_template_arg(&self) -> bool {
+        self.template_arg
+    }
+
+    /// Returns the underlying record value.
+    pub fn value(&self) -> RecordValue<'a> {
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TableGenParser;
+
+    #[test]
+    fn class_schema() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class A<int x, string s = "d"> {
+                    int i = x; bits<2> b; list<int> l = [1];
+                }
+                class B<int y> : A<y> { string t = ?; }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.class_schema("A").unwrap();
+        assert_eq!(a.name().unwrap(), "A");
+        let names = a
+            .fields()
+            .iter()
+            .map(|field| field.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["x", "s", "i", "b", "l"]);
+        assert_eq!(
+            a.template_args()
+                .map(|field| field.name())
+                .collect::<Vec<_>>(),
+            ["x", "s"]
+        );
+
+        let x = a.field("x").unwrap();
+        assert!(x.is_template_arg());
+        assert_eq!(x.default(), None);
+        let s = a.field("s").unwrap();
+        assert_eq!(s.ty().unwrap().to_string(), "string");
+        assert_eq!(s.default().unwrap().as_string().unwrap().to_str(), Ok("d"));
+        let b = a.field("b").unwrap();
+        assert!(!b.is_template_arg());
+        assert_eq!(b.ty().unwrap().to_string(), "bits<2>");
+        assert_eq!(b.default(), None);
+        assert_eq!(a.field("l").unwrap().default().unwrap().to_string(), "[1]");
+
+        let b = rk.class("B").unwrap().schema().unwrap();
+        assert_eq!(b.super_classes(), [rk.class("A").unwrap()]);
+        assert_eq!(b.template_args().count(), 1);
+        assert_eq!(b.field("t").unwrap().default(), None);
+        assert!(rk.class_schema("C").is_err());
+    }
+}
@@ -0,0 +1,401 @@
+//! Typed views over the generic tables of `llvm/TableGen/SearchableTable.td`.
+//!
+//! The `-gen-searchable-tables` backend of `llvm-tblgen` emits a C++ enum for
+//! each `GenericEnum`, an array of rows for each `GenericTable` and a lookup
+//! function for each `SearchIndex`. The wrappers in this module expose the same
+//! information, so that equivalent Rust code can be generated.
+//!
+//! ```rust
+//! use tblgen::{TableGenParser, searchable_tables::generic_tables};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source(
+//!         r#"
+//!         class GenericTable {
+//!             string FilterClass; string FilterClassField = "";
+//!             string CppTypeName = FilterClass; list<string> Fields;
+//!             list<string> PrimaryKey = []; string PrimaryKeyName = "";
+//!         }
+//!         class SysReg<string name, int encoding> {
+//!             string Name = name; int Encoding = encoding;
+//!         }
+//!         def : SysReg<"sp", 1>;
+//!         def : SysReg<"pc", 2>;
+//!         def SysRegsList : GenericTable {
+//!             let FilterClass = "SysReg";
+//!             let Fields = ["Name", "Encoding"];
+//!             let PrimaryKey = ["Encoding"];
+//!             let PrimaryKeyName = "lookupSysRegByEncoding";
+//!         }
+//!         "#,
+//!     )?
+//!     .parse()?;
+//! let table = generic_tables(&keeper)?[0];
+//! assert_eq!(table.fields()?, ["Name", "Encoding"]);
+//! assert_eq!(table.rows(&keeper)?.len(), 2);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    Error, Record, RecordKeeper,
+    error::{TableGenError, WithLocation},
+};
+
+macro_rules! view {
+    ($name:ident, $class:literal) => {
+        impl<'a> TryFrom<Record<'a>> for $name<'a> {
+            type Error = Error;
+
+            fn try_from(record: Record<'a>) -> Result<Self, Self::Error> {
+                if record.subclass_of($class) {
+                    Ok(Self { record })
+                } else {
+                    Err(TableGenError::InitConversion {
+                        from: "Record",
+                        to: stringify!($name),
+                    }
+                    .with_location(record))
+                }
+            }
+        }
+
+        impl<'a> $name<'a> {
+            /// Returns the underlying record.
+            pub fn record(self) -> Record<'a> {
+                self.record
+            }
+        }
+    };
+}
+
+/// A table, i.e. a def deriving from the `GenericTable` class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GenericTable<'a> {
+    record: Record<'a>,
+}
+
+view!(GenericTable, "GenericTable");
+
+impl<'a> GenericTable<'a> {
+    /// Returns the name of the table, i.e. the name of the emitted array.
+    pub fn name(self) -> Result<&'a str, Error> {
+        self.record.name()
+    }
+
+    /// Returns the class the rows of the table derive from.
+    pub fn filter_class(self) -> Result<&'a str, Error> {
+        self.record.str_value("FilterClass")
+    }
+
+    /// Returns the name of the bit field that rows must set to be included
+    /// in the table, if any.
+    pub fn filter_class_field(self) -> Result<Option<&'a str>, Error> {
+        non_empty_str(self.record, "FilterClassField")
+    }
+
+    /// Returns the C++ type of the rows.
+    pub fn cpp_type_name(self) -> Result<&'a str, Error> {
+        self.record.str_value("CppTypeName")
+    }
+
+    /// Returns the names of the fields of each row, in the order of the
+    /// emitted struct.
+    pub fn fields(self) -> Result<Vec<&'a str>, Error> {
+        self.record.list_of_strings_value("Fields")
+    }
+
+    /// Returns the fields of the primary key the rows are sorted by.
+    pub fn primary_key(self) -> Result<Vec<&'a str>, Error> {
+        if self.record.has_field("PrimaryKey") {
+            self.record.list_of_strings_value("PrimaryKey")
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Returns the name of the lookup function of the primary key, if any.
+    pub fn primary_key_name(self) -> Result<Option<&'a str>, Error> {
+        non_empty_str(self.record, "PrimaryKeyName")
+    }
+
+    /// Returns the rows of the table, in the order of their names.
+    ///
+    /// `llvm-tblgen` additionally sorts the emitted rows by the primary key.
+    pub fn rows(self, keeper: &'a RecordKeeper) -> Result<Vec<Record<'a>>, Error> {
+        let filter = self.filter_class_field()?;
+        keeper
+            .all_derived_definitions(self.filter_class()?)?
+            .filter_map(|row| match filter {
+                Some(field) => match row.bit_value(field) {
+                    Ok(true) => Some(Ok(row)),
+                    Ok(false) => None,
+                    Err(error) => Some(Err(error)),
+                },
+                None => Some(Ok(row)),
+            })
+            .collect()
+    }
+
+    /// Returns the secondary search indices of the table.
+    pub fn search_indices(self, keeper: &'a RecordKeeper) -> Result<Vec<SearchIndex<'a>>, Error> {
+        Ok(search_indices(keeper)?
+            .into_iter()
+            .filter(|index| index.table().is_ok_and(|table| table == self))
+            .collect())
+    }
+}
+
+/// A secondary index of a table, i.e. a def deriving from the `SearchIndex`
+/// class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SearchIndex<'a> {
+    record: Record<'a>,
+}
+
+view!(SearchIndex, "SearchIndex");
+
+impl<'a> SearchIndex<'a> {
+    /// Returns the name of the index, i.e. the name of the emitted lookup
+    /// function.
+    pub fn name(self) -> Result<&'a str, Error> {
+        self.record.name()
+    }
+
+    /// Returns the table the index searches.
+    pub fn table(self) -> Result<GenericTable<'a>, Error> {
+        GenericTable::try_from(self.record.def_value("Table")?)
+    }
+
+    /// Returns the fields of the key of the index.
+    pub fn key(self) -> Result<Vec<&'a str>, Error> {
+        self.record.list_of_strings_value("Key")
+    }
+
+    /// Returns true if the lookup function returns early if the key is out of
+    /// the range of the keys of the rows.
+    pub fn early_out(self) -> bool {
+        self.record.bit_value("EarlyOut").is_ok_and(|bit| bit)
+    }
+}
+
+/// An enum, i.e. a def deriving from the `GenericEnum` class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GenericEnum<'a> {
+    record: Record<'a>,
+}
+
+view!(GenericEnum, "GenericEnum");
+
+impl<'a> GenericEnum<'a> {
+    /// Returns the name of the enum.
+    pub fn name(self) -> Result<&'a str, Error> {
+        self.record.name()
+    }
+
+    /// Returns the class the entries of the enum derive from.
+    pub fn filter_class(self) -> Result<&'a str, Error> {
+        self.record.str_value("FilterClass")
+    }
+
+    /// Returns the entries of the enum.
+    ///
+    /// Entries are named after the `NameField` field of their record, or after
+    /// the record itself if the enum does not specify one. If the enum does
+    /// not specify a `ValueField`, entries are sorted by name and numbered
+    /// consecutively like `llvm-tblgen` does.
+    pub fn entries(self, keeper: &'a RecordKeeper) -> Result<Vec<EnumEntry<'a>>, Error> {
+        let name_field = non_empty_str(self.record, "NameField")?;
+        let value_field = non_empty_str(self.record, "ValueField")?;
+
+        let mut entries = keeper
+            .all_derived_definitions(self.filter_class()?)?
+            .map(|record| {
+                Ok(EnumEntry {
+                    name: match name_field {
+                        Some(field) => record.str_value(field)?,
+                        None => record.name()?,
+                    },
+                    value: match value_field {
+                        Some(field) => record.int_value(field)?,
+                        None => 0,
+                    },
+                    record,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        if value_field.is_none() {
+            entries.sort_by(|a, b| a.name.cmp(b.name));
+            for (index, entry) in entries.iter_mut().enumerate() {
+                entry.value = index as i64;
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// An entry of a [`GenericEnum`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EnumEntry<'a> {
+    name: &'a str,
+    value: i64,
+    record: Record<'a>,
+}
+
+impl<'a> EnumEntry<'a> {
+    /// Returns the name of the entry.
+    pub fn name(self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the value of the entry.
+    pub fn value(self) -> i64 {
+        self.value
+    }
+
+    /// Returns the record of the entry.
+    pub fn record(self) -> Record<'a> {
+        self.record
+    }
+}
+
+/// Returns the value of a string field, or `None` if the field is missing,
+/// unset or empty.
+fn non_empty_str<'a>(record: Record<'a>, name: &str) -> Result<Option<&'a str>, Error> {
+    if !record.has_field(name) {
+        return Ok(None);
+    }
+    Ok(record
+        .optional_str_value(name)?
+        .filter(|value| !value.is_empty()))
+}
+
+/// Returns all tables of the given record keeper.
+pub fn generic_tables<'a>(keeper: &'a RecordKeeper) -> Result<Vec<GenericTable<'a>>, Error> {
+    Ok(keeper
+        .all_derived_definitions("GenericTable")?
+        .map(|record| GenericTable { record })
+        .collect())
+}
+
+/// Returns all enums of the given record keeper.
+pub fn generic_enums<'a>(keeper: &'a RecordKeeper) -> Result<Vec<GenericEnum<'a>>, Error> {
+    Ok(keeper
+        .all_derived_definitions("GenericEnum")?
+        .map(|record| GenericEnum { record })
+        .collect())
+}
+
+/// Returns all search indices of the given record keeper.
+pub fn search_indices<'a>(keeper: &'a RecordKeeper) -> Result<Vec<SearchIndex<'a>>, Error> {
+    Ok(keeper
+        .all_derived_definitions("SearchIndex")?
+        .map(|record| SearchIndex { record })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    const SEARCHABLE_TABLE: &str = r#"
+        class GenericEnum {
+            string FilterClass; string NameField = ""; string ValueField = "";
+        }
+        class GenericTable {
+            string FilterClass; string FilterClassField = "";
+            string CppTypeName = FilterClass; list<string> Fields;
+            list<string> PrimaryKey = []; string PrimaryKeyName = "";
+            bit PrimaryKeyEarlyOut = false;
+        }
+        class SearchIndex {
+            GenericTable Table = ?; list<string> Key = ?; bit EarlyOut = false;
+        }
+    "#;
+
+    fn parse(source: &str) -> RecordKeeper<'static> {
+        TableGenParser::new()
+            .add_source(&format!("{}{}", SEARCHABLE_TABLE, source))
+            .unwrap()
+            .parse()
+            .expect("valid tablegen")
+    }
+
+    #[test]
+    fn table() {
+        let rk = parse(
+            r#"
+            class Kind<bit enabled> { bit Enabled = enabled; }
+            def KindB : Kind<1>;
+            def KindA : Kind<1>;
+            def KindC : Kind<0>;
+            def Kinds : GenericEnum { let FilterClass = "Kind"; }
+            def KindTable : GenericTable {
+                let FilterClass = "Kind";
+                let FilterClassField = "Enabled";
+                let CppTypeName = "KindInfo";
+                let Fields = ["Enabled"];
+                let PrimaryKey = ["Enabled"];
+                let PrimaryKeyName = "lookupKind";
+            }
+            def lookupKindByName : SearchIndex {
+                let Table = KindTable;
+                let Key = ["Enabled"];
+                let EarlyOut = true;
+            }
+            "#,
+        );
+        let table = generic_tables(&rk).unwrap()[0];
+        assert_eq!(table.name().unwrap(), "KindTable");
+        assert_eq!(table.filter_class().unwrap(), "Kind");
+        assert_eq!(table.filter_class_field().unwrap(), Some("Enabled"));
+        assert_eq!(table.cpp_type_name().unwrap(), "KindInfo");
+        assert_eq!(table.fields().unwrap(), ["Enabled"]);
+        assert_eq!(table.primary_key().unwrap(), ["Enabled"]);
+        assert_eq!(table.primary_key_name().unwrap(), Some("lookupKind"));
+        assert_eq!(
+            table.rows(&rk).unwrap(),
+            [rk.def("KindA").unwrap(), rk.def("KindB").unwrap()]
+        );
+
+        let index = table.search_indices(&rk).unwrap()[0];
+        assert_eq!(index.name().unwrap(), "lookupKindByName");
+        assert_eq!(index.table().unwrap(), table);
+        assert_eq!(index.key().unwrap(), ["Enabled"]);
+        assert!(index.early_out());
+    }
+
+    #[test]
+    fn enums() {
+        let rk = parse(
+            r#"
+            class Kind<string name, int value> {
+                string Name = name; int Value = value;
+            }
+            def KindB : Kind<"b", 4>;
+            def KindA : Kind<"a", 7>;
+            def Kinds : GenericEnum { let FilterClass = "Kind"; }
+            def Values : GenericEnum {
+                let FilterClass = "Kind";
+                let NameField = "Name";
+                let ValueField = "Value";
+            }
+            "#,
+        );
+        let entries = |name| {
+            GenericEnum::try_from(rk.def(name).unwrap())
+                .unwrap()
+                .entries(&rk)
+                .unwrap()
+                .into_iter()
+                .map(|entry| (entry.name(), entry.value()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(entries("Kinds"), [("KindA", 0), ("KindB", 1)]);
+        assert_eq!(entries("Values"), [("a", 7), ("b", 4)]);
+        assert_eq!(generic_enums(&rk).unwrap().len(), 2);
+        assert!(GenericTable::try_from(rk.def("Kinds").unwrap()).is_err());
+    }
+}
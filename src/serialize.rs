@@ -0,0 +1,130 @@
+//! [`Serialize`] implementations for records and inits.
+//!
+//! Values are serialized in the same layout as the output of
+//! `llvm-tblgen --dump-json` (see the `dump` module).
+
+use serde::{
+    Serialize, Serializer,
+    ser::{Error as _, SerializeMap},
+};
+
+use crate::{Record, RecordKeeper, RecordValue, TypedInit, dump, json::Json};
+
+impl Serialize for Json {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Bool(value) => serializer.serialize_bool(*value),
+            Self::Int(value) => serializer.serialize_i64(*value),
+            Self::String(value) => serializer.serialize_str(value),
+            Self::Array(values) => serializer.collect_seq(values),
+            Self::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl Serialize for TypedInit<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        dump::init(*self)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for RecordValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.init().serialize(serializer)
+    }
+}
+
+impl Serialize for Record<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        dump::record(*self)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for RecordKeeper<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        dump::keeper(self)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TableGenParser;
+    use serde_json::json;
+
+    #[test]
+    fn record() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class A<int x> { int i = x; bits<2> b = { 1, ? }; }
+                class B : A<3>;
+                def op;
+                def D : B {
+                    string s = "s";
+                    list<int> l = [1, 2];
+                    dag d = (op 1, "a":$n);
+                    B r = D;
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let value = serde_json::to_value(rk.def("D").expect("def D exists")).unwrap();
+        assert_eq!(value["i"], 3);
+        assert_eq!(value["b"], json!([1, null]));
+        assert_eq!(value["s"], "s");
+        assert_eq!(value["l"], json!([1, 2]));
+        assert_eq!(value["d"]["kind"], "dag");
+        assert_eq!(value["d"]["operator"]["def"], "op");
+        assert_eq!(value["d"]["args"], json!([[1, null], ["a", "n"]]));
+        assert_eq!(value["r"]["kind"], "def");
+        assert_eq!(value["r"]["def"], "D");
+        assert_eq!(value["!name"], "D");
+        assert_eq!(value["!anonymous"], false);
+        assert_eq!(value["!superclasses"], json!(["A", "B"]));
+        assert!(value.as_object().unwrap().get("x").is_none());
+    }
+
+    #[test]
+    fn record_keeper() {
+        let rk = TableGenParser::new()
+            .add_source("class A; class B : A; class C; def D : B; def E : A;")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let value = serde_json::to_value(&rk).unwrap();
+        assert_eq!(value["D"]["!name"], "D");
+        assert_eq!(value["E"]["!superclasses"], json!(["A"]));
+        assert_eq!(
+            value["!instanceof"],
+            json!({ "A": ["D", "E"], "B": ["D"], "C": [] })
+        );
+        assert_eq!(value["!tablegen_json_version"], 1);
+    }
+
+    #[test]
+    fn template_arg() {
+        let rk = TableGenParser::new()
+            .add_source("class A<int x> { int i = x; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let value = serde_json::to_value(rk.class("A").expect("class A exists")).unwrap();
+        assert_eq!(value["i"]["kind"], "complex");
+        assert_eq!(value["i"]["printable"], "A:x");
+    }
+}
@@ -0,0 +1,709 @@
+//! Owned snapshots of all records of a record keeper.
+//!
+//! [`RecordKeeper::snapshot`] copies all classes, defs, fields and values
+//! into a [`Snapshot`] that does not borrow the record keeper. Records and
+//! values are stored contiguously and refer to each other with
+//! [`RecordId`]s and [`InitId`]s, so analyses that traverse the records many
+//! times do not cross the FFI boundary and can keep ids without lifetimes.
+//! Equal values are stored only once.
+//!
+//! ```rust
+//! use tblgen::{TableGenParser, snapshot::SnapshotInit};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source("class Op { int size = 1; } def A : Op; def B : Op { let size = 2; }")?
+//!     .parse()?;
+//! let snapshot = keeper.snapshot()?;
+//! drop(keeper);
+//!
+//! let mut sizes = Vec::new();
+//! for def in snapshot.defs() {
+//!     let def = snapshot.record(def);
+//!     if let Some(SnapshotInit::Int(size)) = def.value("size").map(|id| snapshot.init(id)) {
+//!         sizes.push((def.name(), size));
+//!     }
+//! }
+//! assert_eq!(sizes, [("A", 1), ("B", 2)]);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, ops::Range};
+
+use crate::{Error, Record, RecordKeeper, TypedInit, error::TableGenError, raw::TableGenRecordRef};
+
+/// Index of a record in a [`Snapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RecordId(u32);
+
+/// Index of a value in a [`Snapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InitId(u32);
+
+/// Range of elements in one of the arrays of a [`Snapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    start: u32,
+    len: u32,
+}
+
+impl Span {
+    fn index<T>(self, elements: &[T]) -> &[T] {
+        &elements[self.start as usize..][..self.len as usize]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RecordData {
+    name: Span,
+    super_classes: Span,
+    fields: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum InitData {
+    Unset,
+    Bit(bool),
+    Bits(Span),
+    Int(i64),
+    String(Span),
+    Code(Span),
+    List(Span),
+    Dag { operator: InitId, args: Span },
+    Def(RecordId),
+    Complex(Span),
+}
+
+/// Copy of all records of a record keeper.
+///
+/// See the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    strings: String,
+    /// Classes followed by defs, each in name order.
+    records: Vec<RecordData>,
+    classes: usize,
+    super_classes: Vec<RecordId>,
+    fields: Vec<(Span, InitId)>,
+    inits: Vec<InitData>,
+    /// Bits and list elements.
+    elements: Vec<InitId>,
+    dag_args: Vec<(Option<Span>, InitId)>,
+}
+
+impl Snapshot {
+    pub(crate) fn new(keeper: &RecordKeeper) -> Result<Self, Error> {
+        let records = keeper
+            .classes()
+            .chain(keeper.defs())
+            .map(|(_, record)| record)
+            .collect::<Vec<_>>();
+        let mut builder = Builder {
+            snapshot: Self {
+                strings: String::new(),
+                records: Vec::with_capacity(records.len()),
+                classes: keeper.classes().count(),
+                super_classes: Vec::new(),
+                fields: Vec::new(),
+                inits: Vec::new(),
+                elements: Vec::new(),
+                dag_args: Vec::new(),
+            },
+            record_ids: records
+                .iter()
+                .enumerate()
+                .map(|(index, record)| (record.to_raw(), RecordId(index as u32)))
+                .collect(),
+            strings: HashMap::new(),
+            inits: HashMap::new(),
+        };
+        for record in records {
+            builder.record(record)?;
+        }
+        Ok(builder.snapshot)
+    }
+
+    /// Writes the snapshot in the archive format of the
+    /// [`tblgen_archive`] crate, which reads it without linking LLVM.
+    ///
+    /// Records and values are in the same order in the archive.
+    #[cfg(feature = "archive")]
+    pub fn to_archive(&self) -> Vec<u8> {
+        use tblgen_archive::format::{HEADER_SIZE, MAGIC, NO_NAME, SECTIONS, VERSION, tag};
+
+        let mut records = Vec::new();
+        for record in &self.records {
+            put(
+                &mut records,
+                [
+                    record.name.start,
+                    record.name.len,
+                    record.super_classes.start,
+                    record.super_classes.len,
+                    record.fields.start,
+                    record.fields.len,
+                ],
+            );
+        }
+        let mut super_classes = Vec::new();
+        for &RecordId(id) in &self.super_classes {
+            put(&mut super_classes, [id]);
+        }
+        let mut fields = Vec::new();
+        for &(name, InitId(init)) in &self.fields {
+            put(&mut fields, [name.start, name.len, init]);
+        }
+        let mut inits = Vec::new();
+        for init in &self.inits {
+            let (kind, operands) = match *init {
+                InitData::Unset => (tag::UNSET, [0, 0, 0]),
+                InitData::Bit(value) => (tag::BIT, [value.into(), 0, 0]),
+                InitData::Bits(bits) => (tag::BITS, [bits.start, bits.len, 0]),
+                InitData::Int(value) => (tag::INT, [value as u32, (value >> 32) as u32, 0]),
+                InitData::String(value) => (tag::STRING, [value.start, value.len, 0]),
+                InitData::Code(value) => (tag::CODE, [value.start, value.len, 0]),
+                InitData::List(elements) => (tag::LIST, [elements.start, elements.len, 0]),
+                InitData::Dag {
+                    operator: InitId(operator),
+                    args,
+                } => (tag::DAG, [operator, args.start, args.len]),
+                InitData::Def(RecordId(id)) => (tag::DEF, [id, 0, 0]),
+                InitData::Complex(printable) => (tag::COMPLEX, [printable.start, printable.len, 0]),
+            };
+            put(&mut inits, [kind, operands[0], operands[1], operands[2]]);
+        }
+        let mut elements = Vec::new();
+        for &InitId(id) in &self.elements {
+            put(&mut elements, [id]);
+        }
+        let mut dag_args = Vec::new();
+        for &(name, InitId(init)) in &self.dag_args {
+            let name = name.unwrap_or(Span {
+                start: NO_NAME,
+                len: 0,
+            });
+            put(&mut dag_args, [name.start, name.len, init]);
+        }
+
+        // The sections are in the order of `tblgen_archive::format::section`.
+        let sections: [&[u8]; SECTIONS] = [
+            self.strings.as_bytes(),
+            &records,
+            &super_classes,
+            &fields,
+            &inits,
+            &elements,
+            &dag_args,
+        ];
+        let mut archive = MAGIC.to_vec();
+        put(&mut archive, [VERSION, self.classes as u32]);
+        let mut offset = HEADER_SIZE;
+        for section in sections {
+            put(&mut archive, [offset as u32, section.len() as u32]);
+            offset += section.len();
+        }
+        for section in sections {
+            archive.extend_from_slice(section);
+        }
+        archive
+    }
+
+    fn str(&self, span: Span) -> &str {
+        &self.strings[span.start as usize..][..span.len as usize]
+    }
+
+    fn ids(range: Range<usize>) -> impl ExactSizeIterator<Item = RecordId> {
+        range.map(|index| RecordId(index as u32))
+    }
+
+    /// Returns all classes in name order.
+    pub fn classes(&self) -> impl ExactSizeIterator<Item = RecordId> + use<> {
+        Self::ids(0..self.classes)
+    }
+
+    /// Returns all defs in name order.
+    pub fn defs(&self) -> impl ExactSizeIterator<Item = RecordId> + use<> {
+        Self::ids(self.classes..self.records.len())
+    }
+
+    /// Returns the class with the given name.
+    pub fn class(&self, name: &str) -> Option<RecordId> {
+        self.find(0..self.classes, name)
+    }
+
+    /// Returns the def with the given name.
+    pub fn def(&self, name: &str) -> Option<RecordId> {
+        self.find(self.classes..self.records.len(), name)
+    }
+
+    fn find(&self, range: Range<usize>, name: &str) -> Option<RecordId> {
+        let offset = range.start;
+        self.records[range]
+            .binary_search_by(|record| self.str(record.name).cmp(name))
+            .ok()
+            .map(|index| RecordId((offset + index) as u32))
+    }
+
+    /// Returns the record with the given id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id is not from this snapshot.
+    pub fn record(&self, id: RecordId) -> SnapshotRecord<'_> {
+        SnapshotRecord {
+            snapshot: self,
+            id,
+            data: &self.records[id.0 as usize],
+        }
+    }
+
+    /// Returns the value with the given id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id is not from this snapshot.
+    pub fn init(&self, id: InitId) -> SnapshotInit<'_> {
+        match self.inits[id.0 as usize] {
+            InitData::Unset => SnapshotInit::Unset,
+            InitData::Bit(value) => SnapshotInit::Bit(value),
+            InitData::Bits(bits) => SnapshotInit::Bits(bits.index(&self.elements)),
+            InitData::Int(value) => SnapshotInit::Int(value),
+            InitData::String(value) => SnapshotInit::String(self.str(value)),
+            InitData::Code(value) => SnapshotInit::Code(self.str(value)),
+            InitData::List(elements) => SnapshotInit::List(elements.index(&self.elements)),
+            InitData::Dag { operator, args } => SnapshotInit::Dag(SnapshotDag {
+                snapshot: self,
+                operator,
+                args: args.index(&self.dag_args),
+            }),
+            InitData::Def(record) => SnapshotInit::Def(record),
+            InitData::Complex(printable) => SnapshotInit::Complex(self.str(printable)),
+        }
+    }
+}
+
+/// A record of a [`Snapshot`].
+#[derive(Debug, Clone, Copy)]
+pub struct SnapshotRecord<'s> {
+    snapshot: &'s Snapshot,
+    id: RecordId,
+    data: &'s RecordData,
+}
+
+impl<'s> SnapshotRecord<'s> {
+    /// Returns the id of the record.
+    pub fn id(self) -> RecordId {
+        self.id
+    }
+
+    /// Returns the name of the record.
+    pub fn name(self) -> &'s str {
+        self.snapshot.str(self.data.name)
+    }
+
+    /// Returns true if the record is a class.
+    pub fn is_class(self) -> bool {
+        (self.id.0 as usize) < self.snapshot.classes
+    }
+
+    /// Returns the direct super classes of the record.
+    pub fn direct_super_classes(self) -> &'s [RecordId] {
+        self.data.super_classes.index(&self.snapshot.super_classes)
+    }
+
+    /// Returns the names and values of the fields of the record, in the order
+    /// of their declaration.
+    pub fn fields(self) -> impl ExactSizeIterator<Item = (&'s str, InitId)> {
+        let snapshot = self.snapshot;
+        self.data
+            .fields
+            .index(&snapshot.fields)
+            .iter()
+            .map(move |&(name, init)| (snapshot.str(name), init))
+    }
+
+    /// Returns the value of the field with the given name.
+    pub fn value(self, name: &str) -> Option<InitId> {
+        self.fields()
+            .find_map(|(field, init)| (field == name).then_some(init))
+    }
+}
+
+impl PartialEq for SnapshotRecord<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.snapshot, other.snapshot) && self.id == other.id
+    }
+}
+
+impl Eq for SnapshotRecord<'_> {}
+
+/// A value of a [`Snapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotInit<'s> {
+    /// An unset value (`?`).
+    Unset,
+    /// A bit.
+    Bit(bool),
+    /// Bits, each of which is a bit, an unset value or a complex value.
+    Bits(&'s [InitId]),
+    /// An int.
+    Int(i64),
+    /// A string.
+    String(&'s str),
+    /// A code string.
+    Code(&'s str),
+    /// A list.
+    List(&'s [InitId]),
+    /// A dag.
+    Dag(SnapshotDag<'s>),
+    /// A reference to a def.
+    Def(RecordId),
+    /// A value without a concrete representation (e.g. a reference to a
+    /// template argument), in TableGen syntax.
+    Complex(&'s str),
+}
+
+/// A dag of a [`Snapshot`].
+#[derive(Debug, Clone, Copy)]
+pub struct SnapshotDag<'s> {
+    snapshot: &'s Snapshot,
+    operator: InitId,
+    args: &'s [(Option<Span>, InitId)],
+}
+
+impl<'s> SnapshotDag<'s> {
+    /// Returns the operator, which is usually a def.
+    pub fn operator(self) -> InitId {
+        self.operator
+    }
+
+    /// Returns the number of arguments.
+    pub fn len(self) -> usize {
+        self.args.len()
+    }
+
+    /// Returns true if the dag has no arguments.
+    pub fn is_empty(self) -> bool {
+        self.args.is_empty()
+    }
+
+    /// Returns the names and values of the arguments.
+    pub fn args(self) -> impl ExactSizeIterator<Item = (Option<&'s str>, InitId)> {
+        let snapshot = self.snapshot;
+        self.args
+            .iter()
+            .map(move |&(name, init)| (name.map(|name| snapshot.str(name)), init))
+    }
+}
+
+impl PartialEq for SnapshotDag<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.snapshot, other.snapshot)
+            && self.operator == other.operator
+            && self.args == other.args
+    }
+}
+
+impl Eq for SnapshotDag<'_> {}
+
+struct Builder<'a> {
+    snapshot: Snapshot,
+    record_ids: HashMap<TableGenRecordRef, RecordId>,
+    strings: HashMap<&'a str, Span>,
+    inits: HashMap<TypedInit<'a>, InitId>,
+}
+
+impl<'a> Builder<'a> {
+    fn string(&mut self, string: &'a str) -> Span {
+        let strings = &mut self.snapshot.strings;
+        *self.strings.entry(string).or_insert_with(|| {
+            let start = strings.len() as u32;
+            strings.push_str(string);
+            Span {
+                start,
+                len: string.len() as u32,
+            }
+        })
+    }
+
+    fn owned_string(&mut self, string: &str) -> Span {
+        let start = self.snapshot.strings.len() as u32;
+        self.snapshot.strings.push_str(string);
+        Span {
+            start,
+            len: string.len() as u32,
+        }
+    }
+
+    fn record(&mut self, record: Record<'a>) -> Result<(), Error> {
+        let name = self.string(record.name()?);
+
+        let super_classes = record
+            .direct_super_classes()
+            .filter_map(|class| self.record_ids.get(&class.to_raw()).copied())
+            .collect::<Vec<_>>();
+        let super_classes = push(&mut self.snapshot.super_classes, super_classes);
+
+        let fields = record
+            .values()
+            .map(|value| {
+                let name = value.name().to_str().map_err(TableGenError::from)?;
+                Ok((self.string(name), self.init(value.init())?))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let fields = push(&mut self.snapshot.fields, fields);
+
+        self.snapshot.records.push(RecordData {
+            name,
+            super_classes,
+            fields,
+        });
+        Ok(())
+    }
+
+    fn init(&mut self, init: TypedInit<'a>) -> Result<InitId, Error> {
+        if let Some(&id) = self.inits.get(&init) {
+            return Ok(id);
+        }
+
+        let data = match init {
+            TypedInit::Bit(bit) => match bit.as_literal() {
+                Some(value) => InitData::Bit(value),
+                None => InitData::Complex(self.owned_string(&bit.to_string())),
+            },
+            TypedInit::Bits(bits) => {
+                let elements = (0..bits.num_bits())
+                    .map(|index| match bits.bit(index) {
+                        Some(bit) => self.init(TypedInit::Bit(bit)),
+                        None => self.init(TypedInit::Invalid),
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                InitData::Bits(push(&mut self.snapshot.elements, elements))
+            }
+            TypedInit::Int(int) => match i64::try_from(int) {
+                Ok(value) => InitData::Int(value),
+                Err(_) => InitData::Complex(self.owned_string(&int.to_string())),
+            },
+            TypedInit::String(string) => {
+                InitData::String(self.string(string.to_str().map_err(TableGenError::from)?))
+            }
+            TypedInit::Code(code) => {
+                InitData::Code(self.string(code.to_str().map_err(TableGenError::from)?))
+            }
+            TypedInit::List(list) => {
+                let elements = list
+                    .iter()
+                    .map(|element| self.init(element))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                InitData::List(push(&mut self.snapshot.elements, elements))
+            }
+            TypedInit::Dag(dag) => {
+                let operator = self.init(TypedInit::Def(dag.operator()?.def_init()))?;
+                let args = dag
+                    .args()
+                    .map(|(name, value)| {
+                        Ok((name.map(|name| self.string(name)), self.init(value)?))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                InitData::Dag {
+                    operator,
+                    args: push(&mut self.snapshot.dag_args, args),
+                }
+            }
+            TypedInit::Def(def) => match self.record_ids.get(&Record::from(def).to_raw()) {
+                Some(&id) => InitData::Def(id),
+                None => InitData::Complex(self.owned_string(&def.to_string())),
+            },
+            TypedInit::Invalid => InitData::Unset,
+        };
+
+        let id = InitId(self.snapshot.inits.len() as u32);
+        self.snapshot.inits.push(data);
+        self.inits.insert(init, id);
+        Ok(id)
+    }
+}
+
+/// Appends little-endian `u32`s to a section of an archive.
+#[cfg(feature = "archive")]
+fn put<const N: usize>(section: &mut Vec<u8>, values: [u32; N]) {
+    for value in values {
+        section.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// Appends elements to an array of a snapshot, and returns their range.
+fn push<T>(array: &mut Vec<T>, elements: Vec<T>) -> Span {
+    let start = array.len() as u32;
+    let len = elements.len() as u32;
+    array.extend(elements);
+    Span { start, len }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    #[test]
+    fn snapshot() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class Base;
+                class Op<int n> : Base {
+                    int size = n;
+                    bits<3> opcode = { 1, 0, ? };
+                    string name = "op";
+                    list<Op> operands = [];
+                }
+                def ins;
+                def B : Op<2> { let name = "b"; }
+                def A : Op<1> {
+                    let operands = [B, B];
+                    code body = [{ return; }];
+                    dag pattern = (ins B:$x, 3);
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let snapshot = rk.snapshot().unwrap();
+        drop(rk);
+
+        let names = |ids: &mut dyn Iterator<Item = RecordId>| {
+            ids.map(|id| snapshot.record(id).name()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(&mut snapshot.classes()), ["Base", "Op"]);
+        assert_eq!(names(&mut snapshot.defs()), ["A", "B", "ins"]);
+        assert_eq!(snapshot.class("A"), None);
+        assert_eq!(snapshot.def("Missing"), None);
+
+        let op = snapshot.class("Op").unwrap();
+        let a = snapshot.record(snapshot.def("A").unwrap());
+        let b = snapshot.def("B").unwrap();
+        assert!(snapshot.record(op).is_class());
+        assert!(!a.is_class());
+        assert_eq!(
+            a.direct_super_classes(),
+            [snapshot.class("Base").unwrap(), op]
+        );
+        assert_eq!(
+            a.fields().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["size", "opcode", "name", "operands", "body", "pattern"]
+        );
+
+        let value = |name| snapshot.init(a.value(name).unwrap());
+        assert_eq!(value("size"), SnapshotInit::Int(1));
+        assert_eq!(value("name"), SnapshotInit::String("op"));
+        assert_eq!(value("body"), SnapshotInit::Code(" return; "));
+        let SnapshotInit::Bits(bits) = value("opcode") else {
+            panic!("expected bits");
+        };
+        assert_eq!(
+            bits.iter()
+                .map(|&bit| snapshot.init(bit))
+                .collect::<Vec<_>>(),
+            [
+                SnapshotInit::Unset,
+                SnapshotInit::Bit(false),
+                SnapshotInit::Bit(true)
+            ]
+        );
+        let SnapshotInit::List(operands) = value("operands") else {
+            panic!("expected a list");
+        };
+        assert_eq!(operands.len(), 2);
+        // Equal values are stored once.
+        assert_eq!(operands[0], operands[1]);
+        assert_eq!(snapshot.init(operands[0]), SnapshotInit::Def(b));
+
+        let SnapshotInit::Dag(pattern) = value("pattern") else {
+            panic!("expected a dag");
+        };
+        assert_eq!(
+            snapshot.init(pattern.operator()),
+            SnapshotInit::Def(snapshot.def("ins").unwrap())
+        );
+        let args = pattern.args().collect::<Vec<_>>();
+        assert_eq!(args.len(), 2);
+        assert_eq!(args[0], (Some("x"), operands[0]));
+        assert_eq!(args[1].0, None);
+        assert_eq!(snapshot.init(args[1].1), SnapshotInit::Int(3));
+
+        let SnapshotInit::Complex(size) = snapshot.init(snapshot.record(op).value("size").unwrap())
+        else {
+            panic!("expected a complex value");
+        };
+        // Template arguments may be qualified with the name of the class.
+        assert!(size.ends_with('n'));
+        assert_eq!(snapshot.record(b).value("missing"), None);
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn archive() {
+        use tblgen_archive::{Archive, ArchivedInit};
+
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class Op<int n> { int size = n; bits<2> opcode = { 1, ? }; }
+                def ins;
+                def A : Op<-3> {
+                    list<string> names = ["a", "a"];
+                    dag pattern = (ins A:$x, "y");
+                    int big = 0x7fffffffffffffff;
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let bytes = rk.snapshot().unwrap().to_archive();
+        drop(rk);
+        let archive = Archive::new(&bytes).unwrap();
+
+        assert_eq!(
+            archive
+                .defs()
+                .map(|id| archive.record(id).name())
+                .collect::<Vec<_>>(),
+            ["A", "ins"]
+        );
+        let op = archive.class("Op").unwrap();
+        let a = archive.record(archive.def("A").unwrap());
+        assert_eq!(a.direct_super_classes().collect::<Vec<_>>(), [op]);
+        let value = |name| archive.init(a.value(name).unwrap());
+        assert_eq!(value("size"), ArchivedInit::Int(-3));
+        assert_eq!(value("big"), ArchivedInit::Int(i64::MAX));
+        let ArchivedInit::Bits(bits) = value("opcode") else {
+            panic!("expected bits");
+        };
+        assert_eq!(
+            bits.map(|bit| archive.init(bit)).collect::<Vec<_>>(),
+            [ArchivedInit::Unset, ArchivedInit::Bit(true)]
+        );
+        let ArchivedInit::List(names) = value("names") else {
+            panic!("expected a list");
+        };
+        assert_eq!(
+            names.map(|name| archive.init(name)).collect::<Vec<_>>(),
+            [ArchivedInit::String("a"), ArchivedInit::String("a")]
+        );
+        let ArchivedInit::Dag(pattern) = value("pattern") else {
+            panic!("expected a dag");
+        };
+        assert_eq!(
+            archive.init(pattern.operator()),
+            ArchivedInit::Def(archive.def("ins").unwrap())
+        );
+        let args = pattern.args().collect::<Vec<_>>();
+        assert_eq!(args[0].0, Some("x"));
+        assert_eq!(archive.init(args[0].1), ArchivedInit::Def(a.id()));
+        assert_eq!(args[1].0, None);
+        assert_eq!(archive.init(args[1].1), ArchivedInit::String("y"));
+    }
+}
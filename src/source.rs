@@ -0,0 +1,154 @@
+use crate::{
+    raw::{
+        TableGenParserRef, tableGenGetNumSourceBuffers, tableGenSourceBufferGetIncludeLoc,
+        tableGenSourceBufferGetName,
+    },
+    string_ref::StringRef,
+};
+
+/// A source file (or in-memory source) that was loaded while parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    name: String,
+    included_from: Option<IncludeLocation>,
+}
+
+impl SourceFile {
+    /// Returns the name of the file, as it was resolved by TableGen.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the location of the `include` directive that loaded this file,
+    /// or `None` if the file was passed to the parser directly.
+    pub fn included_from(&self) -> Option<IncludeLocation> {
+        self.included_from
+    }
+}
+
+/// Location of an `include` directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IncludeLocation {
+    /// Index of the including file in [`IncludeGraph::files`].
+    pub file: usize,
+    /// Line of the directive (starting at 1).
+    pub line: usize,
+    /// Column of the end of the directive (starting at 1).
+    pub column: usize,
+}
+
+/// Graph of the `include` directives of all files that were loaded while
+/// parsing.
+///
+/// Files are identified by their index in [`IncludeGraph::files`]. A file that
+/// is included multiple times appears multiple times in the graph. A prelude
+/// (see [`TableGenParser::add_prelude`](crate::TableGenParser::add_prelude))
+/// appears as a file named `<prelude>` that is included at the start of the
+/// main source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeGraph {
+    files: Vec<SourceFile>,
+}
+
+impl IncludeGraph {
+    /// # Safety
+    /// The passed pointer should be a valid parser.
+    pub(crate) unsafe fn from_parser(parser: TableGenParserRef) -> Self {
+        let files = (0..unsafe { tableGenGetNumSourceBuffers(parser) })
+            .map(|index| {
+                let name =
+                    unsafe { StringRef::from_raw(tableGenSourceBufferGetName(parser, index)) };
+                let (mut file, mut line, mut column) = (0, 0, 0);
+                let included = unsafe {
+                    tableGenSourceBufferGetIncludeLoc(
+                        parser,
+                        index,
+                        &mut file,
+                        &mut line,
+                        &mut column,
+                    )
+                } > 0;
+                SourceFile {
+                    name: String::from_utf8_lossy(name.as_bytes()).into_owned(),
+                    included_from: included.then_some(IncludeLocation {
+                        file,
+                        line: line as usize,
+                        column: column as usize,
+                    }),
+                }
+            })
+            .collect();
+        Self { files }
+    }
+
+    /// Returns all files in the order in which they were loaded.
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    /// Returns the indices of the files that were passed to the parser
+    /// directly.
+    pub fn roots(&self) -> impl Iterator<Item = usize> + '_ {
+        self.files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| file.included_from.is_none())
+            .map(|(index, _)| index)
+    }
+
+    /// Returns the indices of the files that are directly included by the file
+    /// with the given index.
+    pub fn includes(&self, file: usize) -> impl Iterator<Item = usize> + '_ {
+        self.files
+            .iter()
+            .enumerate()
+            .filter(move |(_, f)| f.included_from.is_some_and(|i| i.file == file))
+            .map(|(index, _)| index)
+    }
+
+    /// Returns the chain of `include` directives that loaded the file with the
+    /// given index, starting with the directive in a root file.
+    pub fn include_chain(&self, file: usize) -> Vec<IncludeLocation> {
+        let mut chain = Vec::new();
+        let mut current = self.files.get(file);
+        while let Some(location) = current.and_then(|f| f.included_from) {
+            chain.push(location);
+            current = self.files.get(location.file);
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TableGenParser;
+    use std::fs;
+
+    #[test]
+    fn include_graph() {
+        let dir = std::env::temp_dir().join(format!("tblgen-include-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("A.td"), "include \"B.td\"\ndef A;").unwrap();
+        fs::write(dir.join("B.td"), "def B;").unwrap();
+
+        let rk = TableGenParser::new()
+            .add_include_directory(dir.to_str().unwrap())
+            .add_source("\n// main\ninclude \"A.td\"\n")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let graph = rk.include_graph();
+        assert_eq!(graph.files().len(), 3);
+        assert_eq!(graph.roots().collect::<Vec<_>>(), [0]);
+        assert_eq!(graph.includes(0).collect::<Vec<_>>(), [1]);
+        assert_eq!(graph.includes(1).collect::<Vec<_>>(), [2]);
+        assert!(graph.files()[2].name().ends_with("B.td"));
+
+        let chain = graph.include_chain(2);
+        assert_eq!(chain.len(), 2);
+        assert_eq!((chain[0].file, chain[0].line), (0, 3));
+        assert_eq!((chain[1].file, chain[1].line), (1, 1));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,89 @@
+//! Export of records to SQLite databases.
+
+use rusqlite::{Connection, params_from_iter, types::Value};
+
+use crate::{
+    Error, Record, RecordKeeper, TypedInit, dump, error::TableGenError, init::BitInit, ty::RecTy,
+};
+
+pub(crate) fn write(keeper: &RecordKeeper, connection: &mut Connection) -> rusqlite::Result<()> {
+    let transaction = connection.transaction()?;
+    for (_, class) in keeper.classes() {
+        let name = class.name().map_err(conversion)?;
+        let columns = class
+            .values()
+            .filter(|value| !value.is_template_arg())
+            .map(|value| {
+                let name = value.name().to_str().map_err(TableGenError::from)?;
+                Ok((name, value.rec_ty().map_or("", column_type)))
+            })
+            .collect::<Result<Vec<_>, Error>>()
+            .map_err(conversion)?;
+
+        transaction.execute(&format!("DROP TABLE IF EXISTS {}", quote(name)), ())?;
+        transaction.execute(
+            &format!(
+                "CREATE TABLE {} (name TEXT PRIMARY KEY{})",
+                quote(name),
+                columns
+                    .iter()
+                    .map(|(name, ty)| format!(", {} {}", quote(name), ty))
+                    .collect::<String>()
+            ),
+            (),
+        )?;
+
+        let mut statement = transaction.prepare(&format!(
+            "INSERT INTO {} VALUES (?{})",
+            quote(name),
+            ", ?".repeat(columns.len())
+        ))?;
+        for def in keeper.all_derived_definitions(name).map_err(conversion)? {
+            let mut row = vec![Value::Text(def.name().map_err(conversion)?.into())];
+            for (field, _) in &columns {
+                row.push(value(def, field).map_err(conversion)?);
+            }
+            statement.execute(params_from_iter(row))?;
+        }
+    }
+    transaction.commit()
+}
+
+fn conversion(error: Error) -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(error.to_string().into())
+}
+
+/// Returns a quoted SQL identifier.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn column_type(ty: RecTy) -> &'static str {
+    match ty {
+        RecTy::Bit(_) | RecTy::Int(_) => "INTEGER",
+        RecTy::Bits(ty) if ty.num_bits() < 64 => "INTEGER",
+        _ => "TEXT",
+    }
+}
+
+fn value(record: Record, field: &str) -> Result<Value, Error> {
+    let value = record.value(field)?;
+    Ok(match value.init() {
+        TypedInit::Bit(init) => init
+            .as_literal()
+            .map_or(Value::Null, |bit| Value::Integer(bit.into())),
+        TypedInit::Bits(init) if init.num_bits() < 64 => (0..init.num_bits())
+            .try_fold(0, |value, index| {
+                let bit = init.bit(index).and_then(BitInit::as_literal)?;
+                Some(value | i64::from(bit) << index)
+            })
+            .map_or(Value::Null, Value::Integer),
+        TypedInit::Int(init) => i64::try_from(init).map_or(Value::Null, Value::Integer),
+        TypedInit::Code(init) | TypedInit::String(init) => {
+            Value::Text(init.to_str().map_err(TableGenError::from)?.into())
+        }
+        TypedInit::Def(init) => Value::Text(Record::from(init).name()?.into()),
+        TypedInit::Invalid => Value::Null,
+        init => Value::Text(dump::init(init)?.to_string()),
+    })
+}
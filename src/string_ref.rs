@@ -8,7 +8,7 @@ pub struct StringRef<'a> {
     _reference: PhantomData<&'a TableGenStringRef>,
 }
 
-impl StringRef<'_> {
+impl<'a> StringRef<'a> {
     pub unsafe fn to_raw(self) -> TableGenStringRef {
         self.raw
     }
@@ -31,10 +31,26 @@ impl StringRef<'_> {
     pub fn as_str(&self) -> Result<&str, Utf8Error> {
         <&str as TryFrom<Self>>::try_from(*self)
     }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        if self.raw.len == 0 {
+            // The data of empty references may be null, which slices do not
+            // allow.
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(self.raw.data as *const _, self.raw.len) }
+        }
+    }
 }
 
 impl<'a> From<&'a str> for StringRef<'a> {
     fn from(value: &'a str) -> Self {
+        value.as_bytes().into()
+    }
+}
+
+impl<'a> From<&'a [u8]> for StringRef<'a> {
+    fn from(value: &'a [u8]) -> Self {
         unsafe {
             StringRef::from_raw(TableGenStringRef {
                 data: value.as_ptr() as *const _,
@@ -54,6 +70,63 @@ impl<'a> TryFrom<StringRef<'a>> for &'a str {
 
 impl<'a> From<StringRef<'a>> for &'a [u8] {
     fn from(value: StringRef<'a>) -> Self {
-        unsafe { std::slice::from_raw_parts(value.raw.data as *const _, value.raw.len) }
+        value.as_bytes()
+    }
+}
+
+impl PartialEq<[u8]> for StringRef<'_> {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_bytes() == other
+    }
+}
+
+impl PartialEq<&[u8]> for StringRef<'_> {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.as_bytes() == *other
+    }
+}
+
+impl PartialEq<str> for StringRef<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for StringRef<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes() {
+        let bytes: &[u8] = b"a\xffb";
+        let string = StringRef::from(bytes);
+        assert_eq!(string.as_bytes(), bytes);
+        assert!(string.as_str().is_err());
+        assert!(string == bytes);
+        assert!(string != "ab");
+
+        let string = StringRef::from("ab");
+        assert!(string == "ab");
+        assert!(string == *"ab");
+        assert!(string == b"ab".as_slice());
+        assert_eq!(string.as_str(), Ok("ab"));
+    }
+
+    #[test]
+    fn empty() {
+        let string = unsafe {
+            StringRef::from_raw(TableGenStringRef {
+                data: std::ptr::null(),
+                len: 0,
+            })
+        };
+        assert_eq!(string.as_bytes(), b"");
+        assert!(string == "");
     }
 }
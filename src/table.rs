@@ -0,0 +1,117 @@
+//! Export of record fields as delimiter-separated values (CSV or TSV).
+
+use std::io::{self, Write};
+
+use crate::{
+    Error, Record, RecordKeeper, TypedInit,
+    error::TableGenError,
+    init::{BitInit, BitsInit},
+};
+
+/// Writes a header row with `name` and the given fields, followed by a row for
+/// each def deriving from the given class.
+pub(crate) fn write(
+    keeper: &RecordKeeper,
+    class: &str,
+    fields: &[&str],
+    delimiter: char,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    let invalid = |error: Error| io::Error::new(io::ErrorKind::InvalidData, error.to_string());
+
+    let header = ["name"].iter().chain(fields).map(|&name| name.to_owned());
+    write_row(writer, header, delimiter)?;
+    for def in keeper.all_derived_definitions(class).map_err(invalid)? {
+        let mut row = vec![def.name().map_err(invalid)?.to_owned()];
+        for &field in fields {
+            row.push(cell(def, field).map_err(invalid)?);
+        }
+        write_row(writer, row, delimiter)?;
+    }
+    Ok(())
+}
+
+fn write_row(
+    writer: &mut impl Write,
+    cells: impl IntoIterator<Item = String>,
+    delimiter: char,
+) -> io::Result<()> {
+    for (index, cell) in cells.into_iter().enumerate() {
+        if index > 0 {
+            write!(writer, "{}", delimiter)?;
+        }
+        if cell.contains([delimiter, '"', '\n', '\r']) {
+            write!(writer, "\"{}\"", cell.replace('"', "\"\""))?;
+        } else {
+            write!(writer, "{}", cell)?;
+        }
+    }
+    writeln!(writer)
+}
+
+/// Returns the text of a field, which is empty if the field is missing or
+/// unset.
+fn cell(record: Record, field: &str) -> Result<String, Error> {
+    if !record.has_field(field) {
+        return Ok(String::new());
+    }
+    Ok(match record.value(field)?.init() {
+        TypedInit::Bit(init) => bit(init),
+        TypedInit::Bits(init) => bits(init),
+        TypedInit::Int(init) => match i64::try_from(init) {
+            Ok(value) => value.to_string(),
+            Err(_) => init.to_string(),
+        },
+        TypedInit::Code(init) | TypedInit::String(init) => {
+            init.to_str().map_err(TableGenError::from)?.into()
+        }
+        TypedInit::Def(init) => Record::from(init).name()?.into(),
+        TypedInit::Invalid => String::new(),
+        init => init.to_string(),
+    })
+}
+
+fn bit(init: BitInit) -> String {
+    match init.as_literal() {
+        Some(value) => u8::from(value).to_string(),
+        None => init.to_string(),
+    }
+}
+
+/// Returns the bits as a binary literal (e.g. `0b0110`) if all bits are known,
+/// nothing if all bits are unset, and in TableGen syntax otherwise.
+fn bits(init: BitsInit) -> String {
+    if (0..init.num_bits()).all(|index| init.bit(index).is_none()) {
+        return String::new();
+    }
+    let bits = (0..init.num_bits())
+        .rev()
+        .map(|index| init.bit(index).and_then(BitInit::as_literal))
+        .collect::<Option<Vec<_>>>();
+    match bits {
+        Some(bits) => format!(
+            "0b{}",
+            bits.into_iter()
+                .map(|bit| if bit { '1' } else { '0' })
+                .collect::<String>()
+        ),
+        None => init.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        let mut output = Vec::new();
+        write_row(
+            &mut output,
+            ["a".into(), "b,c".into(), "d\"e".into(), "f\tg".into()],
+            ',',
+        )
+        .unwrap();
+        assert_eq!(output, b"a,\"b,c\",\"d\"\"e\",f\tg\n");
+    }
+}
@@ -0,0 +1,795 @@
+//! Typed views over LLVM target description records.
+//!
+//! The wrappers in this module follow the classes of `llvm/Target/Target.td`
+//! (e.g. `Instruction`, `Register`, `RegisterClass` and `Processor`), so that
+//! tools such as disassemblers or emulators can be built from the `.td` files
+//! of LLVM targets.
+//!
+//! ```rust
+//! use tblgen::{TableGenParser, target::Instruction};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source(
+//!         r#"
+//!         class Register<string n> { string AsmName = n; }
+//!         class RegisterClass<dag regs> { dag MemberList = regs; }
+//!         def add; def ins; def outs;
+//!         def R0 : Register<"r0">; def R1 : Register<"r1">;
+//!         def GPR : RegisterClass<(add R0, R1)>;
+//!         class Instruction {
+//!             dag OutOperandList; dag InOperandList; string AsmString = "";
+//!             bits<8> Inst;
+//!         }
+//!         def MOV : Instruction {
+//!             bits<4> rd; bits<4> rs;
+//!             let OutOperandList = (outs GPR:$rd);
+//!             let InOperandList = (ins GPR:$rs);
+//!             let Inst{7-4} = rd;
+//!             let Inst{3-0} = rs;
+//!         }
+//!         "#,
+//!     )?
+//!     .parse()?;
+//! let mov = Instruction::try_from(keeper.def("MOV")?)?;
+//! assert_eq!(mov.inputs()?[0].name(), Some("rs"));
+//! assert_eq!(mov.operand_bits("rd")?, [(4, 0), (5, 1), (6, 2), (7, 3)]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    Error, Record, RecordKeeper, TypedInit,
+    error::{TableGenError, WithLocation},
+    init::{BitInit, BitsInit, DagInit},
+};
+
+macro_rules! view {
+    ($name:ident, $class:literal) => {
+        impl<'a> TryFrom<Record<'a>> for $name<'a> {
+            type Error = Error;
+
+            fn try_from(record: Record<'a>) -> Result<Self, Self::Error> {
+                if record.subclass_of($class) {
+                    Ok(Self { record })
+                } else {
+                    Err(TableGenError::InitConversion {
+                        from: "Record",
+                        to: stringify!($name),
+                    }
+                    .with_location(record))
+                }
+            }
+        }
+
+        impl<'a> $name<'a> {
+            /// Returns the underlying record.
+            pub fn record(self) -> Record<'a> {
+                self.record
+            }
+        }
+    };
+}
+
+/// An instruction, i.e. a def deriving from the `Instruction` class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instruction<'a> {
+    record: Record<'a>,
+}
+
+view!(Instruction, "Instruction");
+
+impl<'a> Instruction<'a> {
+    /// Returns the namespace of the target (e.g. `X86`).
+    pub fn namespace(self) -> Result<&'a str, Error> {
+        self.record.str_value("Namespace")
+    }
+
+    /// Returns the assembly string (e.g. `add $dst, $src`).
+    pub fn asm_string(self) -> Result<&'a str, Error> {
+        self.record.str_value("AsmString")
+    }
+
+    /// Returns the operands of the `OutOperandList` dag.
+    pub fn outputs(self) -> Result<Vec<InstructionOperand<'a>>, Error> {
+        self.operand_list("OutOperandList")
+    }
+
+    /// Returns the operands of the `InOperandList` dag.
+    pub fn inputs(self) -> Result<Vec<InstructionOperand<'a>>, Error> {
+        self.operand_list("InOperandList")
+    }
+
+    /// Returns the output operands followed by the input operands, in the
+    /// order of the `MachineInstr` operands.
+    pub fn operands(self) -> Result<Vec<InstructionOperand<'a>>, Error> {
+        let mut operands = self.outputs()?;
+        operands.extend(self.inputs()?);
+        Ok(operands)
+    }
+
+    fn operand_list(self, field: &str) -> Result<Vec<InstructionOperand<'a>>, Error> {
+        Ok(self
+            .record
+            .value(field)?
+            .dag_args::<Record>()?
+            .into_iter()
+            .map(|(name, record)| InstructionOperand { name, record })
+            .collect())
+    }
+
+    /// Returns the size of the instruction in bytes, or 0 if unknown.
+    pub fn size(self) -> Result<i64, Error> {
+        self.record.int_value("Size")
+    }
+
+    /// Returns the registers implicitly read by the instruction.
+    pub fn implicit_uses(self) -> Result<Vec<Register<'a>>, Error> {
+        self.registers("Uses")
+    }
+
+    /// Returns the registers implicitly written by the instruction.
+    pub fn implicit_defs(self) -> Result<Vec<Register<'a>>, Error> {
+        self.registers("Defs")
+    }
+
+    fn registers(self, field: &str) -> Result<Vec<Register<'a>>, Error> {
+        self.record
+            .list_of_defs_value(field)?
+            .into_iter()
+            .map(Register::try_from)
+            .collect()
+    }
+
+    /// Returns the predicates required to select the instruction.
+    pub fn predicates(self) -> Result<Vec<Record<'a>>, Error> {
+        self.record.list_of_defs_value("Predicates")
+    }
+
+    /// Returns true if the instruction is a pseudo instruction without an
+    /// encoding.
+    pub fn is_pseudo(self) -> bool {
+        self.record.bit_value("isPseudo").is_ok_and(|bit| bit)
+    }
+
+    /// Returns true if the instruction is only used by code generation and
+    /// not by the assembler or disassembler.
+    pub fn is_code_gen_only(self) -> bool {
+        self.record.bit_value("isCodeGenOnly").is_ok_and(|bit| bit)
+    }
+
+    /// Returns the encoding of the instruction, i.e. its `Inst` field, if any.
+    ///
+    /// Bits of the encoding either are fixed or refer to bits of operand
+    /// fields (see [`operand_bits`](Self::operand_bits)).
+    pub fn encoding(self) -> Option<BitsInit<'a>> {
+        if self.record.has_field("Inst") {
+            self.record.bits_init_value("Inst").ok()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the bits of the encoding that refer to the field with the given
+    /// name as pairs of the index in the encoding and the index in the field.
+    pub fn operand_bits(self, name: &str) -> Result<Vec<(usize, usize)>, Error> {
+        let encoding = self.record.bits_init_value("Inst")?;
+        Ok((0..encoding.num_bits())
+            .filter_map(|index| {
+                let (var, bit) = encoding.bit(index)?.as_var_bit()?;
+                (var == name).then_some((index, bit))
+            })
+            .collect())
+    }
+
+    /// Returns the itinerary class of the instruction, if any.
+    pub fn itinerary(self) -> Option<Record<'a>> {
+        self.record.optional_def_value("Itinerary")
+    }
+
+    /// Returns the scheduling reads and writes of the instruction (the
+    /// `SchedRW` field).
+    pub fn sched_rw(self) -> Result<Vec<Record<'a>>, Error> {
+        self.record.list_of_defs_value("SchedRW")
+    }
+}
+
+/// A named operand of the `OutOperandList` or `InOperandList` dag of an
+/// instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InstructionOperand<'a> {
+    name: Option<&'a str>,
+    record: Record<'a>,
+}
+
+impl<'a> InstructionOperand<'a> {
+    /// Returns the name of the operand (e.g. `dst` for `GR32:$dst`).
+    pub fn name(self) -> Option<&'a str> {
+        self.name
+    }
+
+    /// Returns the operand record, e.g. a register class, a
+    /// `RegisterOperand` or an `Operand`.
+    pub fn record(self) -> Record<'a> {
+        self.record
+    }
+
+    /// Returns the register class of a register operand.
+    ///
+    /// Operands of a `RegisterOperand` return the register class of its
+    /// `RegClass` field.
+    pub fn register_class(self) -> Option<RegisterClass<'a>> {
+        let record = if self.record.subclass_of("RegisterOperand") {
+            self.record.def_value("RegClass").ok()?
+        } else {
+            self.record
+        };
+        RegisterClass::try_from(record).ok()
+    }
+
+    /// Returns the operand type of an `Operand` (e.g. `OPERAND_IMMEDIATE`).
+    pub fn operand_type(self) -> Option<&'a str> {
+        if self.record.has_field("OperandType") {
+            self.record.str_value("OperandType").ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// A register, i.e. a def deriving from the `Register` class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Register<'a> {
+    record: Record<'a>,
+}
+
+view!(Register, "Register");
+
+impl<'a> Register<'a> {
+    /// Returns the namespace of the target (e.g. `X86`).
+    pub fn namespace(self) -> Result<&'a str, Error> {
+        self.record.str_value("Namespace")
+    }
+
+    /// Returns the name of the register in assembly (e.g. `eax`).
+    pub fn asm_name(self) -> Result<&'a str, Error> {
+        self.record.str_value("AsmName")
+    }
+
+    /// Returns the alternative names of the register in assembly.
+    pub fn alt_names(self) -> Result<Vec<&'a str>, Error> {
+        self.record.list_of_strings_value("AltNames")
+    }
+
+    /// Returns the registers aliasing this register.
+    pub fn aliases(self) -> Result<Vec<Register<'a>>, Error> {
+        self.registers("Aliases")
+    }
+
+    /// Returns the sub-registers of this register.
+    pub fn sub_registers(self) -> Result<Vec<Register<'a>>, Error> {
+        self.registers("SubRegs")
+    }
+
+    fn registers(self, field: &str) -> Result<Vec<Register<'a>>, Error> {
+        self.record
+            .list_of_defs_value(field)?
+            .into_iter()
+            .map(Register::try_from)
+            .collect()
+    }
+
+    /// Returns the DWARF register numbers, one per DWARF flavour.
+    pub fn dwarf_numbers(self) -> Result<Vec<i64>, Error> {
+        self.record.list_of_ints_value("DwarfNumbers")
+    }
+
+    /// Returns the hardware encoding of the register (the `HWEncoding`
+    /// field), with unset bits read as zero.
+    pub fn hw_encoding(self) -> Result<u64, Error> {
+        let bits = self.record.bits_init_value("HWEncoding")?;
+        Ok((0..bits.num_bits().min(64))
+            .filter(|&index| bits.bit(index).and_then(BitInit::as_literal) == Some(true))
+            .fold(0, |value, index| value | 1 << index))
+    }
+}
+
+/// A register class, i.e. a def deriving from the `RegisterClass` class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegisterClass<'a> {
+    record: Record<'a>,
+}
+
+view!(RegisterClass, "RegisterClass");
+
+impl<'a> RegisterClass<'a> {
+    /// Returns the namespace of the target (e.g. `X86`).
+    pub fn namespace(self) -> Result<&'a str, Error> {
+        self.record.str_value("Namespace")
+    }
+
+    /// Returns the value types of the registers in the class.
+    pub fn types(self) -> Result<Vec<Record<'a>>, Error> {
+        self.record.list_of_defs_value("RegTypes")
+    }
+
+    /// Returns the alignment of the registers in bits.
+    pub fn alignment(self) -> Result<i64, Error> {
+        self.record.int_value("Alignment")
+    }
+
+    /// Returns true if the registers of the class can be allocated.
+    pub fn is_allocatable(self) -> bool {
+        self.record.bit_value("isAllocatable").is_ok_and(|bit| bit)
+    }
+
+    /// Returns the registers of the class in allocation order.
+    ///
+    /// Only unions of registers and register classes (`add`) are evaluated;
+    /// other set operations such as `sub` or `sequence` result in an error.
+    pub fn registers(self) -> Result<Vec<Register<'a>>, Error> {
+        let mut registers = Vec::new();
+        self.collect_registers(self.record, &mut registers)?;
+        Ok(registers)
+    }
+
+    fn collect_registers(
+        self,
+        record: Record<'a>,
+        registers: &mut Vec<Register<'a>>,
+    ) -> Result<(), Error> {
+        let members = record.dag_value("MemberList")?;
+        let operator = members.operator()?.name()?;
+        if operator != "add" {
+            return Err(TableGenError::InvalidArgument(format!(
+                "unsupported register set operator {}",
+                operator
+            ))
+            .with_location(self.record));
+        }
+        for (_, member) in record.value("MemberList")?.dag_args::<Record>()? {
+            if member.subclass_of("RegisterClass") {
+                self.collect_registers(member, registers)?;
+            } else {
+                let register = Register::try_from(member)?;
+                if !registers.contains(&register) {
+                    registers.push(register);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A processor, i.e. a def deriving from the `Processor` class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Processor<'a> {
+    record: Record<'a>,
+}
+
+view!(Processor, "Processor");
+
+impl<'a> Processor<'a> {
+    /// Returns the name of the processor (e.g. `skylake`).
+    pub fn name(self) -> Result<&'a str, Error> {
+        self.record.str_value("Name")
+    }
+
+    /// Returns the subtarget features enabled by the processor.
+    pub fn features(self) -> Result<Vec<Record<'a>>, Error> {
+        self.record.list_of_defs_value("Features")
+    }
+
+    /// Returns the tuning features of the processor.
+    ///
+    /// Older LLVM versions do not have tuning features, in which case the list
+    /// is empty.
+    pub fn tune_features(self) -> Result<Vec<Record<'a>>, Error> {
+        if self.record.has_field("TuneFeatures") {
+            self.record.list_of_defs_value("TuneFeatures")
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Returns the scheduling model of the processor, if any.
+    pub fn sched_model(self) -> Option<Record<'a>> {
+        self.record.optional_def_value("SchedModel")
+    }
+
+    /// Returns the itineraries of the processor, if any.
+    pub fn itineraries(self) -> Option<Record<'a>> {
+        self.record.optional_def_value("ProcItin")
+    }
+}
+
+/// An instruction selection pattern, i.e. a def deriving from the `Pattern`
+/// class (e.g. `Pat<(add GPR:$a, GPR:$b), (ADD $a, $b)>`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pattern<'a> {
+    record: Record<'a>,
+}
+
+view!(Pattern, "Pattern");
+
+impl<'a> Pattern<'a> {
+    /// Returns the source pattern to match (the `PatternToMatch` field).
+    pub fn source(self) -> Result<PatternNode<'a>, Error> {
+        PatternNode::from_dag(None, self.record.dag_value("PatternToMatch")?)
+    }
+
+    /// Returns the result patterns (the `ResultInstrs` field). `Pat` defs have
+    /// a single result.
+    pub fn results(self) -> Result<Vec<PatternNode<'a>>, Error> {
+        self.record
+            .list_init_value("ResultInstrs")?
+            .iter()
+            .map(|init| PatternNode::new(None, init))
+            .collect()
+    }
+
+    /// Returns the predicates required to apply the pattern.
+    pub fn predicates(self) -> Result<Vec<Record<'a>>, Error> {
+        self.record.list_of_defs_value("Predicates")
+    }
+
+    /// Returns the complexity added to the pattern to prioritize it.
+    pub fn added_complexity(self) -> Result<i64, Error> {
+        self.record.int_value("AddedComplexity")
+    }
+}
+
+/// A node of an instruction selection pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternNode<'a> {
+    /// An operator applied to operands, e.g. `(add GPR:$a, 1)`.
+    Dag {
+        /// The operator, e.g. an SDNode such as `add` or an instruction.
+        operator: Record<'a>,
+        /// The name the node is captured as, e.g. `x` for `(add ...):$x`.
+        name: Option<&'a str>,
+        /// The operands of the operator.
+        operands: Vec<PatternNode<'a>>,
+    },
+    /// A leaf constrained by a def, e.g. `GPR:$a` or `imm`.
+    Def {
+        /// The constraint, e.g. a register class or a `PatFrag`.
+        constraint: Record<'a>,
+        /// The name the leaf is captured as.
+        name: Option<&'a str>,
+    },
+    /// An integer leaf, e.g. `1`.
+    Int {
+        /// The value of the integer.
+        value: i64,
+        /// The name the leaf is captured as.
+        name: Option<&'a str>,
+    },
+    /// An unconstrained leaf referring to a captured name, e.g. `$a`.
+    Name(&'a str),
+}
+
+impl<'a> PatternNode<'a> {
+    fn new(name: Option<&'a str>, init: TypedInit<'a>) -> Result<Self, Error> {
+        Ok(match init {
+            TypedInit::Dag(init) => Self::from_dag(name, init)?,
+            TypedInit::Def(init) => Self::Def {
+                constraint: init.into(),
+                name,
+            },
+            TypedInit::Int(init) => Self::Int {
+                value: init.try_into()?,
+                name,
+            },
+            TypedInit::Invalid => match name {
+                Some(name) => Self::Name(name),
+                None => return Err(TableGenError::MissingValue("pattern leaf".into()).into()),
+            },
+            _ => {
+                return Err(TableGenError::InitConversion {
+                    from: init.variant_name(),
+                    to: "PatternNode",
+                }
+                .into());
+            }
+        })
+    }
+
+    fn from_dag(name: Option<&'a str>, init: DagInit<'a>) -> Result<Self, Error> {
+        Ok(Self::Dag {
+            operator: init.operator()?,
+            name,
+            operands: init
+                .args()
+                .map(|(name, init)| Self::new(name, init))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Returns the name the node is captured as, if any.
+    pub fn name(&self) -> Option<&'a str> {
+        match self {
+            Self::Dag { name, .. } | Self::Def { name, .. } | Self::Int { name, .. } => *name,
+            Self::Name(name) => Some(name),
+        }
+    }
+
+    /// Returns the operator of a dag node.
+    pub fn operator(&self) -> Option<Record<'a>> {
+        match self {
+            Self::Dag { operator, .. } => Some(*operator),
+            _ => None,
+        }
+    }
+
+    /// Returns the operands of a dag node, or an empty slice for leaves.
+    pub fn operands(&self) -> &[PatternNode<'a>] {
+        match self {
+            Self::Dag { operands, .. } => operands,
+            _ => &[],
+        }
+    }
+
+    /// Returns the names captured by the node and its operands in pre-order,
+    /// without duplicates.
+    pub fn captured_names(&self) -> Vec<&'a str> {
+        fn visit<'a>(node: &PatternNode<'a>, names: &mut Vec<&'a str>) {
+            if let Some(name) = node.name()
+                && !names.contains(&name)
+            {
+                names.push(name);
+            }
+            for operand in node.operands() {
+                visit(operand, names);
+            }
+        }
+
+        let mut names = Vec::new();
+        visit(self, &mut names);
+        names
+    }
+}
+
+macro_rules! all {
+    ($function:ident, $name:ident, $class:literal, $doc:literal) => {
+        #[doc = $doc]
+        pub fn $function<'a>(keeper: &'a RecordKeeper) -> Result<Vec<$name<'a>>, Error> {
+            Ok(keeper
+                .all_derived_definitions($class)?
+                .map(|record| $name { record })
+                .collect())
+        }
+    };
+}
+
+all!(
+    instructions,
+    Instruction,
+    "Instruction",
+    "Returns all instructions of the given record keeper."
+);
+all!(
+    registers,
+    Register,
+    "Register",
+    "Returns all registers of the given record keeper."
+);
+all!(
+    register_classes,
+    RegisterClass,
+    "RegisterClass",
+    "Returns all register classes of the given record keeper."
+);
+all!(
+    patterns,
+    Pattern,
+    "Pattern",
+    "Returns all instruction selection patterns of the given record keeper."
+);
+all!(
+    processors,
+    Processor,
+    "Processor",
+    "Returns all processors of the given record keeper."
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    const TARGET: &str = r#"
+        class Register<string n, list<string> altNames = []> {
+            string Namespace = "Test"; string AsmName = n;
+            list<string> AltNames = altNames;
+            list<Register> Aliases = []; list<Register> SubRegs = [];
+            list<int> DwarfNumbers = []; bits<16> HWEncoding = 0;
+        }
+        class ValueType;
+        def i32 : ValueType;
+        def add; def sequence; def ins; def outs;
+        class RegisterClass<list<ValueType> types, int alignment, dag regs> {
+            string Namespace = "Test"; list<ValueType> RegTypes = types;
+            int Alignment = alignment; dag MemberList = regs;
+            bit isAllocatable = 1;
+        }
+        class RegisterOperand<RegisterClass c> { RegisterClass RegClass = c; }
+        class Operand { string OperandType = "OPERAND_UNKNOWN"; }
+        class Predicate;
+        class InstrItinClass;
+        def NoItinerary : InstrItinClass;
+        class SchedReadWrite;
+        def WriteALU : SchedReadWrite;
+        class Instruction {
+            string Namespace = "Test"; string AsmString = "";
+            dag OutOperandList; dag InOperandList;
+            int Size = 0; list<Register> Uses = []; list<Register> Defs = [];
+            list<Predicate> Predicates = []; bit isPseudo = 0;
+            bit isCodeGenOnly = 0; InstrItinClass Itinerary = NoItinerary;
+            list<SchedReadWrite> SchedRW = [];
+        }
+        class SchedMachineModel;
+        class ProcessorItineraries;
+        class SubtargetFeature;
+        def FeatureA : SubtargetFeature;
+        class Processor<string n, list<SubtargetFeature> f> {
+            string Name = n; list<SubtargetFeature> Features = f;
+            list<SubtargetFeature> TuneFeatures = [];
+            SchedMachineModel SchedModel = ?;
+            ProcessorItineraries ProcItin = ?;
+        }
+    "#;
+
+    fn parse(source: &str) -> RecordKeeper<'static> {
+        TableGenParser::new()
+            .add_source(&format!("{}{}", TARGET, source))
+            .unwrap()
+            .parse()
+            .expect("valid tablegen")
+    }
+
+    #[test]
+    fn instruction() {
+        let rk = parse(
+            r#"
+            def SP : Register<"sp">;
+            def R0 : Register<"r0", ["x0"]> { let HWEncoding = 5; }
+            def GPR : RegisterClass<[i32], 32, (add R0)>;
+            def GPROp : RegisterOperand<GPR>;
+            def imm : Operand { let OperandType = "OPERAND_IMMEDIATE"; }
+            def PUSH : Instruction {
+                bits<4> rs; bits<4> imm;
+                let OutOperandList = (outs);
+                let InOperandList = (ins GPROp:$rs, imm:$imm);
+                let AsmString = "push $rs, $imm";
+                let Size = 1;
+                let Uses = [SP];
+                let Defs = [SP];
+                let SchedRW = [WriteALU];
+                bits<8> Inst;
+                let Inst{7-4} = imm;
+                let Inst{3-0} = rs;
+            }
+            "#,
+        );
+        let push = Instruction::try_from(rk.def("PUSH").unwrap()).unwrap();
+        assert_eq!(push.namespace().unwrap(), "Test");
+        assert_eq!(push.asm_string().unwrap(), "push $rs, $imm");
+        assert_eq!(push.size().unwrap(), 1);
+        assert!(push.outputs().unwrap().is_empty());
+        let operands = push.operands().unwrap();
+        assert_eq!(operands[0].name(), Some("rs"));
+        assert_eq!(
+            operands[0].register_class().map(RegisterClass::record),
+            Some(rk.def("GPR").unwrap())
+        );
+        assert_eq!(operands[1].operand_type(), Some("OPERAND_IMMEDIATE"));
+        assert_eq!(operands[1].register_class(), None);
+        let sp = Register::try_from(rk.def("SP").unwrap()).unwrap();
+        assert_eq!(push.implicit_uses().unwrap(), [sp]);
+        assert_eq!(push.implicit_defs().unwrap(), [sp]);
+        assert_eq!(push.sched_rw().unwrap(), [rk.def("WriteALU").unwrap()]);
+        assert_eq!(push.itinerary(), Some(rk.def("NoItinerary").unwrap()));
+        assert!(!push.is_pseudo());
+        assert_eq!(push.encoding().map(|inst| inst.num_bits()), Some(8));
+        assert_eq!(
+            push.operand_bits("rs").unwrap(),
+            [(0, 0), (1, 1), (2, 2), (3, 3)]
+        );
+        assert_eq!(instructions(&rk).unwrap(), [push]);
+    }
+
+    #[test]
+    fn registers() {
+        let rk = parse(
+            r#"
+            def R0 : Register<"r0", ["x0"]> { let HWEncoding = 5; }
+            def R1 : Register<"r1">;
+            def R2 : Register<"r2">;
+            def Low : RegisterClass<[i32], 32, (add R0, R1)>;
+            def All : RegisterClass<[i32], 32, (add R2, Low, R1)>;
+            def Seq : RegisterClass<[i32], 32, (sequence "R%u", 0, 1)>;
+            "#,
+        );
+        let register = |name| Register::try_from(rk.def(name).unwrap()).unwrap();
+        let r0 = register("R0");
+        assert_eq!(r0.asm_name().unwrap(), "r0");
+        assert_eq!(r0.alt_names().unwrap(), ["x0"]);
+        assert_eq!(r0.hw_encoding().unwrap(), 5);
+        assert!(r0.aliases().unwrap().is_empty());
+
+        let class = |name| RegisterClass::try_from(rk.def(name).unwrap()).unwrap();
+        let all = class("All");
+        assert_eq!(all.types().unwrap(), [rk.def("i32").unwrap()]);
+        assert_eq!(all.alignment().unwrap(), 32);
+        assert!(all.is_allocatable());
+        assert_eq!(
+            all.registers().unwrap(),
+            [register("R2"), r0, register("R1")]
+        );
+        assert!(class("Seq").registers().is_err());
+        assert_eq!(super::registers(&rk).unwrap().len(), 3);
+        assert_eq!(register_classes(&rk).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn pattern() {
+        let rk = parse(
+            r#"
+            def GPR : RegisterClass<[i32], 32, (add)>;
+            class SDNode;
+            def shl : SDNode; def imm : SDNode;
+            def ADDri : Instruction;
+            class Pattern<dag source, list<dag> results> {
+                dag PatternToMatch = source; list<dag> ResultInstrs = results;
+                list<Predicate> Predicates = []; int AddedComplexity = 0;
+            }
+            class Pat<dag source, dag result> : Pattern<source, [result]>;
+            def HasShift : Predicate;
+            def : Pat<(add (shl GPR:$a, 1):$s, imm:$i), (ADDri $s, imm:$i)> {
+                let Predicates = [HasShift];
+                let AddedComplexity = 2;
+            }
+            "#,
+        );
+        let pattern = patterns(&rk).unwrap()[0];
+        assert_eq!(pattern.predicates().unwrap(), [rk.def("HasShift").unwrap()]);
+        assert_eq!(pattern.added_complexity().unwrap(), 2);
+
+        let source = pattern.source().unwrap();
+        assert_eq!(source.operator(), Some(rk.def("add").unwrap()));
+        assert_eq!(source.captured_names(), ["s", "a", "i"]);
+        let shl = &source.operands()[0];
+        assert_eq!(shl.name(), Some("s"));
+        assert_eq!(
+            shl.operands(),
+            [
+                PatternNode::Def {
+                    constraint: rk.def("GPR").unwrap(),
+                    name: Some("a"),
+                },
+                PatternNode::Int {
+                    value: 1,
+                    name: None
+                },
+            ]
+        );
+
+        let results = pattern.results().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].operator(), Some(rk.def("ADDri").unwrap()));
+        assert_eq!(results[0].operands()[0], PatternNode::Name("s"));
+        assert_eq!(results[0].captured_names(), ["s", "i"]);
+    }
+
+    #[test]
+    fn processor() {
+        let rk = parse(r#"def : Processor<"generic", [FeatureA]>;"#);
+        let processor = processors(&rk).unwrap()[0];
+        assert_eq!(processor.name().unwrap(), "generic");
+        assert_eq!(processor.features().unwrap(), [rk.def("FeatureA").unwrap()]);
+        assert!(processor.tune_features().unwrap().is_empty());
+        assert_eq!(processor.sched_model(), None);
+        assert!(Instruction::try_from(processor.record()).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Emission of TableGen source code that re-declares resolved records.
+//!
+//! Classes are declared with their direct super classes, but without fields
+//! or template arguments. Each def declares all of its resolved fields, so
+//! that parsing the output yields defs with the same fields and super classes
+//! as the original ones.
+
+use crate::{
+    Error, Record, RecordKeeper, TypedInit,
+    error::TableGenError,
+    init::{BitInit, StringInit},
+};
+
+/// Writes a TableGen declaration of the given record.
+pub(crate) fn record(output: &mut String, record: Record) -> Result<(), Error> {
+    output.push_str(if record.is_class() { "class " } else { "def " });
+    output.push_str(record.name()?);
+    let classes = record
+        .direct_super_classes()
+        .filter(|&class| record.has_direct_super_class(class))
+        .map(Record::name)
+        .collect::<Result<Vec<_>, _>>()?;
+    if !classes.is_empty() {
+        output.push_str(" : ");
+        output.push_str(&classes.join(", "));
+    }
+    if record.is_class() {
+        output.push_str(";\n");
+        return Ok(());
+    }
+
+    output.push_str(" {\n");
+    for value in record.values() {
+        let name = value.name().to_str().map_err(TableGenError::from)?;
+        output.push_str("  ");
+        if let Some(ty) = value.rec_ty() {
+            output.push_str(&ty.to_string());
+            output.push(' ');
+        }
+        output.push_str(name);
+        output.push_str(" = ");
+        init(output, value.init())?;
+        output.push_str(";\n");
+    }
+    output.push_str("}\n");
+    Ok(())
+}
+
+/// Writes TableGen declarations of all classes and defs of the given record
+/// keeper.
+///
+/// Super classes are declared before the classes deriving from them, and defs
+/// are declared before the defs referencing them where possible.
+pub(crate) fn keeper(output: &mut String, keeper: &RecordKeeper) -> Result<(), Error> {
+    let mut declared = Vec::new();
+    for (_, class) in keeper.classes() {
+        declare(output, class, &mut declared)?;
+    }
+    for (_, def) in keeper.defs() {
+        declare(output, def, &mut declared)?;
+    }
+    Ok(())
+}
+
+fn declare<'a>(
+    output: &mut String,
+    record: Record<'a>,
+    declared: &mut Vec<Record<'a>>,
+) -> Result<(), Error> {
+    if declared.contains(&record) {
+        return Ok(());
+    }
+    // Mark the record as declared first to stop at cyclic references.
+    declared.push(record);
+    if record.is_class() {
+        for class in record.direct_super_classes() {
+            declare(output, class, declared)?;
+        }
+    } else {
+        for value in record.values() {
+            visit_defs(value.init(), &mut |def| declare(output, def, declared))?;
+        }
+    }
+    self::record(output, record)
+}
+
+/// Calls the given function with every def referenced by an init.
+fn visit_defs<'a>(
+    init: TypedInit<'a>,
+    visit: &mut impl FnMut(Record<'a>) -> Result<(), Error>,
+) -> Result<(), Error> {
+    match init {
+        TypedInit::Def(init) => visit(init.into()),
+        TypedInit::List(init) => init.iter().try_for_each(|init| visit_defs(init, visit)),
+        TypedInit::Dag(init) => {
+            visit(init.operator()?)?;
+            init.args()
+                .try_for_each(|(_, init)| visit_defs(init, visit))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn init(output: &mut String, init: TypedInit) -> Result<(), Error> {
+    match init {
+        TypedInit::Bit(init) => bit(output, init),
+        TypedInit::Bits(init) => {
+            output.push('{');
+            for index in (0..init.num_bits()).rev() {
+                output.push_str(if index + 1 == init.num_bits() {
+                    " "
+                } else {
+                    ", "
+                });
+                match init.bit(index) {
+                    Some(init) => bit(output, init),
+                    None => output.push('?'),
+                }
+            }
+            output.push_str(" }");
+        }
+        TypedInit::Int(init) => match i64::try_from(init) {
+            Ok(value) => output.push_str(&value.to_string()),
+            Err(_) => output.push_str(&init.to_string()),
+        },
+        TypedInit::Code(init) | TypedInit::String(init) => string(output, init)?,
+        TypedInit::List(init) => {
+            output.push('[');
+            for (index, element) in init.iter().enumerate() {
+                if index > 0 {
+                    output.push_str(", ");
+                }
+                self::init(output, element)?;
+            }
+            output.push(']');
+        }
+        TypedInit::Dag(init) => {
+            output.push('(');
+            output.push_str(init.operator()?.name()?);
+            for (index, (name, value)) in init.args().enumerate() {
+                output.push_str(if index == 0 { " " } else { ", " });
+                self::init(output, value)?;
+                if let Some(name) = name {
+                    output.push_str(":$");
+                    output.push_str(name);
+                }
+            }
+            output.push(')');
+        }
+        TypedInit::Def(init) => output.push_str(Record::from(init).name()?),
+        TypedInit::Invalid => output.push('?'),
+    }
+    Ok(())
+}
+
+fn bit(output: &mut String, init: BitInit) {
+    match init.as_literal() {
+        Some(value) => output.push(if value { '1' } else { '0' }),
+        None => output.push_str(&init.to_string()),
+    }
+}
+
+/// Writes a string literal, escaping the characters that TableGen supports
+/// escape sequences for.
+fn string(output: &mut String, init: StringInit) -> Result<(), Error> {
+    output.push('"');
+    for c in init.to_str().map_err(TableGenError::from)?.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\'' => output.push_str("\\'"),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    Ok(())
+}
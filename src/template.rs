@@ -0,0 +1,243 @@
+//! Rendering of [`minijinja`] templates driven by records.
+//!
+//! A [`RenderContext`] exposes the following variables to templates:
+//!
+//! - `defs`: all defs, by name,
+//! - `classes`: all classes, by name,
+//! - `instances`: the defs deriving from each class, by class name and in
+//!   name order.
+//!
+//! Records are maps with the keys `name`, `super_classes` (the names of all
+//! super classes) and `fields` (the values of all fields, by name). Values
+//! are converted as follows:
+//!
+//! - `bit` values are converted to booleans, and `bits` values with all bits
+//!   known to integers,
+//! - ints are converted to integers, strings and code to strings, and lists to
+//!   sequences,
+//! - defs are converted to their names, which can be looked up in `defs`,
+//! - dags are converted to maps with the keys `operator` (the name of the
+//!   operator), `args` and `names`,
+//! - unset values are converted to `none`,
+//! - any other values (e.g. references to template arguments) are converted
+//!   to strings in TableGen syntax.
+//!
+//! ```rust
+//! use tblgen::{TableGenParser, template::RenderContext};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source(
+//!         r#"
+//!         class Op<string s> { string summary = s; }
+//!         def AddOp : Op<"addition">;
+//!         def SubOp : Op<"subtraction">;
+//!         "#,
+//!     )?
+//!     .parse()?;
+//! let output = RenderContext::new(&keeper)?.render_str(
+//!     "{% for op in instances.Op %}{{ op.name }}: {{ op.fields.summary }}\n{% endfor %}",
+//! )?;
+//! assert_eq!(output, "AddOp: addition\nSubOp: subtraction\n");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+
+use minijinja::{Environment, Template, Value};
+
+use crate::{
+    Error, Record, RecordKeeper, TypedInit, dump,
+    error::TableGenError,
+    init::{BitInit, BitsInit},
+};
+
+/// Variables of a template rendered with records.
+#[derive(Debug, Clone)]
+pub struct RenderContext {
+    variables: BTreeMap<String, Value>,
+}
+
+impl RenderContext {
+    /// Creates a context with all classes and defs of a record keeper.
+    pub fn new(keeper: &RecordKeeper) -> Result<Self, Error> {
+        let mut defs = BTreeMap::new();
+        for (name, def) in keeper.defs() {
+            defs.insert(name.map_err(TableGenError::from)?, record(def)?);
+        }
+        let mut classes = BTreeMap::new();
+        let mut instances = BTreeMap::new();
+        for (name, class) in keeper.classes() {
+            let name = name.map_err(TableGenError::from)?;
+            classes.insert(name, record(class)?);
+            instances.insert(
+                name,
+                keeper
+                    .all_derived_definitions(name)?
+                    .map(|def| Ok(defs[def.name()?].clone()))
+                    .collect::<Result<Vec<_>, Error>>()?,
+            );
+        }
+
+        Ok(Self {
+            variables: BTreeMap::from([
+                ("defs".into(), defs.into()),
+                ("classes".into(), classes.into()),
+                ("instances".into(), instances.into()),
+            ]),
+        })
+    }
+
+    /// Adds a variable, replacing any existing variable with the same name.
+    pub fn insert(&mut self, name: &str, value: impl Into<Value>) {
+        self.variables.insert(name.into(), value.into());
+    }
+
+    /// Returns the variables as a map, to be passed to e.g.
+    /// [`Template::render`].
+    pub fn value(&self) -> Value {
+        self.variables.clone().into()
+    }
+
+    /// Renders a template of an environment.
+    pub fn render(&self, template: &Template) -> Result<String, minijinja::Error> {
+        template.render(self.value())
+    }
+
+    /// Renders a template given as a string in a default environment.
+    pub fn render_str(&self, source: &str) -> Result<String, minijinja::Error> {
+        Environment::new().render_str(source, self.value())
+    }
+}
+
+/// Converts a record to a template value.
+pub fn record(record: Record) -> Result<Value, Error> {
+    let mut fields = BTreeMap::new();
+    for value in record.values().filter(|value| !value.is_template_arg()) {
+        fields.insert(
+            value.name().to_str().map_err(TableGenError::from)?,
+            init(value.init())?,
+        );
+    }
+    Ok(BTreeMap::from([
+        ("name", Value::from(record.name()?)),
+        (
+            "super_classes",
+            dump::super_classes(record)
+                .into_iter()
+                .map(|class| Ok(Value::from(class.name()?)))
+                .collect::<Result<Vec<_>, Error>>()?
+                .into(),
+        ),
+        ("fields", fields.into()),
+    ])
+    .into())
+}
+
+/// Converts an init to a template value.
+pub fn init(init: TypedInit) -> Result<Value, Error> {
+    Ok(match init {
+        TypedInit::Bit(init) => match init.as_literal() {
+            Some(value) => value.into(),
+            None => init.to_string().into(),
+        },
+        TypedInit::Bits(init) => match bits(init) {
+            Some(value) => value.into(),
+            None => init.to_string().into(),
+        },
+        TypedInit::Int(init) => match i64::try_from(init) {
+            Ok(value) => value.into(),
+            Err(_) => init.to_string().into(),
+        },
+        TypedInit::Code(init) | TypedInit::String(init) => {
+            init.to_str().map_err(TableGenError::from)?.into()
+        }
+        TypedInit::List(init) => init
+            .iter()
+            .map(self::init)
+            .collect::<Result<Vec<_>, _>>()?
+            .into(),
+        TypedInit::Dag(init) => {
+            let (names, args) = init
+                .args()
+                .map(|(name, value)| {
+                    Ok((
+                        name.map_or(Value::from(()), Value::from),
+                        self::init(value)?,
+                    ))
+                })
+                .collect::<Result<(Vec<_>, Vec<_>), Error>>()?;
+            BTreeMap::from([
+                ("operator", Value::from(init.operator()?.name()?)),
+                ("args", args.into()),
+                ("names", names.into()),
+            ])
+            .into()
+        }
+        TypedInit::Def(init) => Record::from(init).name()?.into(),
+        TypedInit::Invalid => ().into(),
+    })
+}
+
+/// Returns the value of bits if all bits are known.
+fn bits(init: BitsInit) -> Option<u64> {
+    if init.num_bits() > 64 {
+        return None;
+    }
+    (0..init.num_bits()).try_fold(0, |value, index| {
+        let bit = init.bit(index).and_then(BitInit::as_literal)?;
+        Some(value | u64::from(bit) << index)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    #[test]
+    fn render() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class A<int x> { int i = x; bits<4> b = { 0, 1, 1, 0 }; }
+                class B : A<3>;
+                def op;
+                def D : B {
+                    string s = "s";
+                    list<int> l = [1, 2];
+                    dag d = (op 1, "a":$n);
+                    B r = D;
+                    int u = ?;
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let mut context = RenderContext::new(&rk).unwrap();
+        context.insert("prefix", "X");
+        let render = |source: &str| context.render_str(source).unwrap();
+
+        assert_eq!(render("{{ prefix }}{{ defs.D.name }}"), "XD");
+        assert_eq!(render("{{ defs.D.super_classes|join(',') }}"), "A,B");
+        assert_eq!(render("{{ instances.A|map(attribute='name')|join }}"), "D");
+        assert_eq!(render("{{ classes.A.fields.i }}"), "A:x");
+        let d = rk.def("D").unwrap();
+        assert_eq!(
+            init(d.value("i").unwrap().init()).unwrap(),
+            Value::from(3i64)
+        );
+        assert_eq!(render("{{ defs.D.fields.b }}"), "6");
+        assert_eq!(render("{{ defs.D.fields.l|sum }}"), "3");
+        assert_eq!(
+            render(
+                "{{ defs.D.fields.d.operator }} {{ defs.D.fields.d.args }} {{ defs.D.fields.d.names }}"
+            ),
+            r#"op [1, "a"] [none, "n"]"#
+        );
+        assert_eq!(render("{{ defs[defs.D.fields.r].name }}"), "D");
+        assert_eq!(render("{{ defs.D.fields.u is none }}"), "true");
+    }
+}
@@ -0,0 +1,187 @@
+//! Deterministic dumps of records for golden tests (e.g. with `insta`).
+//!
+//! Unlike [`Record`]'s `Display` implementation, dumps do not depend on the
+//! rest of the sources: records are sorted by name, fields are printed in
+//! declaration order without template arguments, and the numbers of
+//! anonymous records are replaced by their order of appearance in the dump.
+//!
+//! ```rust
+//! use tblgen::{TableGenParser, testing};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source(
+//!         r#"
+//!         class Op<string n> { string name = n; list<int> sizes = []; }
+//!         def SubOp: Op<"sub">;
+//!         def AddOp: Op<"add">;
+//!         "#,
+//!     )?
+//!     .parse()?;
+//! assert_eq!(
+//!     testing::dump_derived(&keeper, "Op")?,
+//!     "def AddOp: Op {\n  string name = \"add\";\n  list<int> sizes = [];\n}\n\n\
+//!      def SubOp: Op {\n  string name = \"sub\";\n  list<int> sizes = [];\n}\n"
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::{Error, Record, RecordKeeper};
+
+/// Returns a dump of the given records.
+pub fn dump<'a>(records: impl IntoIterator<Item = Record<'a>>) -> String {
+    let mut records: Vec<_> = records.into_iter().collect();
+    // Anonymous records are sorted in the order in which they were created,
+    // since their names contain a counter of all anonymous records.
+    records.sort_by_cached_key(|record| {
+        if record.anonymous() {
+            (true, record.id(), Vec::new())
+        } else {
+            (false, 0, record.name_bytes().to_vec())
+        }
+    });
+
+    let first = records.first().copied();
+    let mut output = String::new();
+    for (index, record) in records.into_iter().enumerate() {
+        if index > 0 {
+            output.push('\n');
+        }
+        self::record(&mut output, record);
+    }
+    // Anonymous records referenced by the dumped records are not necessarily
+    // dumped themselves, so the names are collected from the record keeper.
+    let anonymous = first
+        .into_iter()
+        .flat_map(|record| record.record_keeper().defs())
+        .filter(|(_, def)| def.anonymous())
+        .map(|(_, def)| String::from_utf8_lossy(def.name_bytes()).into_owned())
+        .collect();
+    normalize_anonymous(&output, &anonymous)
+}
+
+/// Returns a dump of the defs deriving from the class with the given name.
+///
+/// # Errors
+///
+/// Returns an error if the class is not defined.
+pub fn dump_derived(keeper: &RecordKeeper, class: &str) -> Result<String, Error> {
+    Ok(dump(keeper.all_derived_definitions(class)?))
+}
+
+/// Returns a dump of all defs.
+pub fn dump_defs(keeper: &RecordKeeper) -> String {
+    dump(keeper.defs().map(|(_, def)| def))
+}
+
+fn record(output: &mut String, record: Record) {
+    let name = |record: Record| String::from_utf8_lossy(record.name_bytes()).into_owned();
+    output.push_str(if record.is_class() { "class " } else { "def " });
+    output.push_str(&name(record));
+    let classes: Vec<_> = record
+        .direct_super_classes()
+        .filter(|&class| record.has_direct_super_class(class))
+        .map(name)
+        .collect();
+    if !classes.is_empty() {
+        output.push_str(": ");
+        output.push_str(&classes.join(", "));
+    }
+    output.push_str(" {\n");
+    for value in record.values().filter(|value| !value.is_template_arg()) {
+        output.push_str("  ");
+        if let Some(ty) = value.rec_ty() {
+            // Writing to a string cannot fail.
+            let _ = write!(output, "{} ", ty);
+        }
+        output.push_str(&String::from_utf8_lossy(value.name().as_bytes()));
+        output.push_str(" = ");
+        if value.is_unset() {
+            output.push('?');
+        } else {
+            // Writing to a string cannot fail.
+            let _ = write!(output, "{}", value.init());
+        }
+        output.push_str(";\n");
+    }
+    output.push_str("}\n");
+}
+
+/// Replaces the numbers of the given names of anonymous records (e.g.
+/// `anonymous_42`) by their order of appearance.
+fn normalize_anonymous(dump: &str, names: &HashSet<String>) -> String {
+    const PREFIX: &str = "anonymous_";
+    let mut numbers = HashMap::new();
+    let mut output = String::with_capacity(dump.len());
+    let mut rest = dump;
+    while let Some(start) = rest.find(PREFIX) {
+        let (before, after) = rest.split_at(start);
+        output.push_str(before);
+        let end = PREFIX.len()
+            + after[PREFIX.len()..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(after.len() - PREFIX.len());
+        let (name, next) = after.split_at(end);
+        // Names must not be part of a longer identifier.
+        let identifier = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        let separate = !before.ends_with(identifier) && !next.starts_with(identifier);
+        if separate && names.contains(name) {
+            let next = numbers.len();
+            output.push_str(PREFIX);
+            // Writing to a string cannot fail.
+            let _ = write!(output, "{}", numbers.entry(name).or_insert(next));
+        } else {
+            output.push_str(name);
+        }
+        rest = next;
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableGenParser;
+
+    #[test]
+    fn dump_records() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class A<int x> { int a = x; string s = ?; }
+                def B { A a = A<2>; }
+                def C: A<1> { list<A> l = [A<3>, A<2>]; }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        assert_eq!(
+            dump_defs(&rk),
+            "def B {\n  A a = anonymous_0;\n}\n\n\
+             def C: A {\n  int a = 1;\n  string s = ?;\n  list<A> l = [anonymous_1, anonymous_0];\n}\n\n\
+             def anonymous_0: A {\n  int a = 2;\n  string s = ?;\n}\n\n\
+             def anonymous_1: A {\n  int a = 3;\n  string s = ?;\n}\n"
+        );
+        assert!(dump_derived(&rk, "X").is_err());
+    }
+
+    #[test]
+    fn normalize() {
+        let names = ["anonymous_3", "anonymous_7"].map(String::from).into();
+        assert_eq!(
+            normalize_anonymous(
+                "anonymous_7 anonymous_3 anonymous_7 anonymous_ anonymous_5 xanonymous_3 \"anonymous_3\"",
+                &names
+            ),
+            "anonymous_0 anonymous_1 anonymous_0 anonymous_ anonymous_5 xanonymous_3 \"anonymous_1\""
+        );
+    }
+}
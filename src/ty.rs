@@ -0,0 +1,605 @@
+//! This module contains references to the types of TableGen values (`RecTy`
+//! in TableGen).
+//!
+//! Types are obtained from the declaration of a field with
+//! [`RecordValue::rec_ty`](crate::RecordValue::rec_ty) or from a value with
+//! [`TypedInit::rec_ty`](crate::TypedInit::rec_ty).
+//!
+//! ```rust
+//! use tblgen::{TableGenParser, ty::RecTy};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = TableGenParser::new()
+//!     .add_source("def A { list<int> l = []; }")?
+//!     .parse()?;
+//! let field = keeper.def("A")?.value("l")?;
+//! assert!(matches!(field.rec_ty(), Some(RecTy::List(_))));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    ffi::c_void,
+    fmt::{self, Debug, Display, Formatter},
+    marker::PhantomData,
+};
+
+use crate::raw::{
+    TableGenRecTyKind, TableGenRecTyRef, tableGenBitsRecTyGetNumBits,
+    tableGenListRecTyGetElementType, tableGenRecTyGetClass, tableGenRecTyGetKind,
+    tableGenRecTyGetNumClasses, tableGenRecTyGetRecordKeeper, tableGenRecTyIsA,
+    tableGenRecTyIsConvertibleTo, tableGenRecTyPrint, tableGenRecTyResolveTypes,
+};
+use crate::{
+    record::Record,
+    record_keeper::RecordKeeperRef,
+    util::{print_callback, raw_fn},
+};
+use paste::paste;
+
+/// Enum that holds a reference to a `RecTy`.
+///
+/// Types are displayed as they are written in TableGen, e.g. `bits<5>` or
+/// `list<Register>`.
+///
+/// TableGen creates each type only once per record keeper, so two types are
+/// equal if and only if they refer to the same object.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RecTy<'a> {
+    Bit(BitRecTy<'a>),
+    Bits(BitsRecTy<'a>),
+    Int(IntRecTy<'a>),
+    String(StringRecTy<'a>),
+    List(ListRecTy<'a>),
+    Dag(DagRecTy<'a>),
+    Record(RecordRecTy<'a>),
+}
+
+impl Display for RecTy<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Bit(ty) => Display::fmt(ty, formatter),
+            Self::Bits(ty) => Display::fmt(ty, formatter),
+            Self::Int(ty) => Display::fmt(ty, formatter),
+            Self::String(ty) => Display::fmt(ty, formatter),
+            Self::List(ty) => Display::fmt(ty, formatter),
+            Self::Dag(ty) => Display::fmt(ty, formatter),
+            Self::Record(ty) => Display::fmt(ty, formatter),
+        }
+    }
+}
+
+macro_rules! as_inner {
+    ($name:ident, $variant:ident, $type:ident) => {
+        paste! {
+            #[doc = concat!(
+                "Returns the [`", stringify!($type), "`] if this is a `",
+                stringify!($variant), "` type."
+            )]
+            pub fn [<as_ $name>](self) -> Option<$type<'a>> {
+                match self {
+                    Self::$variant(ty) => Some(ty),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl<'a> RecTy<'a> {
+    as_inner!(bit, Bit, BitRecTy);
+    as_inner!(bits, Bits, BitsRecTy);
+    as_inner!(int, Int, IntRecTy);
+    as_inner!(string, String, StringRecTy);
+    as_inner!(list, List, ListRecTy);
+    as_inner!(dag, Dag, DagRecTy);
+    as_inner!(record, Record, RecordRecTy);
+
+    raw_fn! {
+        /// Creates a new type from a raw object.
+        ///
+        /// Returns `None` if the object is null or if its kind is unknown.
+        ///
+        /// # Safety
+        ///
+        /// The raw object must be null or valid.
+        #[allow(non_upper_case_globals)]
+        unsafe fn from_raw(raw: TableGenRecTyRef) -> Option<Self> {
+            use TableGenRecTyKind::*;
+
+            if raw.is_null() {
+                return None;
+            }
+            Some(match unsafe { tableGenRecTyGetKind(raw) } {
+                TableGenBitRecTyKind => Self::Bit(unsafe { BitRecTy::from_raw(raw) }),
+                TableGenBitsRecTyKind => Self::Bits(unsafe { BitsRecTy::from_raw(raw) }),
+                TableGenIntRecTyKind => Self::Int(unsafe { IntRecTy::from_raw(raw) }),
+                TableGenStringRecTyKind => Self::String(unsafe { StringRecTy::from_raw(raw) }),
+                TableGenListRecTyKind => Self::List(unsafe { ListRecTy::from_raw(raw) }),
+                TableGenDagRecTyKind => Self::Dag(unsafe { DagRecTy::from_raw(raw) }),
+                TableGenRecordRecTyKind => Self::Record(unsafe { RecordRecTy::from_raw(raw) }),
+                _ => return None,
+            })
+        }
+    }
+
+    raw_fn! {
+        /// Returns the raw object.
+        fn to_raw(self) -> TableGenRecTyRef {
+            match self {
+                Self::Bit(ty) => ty.raw,
+                Self::Bits(ty) => ty.raw,
+                Self::Int(ty) => ty.raw,
+                Self::String(ty) => ty.raw,
+                Self::List(ty) => ty.raw,
+                Self::Dag(ty) => ty.raw,
+                Self::Record(ty) => ty.raw,
+            }
+        }
+    }
+
+    /// Returns true if values of this type can be converted to the given
+    /// type, i.e. if TableGen accepts them for a field of that type.
+    ///
+    /// For example, `int` is convertible to `bits<8>`, and a record type is
+    /// convertible to another record type if it is a subclass of all its
+    /// classes.
+    pub fn convertible_to(self, other: RecTy<'a>) -> bool {
+        unsafe { tableGenRecTyIsConvertibleTo(self.to_raw(), other.to_raw()) > 0 }
+    }
+
+    /// Returns true if this type is the given type or, for record types, a
+    /// subtype of it.
+    ///
+    /// Unlike [`RecTy::convertible_to`], this does not allow conversions
+    /// between different kinds of types.
+    pub fn is_a(self, other: RecTy<'a>) -> bool {
+        unsafe { tableGenRecTyIsA(self.to_raw(), other.to_raw()) > 0 }
+    }
+
+    /// Returns the least common type that values of both types can be
+    /// converted to, as determined by TableGen (e.g. for the elements of a list
+    /// literal), or `None` if there is no such type.
+    ///
+    /// For record types, the common type is a record type of the classes that
+    /// both types are a subclass of.
+    ///
+    /// ```rust
+    /// use tblgen::TableGenParser;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let keeper = TableGenParser::new()
+    ///     .add_source(
+    ///         r#"
+    ///         class A;
+    ///         def B { A a = ?; bit b = 0; int i = 0; string s = ""; }
+    ///         "#,
+    ///     )?
+    ///     .parse()?;
+    /// let b = keeper.def("B")?;
+    /// let types = ["b", "i"].map(|name| b.value(name).unwrap().rec_ty().unwrap());
+    /// let common = types[1..]
+    ///     .iter()
+    ///     .try_fold(types[0], |common, &ty| common.resolve_types(ty));
+    /// assert_eq!(common.map(|ty| ty.to_string()).as_deref(), Some("int"));
+    ///
+    /// let s = b.value("s")?.rec_ty().unwrap();
+    /// assert!(s.resolve_types(types[0]).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve_types(self, other: RecTy<'a>) -> Option<RecTy<'a>> {
+        unsafe { RecTy::from_raw(tableGenRecTyResolveTypes(self.to_raw(), other.to_raw())) }
+    }
+
+    /// Returns the record keeper that owns this type.
+    ///
+    /// ```rust
+    /// use tblgen::TableGenParser;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let keeper = TableGenParser::new()
+    ///     .add_source("class A; def B { A a = ?; }")?
+    ///     .parse()?;
+    /// let ty = keeper.def("B")?.value("a")?.rec_ty().unwrap();
+    /// assert!(ty.record_keeper().class("A").is_ok());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn record_keeper(self) -> RecordKeeperRef<'a> {
+        unsafe { RecordKeeperRef::from_raw(tableGenRecTyGetRecordKeeper(self.to_raw())) }
+    }
+
+    /// Returns the [`TableGenRecTyKind`](crate::raw::TableGenRecTyKind) of
+    /// this type.
+    pub fn kind(self) -> TableGenRecTyKind::Type {
+        match self {
+            Self::Bit(_) => TableGenRecTyKind::TableGenBitRecTyKind,
+            Self::Bits(_) => TableGenRecTyKind::TableGenBitsRecTyKind,
+            Self::Int(_) => TableGenRecTyKind::TableGenIntRecTyKind,
+            Self::String(_) => TableGenRecTyKind::TableGenStringRecTyKind,
+            Self::List(_) => TableGenRecTyKind::TableGenListRecTyKind,
+            Self::Dag(_) => TableGenRecTyKind::TableGenDagRecTyKind,
+            Self::Record(_) => TableGenRecTyKind::TableGenRecordRecTyKind,
+        }
+    }
+}
+
+macro_rules! rec_ty {
+    ($name:ident) => {
+        #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name<'a> {
+            raw: TableGenRecTyRef,
+            _reference: PhantomData<&'a TableGenRecTyRef>,
+        }
+
+        impl<'a> $name<'a> {
+            raw_fn! {
+                /// Creates a new type from a raw object.
+                ///
+                /// # Safety
+                ///
+                /// The raw object must be valid.
+                unsafe fn from_raw(raw: TableGenRecTyRef) -> Self {
+                    Self {
+                        raw,
+                        _reference: PhantomData,
+                    }
+                }
+            }
+
+            raw_fn! {
+                /// Returns the raw object.
+                fn to_raw(self) -> TableGenRecTyRef {
+                    self.raw
+                }
+            }
+        }
+
+        impl Display for $name<'_> {
+            fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+                let mut data = (formatter, Ok(()));
+
+                unsafe {
+                    tableGenRecTyPrint(
+                        self.raw,
+                        Some(print_callback),
+                        &mut data as *mut _ as *mut c_void,
+                    );
+                }
+
+                data.1
+            }
+        }
+
+        impl Debug for $name<'_> {
+            fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+                write!(formatter, "{}(", stringify!($name))?;
+                Display::fmt(self, formatter)?;
+                write!(formatter, ")")
+            }
+        }
+    };
+}
+
+rec_ty!(BitRecTy);
+rec_ty!(BitsRecTy);
+rec_ty!(IntRecTy);
+rec_ty!(StringRecTy);
+rec_ty!(ListRecTy);
+rec_ty!(DagRecTy);
+rec_ty!(RecordRecTy);
+
+impl BitsRecTy<'_> {
+    /// Returns the declared number of bits, e.g. 5 for `bits<5>`.
+    pub fn num_bits(self) -> usize {
+        unsafe { tableGenBitsRecTyGetNumBits(self.raw) }
+    }
+}
+
+impl<'a> ListRecTy<'a> {
+    /// Returns the declared type of the elements of this list type.
+    ///
+    /// Unlike [`ListInit::element_type`](crate::init::ListInit::element_type),
+    /// this does not depend on the elements of a particular value, so it is
+    /// also available for empty lists.
+    pub fn element_type(self) -> Option<RecTy<'a>> {
+        unsafe { RecTy::from_raw(tableGenListRecTyGetElementType(self.raw)) }
+    }
+}
+
+impl<'a> RecordRecTy<'a> {
+    /// Returns the number of classes that a value of this type must be a
+    /// subclass of.
+    pub fn num_classes(self) -> usize {
+        unsafe { tableGenRecTyGetNumClasses(self.raw) }
+    }
+
+    /// Returns the class at the given index.
+    pub fn class(self, index: usize) -> Option<Record<'a>> {
+        let ptr = unsafe { tableGenRecTyGetClass(self.raw, index) };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { Record::from_raw(ptr) })
+        }
+    }
+
+    /// Returns the classes that a value of this type must be a subclass of.
+    ///
+    /// A def satisfies this type if it is a subclass of all returned classes.
+    pub fn classes(self) -> impl Iterator<Item = Record<'a>> {
+        (0..self.num_classes()).filter_map(move |index| self.class(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TableGenParser, TypedInit};
+
+    #[test]
+    fn rec_ty() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class C;
+                def D: C;
+                def A {
+                    bit b = 0;
+                    bits<4> bs = 0;
+                    int i = 1;
+                    string s = "";
+                    code c = [{}];
+                    list<int> l = [];
+                    dag d = (ins);
+                    C r = D;
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        let ty = |name| a.value(name).unwrap().rec_ty().unwrap();
+        assert!(ty("b").as_bit().is_some());
+        assert!(ty("bs").as_bits().is_some());
+        assert!(ty("i").as_int().is_some());
+        assert!(ty("s").as_string().is_some());
+        assert!(ty("c").as_string().is_some());
+        assert!(ty("l").as_list().is_some());
+        assert!(ty("d").as_dag().is_some());
+        assert!(ty("r").as_record().is_some());
+        assert!(ty("r").as_int().is_none());
+        assert_eq!(ty("i").kind(), TableGenRecTyKind::TableGenIntRecTyKind);
+
+        let init = a.value("l").unwrap().init();
+        assert!(matches!(init.rec_ty(), Some(RecTy::List(_))));
+    }
+
+    #[test]
+    fn list_element_type() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class Attr;
+                def A {
+                    list<Attr> attrs = [];
+                    list<list<int>> nested = [[1]];
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let a = rk.def("A").unwrap();
+        let element = |name| {
+            a.value(name)
+                .unwrap()
+                .rec_ty()
+                .and_then(RecTy::as_list)
+                .and_then(ListRecTy::element_type)
+                .unwrap()
+        };
+        assert!(element("attrs").as_record().is_some());
+        let inner = element("nested").as_list().unwrap().element_type().unwrap();
+        assert!(inner.as_int().is_some());
+    }
+
+    #[test]
+    fn record_classes() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class A;
+                class B;
+                def D: A, B;
+                def E: A;
+                class C<A a> {
+                    A x = a;
+                }
+                def F: C<D>;
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let f = rk.def("F").unwrap();
+        let ty = f.value("x").unwrap().rec_ty().unwrap().as_record().unwrap();
+        let classes = ty.classes().collect::<Vec<_>>();
+        assert_eq!(classes.len(), 1);
+        assert_eq!(classes[0].name(), Ok("A"));
+        assert!(ty.class(1).is_none());
+
+        let d = TypedInit::Def(rk.def("D").unwrap().def_init()).rec_ty();
+        let d = d.and_then(RecTy::as_record).unwrap();
+        assert_eq!(
+            d.classes().map(|c| c.name().unwrap()).collect::<Vec<_>>(),
+            ["A", "B"]
+        );
+    }
+
+    #[test]
+    fn convertible_to() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class A;
+                class B: A;
+                def X {
+                    int i = 0;
+                    bits<8> bs = 0;
+                    bit b = 0;
+                    string s = "";
+                    A a = ?;
+                    B b2 = ?;
+                    list<int> li = [];
+                    list<bits<8>> lb = [];
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let x = rk.def("X").unwrap();
+        let ty = |name| x.value(name).unwrap().rec_ty().unwrap();
+        assert!(ty("i").convertible_to(ty("bs")));
+        assert!(ty("b").convertible_to(ty("i")));
+        assert!(!ty("s").convertible_to(ty("i")));
+        assert!(ty("b2").convertible_to(ty("a")));
+        assert!(!ty("a").convertible_to(ty("b2")));
+        assert!(ty("li").convertible_to(ty("lb")));
+
+        assert!(ty("b2").is_a(ty("a")));
+        assert!(ty("i").is_a(ty("i")));
+        assert!(!ty("i").is_a(ty("bs")));
+        assert!(!ty("li").is_a(ty("lb")));
+    }
+
+    #[test]
+    fn record_keeper() {
+        let rk = TableGenParser::new()
+            .add_source("class A; def B { A a = ?; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let ty = rk.def("B").unwrap().value("a").unwrap().rec_ty().unwrap();
+        let keeper = ty.record_keeper();
+        assert_eq!(keeper, rk.as_keeper_ref());
+        let class = keeper.class("A").unwrap();
+        assert!(ty.as_record().unwrap().classes().eq([class]));
+    }
+
+    #[test]
+    fn display() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class Register;
+                def X {
+                    bits<5> b = 0;
+                    list<Register> l = [];
+                    list<list<int>> ll = [];
+                    dag d = (ins);
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let x = rk.def("X").unwrap();
+        let ty = |name| x.value(name).unwrap().rec_ty().unwrap();
+        assert_eq!(ty("b").to_string(), "bits<5>");
+        assert_eq!(ty("l").to_string(), "list<Register>");
+        assert_eq!(ty("ll").to_string(), "list<list<int>>");
+        assert_eq!(ty("d").to_string(), "dag");
+        assert_eq!(format!("{:?}", ty("b")), "Bits(BitsRecTy(bits<5>))");
+    }
+
+    #[test]
+    fn resolve_types() {
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class A;
+                class B: A;
+                class C: A;
+                def X {
+                    B b = ?;
+                    C c = ?;
+                    list<B> lb = [];
+                    list<C> lc = [];
+                    int i = 0;
+                    string s = "";
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let x = rk.def("X").unwrap();
+        let ty = |name| x.value(name).unwrap().rec_ty().unwrap();
+        let common = ty("b").resolve_types(ty("c")).unwrap();
+        assert_eq!(common.to_string(), "A");
+        let common = ty("lb").resolve_types(ty("lc")).unwrap();
+        assert_eq!(common.to_string(), "list<A>");
+        assert!(ty("i").resolve_types(ty("s")).is_none());
+    }
+
+    #[test]
+    fn num_bits() {
+        let rk = TableGenParser::new()
+            .add_source("def X { bits<5> b = 0; }")
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let x = rk.def("X").unwrap();
+        let ty = x.value("b").unwrap().rec_ty().unwrap();
+        assert_eq!(ty.as_bits().unwrap().num_bits(), 5);
+        assert_eq!(
+            x.value("b").unwrap().init().as_bits().unwrap().num_bits(),
+            5
+        );
+    }
+
+    #[test]
+    fn equality() {
+        use std::collections::HashSet;
+
+        let rk = TableGenParser::new()
+            .add_source(
+                r#"
+                class A;
+                class B;
+                def X {
+                    int i = 0;
+                    int j = 1;
+                    bits<2> b2 = 0;
+                    bits<3> b3 = 0;
+                    list<A> la = [];
+                    list<A> la2 = [];
+                    list<B> lb = [];
+                }
+                "#,
+            )
+            .unwrap()
+            .parse()
+            .expect("valid tablegen");
+        let x = rk.def("X").unwrap();
+        let ty = |name| x.value(name).unwrap().rec_ty().unwrap();
+        assert_eq!(ty("i"), ty("j"));
+        assert_ne!(ty("b2"), ty("b3"));
+        assert_eq!(ty("la"), ty("la2"));
+        assert_ne!(ty("la"), ty("lb"));
+        assert_eq!(
+            ty("la").as_list().unwrap().element_type(),
+            ty("la2").as_list().unwrap().element_type()
+        );
+
+        let types = x
+            .values()
+            .filter_map(|value| value.rec_ty())
+            .collect::<HashSet<_>>();
+        assert_eq!(types.len(), 5);
+    }
+}
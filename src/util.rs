@@ -5,6 +5,33 @@ use std::{
 
 use crate::{error::TableGenError, raw::TableGenStringRef, string_ref::StringRef};
 
+/// Defines a `from_raw` or `to_raw` function of a wrapper type, which is public
+/// with the `raw` feature and only visible in this crate otherwise.
+macro_rules! raw_fn {
+    ($(#[$meta:meta])* fn $($rest:tt)*) => {
+        #[cfg(feature = "raw")]
+        $(#[$meta])*
+        pub fn $($rest)*
+
+        #[cfg(not(feature = "raw"))]
+        #[allow(dead_code)]
+        $(#[$meta])*
+        pub(crate) fn $($rest)*
+    };
+    ($(#[$meta:meta])* unsafe fn $($rest:tt)*) => {
+        #[cfg(feature = "raw")]
+        $(#[$meta])*
+        pub unsafe fn $($rest)*
+
+        #[cfg(not(feature = "raw"))]
+        #[allow(dead_code)]
+        $(#[$meta])*
+        pub(crate) unsafe fn $($rest)*
+    };
+}
+
+pub(crate) use raw_fn;
+
 pub(crate) unsafe extern "C" fn print_callback(string: TableGenStringRef, data: *mut c_void) {
     let (formatter, result) = unsafe { &mut *(data as *mut (&mut Formatter, fmt::Result)) };
 
@@ -40,3 +67,128 @@ pub(crate) unsafe extern "C" fn print_string_callback(
         Ok(())
     })();
 }
+
+/// Returns the name of the given type without module paths, e.g. `Vec<Record>`
+/// instead of `alloc::vec::Vec<tblgen::record::Record>`.
+pub(crate) fn short_type_name<T: ?Sized>() -> String {
+    let mut name = String::new();
+    let mut start = 0;
+    let mut chars = std::any::type_name::<T>().chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            name.truncate(start);
+        } else {
+            name.push(c);
+            if !(c.is_alphanumeric() || c == '_') {
+                start = name.len();
+            }
+        }
+    }
+    name
+}
+
+/// Returns the number of columns the given character occupies in a terminal.
+///
+/// Combining marks and zero width characters occupy no columns, while wide
+/// characters (e.g. CJK ideographs and most emoji) occupy two.
+pub(crate) fn char_width(c: char) -> usize {
+    match u32::from(c) {
+        0x0300..=0x036F
+        | 0x0483..=0x0489
+        | 0x0591..=0x05BD
+        | 0x0610..=0x061A
+        | 0x064B..=0x065F
+        | 0x1AB0..=0x1AFF
+        | 0x1DC0..=0x1DFF
+        | 0x200B..=0x200F
+        | 0x20D0..=0x20FF
+        | 0xFE00..=0xFE0F
+        | 0xFE20..=0xFE2F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Returns the indentation that aligns a caret with the end of the given
+/// prefix of a source line. Tabs are kept, so that the caret is aligned
+/// regardless of the tab width.
+pub(crate) fn caret_indent(prefix: &str) -> String {
+    let mut indent = String::with_capacity(prefix.len());
+    for c in prefix.chars() {
+        if c == '\t' {
+            indent.push('\t');
+        } else {
+            indent.extend(std::iter::repeat_n(' ', char_width(c)));
+        }
+    }
+    indent
+}
+
+/// Rust keywords that can be used as raw identifiers (e.g. `r#type`).
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Rust keywords that cannot be used as raw identifiers.
+const RESERVED: &[&str] = &["_", "crate", "self", "Self", "super"];
+
+/// Returns a Rust identifier for a name that may be a Rust keyword.
+///
+/// Keywords are escaped as raw identifiers (e.g. `r#type`), and keywords that
+/// cannot be raw identifiers (e.g. `self`) are suffixed with `_`.
+pub(crate) fn escape_keyword(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{}_", name)
+    } else if KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Record;
+
+    #[test]
+    fn short_type_names() {
+        assert_eq!(short_type_name::<String>(), "String");
+        assert_eq!(short_type_name::<Vec<Record>>(), "Vec<Record>");
+        assert_eq!(short_type_name::<&str>(), "&str");
+    }
+
+    #[test]
+    fn escape_keywords() {
+        assert_eq!(escape_keyword("Op"), "Op");
+        assert_eq!(escape_keyword("type"), "r#type");
+        assert_eq!(escape_keyword("self"), "self_");
+        assert_eq!(escape_keyword("_"), "__");
+    }
+
+    #[test]
+    fn caret_indents() {
+        assert_eq!(caret_indent("  int i"), "       ");
+        assert_eq!(caret_indent("\tü = "), "\t    ");
+        assert_eq!(caret_indent("\"日本"), "     ");
+        assert_eq!(caret_indent("e\u{301}"), " ");
+    }
+}
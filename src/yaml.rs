@@ -0,0 +1,174 @@
+use std::io::{self, Write};
+
+use crate::json::Json;
+
+/// Writes a value as a YAML document in block style.
+///
+/// The keys of objects are sorted, so that the output does not depend on the
+/// order in which values were added. Arrays of scalars are written in flow
+/// style (e.g. `[0, 1]`) to keep `bits` values on a single line.
+pub(crate) fn write(value: &Json, writer: &mut impl Write) -> io::Result<()> {
+    match value {
+        Json::Object(entries) if !entries.is_empty() => write_entries(writer, entries, 0, false),
+        Json::Array(values) if !is_flow(values) => write_items(writer, values, 0),
+        _ => {
+            write_scalar(writer, value)?;
+            writeln!(writer)
+        }
+    }
+}
+
+fn write_entries(
+    writer: &mut impl Write,
+    entries: &[(String, Json)],
+    indent: usize,
+    inline_first: bool,
+) -> io::Result<()> {
+    let mut entries = entries.iter().collect::<Vec<_>>();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (index, (key, value)) in entries.into_iter().enumerate() {
+        if index > 0 || !inline_first {
+            write!(writer, "{:indent$}", "")?;
+        }
+        write_string(writer, key)?;
+        write!(writer, ":")?;
+        write_child(writer, value, indent + 2)?;
+    }
+    Ok(())
+}
+
+fn write_items(writer: &mut impl Write, values: &[Json], indent: usize) -> io::Result<()> {
+    for value in values {
+        write!(writer, "{:indent$}-", "")?;
+        match value {
+            Json::Object(entries) if !entries.is_empty() => {
+                write!(writer, " ")?;
+                write_entries(writer, entries, indent + 2, true)?;
+            }
+            _ => write_child(writer, value, indent + 2)?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes a value after a key or list item marker, followed by a new line.
+fn write_child(writer: &mut impl Write, value: &Json, indent: usize) -> io::Result<()> {
+    match value {
+        Json::Object(entries) if !entries.is_empty() => {
+            writeln!(writer)?;
+            write_entries(writer, entries, indent, false)
+        }
+        Json::Array(values) if !is_flow(values) => {
+            writeln!(writer)?;
+            write_items(writer, values, indent)
+        }
+        _ => {
+            write!(writer, " ")?;
+            write_scalar(writer, value)?;
+            writeln!(writer)
+        }
+    }
+}
+
+fn is_flow(values: &[Json]) -> bool {
+    values
+        .iter()
+        .all(|value| !matches!(value, Json::Array(_) | Json::Object(_)))
+}
+
+/// Writes a scalar, an empty object or an array of scalars in flow style.
+fn write_scalar(writer: &mut impl Write, value: &Json) -> io::Result<()> {
+    match value {
+        Json::Null => write!(writer, "null"),
+        Json::Bool(value) => write!(writer, "{}", value),
+        Json::Int(value) => write!(writer, "{}", value),
+        Json::String(value) => write_string(writer, value),
+        Json::Array(values) => {
+            write!(writer, "[")?;
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    write!(writer, ", ")?;
+                }
+                write_scalar(writer, value)?;
+            }
+            write!(writer, "]")
+        }
+        Json::Object(_) => write!(writer, "{{}}"),
+    }
+}
+
+/// Writes a string as a plain scalar if it cannot be mistaken for another
+/// value, and as a double-quoted string otherwise.
+fn write_string(writer: &mut impl Write, value: &str) -> io::Result<()> {
+    if is_plain(value) {
+        write!(writer, "{}", value)
+    } else {
+        // JSON strings are valid double-quoted YAML strings.
+        write!(writer, "{}", Json::from(value))
+    }
+}
+
+fn is_plain(value: &str) -> bool {
+    const RESERVED: &[&str] = &[
+        "null", "~", "true", "false", "yes", "no", "on", "off", "y", "n",
+    ];
+
+    value
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '$' | '/' | '-'))
+        && !RESERVED
+            .iter()
+            .any(|reserved| value.eq_ignore_ascii_case(reserved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(value: &Json) -> String {
+        let mut output = Vec::new();
+        write(value, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn document() {
+        let value = Json::object([
+            ("b", [1i64, 0].into_iter().collect()),
+            ("a", "x y".into()),
+            (
+                "!l",
+                Json::Array(vec![
+                    Json::object([("k", "v".into()), ("e", Json::Object(vec![]))]),
+                    Json::Array(vec![Json::Array(vec![])]),
+                ]),
+            ),
+            ("o", Json::object([("n", Json::Null), ("s", "true".into())])),
+        ]);
+        assert_eq!(
+            yaml(&value),
+            concat!(
+                "\"!l\":\n",
+                "  - e: {}\n",
+                "    k: v\n",
+                "  -\n",
+                "    - []\n",
+                "a: \"x y\"\n",
+                "b: [1, 0]\n",
+                "o:\n",
+                "  n: null\n",
+                "  s: \"true\"\n",
+            )
+        );
+    }
+
+    #[test]
+    fn scalar() {
+        assert_eq!(yaml(&Json::Int(1)), "1\n");
+        assert_eq!(yaml(&Json::Object(vec![])), "{}\n");
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt::{self, Display, Formatter};
+
+/// Error of a [`JsonRecordKeeper`](crate::json_dump::JsonRecordKeeper).
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonError {
+    /// The dump is not valid JSON or not in the layout of
+    /// `llvm-tblgen --dump-json`.
+    InvalidJson(String),
+    /// The dump could not be read from a file.
+    Read(String),
+    /// A def does not have the requested field.
+    MissingValue(String),
+    /// A def does not exist.
+    MissingDef(String),
+    /// A class does not exist.
+    MissingClass(String),
+    /// A field has a different type than requested.
+    InitConversion {
+        def: String,
+        field: String,
+        from: &'static str,
+        to: &'static str,
+    },
+}
+
+impl Display for JsonError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidJson(message) => write!(formatter, "invalid JSON: {}", message),
+            Self::Read(message) => write!(formatter, "failed to read {}", message),
+            Self::MissingValue(name) => write!(formatter, "expected field {} in record", name),
+            Self::MissingDef(name) => write!(formatter, "expected def {}", name),
+            Self::MissingClass(name) => write!(formatter, "expected class {}", name),
+            Self::InitConversion {
+                def,
+                field,
+                from,
+                to,
+            } => write!(
+                formatter,
+                "field '{}' of def '{}': invalid conversion from {} to {}",
+                field, def, from, to
+            ),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
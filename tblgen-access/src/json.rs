@@ -0,0 +1,261 @@
+use std::{iter::Peekable, str::CharIndices};
+
+/// Minimal JSON value parsed from a dump of records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a JSON document.
+    ///
+    /// Numbers are limited to integers that fit into an `i64`, which is
+    /// sufficient for the output of `llvm-tblgen --dump-json`.
+    pub(crate) fn parse(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: source.char_indices().peekable(),
+            len: source.len(),
+        };
+        let value = parser.value()?;
+        parser.whitespace();
+        match parser.chars.next() {
+            None => Ok(value),
+            Some((offset, _)) => Err(format!("trailing characters at offset {}", offset)),
+        }
+    }
+
+    /// Returns the value of the given key if this is an object.
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Self::Object(entries) => entries
+                .iter()
+                .find_map(|(name, value)| (name == key).then_some(value)),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    len: usize,
+}
+
+impl Parser<'_> {
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.len, |&(offset, _)| offset)
+    }
+
+    fn error<T>(&mut self, message: &str) -> Result<T, String> {
+        Err(format!("{} at offset {}", message, self.offset()))
+    }
+
+    fn whitespace(&mut self) {
+        while self
+            .chars
+            .next_if(|(_, c)| matches!(c, ' ' | '\t' | '\n' | '\r'))
+            .is_some()
+        {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.whitespace();
+        if self.chars.next_if(|&(_, c)| c == expected).is_some() {
+            Ok(())
+        } else {
+            self.error(&format!("expected '{}'", expected))
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        for expected in keyword.chars() {
+            if self.chars.next_if(|&(_, c)| c == expected).is_none() {
+                return self.error(&format!("expected '{}'", keyword));
+            }
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        match self.chars.peek().map(|&(_, c)| c) {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.chars.next();
+                let mut values = Vec::new();
+                self.whitespace();
+                if self.chars.next_if(|&(_, c)| c == ']').is_some() {
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.whitespace();
+                    if self.chars.next_if(|&(_, c)| c == ']').is_some() {
+                        return Ok(Json::Array(values));
+                    }
+                    if self.chars.next_if(|&(_, c)| c == ',').is_none() {
+                        return self.error("expected ',' or ']'");
+                    }
+                }
+            }
+            Some('{') => {
+                self.chars.next();
+                let mut entries = Vec::new();
+                self.whitespace();
+                if self.chars.next_if(|&(_, c)| c == '}').is_some() {
+                    return Ok(Json::Object(entries));
+                }
+                loop {
+                    self.whitespace();
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    entries.push((key, self.value()?));
+                    self.whitespace();
+                    if self.chars.next_if(|&(_, c)| c == '}').is_some() {
+                        return Ok(Json::Object(entries));
+                    }
+                    if self.chars.next_if(|&(_, c)| c == ',').is_none() {
+                        return self.error("expected ',' or '}'");
+                    }
+                }
+            }
+            Some('-' | '0'..='9') => self.number(),
+            _ => self.error("expected value"),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let mut number = String::new();
+        if self.chars.next_if(|&(_, c)| c == '-').is_some() {
+            number.push('-');
+        }
+        while let Some((_, c)) = self.chars.next_if(|(_, c)| c.is_ascii_digit()) {
+            number.push(c);
+        }
+        if self
+            .chars
+            .peek()
+            .is_some_and(|(_, c)| matches!(c, '.' | 'e' | 'E'))
+        {
+            return self.error("unsupported non-integer number");
+        }
+        match number.parse() {
+            Ok(number) => Ok(Json::Int(number)),
+            Err(_) => self.error("invalid integer"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.chars.next_if(|&(_, c)| c == '"').is_none() {
+            return self.error("expected string");
+        }
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(string),
+                Some((_, '\\')) => {
+                    let c = match self.chars.next() {
+                        Some((_, c @ ('"' | '\\' | '/'))) => c,
+                        Some((_, 'b')) => '\u{8}',
+                        Some((_, 'f')) => '\u{c}',
+                        Some((_, 'n')) => '\n',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'u')) => self.unicode_escape()?,
+                        _ => return self.error("invalid escape"),
+                    };
+                    string.push(c);
+                }
+                Some((_, c)) if u32::from(c) >= 0x20 => string.push(c),
+                _ => return self.error("unterminated string"),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            // Characters outside of the basic multilingual plane are encoded
+            // as surrogate pairs.
+            if self.chars.next_if(|&(_, c)| c == '\\').is_none()
+                || self.chars.next_if(|&(_, c)| c == 'u').is_none()
+            {
+                return self.error("expected low surrogate");
+            }
+            let low = self.hex()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return self.error("invalid low surrogate");
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+        match char::from_u32(code) {
+            Some(c) => Ok(c),
+            None => self.error("invalid unicode escape"),
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            match self.chars.next_if(|(_, c)| c.is_ascii_hexdigit()) {
+                Some((_, c)) => code = code * 16 + c.to_digit(16).unwrap_or_default(),
+                None => return self.error("expected hexadecimal digit"),
+            }
+        }
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let source = r#" {"s": "a \"b\"\n\u0001\ud83d\ude00", "n": null,
+            "l": [1, -2, []], "o": {"b": true, "f": false}} "#;
+        let json = Json::parse(source).unwrap();
+        assert_eq!(
+            json.get("s"),
+            Some(&Json::String("a \"b\"\n\u{1}\u{1f600}".into()))
+        );
+        assert_eq!(json.get("n"), Some(&Json::Null));
+        assert_eq!(
+            json.get("l"),
+            Some(&Json::Array(vec![
+                Json::Int(1),
+                Json::Int(-2),
+                Json::Array(vec![])
+            ]))
+        );
+        assert_eq!(
+            json.get("o").and_then(|o| o.get("f")),
+            Some(&Json::Bool(false))
+        );
+    }
+
+    #[test]
+    fn parse_error() {
+        assert_eq!(
+            Json::parse("[1, 2"),
+            Err("expected ',' or ']' at offset 5".into())
+        );
+        assert_eq!(
+            Json::parse("1.5"),
+            Err("unsupported non-integer number at offset 1".into())
+        );
+        assert_eq!(
+            Json::parse("{} x"),
+            Err("trailing characters at offset 3".into())
+        );
+    }
+}
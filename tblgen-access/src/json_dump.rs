@@ -0,0 +1,395 @@
+//! Records loaded from the output of `llvm-tblgen --dump-json`.
+//!
+//! A [`JsonRecordKeeper`] gives access to the defs of a JSON dump through the
+//! traits of this crate, so that code generators can run in environments
+//! where LLVM cannot be linked.
+//!
+//! ```rust
+//! use tblgen_access::{RecordAccess, json_dump::JsonRecordKeeper};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = JsonRecordKeeper::parse(
+//!     r#"{
+//!         "A": { "i": 1, "!name": "A", "!anonymous": false,
+//!                "!superclasses": ["C"], "!fields": [] },
+//!         "!instanceof": { "C": ["A"] },
+//!         "!tablegen_json_version": 1
+//!     }"#,
+//! )?;
+//! assert_eq!(keeper.def("A")?.int_value("i")?, 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::BTreeMap, fs, path::Path, str::FromStr};
+
+use crate::{JsonError, RecordAccess, RecordKeeperAccess, json::Json};
+
+/// Defs loaded from the output of `llvm-tblgen --dump-json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonRecordKeeper {
+    defs: BTreeMap<String, Json>,
+    instances: BTreeMap<String, Vec<String>>,
+}
+
+impl JsonRecordKeeper {
+    /// Parses the output of `llvm-tblgen --dump-json`.
+    pub fn parse(source: &str) -> Result<Self, JsonError> {
+        let Json::Object(entries) = Json::parse(source).map_err(JsonError::InvalidJson)? else {
+            return Err(invalid("expected an object of records"));
+        };
+
+        let mut defs = BTreeMap::new();
+        let mut instances = BTreeMap::new();
+        for (name, value) in entries {
+            match (name.as_str(), value) {
+                ("!instanceof", Json::Object(classes)) => {
+                    for (class, names) in classes {
+                        let names = match names {
+                            Json::Array(names) => names
+                                .into_iter()
+                                .map(|name| match name {
+                                    Json::String(name) => Ok(name),
+                                    _ => Err(invalid("expected a def name in !instanceof")),
+                                })
+                                .collect::<Result<_, _>>()?,
+                            _ => return Err(invalid("expected a list of defs in !instanceof")),
+                        };
+                        instances.insert(class, names);
+                    }
+                }
+                (name, _) if name.starts_with('!') => {}
+                (_, value @ Json::Object(_)) => {
+                    defs.insert(name, value);
+                }
+                (name, _) => return Err(invalid(&format!("expected an object for def {}", name))),
+            }
+        }
+
+        Ok(Self { defs, instances })
+    }
+
+    /// Reads and parses the output of `llvm-tblgen --dump-json` from a file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, JsonError> {
+        let source = fs::read_to_string(path.as_ref())
+            .map_err(|error| JsonError::Read(format!("{}: {}", path.as_ref().display(), error)))?;
+        Self::parse(&source)
+    }
+
+    /// Returns the def with the given name.
+    pub fn def(&self, name: &str) -> Result<JsonRecord<'_>, JsonError> {
+        self.defs
+            .get_key_value(name)
+            .map(|(name, value)| JsonRecord::new(self, name, value))
+            .ok_or_else(|| JsonError::MissingDef(name.into()))
+    }
+
+    /// Returns an iterator over all defs, ordered by name.
+    pub fn defs(&self) -> impl Iterator<Item = JsonRecord<'_>> {
+        self.defs
+            .iter()
+            .map(|(name, value)| JsonRecord::new(self, name, value))
+    }
+
+    /// Returns all defs that derive from the given class.
+    pub fn all_derived_definitions(&self, class: &str) -> Result<Vec<JsonRecord<'_>>, JsonError> {
+        self.instances
+            .get(class)
+            .ok_or_else(|| JsonError::MissingClass(class.into()))?
+            .iter()
+            .map(|name| self.def(name))
+            .collect()
+    }
+}
+
+impl FromStr for JsonRecordKeeper {
+    type Err = JsonError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Self::parse(source)
+    }
+}
+
+impl RecordKeeperAccess for JsonRecordKeeper {
+    type Error = JsonError;
+    type Record<'a> = JsonRecord<'a>;
+
+    fn def(&self, name: &str) -> Result<JsonRecord<'_>, JsonError> {
+        JsonRecordKeeper::def(self, name)
+    }
+
+    fn def_records(&self) -> impl Iterator<Item = JsonRecord<'_>> {
+        self.defs()
+    }
+
+    fn all_derived_definitions(&self, class: &str) -> Result<Vec<JsonRecord<'_>>, JsonError> {
+        JsonRecordKeeper::all_derived_definitions(self, class)
+    }
+}
+
+fn invalid(message: &str) -> JsonError {
+    JsonError::InvalidJson(message.into())
+}
+
+/// A def loaded from a JSON dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonRecord<'a> {
+    keeper: &'a JsonRecordKeeper,
+    name: &'a str,
+    value: &'a Json,
+}
+
+impl<'a> JsonRecord<'a> {
+    fn new(keeper: &'a JsonRecordKeeper, name: &'a str, value: &'a Json) -> Self {
+        Self {
+            keeper,
+            name,
+            value,
+        }
+    }
+
+    /// Returns the name of the def.
+    pub fn name(self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the names of the fields of the def.
+    pub fn field_names(self) -> impl Iterator<Item = &'a str> {
+        let entries = match self.value {
+            Json::Object(entries) => entries.as_slice(),
+            _ => &[],
+        };
+        entries
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| !name.starts_with('!'))
+    }
+
+    /// Returns the names of all super classes of the def.
+    pub fn super_classes(self) -> impl Iterator<Item = &'a str> {
+        let classes = match self.value.get("!superclasses") {
+            Some(Json::Array(classes)) => classes.as_slice(),
+            _ => &[],
+        };
+        classes.iter().filter_map(|class| match class {
+            Json::String(class) => Some(class.as_str()),
+            _ => None,
+        })
+    }
+
+    fn field(self, name: &str) -> Result<&'a Json, JsonError> {
+        match self.value.get(name) {
+            Some(value) if !name.starts_with('!') => Ok(value),
+            _ => Err(JsonError::MissingValue(name.into())),
+        }
+    }
+
+    fn convert<T>(
+        self,
+        name: &str,
+        to: &'static str,
+        convert: impl FnOnce(&'a Json) -> Option<T>,
+    ) -> Result<T, JsonError> {
+        let value = self.field(name)?;
+        convert(value).ok_or_else(|| JsonError::InitConversion {
+            def: self.name.into(),
+            field: name.into(),
+            from: kind(value),
+            to,
+        })
+    }
+
+    fn def_reference(self, value: &'a Json) -> Option<JsonRecord<'a>> {
+        match value.get("kind") {
+            Some(Json::String(kind)) if kind == "def" => match value.get("def") {
+                Some(Json::String(name)) => self.keeper.def(name).ok(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn list<T>(
+        self,
+        name: &str,
+        to: &'static str,
+        convert: impl Fn(&'a Json) -> Option<T>,
+    ) -> Result<Vec<T>, JsonError> {
+        self.convert(name, to, |value| match value {
+            Json::Array(values) => values.iter().map(convert).collect(),
+            _ => None,
+        })
+    }
+}
+
+/// Returns the kind of a value, named like the variants of `tblgen::TypedInit`.
+fn kind(value: &Json) -> &'static str {
+    match value {
+        Json::Null => "Unset",
+        Json::Bool(_) => "Bool",
+        Json::Int(_) => "Int",
+        Json::String(_) => "String",
+        Json::Array(_) => "List",
+        Json::Object(_) => match value.get("kind") {
+            Some(Json::String(kind)) if kind == "def" => "Def",
+            Some(Json::String(kind)) if kind == "dag" => "Dag",
+            Some(Json::String(kind)) if kind == "varbit" => "VarBit",
+            _ => "Complex",
+        },
+    }
+}
+
+fn string(value: &Json) -> Option<&str> {
+    match value {
+        Json::String(value) => Some(value),
+        _ => None,
+    }
+}
+
+fn int(value: &Json) -> Option<i64> {
+    match value {
+        Json::Int(value) => Some(*value),
+        _ => None,
+    }
+}
+
+impl<'a> RecordAccess<'a> for JsonRecord<'a> {
+    type Error = JsonError;
+
+    fn name(self) -> Result<&'a str, JsonError> {
+        Ok(self.name)
+    }
+
+    fn anonymous(self) -> bool {
+        self.value.get("!anonymous") == Some(&Json::Bool(true))
+    }
+
+    fn subclass_of(self, class: &str) -> bool {
+        self.super_classes().any(|name| name == class)
+    }
+
+    fn has_field(self, name: &str) -> bool {
+        self.field(name).is_ok()
+    }
+
+    fn bit_value(self, name: &str) -> Result<bool, JsonError> {
+        self.convert(name, "bool", |value| match value {
+            Json::Int(0) => Some(false),
+            Json::Int(1) => Some(true),
+            _ => None,
+        })
+    }
+
+    fn int_value(self, name: &str) -> Result<i64, JsonError> {
+        self.convert(name, "i64", int)
+    }
+
+    fn str_value(self, name: &str) -> Result<&'a str, JsonError> {
+        self.convert(name, "&str", string)
+    }
+
+    fn def_value(self, name: &str) -> Result<Self, JsonError> {
+        self.convert(name, "Record", |value| self.def_reference(value))
+    }
+
+    fn list_of_defs_value(self, name: &str) -> Result<Vec<Self>, JsonError> {
+        self.list(name, "Vec<Record>", |value| self.def_reference(value))
+    }
+
+    fn list_of_ints_value(self, name: &str) -> Result<Vec<i64>, JsonError> {
+        self.list(name, "Vec<i64>", int)
+    }
+
+    fn list_of_strings_value(self, name: &str) -> Result<Vec<&'a str>, JsonError> {
+        self.list(name, "Vec<&str>", string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"{
+        "A": {
+            "b": 1, "i": 2, "s": "s", "u": null,
+            "r": { "kind": "def", "def": "B", "printable": "B" },
+            "l": [{ "kind": "def", "def": "A", "printable": "A" }],
+            "li": [1, 2], "ls": ["x"],
+            "!name": "A", "!anonymous": false,
+            "!superclasses": ["C", "D"], "!fields": []
+        },
+        "B": {
+            "!name": "B", "!anonymous": true,
+            "!superclasses": ["C"], "!fields": []
+        },
+        "!instanceof": { "C": ["A", "B"], "D": ["A"], "E": [] },
+        "!tablegen_json_version": 1
+    }"#;
+
+    #[test]
+    fn fields() {
+        let keeper = JsonRecordKeeper::parse(DUMP).unwrap();
+        let a = keeper.def("A").unwrap();
+        assert!(a.bit_value("b").unwrap());
+        assert_eq!(a.int_value("i").unwrap(), 2);
+        assert_eq!(a.str_value("s").unwrap(), "s");
+        assert_eq!(a.def_value("r").unwrap().name(), "B");
+        assert_eq!(a.list_of_defs_value("l").unwrap(), vec![a]);
+        assert_eq!(a.list_of_ints_value("li").unwrap(), vec![1, 2]);
+        assert_eq!(a.list_of_strings_value("ls").unwrap(), vec!["x"]);
+        assert!(a.has_field("u"));
+        assert!(!a.has_field("!name"));
+        assert!(a.subclass_of("D"));
+        assert!(!a.anonymous());
+        assert!(keeper.def("B").unwrap().anonymous());
+        assert_eq!(
+            a.field_names().collect::<Vec<_>>(),
+            ["b", "i", "s", "u", "r", "l", "li", "ls"]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let keeper = JsonRecordKeeper::parse(DUMP).unwrap();
+        let a = keeper.def("A").unwrap();
+        assert_eq!(
+            a.int_value("missing"),
+            Err(JsonError::MissingValue("missing".into()))
+        );
+        assert_eq!(
+            a.int_value("u").unwrap_err().to_string(),
+            "field 'u' of def 'A': invalid conversion from Unset to i64"
+        );
+        assert_eq!(keeper.def("X"), Err(JsonError::MissingDef("X".into())));
+        assert_eq!(
+            keeper.all_derived_definitions("X"),
+            Err(JsonError::MissingClass("X".into()))
+        );
+        assert!(matches!(
+            JsonRecordKeeper::parse("["),
+            Err(JsonError::InvalidJson(_))
+        ));
+    }
+
+    #[test]
+    fn derived_definitions() {
+        let keeper = JsonRecordKeeper::parse(DUMP).unwrap();
+        let names = |class| {
+            keeper
+                .all_derived_definitions(class)
+                .unwrap()
+                .into_iter()
+                .map(JsonRecord::name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names("C"), ["A", "B"]);
+        assert_eq!(names("E"), Vec::<&str>::new());
+        assert_eq!(
+            keeper
+                .def_records()
+                .map(JsonRecord::name)
+                .collect::<Vec<_>>(),
+            ["A", "B"]
+        );
+    }
+}
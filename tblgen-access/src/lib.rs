@@ -0,0 +1,104 @@
+//! Traits to access TableGen records independently of where they were loaded
+//! from.
+//!
+//! Records parsed by TableGen (`tblgen::RecordKeeper`, with the `access`
+//! feature of the [`tblgen`](https://docs.rs/tblgen) crate) and records loaded
+//! from the output of `llvm-tblgen --dump-json`
+//! ([`JsonRecordKeeper`](json_dump::JsonRecordKeeper)) implement the same
+//! traits, so that code generators can be written once and run without
+//! linking LLVM. This crate does not depend on LLVM.
+//!
+//! ```rust
+//! use tblgen_access::{RecordAccess, RecordKeeperAccess, json_dump::JsonRecordKeeper};
+//!
+//! fn opcodes<K: RecordKeeperAccess>(keeper: &K) -> Result<Vec<i64>, K::Error> {
+//!     keeper
+//!         .all_derived_definitions("Inst")?
+//!         .into_iter()
+//!         .map(|def| def.int_value("opcode"))
+//!         .collect()
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let keeper = JsonRecordKeeper::parse(
+//!     r#"{
+//!         "A": { "opcode": 1, "!name": "A", "!anonymous": false,
+//!                "!superclasses": ["Inst"], "!fields": [] },
+//!         "!instanceof": { "Inst": ["A"] },
+//!         "!tablegen_json_version": 1
+//!     }"#,
+//! )?;
+//! assert_eq!(opcodes(&keeper)?, vec![1]);
+//! # Ok(())
+//! # }
+//! ```
+
+mod error;
+mod json;
+pub mod json_dump;
+
+pub use error::JsonError;
+
+/// Read access to the fields of a record.
+///
+/// See `tblgen::Record` for the documentation of each method.
+pub trait RecordAccess<'a>: Copy {
+    /// Error returned if a field is missing or has a different type.
+    type Error: std::error::Error;
+
+    /// Returns the name of the record.
+    fn name(self) -> Result<&'a str, Self::Error>;
+
+    /// Returns true if the record is anonymous.
+    fn anonymous(self) -> bool;
+
+    /// Returns true if the record derives from the given class.
+    fn subclass_of(self, class: &str) -> bool;
+
+    /// Returns true if the record has a field with the given name.
+    fn has_field(self, name: &str) -> bool;
+
+    /// Returns the boolean value of the given `bit` field.
+    fn bit_value(self, name: &str) -> Result<bool, Self::Error>;
+
+    /// Returns the value of the given `int` field.
+    fn int_value(self, name: &str) -> Result<i64, Self::Error>;
+
+    /// Returns the value of the given `string` or `code` field.
+    fn str_value(self, name: &str) -> Result<&'a str, Self::Error>;
+
+    /// Returns the def referenced by the given field.
+    fn def_value(self, name: &str) -> Result<Self, Self::Error>;
+
+    /// Returns the defs of the given `list` field.
+    fn list_of_defs_value(self, name: &str) -> Result<Vec<Self>, Self::Error>;
+
+    /// Returns the values of the given `list<int>` field.
+    fn list_of_ints_value(self, name: &str) -> Result<Vec<i64>, Self::Error>;
+
+    /// Returns the values of the given `list<string>` field.
+    fn list_of_strings_value(self, name: &str) -> Result<Vec<&'a str>, Self::Error>;
+}
+
+/// Read access to the defs of a record keeper.
+///
+/// See `tblgen::RecordKeeper` for the documentation of each method.
+pub trait RecordKeeperAccess {
+    /// Error returned if a record is missing, which is also returned by its
+    /// records.
+    type Error: std::error::Error;
+
+    /// Type of the records of the keeper.
+    type Record<'a>: RecordAccess<'a, Error = Self::Error>
+    where
+        Self: 'a;
+
+    /// Returns the def with the given name.
+    fn def(&self, name: &str) -> Result<Self::Record<'_>, Self::Error>;
+
+    /// Returns all defs, ordered by name.
+    fn def_records(&self) -> impl Iterator<Item = Self::Record<'_>>;
+
+    /// Returns all defs that derive from the given class.
+    fn all_derived_definitions(&self, class: &str) -> Result<Vec<Self::Record<'_>>, Self::Error>;
+}
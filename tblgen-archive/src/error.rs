@@ -0,0 +1,52 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::Utf8Error,
+};
+
+/// Error of an invalid archive.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveError {
+    /// The bytes do not start with the header of an archive.
+    InvalidHeader,
+    /// The archive was written with an unsupported version of the layout.
+    UnsupportedVersion(u32),
+    /// A section is out of bounds or its size is not a multiple of the size
+    /// of its entries.
+    InvalidSection(&'static str),
+    /// The strings are not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+    /// An entry of a section refers to an element that does not exist.
+    InvalidEntry { section: &'static str, index: usize },
+}
+
+impl Display for ArchiveError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidHeader => write!(formatter, "invalid archive header"),
+            Self::UnsupportedVersion(version) => {
+                write!(formatter, "unsupported archive version {}", version)
+            }
+            Self::InvalidSection(section) => write!(formatter, "invalid {} section", section),
+            Self::InvalidUtf8(error) => write!(formatter, "invalid archive strings: {}", error),
+            Self::InvalidEntry { section, index } => {
+                write!(formatter, "invalid entry {} of {} section", index, section)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidUtf8(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<Utf8Error> for ArchiveError {
+    fn from(error: Utf8Error) -> Self {
+        Self::InvalidUtf8(error)
+    }
+}
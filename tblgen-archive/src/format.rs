@@ -0,0 +1,85 @@
+//! Layout of archives.
+//!
+//! All integers are little-endian. An archive starts with a header:
+//!
+//! | Offset | Size     | Content                                     |
+//! | ------ | -------- | ------------------------------------------- |
+//! | 0      | 4        | [`MAGIC`]                                   |
+//! | 4      | 4        | [`VERSION`]                                 |
+//! | 8      | 4        | Number of classes                           |
+//! | 12     | 8 × 7    | Offset and size in bytes of each section    |
+//!
+//! The sections are, in the order of the [`section`] indices:
+//!
+//! - Strings: UTF-8 text that the other sections refer to with spans, i.e.
+//!   a `u32` byte offset followed by a `u32` length.
+//! - Records: classes followed by defs, each in name order. A record is the
+//!   span of its name, and the spans of its direct super classes and fields
+//!   ([`RECORD_SIZE`] bytes).
+//! - Super classes: `u32` record indices.
+//! - Fields: the span of the field name and the `u32` index of its value
+//!   ([`FIELD_SIZE`] bytes).
+//! - Inits: a [`tag`] followed by three `u32` operands ([`INIT_SIZE`]
+//!   bytes). Bits, lists and dag arguments are spans of the following
+//!   sections, strings are spans of the strings, ints are stored in the
+//!   first two operands (low half first), and dags store the index of their
+//!   operator followed by the span of their arguments.
+//! - Elements: `u32` init indices of bits and lists.
+//! - Dag arguments: the span of the argument name, whose offset is
+//!   [`NO_NAME`] for unnamed arguments, and the `u32` index of its value
+//!   ([`DAG_ARG_SIZE`] bytes).
+
+/// Bytes at the start of every archive.
+pub const MAGIC: [u8; 4] = *b"TGAR";
+
+/// Version of the layout, which is incremented on every incompatible change.
+pub const VERSION: u32 = 1;
+
+/// Number of sections.
+pub const SECTIONS: usize = 7;
+
+/// Size of the header in bytes.
+pub const HEADER_SIZE: usize = 12 + 8 * SECTIONS;
+
+/// Size of a record in bytes.
+pub const RECORD_SIZE: usize = 24;
+
+/// Size of a field in bytes.
+pub const FIELD_SIZE: usize = 12;
+
+/// Size of an init in bytes.
+pub const INIT_SIZE: usize = 16;
+
+/// Size of a dag argument in bytes.
+pub const DAG_ARG_SIZE: usize = 12;
+
+/// Size of a record or init index in bytes.
+pub const ID_SIZE: usize = 4;
+
+/// Name offset of unnamed dag arguments.
+pub const NO_NAME: u32 = u32::MAX;
+
+/// Indices of the sections in the header.
+pub mod section {
+    pub const STRINGS: usize = 0;
+    pub const RECORDS: usize = 1;
+    pub const SUPER_CLASSES: usize = 2;
+    pub const FIELDS: usize = 3;
+    pub const INITS: usize = 4;
+    pub const ELEMENTS: usize = 5;
+    pub const DAG_ARGS: usize = 6;
+}
+
+/// Kinds of inits.
+pub mod tag {
+    pub const UNSET: u32 = 0;
+    pub const BIT: u32 = 1;
+    pub const BITS: u32 = 2;
+    pub const INT: u32 = 3;
+    pub const STRING: u32 = 4;
+    pub const CODE: u32 = 5;
+    pub const LIST: u32 = 6;
+    pub const DAG: u32 = 7;
+    pub const DEF: u32 = 8;
+    pub const COMPLEX: u32 = 9;
+}
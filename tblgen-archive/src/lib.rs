@@ -0,0 +1,597 @@
+//! Zero-copy reader of archives of TableGen records.
+//!
+//! An archive is written by the [`tblgen`](https://docs.rs/tblgen) crate from
+//! a snapshot of a record keeper (with its `archive` feature), usually in a
+//! build script. This crate reads archives without linking LLVM, so tools
+//! that only query precomputed records do not depend on libLLVM at run time.
+//!
+//! [`Archive::new`] validates the archive once, after which records and
+//! values are read directly from the bytes, e.g. of a memory-mapped file or
+//! of `include_bytes!`. The records and values are those of a
+//! `tblgen::snapshot::Snapshot`: records and values refer to each other with
+//! [`RecordId`]s and [`InitId`]s, and equal values are stored only once.
+//!
+//! ```rust,ignore
+//! // build.rs
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let keeper = tblgen::TableGenParser::new()
+//!         .add_source_file("src/Ops.td")
+//!         .parse()?;
+//!     let path = std::path::Path::new(&std::env::var("OUT_DIR")?).join("Ops.tgar");
+//!     std::fs::write(path, keeper.snapshot()?.to_archive())?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! ```rust,ignore
+//! // src/main.rs
+//! use tblgen_archive::{Archive, ArchivedInit};
+//!
+//! static OPS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/Ops.tgar"));
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let archive = Archive::new(OPS)?;
+//!     for def in archive.defs() {
+//!         let def = archive.record(def);
+//!         if let Some(ArchivedInit::String(summary)) = def.value("summary").map(|id| archive.init(id)) {
+//!             println!("{}: {}", def.name(), summary);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+mod error;
+pub mod format;
+
+use std::ops::Range;
+
+pub use error::ArchiveError;
+use format::{
+    DAG_ARG_SIZE, FIELD_SIZE, HEADER_SIZE, ID_SIZE, INIT_SIZE, MAGIC, NO_NAME, RECORD_SIZE,
+    VERSION, section, tag,
+};
+
+/// Index of a record in an [`Archive`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RecordId(u32);
+
+/// Index of a value in an [`Archive`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InitId(u32);
+
+/// Validated archive of TableGen records.
+///
+/// See the [crate documentation](crate).
+#[derive(Debug, Clone, Copy)]
+pub struct Archive<'a> {
+    strings: &'a str,
+    records: &'a [u8],
+    classes: usize,
+    super_classes: &'a [u8],
+    fields: &'a [u8],
+    inits: &'a [u8],
+    elements: &'a [u8],
+    dag_args: &'a [u8],
+}
+
+impl<'a> Archive<'a> {
+    /// Validates an archive, so that its records and values can be read
+    /// without further checks.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, ArchiveError> {
+        if bytes.len() < HEADER_SIZE || bytes[..MAGIC.len()] != MAGIC {
+            return Err(ArchiveError::InvalidHeader);
+        }
+        let version = read_u32(bytes, 4);
+        if version != VERSION {
+            return Err(ArchiveError::UnsupportedVersion(version));
+        }
+
+        let section = |index: usize, name: &'static str, size: usize| {
+            let offset = read_u32(bytes, 12 + 8 * index) as usize;
+            let len = read_u32(bytes, 16 + 8 * index) as usize;
+            bytes
+                .get(offset..)
+                .and_then(|bytes| bytes.get(..len))
+                .filter(|section| section.len() % size == 0)
+                .ok_or(ArchiveError::InvalidSection(name))
+        };
+        let archive = Self {
+            strings: std::str::from_utf8(section(section::STRINGS, "strings", 1)?)?,
+            records: section(section::RECORDS, "records", RECORD_SIZE)?,
+            classes: read_u32(bytes, 8) as usize,
+            super_classes: section(section::SUPER_CLASSES, "super classes", ID_SIZE)?,
+            fields: section(section::FIELDS, "fields", FIELD_SIZE)?,
+            inits: section(section::INITS, "inits", INIT_SIZE)?,
+            elements: section(section::ELEMENTS, "elements", ID_SIZE)?,
+            dag_args: section(section::DAG_ARGS, "dag arguments", DAG_ARG_SIZE)?,
+        };
+        if archive.classes > archive.records.len() / RECORD_SIZE {
+            return Err(ArchiveError::InvalidHeader);
+        }
+        archive.validate()?;
+        Ok(archive)
+    }
+
+    fn validate(&self) -> Result<(), ArchiveError> {
+        let records = self.records.len() / RECORD_SIZE;
+        let inits = self.inits.len() / INIT_SIZE;
+
+        for index in 0..records {
+            let [name, name_len, supers, supers_len, fields, fields_len] =
+                entry(self.records, RECORD_SIZE, index);
+            check(
+                self.is_str(name, name_len)
+                    && is_span(self.super_classes, ID_SIZE, supers, supers_len)
+                    && is_span(self.fields, FIELD_SIZE, fields, fields_len),
+                "records",
+                index,
+            )?;
+        }
+        for index in 0..self.super_classes.len() / ID_SIZE {
+            let [id] = entry(self.super_classes, ID_SIZE, index);
+            check((id as usize) < records, "super classes", index)?;
+        }
+        for index in 0..self.fields.len() / FIELD_SIZE {
+            let [name, name_len, init] = entry(self.fields, FIELD_SIZE, index);
+            check(
+                self.is_str(name, name_len) && (init as usize) < inits,
+                "fields",
+                index,
+            )?;
+        }
+        for index in 0..inits {
+            let [kind, a, b, c] = entry(self.inits, INIT_SIZE, index);
+            let valid = match kind {
+                tag::UNSET | tag::BIT | tag::INT => true,
+                tag::BITS | tag::LIST => is_span(self.elements, ID_SIZE, a, b),
+                tag::STRING | tag::CODE | tag::COMPLEX => self.is_str(a, b),
+                tag::DAG => (a as usize) < inits && is_span(self.dag_args, DAG_ARG_SIZE, b, c),
+                tag::DEF => (a as usize) < records,
+                _ => false,
+            };
+            check(valid, "inits", index)?;
+        }
+        for index in 0..self.elements.len() / ID_SIZE {
+            let [id] = entry(self.elements, ID_SIZE, index);
+            check((id as usize) < inits, "elements", index)?;
+        }
+        for index in 0..self.dag_args.len() / DAG_ARG_SIZE {
+            let [name, name_len, init] = entry(self.dag_args, DAG_ARG_SIZE, index);
+            check(
+                (name == NO_NAME || self.is_str(name, name_len)) && (init as usize) < inits,
+                "dag arguments",
+                index,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn is_str(&self, start: u32, len: u32) -> bool {
+        self.strings
+            .get(start as usize..)
+            .and_then(|string| string.get(..len as usize))
+            .is_some()
+    }
+
+    fn str(&self, start: u32, len: u32) -> &'a str {
+        &self.strings[start as usize..][..len as usize]
+    }
+
+    /// Returns all classes in name order.
+    pub fn classes(&self) -> impl ExactSizeIterator<Item = RecordId> + use<> {
+        record_ids(0..self.classes)
+    }
+
+    /// Returns all defs in name order.
+    pub fn defs(&self) -> impl ExactSizeIterator<Item = RecordId> + use<> {
+        record_ids(self.classes..self.records.len() / RECORD_SIZE)
+    }
+
+    /// Returns the class with the given name.
+    pub fn class(&self, name: &str) -> Option<RecordId> {
+        self.find(0..self.classes, name)
+    }
+
+    /// Returns the def with the given name.
+    pub fn def(&self, name: &str) -> Option<RecordId> {
+        self.find(self.classes..self.records.len() / RECORD_SIZE, name)
+    }
+
+    fn find(&self, range: Range<usize>, name: &str) -> Option<RecordId> {
+        let (mut low, mut high) = (range.start, range.end);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let [start, len, ..] = entry::<6>(self.records, RECORD_SIZE, middle);
+            match self.str(start, len).cmp(name) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Some(RecordId(middle as u32)),
+            }
+        }
+        None
+    }
+
+    /// Returns the record with the given id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id is not from this archive.
+    pub fn record(&self, id: RecordId) -> ArchivedRecord<'_> {
+        let [name, name_len, supers, supers_len, fields, fields_len] =
+            entry(self.records, RECORD_SIZE, id.0 as usize);
+        ArchivedRecord {
+            archive: self,
+            id,
+            name: self.str(name, name_len),
+            super_classes: span(self.super_classes, ID_SIZE, supers, supers_len),
+            fields: span(self.fields, FIELD_SIZE, fields, fields_len),
+        }
+    }
+
+    /// Returns the value with the given id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id is not from this archive.
+    pub fn init(&self, id: InitId) -> ArchivedInit<'_> {
+        let [kind, a, b, c] = entry(self.inits, INIT_SIZE, id.0 as usize);
+        match kind {
+            tag::UNSET => ArchivedInit::Unset,
+            tag::BIT => ArchivedInit::Bit(a != 0),
+            tag::BITS => ArchivedInit::Bits(InitIds(span(self.elements, ID_SIZE, a, b))),
+            tag::INT => ArchivedInit::Int((u64::from(a) | u64::from(b) << 32) as i64),
+            tag::STRING => ArchivedInit::String(self.str(a, b)),
+            tag::CODE => ArchivedInit::Code(self.str(a, b)),
+            tag::LIST => ArchivedInit::List(InitIds(span(self.elements, ID_SIZE, a, b))),
+            tag::DAG => ArchivedInit::Dag(ArchivedDag {
+                archive: self,
+                operator: InitId(a),
+                args: span(self.dag_args, DAG_ARG_SIZE, b, c),
+            }),
+            tag::DEF => ArchivedInit::Def(RecordId(a)),
+            tag::COMPLEX => ArchivedInit::Complex(self.str(a, b)),
+            _ => unreachable!("tags are validated"),
+        }
+    }
+}
+
+/// A record of an [`Archive`].
+#[derive(Debug, Clone, Copy)]
+pub struct ArchivedRecord<'a> {
+    archive: &'a Archive<'a>,
+    id: RecordId,
+    name: &'a str,
+    super_classes: &'a [u8],
+    fields: &'a [u8],
+}
+
+impl<'a> ArchivedRecord<'a> {
+    /// Returns the id of the record.
+    pub fn id(self) -> RecordId {
+        self.id
+    }
+
+    /// Returns the name of the record.
+    pub fn name(self) -> &'a str {
+        self.name
+    }
+
+    /// Returns true if the record is a class.
+    pub fn is_class(self) -> bool {
+        (self.id.0 as usize) < self.archive.classes
+    }
+
+    /// Returns the direct super classes of the record.
+    pub fn direct_super_classes(self) -> impl ExactSizeIterator<Item = RecordId> + use<'a> {
+        self.super_classes
+            .chunks_exact(ID_SIZE)
+            .map(|id| RecordId(read_u32(id, 0)))
+    }
+
+    /// Returns the names and values of the fields of the record, in the order
+    /// of their declaration.
+    pub fn fields(self) -> impl ExactSizeIterator<Item = (&'a str, InitId)> {
+        let archive = self.archive;
+        self.fields.chunks_exact(FIELD_SIZE).map(move |field| {
+            let [name, name_len, init] = entry(field, FIELD_SIZE, 0);
+            (archive.str(name, name_len), InitId(init))
+        })
+    }
+
+    /// Returns the value of the field with the given name.
+    pub fn value(self, name: &str) -> Option<InitId> {
+        self.fields()
+            .find_map(|(field, init)| (field == name).then_some(init))
+    }
+}
+
+impl PartialEq for ArchivedRecord<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.archive, other.archive) && self.id == other.id
+    }
+}
+
+impl Eq for ArchivedRecord<'_> {}
+
+/// A value of an [`Archive`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchivedInit<'a> {
+    /// An unset value (`?`).
+    Unset,
+    /// A bit.
+    Bit(bool),
+    /// Bits, each of which is a bit, an unset value or a complex value.
+    Bits(InitIds<'a>),
+    /// An int.
+    Int(i64),
+    /// A string.
+    String(&'a str),
+    /// A code string.
+    Code(&'a str),
+    /// A list.
+    List(InitIds<'a>),
+    /// A dag.
+    Dag(ArchivedDag<'a>),
+    /// A reference to a def.
+    Def(RecordId),
+    /// A value without a concrete representation (e.g. a reference to a
+    /// template argument), in TableGen syntax.
+    Complex(&'a str),
+}
+
+/// Iterator over the ids of the elements of bits or a list of an
+/// [`Archive`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitIds<'a>(&'a [u8]);
+
+impl InitIds<'_> {
+    /// Returns the id of the element at the given index.
+    pub fn get(&self, index: usize) -> Option<InitId> {
+        (index < self.len()).then(|| InitId(read_u32(self.0, index * ID_SIZE)))
+    }
+}
+
+impl Iterator for InitIds<'_> {
+    type Item = InitId;
+
+    fn next(&mut self) -> Option<InitId> {
+        let (id, rest) = self.0.split_at_checked(ID_SIZE)?;
+        self.0 = rest;
+        Some(InitId(read_u32(id, 0)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.len() / ID_SIZE;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for InitIds<'_> {
+    fn next_back(&mut self) -> Option<InitId> {
+        let (rest, id) = self
+            .0
+            .split_at_checked(self.0.len().checked_sub(ID_SIZE)?)?;
+        self.0 = rest;
+        Some(InitId(read_u32(id, 0)))
+    }
+}
+
+impl ExactSizeIterator for InitIds<'_> {}
+
+/// A dag of an [`Archive`].
+#[derive(Debug, Clone, Copy)]
+pub struct ArchivedDag<'a> {
+    archive: &'a Archive<'a>,
+    operator: InitId,
+    args: &'a [u8],
+}
+
+impl<'a> ArchivedDag<'a> {
+    /// Returns the operator, which is usually a def.
+    pub fn operator(self) -> InitId {
+        self.operator
+    }
+
+    /// Returns the number of arguments.
+    pub fn len(self) -> usize {
+        self.args.len() / DAG_ARG_SIZE
+    }
+
+    /// Returns true if the dag has no arguments.
+    pub fn is_empty(self) -> bool {
+        self.args.is_empty()
+    }
+
+    /// Returns the names and values of the arguments.
+    pub fn args(self) -> impl ExactSizeIterator<Item = (Option<&'a str>, InitId)> {
+        let archive = self.archive;
+        self.args.chunks_exact(DAG_ARG_SIZE).map(move |arg| {
+            let [name, name_len, init] = entry(arg, DAG_ARG_SIZE, 0);
+            let name = (name != NO_NAME).then(|| archive.str(name, name_len));
+            (name, InitId(init))
+        })
+    }
+}
+
+impl PartialEq for ArchivedDag<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.archive, other.archive)
+            && self.operator == other.operator
+            && self.args == other.args
+    }
+}
+
+impl Eq for ArchivedDag<'_> {}
+
+fn record_ids(range: Range<usize>) -> impl ExactSizeIterator<Item = RecordId> {
+    range.map(|index| RecordId(index as u32))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut value = [0; 4];
+    value.copy_from_slice(&bytes[offset..][..4]);
+    u32::from_le_bytes(value)
+}
+
+/// Reads the `u32`s of an entry of a section.
+fn entry<const N: usize>(section: &[u8], size: usize, index: usize) -> [u32; N] {
+    let entry = &section[index * size..][..size];
+    std::array::from_fn(|index| read_u32(entry, 4 * index))
+}
+
+fn is_span(section: &[u8], size: usize, start: u32, len: u32) -> bool {
+    (start as usize)
+        .checked_add(len as usize)
+        .is_some_and(|end| end <= section.len() / size)
+}
+
+fn span(section: &[u8], size: usize, start: u32, len: u32) -> &[u8] {
+    &section[start as usize * size..][..len as usize * size]
+}
+
+fn check(valid: bool, section: &'static str, index: usize) -> Result<(), ArchiveError> {
+    if valid {
+        Ok(())
+    } else {
+        Err(ArchiveError::InvalidEntry { section, index })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes an archive from the `u32`s of its sections.
+    fn archive(classes: u32, strings: &str, sections: [&[u32]; 6]) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend(classes.to_le_bytes());
+        let mut offset = HEADER_SIZE;
+        let mut data = strings.as_bytes().to_vec();
+        for len in [strings.len()]
+            .into_iter()
+            .chain(sections.iter().map(|section| 4 * section.len()))
+        {
+            bytes.extend((offset as u32).to_le_bytes());
+            bytes.extend((len as u32).to_le_bytes());
+            offset += len;
+        }
+        for section in sections {
+            data.extend(section.iter().flat_map(|value| value.to_le_bytes()));
+        }
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn read() {
+        // class C; def A : C { int i = -2; list<string> l = ["x", "x"]; dag d = (A "x":$n); }
+        let bytes = archive(
+            1,
+            "CAilxdn",
+            [
+                &[0, 1, 0, 0, 0, 0, 1, 1, 0, 1, 0, 3],
+                &[0],
+                &[2, 1, 0, 3, 1, 2, 5, 1, 3],
+                [
+                    [tag::INT, -2i32 as u32, u32::MAX, 0],
+                    [tag::STRING, 4, 1, 0],
+                    [tag::LIST, 0, 2, 0],
+                    [tag::DAG, 4, 0, 1],
+                    [tag::DEF, 1, 0, 0],
+                ]
+                .as_flattened(),
+                &[1, 1],
+                &[6, 1, 1],
+            ],
+        );
+        let archive = Archive::new(&bytes).unwrap();
+
+        assert_eq!(archive.classes().len(), 1);
+        assert_eq!(archive.defs().len(), 1);
+        assert_eq!(archive.def("C"), None);
+        let c = archive.class("C").unwrap();
+        let a = archive.record(archive.def("A").unwrap());
+        assert!(archive.record(c).is_class());
+        assert!(!a.is_class());
+        assert_eq!(a.name(), "A");
+        assert_eq!(a.direct_super_classes().collect::<Vec<_>>(), [c]);
+        assert_eq!(
+            a.fields().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["i", "l", "d"]
+        );
+        assert_eq!(archive.init(a.value("i").unwrap()), ArchivedInit::Int(-2));
+        let ArchivedInit::List(elements) = archive.init(a.value("l").unwrap()) else {
+            panic!("expected a list");
+        };
+        assert_eq!(elements.get(1), Some(InitId(1)));
+        assert_eq!(elements.get(2), None);
+        assert_eq!(
+            elements
+                .rev()
+                .map(|id| archive.init(id))
+                .collect::<Vec<_>>(),
+            [ArchivedInit::String("x"), ArchivedInit::String("x")]
+        );
+        let ArchivedInit::Dag(dag) = archive.init(a.value("d").unwrap()) else {
+            panic!("expected a dag");
+        };
+        assert_eq!(archive.init(dag.operator()), ArchivedInit::Def(a.id()));
+        assert_eq!(dag.args().collect::<Vec<_>>(), [(Some("n"), InitId(1))]);
+        assert_eq!(a.value("missing"), None);
+    }
+
+    #[test]
+    fn invalid() {
+        let empty = archive(0, "", [&[]; 6]);
+        assert!(Archive::new(&empty).is_ok());
+        assert_eq!(
+            Archive::new(&empty[..HEADER_SIZE - 1]).unwrap_err(),
+            ArchiveError::InvalidHeader
+        );
+        assert_eq!(
+            Archive::new(b"TGAR\x02\0\0\0").unwrap_err(),
+            ArchiveError::InvalidHeader
+        );
+
+        let mut version = empty.clone();
+        version[4] = 2;
+        assert_eq!(
+            Archive::new(&version).unwrap_err(),
+            ArchiveError::UnsupportedVersion(2)
+        );
+        assert_eq!(
+            Archive::new(&archive(1, "", [&[]; 6])).unwrap_err(),
+            ArchiveError::InvalidHeader
+        );
+        assert_eq!(
+            Archive::new(&archive(0, "", [&[0, 0, 0, 0, 0], &[], &[], &[], &[], &[]])).unwrap_err(),
+            ArchiveError::InvalidSection("records")
+        );
+        assert!(matches!(
+            Archive::new(&archive(
+                0,
+                "\u{e9}",
+                [&[0, 1, 0, 0, 0, 0], &[], &[], &[], &[], &[]]
+            ))
+            .unwrap_err(),
+            ArchiveError::InvalidEntry {
+                section: "records",
+                index: 0
+            }
+        ));
+        assert_eq!(
+            Archive::new(&archive(
+                0,
+                "",
+                [&[], &[], &[], &[tag::DEF, 0, 0, 0], &[], &[]]
+            ))
+            .unwrap_err(),
+            ArchiveError::InvalidEntry {
+                section: "inits",
+                index: 0
+            }
+        );
+    }
+}
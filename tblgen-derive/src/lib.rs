@@ -0,0 +1,218 @@
+//! Derive macros for the [`tblgen`](https://docs.rs/tblgen) crate.
+//!
+//! The macros are re-exported by `tblgen` with the `derive` feature and
+//! should not be used through this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::{
+    Data, DeriveInput, Field, Fields, GenericParam, Lifetime, LifetimeParam, LitStr, ext::IdentExt,
+    parse_macro_input, parse_quote, parse_quote_spanned, spanned::Spanned,
+};
+
+/// Derives `tblgen::FromRecord` for a struct with named fields.
+///
+/// Each field of the struct is read with `Record::get` from the field of the
+/// record with the same name. Fields support the following attributes:
+///
+/// - `#[tblgen(field = "name")]`: reads the field with the given name instead.
+/// - `#[tblgen(default)]`: uses `Default::default()` if the field is missing
+///   or unset.
+///
+/// If the struct has a lifetime parameter, values borrowed from the record
+/// (e.g. `&str` or `Record`) use that lifetime.
+#[proc_macro_derive(FromRecord, attributes(tblgen))]
+pub fn derive_from_record(input: TokenStream) -> TokenStream {
+    from_record(parse_macro_input!(input as DeriveInput))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct FieldOptions {
+    name: Option<LitStr>,
+    default: bool,
+}
+
+impl FieldOptions {
+    fn new(field: &Field) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attribute in field
+            .attrs
+            .iter()
+            .filter(|attribute| attribute.path().is_ident("tblgen"))
+        {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("field") {
+                    if options.name.is_some() {
+                        return Err(meta.error("duplicate `field` attribute"));
+                    }
+                    options.name = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("default") {
+                    options.default = true;
+                } else {
+                    return Err(meta.error("expected `field` or `default`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+fn from_record(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "FromRecord can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new(
+            data.fields.span(),
+            "FromRecord can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut generics = input.generics.clone();
+    let mut lifetimes = input.generics.lifetimes();
+    let lifetime = match (lifetimes.next(), lifetimes.next()) {
+        (None, _) => {
+            let lifetime: Lifetime = parse_quote!('__record);
+            generics.params.insert(
+                0,
+                GenericParam::Lifetime(LifetimeParam::new(lifetime.clone())),
+            );
+            lifetime
+        }
+        (Some(param), None) => param.lifetime.clone(),
+        (Some(_), Some(param)) => {
+            return Err(syn::Error::new(
+                param.span(),
+                "FromRecord can only be derived for structs with at most one lifetime",
+            ));
+        }
+    };
+
+    let where_clause = generics.make_where_clause();
+    let mut values = Vec::new();
+    for field in &fields.named {
+        let options = FieldOptions::new(field)?;
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let name = match options.name {
+            Some(name) => name.value(),
+            None => ident.unraw().to_string(),
+        };
+
+        where_clause
+            .predicates
+            .push(parse_quote_spanned! {ty.span()=>
+                #ty: ::tblgen::init::FromTypedInit<#lifetime>
+            });
+        values.push(if options.default {
+            where_clause
+                .predicates
+                .push(parse_quote_spanned! {ty.span()=>
+                    #ty: ::core::default::Default
+                });
+            quote! {
+                #ident: if record.has_field(#name) && !record.is_value_unset(#name) {
+                    record.get(#name)?
+                } else {
+                    ::core::default::Default::default()
+                }
+            }
+        } else {
+            quote_spanned! {ty.span()=> #ident: record.get(#name)? }
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::tblgen::FromRecord<#lifetime> for #ident #ty_generics
+        #where_clause
+        {
+            fn from_record(
+                record: ::tblgen::Record<#lifetime>,
+            ) -> ::core::result::Result<Self, ::tblgen::Error> {
+                ::core::result::Result::Ok(Self { #(#values,)* })
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: DeriveInput) -> String {
+        from_record(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn expand() {
+        let output = from_record(parse_quote! {
+            struct Op<'a> {
+                r#type: &'a str,
+                #[tblgen(field = "summary")]
+                description: String,
+                #[tblgen(default)]
+                size: i64,
+            }
+        })
+        .unwrap()
+        .to_string();
+        assert!(output.contains("FromRecord < 'a > for Op < 'a >"));
+        assert!(output.contains("r#type : record . get (\"type\") ?"));
+        assert!(output.contains("description : record . get (\"summary\") ?"));
+        assert!(output.contains("record . has_field (\"size\")"));
+        assert!(output.contains("i64 : :: core :: default :: Default"));
+
+        let output = from_record(parse_quote!(
+            struct Empty {}
+        ))
+        .unwrap()
+        .to_string();
+        assert!(
+            output.contains("impl < '__record > :: tblgen :: FromRecord < '__record > for Empty")
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            error(parse_quote!(
+                enum E {}
+            )),
+            "FromRecord can only be derived for structs"
+        );
+        assert_eq!(
+            error(parse_quote!(
+                struct S(i64);
+            )),
+            "FromRecord can only be derived for structs with named fields"
+        );
+        assert_eq!(
+            error(parse_quote!(
+                struct S<'a, 'b> {
+                    a: &'a str,
+                    b: &'b str,
+                }
+            )),
+            "FromRecord can only be derived for structs with at most one lifetime"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                struct S {
+                    #[tblgen(rename = "b")]
+                    a: i64,
+                }
+            }),
+            "expected `field` or `default`"
+        );
+    }
+}